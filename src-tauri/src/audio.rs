@@ -1,15 +1,22 @@
 //! Audio capture module for recording from the microphone.
 //!
 //! Uses the cpal crate for cross-platform audio input.
-//! Audio is captured at 16kHz mono (required by Vosk).
+//! Audio is captured at the device's native rate and resampled
+//! to 16kHz mono (required by Vosk).
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Host, SampleFormat, StreamConfig};
+use cpal::{Device, Host, HostId, SampleFormat, StreamConfig};
 use parking_lot::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Sender};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 /// Audio capture errors
@@ -21,22 +28,1031 @@ pub enum AudioError {
     ConfigError(String),
     #[error("Failed to build input stream: {0}")]
     StreamError(String),
+    #[error("Audio stream failed: {0}")]
+    StreamFailed(String),
     #[error("Failed to play stream: {0}")]
     PlayError(String),
     #[error("Device not found: {0}")]
     DeviceNotFound(String),
+    #[error("Failed to write WAV file: {0}")]
+    WavError(String),
+    #[error("Failed to decode audio file: {0}")]
+    DecodeError(String),
+    #[error("Audio host not available: {0}")]
+    HostUnavailable(String),
+    #[error("Microphone permission denied")]
+    PermissionDenied,
+    #[error("Microphone permission has not been requested yet")]
+    PermissionUndetermined,
 }
 
+impl AudioError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AudioError::NoInputDevice => "no_input_device",
+            AudioError::ConfigError(_) => "config_error",
+            AudioError::StreamError(_) => "stream_error",
+            AudioError::StreamFailed(_) => "stream_failed",
+            AudioError::PlayError(_) => "play_error",
+            AudioError::DeviceNotFound(_) => "device_not_found",
+            AudioError::WavError(_) => "wav_error",
+            AudioError::DecodeError(_) => "decode_error",
+            AudioError::HostUnavailable(_) => "host_unavailable",
+            AudioError::PermissionDenied => "microphone_permission_denied",
+            AudioError::PermissionUndetermined => "microphone_permission_undetermined",
+        }
+    }
+}
+
+/// Sample rate expected by the speech recognizer
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// Resample a mono buffer from `src_rate` to 16kHz using linear interpolation
+pub fn resample_to_16k(input: &[i16], src_rate: u32) -> Vec<i16> {
+    resample(input, src_rate, TARGET_SAMPLE_RATE)
+}
+
+/// Resample a whole mono clip from `src_rate` to `dst_rate` using linear
+/// interpolation. Streams of buffers go through [`StreamResampler`] instead.
+pub fn resample(input: &[i16], src_rate: u32, dst_rate: u32) -> Vec<i16> {
+    if src_rate == dst_rate || src_rate == 0 || dst_rate == 0 || input.is_empty() {
+        return input.to_vec();
+    }
+
+    let out_len = (input.len() as u64 * dst_rate as u64 / src_rate as u64) as usize;
+    let step = src_rate as f64 / dst_rate as f64;
+
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            // Guard against rounding past the last sample
+            let idx = (pos as usize).min(input.len() - 1);
+            let frac = pos - idx as f64;
+            let a = input[idx] as f64;
+            let b = input.get(idx + 1).copied().unwrap_or(input[idx]) as f64;
+            (a + (b - a) * frac).round() as i16
+        })
+        .collect()
+}
+
+/// Q of each second-order section of a 6th-order Butterworth low-pass
+const BUTTERWORTH_Q: [f64; 3] = [0.5176, std::f64::consts::FRAC_1_SQRT_2, 1.9319];
+
+/// Anti-aliasing cutoff as a fraction of the output rate, a little below its
+/// Nyquist frequency so the filter has rolled off by then
+const ANTI_ALIAS_CUTOFF: f64 = 0.4;
+
+/// Second-order low-pass section (RBJ cookbook), filtering one sample at a time
+#[derive(Debug, Clone)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn low_pass(cutoff: f64, rate: f64, q: f64) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * cutoff / rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: (1.0 - cos) / 2.0 / a0,
+            b1: (1.0 - cos) / a0,
+            b2: (1.0 - cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Resamples a stream of mono buffers, e.g. capture callbacks, using linear
+/// interpolation. The position between input samples carries over from one
+/// buffer to the next, so buffer boundaries neither drop nor repeat samples.
+/// When downsampling, a low-pass filter first removes what the output rate
+/// can't represent, which would otherwise alias into the speech band.
+pub struct StreamResampler {
+    src_rate: u64,
+    dst_rate: u64,
+    /// Anti-aliasing filter sections, empty unless downsampling
+    filters: Vec<Biquad>,
+    /// Input samples consumed so far
+    consumed: u64,
+    /// Output samples produced so far
+    produced: u64,
+    /// Last filtered sample of the previous buffer
+    last: f64,
+}
+
+impl StreamResampler {
+    pub fn new(src_rate: u32, dst_rate: u32) -> Self {
+        let filters = if dst_rate > 0 && dst_rate < src_rate {
+            let cutoff = dst_rate as f64 * ANTI_ALIAS_CUTOFF;
+            BUTTERWORTH_Q
+                .iter()
+                .map(|&q| Biquad::low_pass(cutoff, src_rate as f64, q))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            src_rate: src_rate as u64,
+            dst_rate: dst_rate as u64,
+            filters,
+            consumed: 0,
+            produced: 0,
+            last: 0.0,
+        }
+    }
+
+    /// Resample the next buffer of the stream. The output lags by up to one
+    /// input sample, which is interpolated once the next buffer arrives.
+    pub fn process(&mut self, input: &[i16]) -> Vec<i16> {
+        if self.src_rate == self.dst_rate || self.src_rate == 0 || self.dst_rate == 0 {
+            return input.to_vec();
+        }
+
+        let filters = &mut self.filters;
+        let filtered: Vec<f64> = input
+            .iter()
+            .map(|&s| filters.iter_mut().fold(s as f64, |x, f| f.process(x)))
+            .collect();
+
+        // Positions are exact fractions of the stream so far, so rounding
+        // can't drift however the stream is split into buffers
+        let start = self.consumed;
+        let end = start + filtered.len() as u64;
+        let last = self.last;
+        let sample = |i: u64| match i.checked_sub(start) {
+            Some(offset) => filtered[offset as usize],
+            None => last,
+        };
+
+        let mut output = Vec::with_capacity(
+            (filtered.len() as u64 * self.dst_rate / self.src_rate) as usize + 1,
+        );
+        loop {
+            let pos = self.produced * self.src_rate;
+            let idx = pos / self.dst_rate;
+            // Interpolating needs the following sample, which may not have arrived
+            if idx + 1 >= end {
+                break;
+            }
+            let frac = (pos % self.dst_rate) as f64 / self.dst_rate as f64;
+            let (a, b) = (sample(idx), sample(idx + 1));
+            output.push((a + (b - a) * frac).round() as i16);
+            self.produced += 1;
+        }
+
+        if let Some(&sample) = filtered.last() {
+            self.last = sample;
+        }
+        self.consumed = end;
+        output
+    }
+}
+
+/// Writes captured 16kHz mono samples to a WAV file as they arrive.
+/// Safe to share between the stream callback and the audio thread.
+pub struct WavRecorder {
+    writer: Mutex<Option<hound::WavWriter<BufWriter<File>>>>,
+}
+
+impl WavRecorder {
+    pub fn create(path: &Path) -> Result<Self, AudioError> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: TARGET_SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let writer = hound::WavWriter::create(path, spec)
+            .map_err(|e| AudioError::WavError(e.to_string()))?;
+
+        Ok(Self {
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    pub fn write_samples(&self, samples: &[i16]) -> Result<(), AudioError> {
+        if let Some(writer) = self.writer.lock().as_mut() {
+            for &sample in samples {
+                writer
+                    .write_sample(sample)
+                    .map_err(|e| AudioError::WavError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the header and close the file. Further writes are ignored.
+    pub fn finalize(&self) -> Result<(), AudioError> {
+        if let Some(writer) = self.writer.lock().take() {
+            writer
+                .finalize()
+                .map_err(|e| AudioError::WavError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a float sample in -1.0..=1.0 to i16
+pub fn f32_to_i16(sample: f32) -> i16 {
+    (sample * i16::MAX as f32) as i16
+}
+
+/// Convert an i32 sample to i16 by keeping the most significant bits
+pub fn i32_to_i16(sample: i32) -> i16 {
+    (sample >> 16) as i16
+}
+
+/// Convert an unsigned (offset binary) u16 sample to signed i16
+pub fn u16_to_i16(sample: u16) -> i16 {
+    (sample as i32 - 0x8000) as i16
+}
+
+/// Average interleaved multi-channel float samples down to a single channel
+pub fn downmix_to_mono(interleaved: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return interleaved.to_vec();
+    }
+
+    interleaved
+        .chunks_exact(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Average interleaved multi-channel i16 samples down to a single channel
+pub fn downmix_i16_to_mono(interleaved: &[i16], channels: u16) -> Vec<i16> {
+    if channels <= 1 {
+        return interleaved.to_vec();
+    }
+
+    interleaved
+        .chunks_exact(channels as usize)
+        .map(|frame| {
+            let sum: i32 = frame.iter().map(|&s| s as i32).sum();
+            (sum / channels as i32) as i16
+        })
+        .collect()
+}
+
+/// Minimum interval between `audio-level` events (~20Hz)
+pub const LEVEL_EMIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Input level of an audio buffer, normalized to 0.0–1.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct AudioLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// Compute RMS and peak level of a buffer
+pub fn compute_level(samples: &[i16]) -> AudioLevel {
+    if samples.is_empty() {
+        return AudioLevel::default();
+    }
+
+    let full_scale = -(i16::MIN as f32);
+    let sum_squares: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    let rms = (sum_squares / samples.len() as f64).sqrt() as f32 / full_scale;
+    let peak = samples.iter().map(|&s| (s as i32).abs()).max().unwrap_or(0) as f32 / full_scale;
+
+    AudioLevel {
+        rms: rms.min(1.0),
+        peak: peak.min(1.0),
+    }
+}
+
+/// Tracks the most recent input level and throttles level updates
+pub struct LevelMeter {
+    level: Mutex<AudioLevel>,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl LevelMeter {
+    pub fn new() -> Self {
+        Self {
+            level: Mutex::new(AudioLevel::default()),
+            last_emit: Mutex::new(None),
+        }
+    }
+
+    /// Record a buffer's level. Returns it if enough time has passed since the last emit.
+    pub fn update(&self, samples: &[i16]) -> Option<AudioLevel> {
+        let level = compute_level(samples);
+        *self.level.lock() = level;
+
+        let now = Instant::now();
+        let mut last_emit = self.last_emit.lock();
+        match *last_emit {
+            Some(last) if now.duration_since(last) < LEVEL_EMIT_INTERVAL => None,
+            _ => {
+                *last_emit = Some(now);
+                Some(level)
+            }
+        }
+    }
+
+    pub fn level(&self) -> AudioLevel {
+        *self.level.lock()
+    }
+
+    pub fn reset(&self) {
+        *self.level.lock() = AudioLevel::default();
+        *self.last_emit.lock() = None;
+    }
+}
+
+impl Default for LevelMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Points in each `audio-waveform` frame
+pub const WAVEFORM_POINTS: usize = 128;
+
+/// Recent audio drawn in a waveform frame (128ms at 16kHz)
+const WAVEFORM_WINDOW_SAMPLES: usize = 2048;
+
+/// Minimum interval between `audio-waveform` events (~20Hz)
+pub const WAVEFORM_EMIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reduce a buffer to at most `points` (min, max) pairs, one per equal slice,
+/// so a waveform keeps its peaks when drawn at low resolution. A buffer
+/// shorter than `points` gives one pair per sample.
+pub fn downsample_minmax(buf: &[i16], points: usize) -> Vec<(i16, i16)> {
+    if buf.len() <= points {
+        return buf.iter().map(|&s| (s, s)).collect();
+    }
+
+    (0..points)
+        .map(|i| {
+            let slice = &buf[i * buf.len() / points..(i + 1) * buf.len() / points];
+            let min = slice.iter().copied().min().unwrap_or(0);
+            let max = slice.iter().copied().max().unwrap_or(0);
+            (min, max)
+        })
+        .collect()
+}
+
+/// Keeps the most recent samples and throttles waveform updates
+pub struct WaveformMeter {
+    recent: Mutex<VecDeque<i16>>,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl WaveformMeter {
+    pub fn new() -> Self {
+        Self {
+            recent: Mutex::new(VecDeque::with_capacity(WAVEFORM_WINDOW_SAMPLES)),
+            last_emit: Mutex::new(None),
+        }
+    }
+
+    /// Add a buffer. Returns the downsampled recent audio if enough time has
+    /// passed since the last emit.
+    pub fn update(&self, samples: &[i16]) -> Option<Vec<(i16, i16)>> {
+        let mut recent = self.recent.lock();
+        let keep = samples.len().min(WAVEFORM_WINDOW_SAMPLES);
+        let excess = (recent.len() + keep).saturating_sub(WAVEFORM_WINDOW_SAMPLES);
+        recent.drain(..excess);
+        recent.extend(&samples[samples.len() - keep..]);
+
+        let now = Instant::now();
+        let mut last_emit = self.last_emit.lock();
+        match *last_emit {
+            Some(last) if now.duration_since(last) < WAVEFORM_EMIT_INTERVAL => None,
+            _ => {
+                *last_emit = Some(now);
+                Some(downsample_minmax(recent.make_contiguous(), WAVEFORM_POINTS))
+            }
+        }
+    }
+
+    pub fn reset(&self) {
+        self.recent.lock().clear();
+        *self.last_emit.lock() = None;
+    }
+}
+
+impl Default for WaveformMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Largest manual input gain accepted (+20 dB)
+pub const MAX_INPUT_GAIN: f32 = 10.0;
+
+/// RMS level the AGC normalizes towards (about -20 dBFS)
+pub const AGC_TARGET_RMS: f32 = 0.1;
+
+/// Upper bound on the AGC's boost, so near-silence isn't amplified into noise
+pub const AGC_MAX_GAIN: f32 = 20.0;
+
+/// Per-buffer decay of the AGC's tracked level, so it recovers after loud input
+const AGC_LEVEL_DECAY: f32 = 0.95;
+
+/// Scale samples by `gain`, saturating at the i16 range instead of wrapping
+pub fn apply_gain(samples: &mut [i16], gain: f32) {
+    if gain == 1.0 {
+        return;
+    }
+    for sample in samples.iter_mut() {
+        // `as` saturates float to int conversions
+        *sample = (*sample as f32 * gain).round() as i16;
+    }
+}
+
+/// Software input gain applied before recognition, with optional automatic
+/// gain control for microphones that record too quietly
+pub struct GainControl {
+    gain: Mutex<f32>,
+    agc_enabled: AtomicBool,
+    /// Decaying maximum of recent buffer RMS levels
+    agc_level: Mutex<f32>,
+}
+
+impl GainControl {
+    pub fn new() -> Self {
+        Self {
+            gain: Mutex::new(1.0),
+            agc_enabled: AtomicBool::new(false),
+            agc_level: Mutex::new(0.0),
+        }
+    }
+
+    /// Set the manual gain, clamped to 0.0..=MAX_INPUT_GAIN
+    pub fn set_gain(&self, gain: f32) {
+        let gain = if gain.is_finite() { gain } else { 1.0 };
+        *self.gain.lock() = gain.clamp(0.0, MAX_INPUT_GAIN);
+    }
+
+    pub fn gain(&self) -> f32 {
+        *self.gain.lock()
+    }
+
+    pub fn set_agc(&self, enabled: bool) {
+        self.agc_enabled.store(enabled, Ordering::SeqCst);
+        self.reset();
+    }
+
+    pub fn agc_enabled(&self) -> bool {
+        self.agc_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Forget the AGC's tracked level, e.g. when a new recording starts
+    pub fn reset(&self) {
+        *self.agc_level.lock() = 0.0;
+    }
+
+    /// Apply manual gain, then the AGC if enabled
+    pub fn process(&self, samples: &mut [i16]) {
+        // An empty buffer would decay the AGC's level as if it were silence
+        if samples.is_empty() {
+            return;
+        }
+        apply_gain(samples, self.gain());
+
+        if self.agc_enabled() {
+            let gain = self.agc_gain(compute_level(samples));
+            apply_gain(samples, gain);
+        }
+    }
+
+    /// Gain that moves the tracked level towards the target RMS without clipping this buffer
+    fn agc_gain(&self, level: AudioLevel) -> f32 {
+        let mut tracked = self.agc_level.lock();
+        *tracked = level.rms.max(*tracked * AGC_LEVEL_DECAY);
+
+        if *tracked <= 0.0 {
+            return 1.0;
+        }
+
+        let mut gain = (AGC_TARGET_RMS / *tracked).min(AGC_MAX_GAIN);
+        if level.peak > 0.0 {
+            gain = gain.min(1.0 / level.peak);
+        }
+        gain
+    }
+}
+
+impl Default for GainControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Window the noise gate measures RMS over (10ms at 16kHz)
+const GATE_WINDOW_SAMPLES: usize = 160;
+
+/// Time for the gate to open fully, kept short so speech onsets aren't clipped
+const GATE_ATTACK_MS: u32 = 1;
+
+/// Time to fade to silence once the release hold has run out
+const GATE_FADE_MS: u32 = 10;
+
+fn ms_to_samples(ms: u32) -> usize {
+    (TARGET_SAMPLE_RATE as usize * ms as usize / 1000).max(1)
+}
+
+/// Noise gate state carried between buffers
+#[derive(Debug, Clone, Copy, Default)]
+struct GateState {
+    /// Current gain applied to samples, ramps between 0.0 and 1.0
+    gain: f32,
+    /// Samples left before a quiet gate starts closing
+    hold_samples: usize,
+}
+
+fn gate_samples(buf: &[i16], threshold: f32, release_ms: u32, state: &mut GateState) -> Vec<i16> {
+    let attack_step = 1.0 / ms_to_samples(GATE_ATTACK_MS) as f32;
+    let fade_step = 1.0 / ms_to_samples(GATE_FADE_MS) as f32;
+    let release_samples = TARGET_SAMPLE_RATE as usize * release_ms as usize / 1000;
+
+    let mut output = Vec::with_capacity(buf.len());
+    for window in buf.chunks(GATE_WINDOW_SAMPLES) {
+        // Deciding per window lets the gate open at the start of the window
+        // containing an onset, rather than after it
+        let open = if compute_level(window).rms >= threshold {
+            state.hold_samples = release_samples;
+            true
+        } else if state.hold_samples > 0 {
+            state.hold_samples = state.hold_samples.saturating_sub(window.len());
+            true
+        } else {
+            false
+        };
+
+        for &sample in window {
+            state.gain = if open {
+                (state.gain + attack_step).min(1.0)
+            } else {
+                (state.gain - fade_step).max(0.0)
+            };
+            output.push((sample as f32 * state.gain).round() as i16);
+        }
+    }
+    output
+}
+
+/// Zero 16kHz samples whose RMS falls below `threshold` (0.0–1.0), keeping
+/// the gate open for `release_ms` after the signal drops so word endings survive
+pub fn apply_noise_gate(buf: &[i16], threshold: f32, release_ms: u32) -> Vec<i16> {
+    if threshold <= 0.0 {
+        return buf.to_vec();
+    }
+    gate_samples(buf, threshold, release_ms, &mut GateState::default())
+}
+
+/// Noise gate applied to the live audio stream, keeping its state between buffers
+pub struct NoiseGate {
+    /// (RMS threshold, release in ms); a threshold of 0 disables the gate
+    config: Mutex<(f32, u32)>,
+    state: Mutex<GateState>,
+}
+
+impl NoiseGate {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new((0.0, 0)),
+            state: Mutex::new(GateState::default()),
+        }
+    }
+
+    /// Set the RMS threshold (0.0 disables) and release time
+    pub fn set(&self, threshold: f32, release_ms: u32) {
+        let threshold = if threshold.is_finite() {
+            threshold
+        } else {
+            0.0
+        };
+        *self.config.lock() = (threshold.clamp(0.0, 1.0), release_ms);
+        self.reset();
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.config.lock().0
+    }
+
+    pub fn release_ms(&self) -> u32 {
+        self.config.lock().1
+    }
+
+    /// Close the gate, e.g. when a new recording starts
+    pub fn reset(&self) {
+        *self.state.lock() = GateState::default();
+    }
+
+    pub fn process(&self, samples: &mut Vec<i16>) {
+        let (threshold, release_ms) = *self.config.lock();
+        if threshold <= 0.0 {
+            return;
+        }
+        *samples = gate_samples(samples, threshold, release_ms, &mut self.state.lock());
+    }
+}
+
+impl Default for NoiseGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Default size of the chunks fed to the recognizer, 200ms at 16kHz
+pub const DEFAULT_CHUNK_FRAMES: usize = 3200;
+
+/// Regroups the variable-size buffers a device delivers into fixed-size chunks,
+/// which keeps recognizer CPU use and partial-result cadence even
+pub struct Chunker {
+    /// Chunk size in samples; 0 passes buffers through unchanged
+    frames: Mutex<usize>,
+    pending: Mutex<Vec<i16>>,
+}
+
+impl Chunker {
+    pub fn new(frames: usize) -> Self {
+        Self {
+            frames: Mutex::new(frames),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn set_frames(&self, frames: usize) {
+        *self.frames.lock() = frames;
+    }
+
+    pub fn frames(&self) -> usize {
+        *self.frames.lock()
+    }
+
+    /// Add a buffer, returning the chunks it completed
+    pub fn push(&self, samples: &[i16]) -> Vec<Vec<i16>> {
+        let frames = self.frames();
+        let mut pending = self.pending.lock();
+        pending.extend_from_slice(samples);
+
+        if frames == 0 {
+            if pending.is_empty() {
+                return Vec::new();
+            }
+            return vec![std::mem::take(&mut *pending)];
+        }
+
+        let complete = pending.len() / frames * frames;
+        let chunks = pending[..complete]
+            .chunks(frames)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        pending.drain(..complete);
+        chunks
+    }
+
+    /// Take the samples that don't fill a whole chunk, e.g. when recording stops
+    pub fn flush(&self) -> Vec<i16> {
+        std::mem::take(&mut *self.pending.lock())
+    }
+
+    /// Drop pending samples, e.g. when a new recording starts
+    pub fn reset(&self) {
+        self.pending.lock().clear();
+    }
+}
+
+impl Default for Chunker {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHUNK_FRAMES)
+    }
+}
+
+/// Whether output devices can be captured as loopback inputs on this platform.
+/// cpal opens a WASAPI output device in loopback mode when an input stream is
+/// built on it; other hosts have no equivalent and list no loopback devices.
+pub const LOOPBACK_SUPPORTED: bool = cfg!(target_os = "windows");
+
 /// Audio device information
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct AudioDeviceInfo {
     pub name: String,
     pub is_default: bool,
+    /// Output device captured as input, e.g. to transcribe system audio
+    pub is_loopback: bool,
+}
+
+/// A range of input configurations supported by a device
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DeviceConfig {
+    pub min_rate: u32,
+    pub max_rate: u32,
+    pub channels: u16,
+    /// Sample format, e.g. "i16" or "f32"
+    pub format: String,
+    /// Whether we can capture in this format
+    pub usable: bool,
+}
+
+/// Sample formats the capture stream can convert to 16kHz i16
+fn is_supported_format(format: SampleFormat) -> bool {
+    matches!(
+        format,
+        SampleFormat::I16 | SampleFormat::F32 | SampleFormat::I32 | SampleFormat::U16
+    )
+}
+
+impl From<&cpal::SupportedStreamConfigRange> for DeviceConfig {
+    fn from(range: &cpal::SupportedStreamConfigRange) -> Self {
+        Self {
+            min_rate: range.min_sample_rate().0,
+            max_rate: range.max_sample_rate().0,
+            channels: range.channels(),
+            format: range.sample_format().to_string(),
+            usable: is_supported_format(range.sample_format()),
+        }
+    }
+}
+
+/// Stream config at the device's native rate and channel count with the requested
+/// buffer size. A fixed size outside the device's advertised range uses the default.
+pub fn stream_config(
+    supported: &cpal::SupportedStreamConfig,
+    buffer_frames: Option<u32>,
+) -> StreamConfig {
+    let buffer_size = match (buffer_frames, supported.buffer_size()) {
+        (Some(frames), cpal::SupportedBufferSize::Range { min, max })
+            if !(*min..=*max).contains(&frames) =>
+        {
+            cpal::BufferSize::Default
+        }
+        (Some(frames), _) => cpal::BufferSize::Fixed(frames),
+        (None, _) => cpal::BufferSize::Default,
+    };
+
+    // Use the native channel count too; some interfaces only offer stereo capture
+    StreamConfig {
+        channels: supported.channels(),
+        sample_rate: supported.sample_rate(),
+        buffer_size,
+    }
+}
+
+/// How often the audio thread checks that the active device is still present
+pub const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a stopped stream stays open so an immediate restart can resume it.
+/// Jittery hotkeys often stop and start again within a few milliseconds.
+pub const RESTART_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Whether a start can resume a stream stopped at `stopped_at` instead of
+/// reopening the device: it must ask for the same device within `RESTART_DEBOUNCE`
+pub fn can_resume(
+    stopped_at: Instant,
+    stream_request: Option<&str>,
+    request: Option<&str>,
+    now: Instant,
+) -> bool {
+    stream_request == request && now.saturating_duration_since(stopped_at) < RESTART_DEBOUNCE
+}
+
+/// Recording flag and transition count packed into one atomic.
+/// Callers flip the flag when they issue Start/Stop; the audio thread may only
+/// clear it for the generation it is working on, so a failure of an old start
+/// never overrides a newer request.
+#[derive(Debug, Default)]
+pub(crate) struct RecordingState(AtomicU64);
+
+impl RecordingState {
+    pub(crate) fn is_recording(&self) -> bool {
+        self.0.load(Ordering::SeqCst) & 1 == 1
+    }
+
+    /// Flip the flag to `recording`, returning the new generation, or None if
+    /// it is already set that way
+    pub(crate) fn transition(&self, recording: bool) -> Option<u64> {
+        self.0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                ((current & 1 == 1) != recording)
+                    .then(|| ((current >> 1) + 1) << 1 | recording as u64)
+            })
+            .ok()
+            .map(|previous| (previous >> 1) + 1)
+    }
+
+    /// Clear the flag after the stream started at `generation` failed or was lost.
+    /// Ignored once a newer Start or Stop has been issued.
+    pub(crate) fn fail(&self, generation: u64) {
+        let _ = self.0.compare_exchange(
+            generation << 1 | 1,
+            generation << 1,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+    }
+}
+
+/// Sends Start/Stop to the audio thread in the order their transitions were made.
+/// The flag flips under the same lock the command is sent with, so the thread
+/// always receives strictly alternating Start and Stop commands.
+struct RecordingGate {
+    state: Arc<RecordingState>,
+    sender: Mutex<Option<Sender<AudioCommand>>>,
+}
+
+impl RecordingGate {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(RecordingState::default()),
+            sender: Mutex::new(None),
+        }
+    }
+
+    /// Send the Start built for the new generation, unless already recording.
+    /// `connect` creates the command channel on first use.
+    fn start(
+        &self,
+        connect: impl FnOnce() -> Sender<AudioCommand>,
+        command: impl FnOnce(u64) -> AudioCommand,
+    ) -> Result<(), AudioError> {
+        let mut sender_guard = self.sender.lock();
+        let sender = sender_guard.get_or_insert_with(connect);
+
+        let Some(generation) = self.state.transition(true) else {
+            return Ok(());
+        };
+
+        sender.send(command(generation)).map_err(|e| {
+            self.state.fail(generation);
+            AudioError::StreamError(e.to_string())
+        })
+    }
+
+    /// Send Stop if recording
+    fn stop(&self) {
+        let sender_guard = self.sender.lock();
+        if let Some(sender) = sender_guard.as_ref() {
+            if self.state.transition(false).is_some() {
+                let _ = sender.send(AudioCommand::Stop);
+            }
+        }
+    }
+}
+
+/// Device and format of a stream that is capturing audio
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct StreamInfo {
+    pub device: String,
+    /// Native capture rate, before resampling to 16kHz
+    pub sample_rate: u32,
+    /// Native channel count, before downmixing to mono
+    pub channels: u16,
+    /// Fixed buffer size in frames, or None for the device default
+    pub buffer_frames: Option<u32>,
+}
+
+/// Input device changes detected while recording
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEvent {
+    /// A stream started playing, after `start_recording` or a fallback switch
+    Started(StreamInfo),
+    /// The active device was unplugged or stopped delivering audio
+    Disconnected(String),
+    /// Recording moved to another device after a disconnect
+    Switched { from: String, to: String },
+    /// The device rejected the requested buffer size and uses its default instead
+    BufferSizeRejected { requested: u32 },
+    /// The audio thread failed to open a device, stream or WAV file
+    Error { kind: &'static str, message: String },
+}
+
+impl From<&AudioError> for DeviceEvent {
+    fn from(error: &AudioError) -> Self {
+        DeviceEvent::Error {
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Error callback for a running stream. A vanished device is flagged for the
+/// device poll to replace; any other failure is reported through `report`.
+fn stream_error_handler(
+    device_lost: Arc<AtomicBool>,
+    report: Arc<dyn Fn(DeviceEvent) + Send + Sync>,
+) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |err| match err {
+        cpal::StreamError::DeviceNotAvailable => device_lost.store(true, Ordering::SeqCst),
+        cpal::StreamError::BackendSpecific { err } => {
+            let error = AudioError::StreamFailed(err.description);
+            report(DeviceEvent::from(&error));
+        }
+    }
+}
+
+/// Result of checking the active device against the current device list
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceCheck {
+    Present,
+    /// The device is gone; `fallback` is the default device to switch to, if any
+    Lost {
+        fallback: Option<String>,
+    },
+}
+
+/// Decide whether the active device is still usable and where to fall back to.
+/// `stream_lost` is set when the stream reported the device as unavailable.
+pub fn check_active_device(
+    active: &str,
+    devices: &[AudioDeviceInfo],
+    stream_lost: bool,
+    allow_fallback: bool,
+) -> DeviceCheck {
+    if !stream_lost && devices.iter().any(|d| d.name == active) {
+        return DeviceCheck::Present;
+    }
+
+    let fallback = devices
+        .iter()
+        .find(|d| d.is_default && d.name != active)
+        .filter(|_| allow_fallback)
+        .map(|d| d.name.clone());
+
+    DeviceCheck::Lost { fallback }
+}
+
+/// Whether the app may record from the microphone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Granted,
+    /// Refused by the user, or restricted by policy
+    Denied,
+    /// Not asked yet; the OS prompts on the first request
+    Undetermined,
+}
+
+/// Microphone access through AVFoundation. Without it macOS hands the app
+/// silent buffers rather than failing to open the stream.
+#[cfg(target_os = "macos")]
+mod macos_permission {
+    use super::PermissionStatus;
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+    use std::sync::mpsc;
+
+    pub fn status() -> PermissionStatus {
+        let Some(audio) = (unsafe { AVMediaTypeAudio }) else {
+            return PermissionStatus::Granted;
+        };
+        match unsafe { AVCaptureDevice::authorizationStatusForMediaType(audio) } {
+            AVAuthorizationStatus::Authorized => PermissionStatus::Granted,
+            AVAuthorizationStatus::NotDetermined => PermissionStatus::Undetermined,
+            _ => PermissionStatus::Denied,
+        }
+    }
+
+    /// Show the system prompt if the user hasn't answered it yet, waiting for the answer
+    pub fn request() -> PermissionStatus {
+        let Some(audio) = (unsafe { AVMediaTypeAudio }) else {
+            return PermissionStatus::Granted;
+        };
+        let (tx, rx) = mpsc::channel();
+        let handler: RcBlock<dyn Fn(Bool)> = RcBlock::new(move |granted: Bool| {
+            let _ = tx.send(granted.as_bool());
+        });
+        unsafe { AVCaptureDevice::requestAccessForMediaType_completionHandler(audio, &handler) };
+
+        match rx.recv() {
+            Ok(true) => PermissionStatus::Granted,
+            Ok(false) => PermissionStatus::Denied,
+            Err(_) => status(),
+        }
+    }
 }
 
 /// Trait for audio capture abstraction (enables testing)
 pub trait AudioCapture: Send + Sync {
     fn list_devices(&self) -> Result<Vec<AudioDeviceInfo>, AudioError>;
+    /// Input configurations the named device supports
+    fn list_device_configs(&self, device_name: &str) -> Result<Vec<DeviceConfig>, AudioError>;
     fn start_recording(
         &self,
         device_name: Option<&str>,
@@ -44,6 +1060,29 @@ pub trait AudioCapture: Send + Sync {
     ) -> Result<(), AudioError>;
     fn stop_recording(&self);
     fn is_recording(&self) -> bool;
+    /// Set a WAV file to record into; takes effect on the next `start_recording`
+    fn set_wav_output(&self, path: Option<PathBuf>);
+    /// Switch to the default input device when the active one disappears
+    fn set_device_fallback(&self, enabled: bool);
+    /// Request a fixed stream buffer size in frames (None uses the device default).
+    /// Smaller buffers make partial results arrive sooner at the cost of more
+    /// wakeups and CPU; larger buffers are cheaper but add latency.
+    /// Takes effect on the next `start_recording`.
+    fn set_buffer_frames(&self, frames: Option<u32>);
+    /// Set callback for stream starts, device disconnects and switches, and
+    /// failures on the capture thread
+    fn set_device_event_callback(&self, callback: Arc<dyn Fn(DeviceEvent) + Send + Sync>);
+    /// Device currently being recorded from, once its stream is playing
+    fn get_active_device(&self) -> Option<AudioDeviceInfo>;
+    /// Audio backends available on this platform (e.g. "ALSA" and "JACK" on Linux)
+    fn list_hosts(&self) -> Vec<String>;
+    /// Capture through the named host (None is the platform default), which
+    /// also changes the devices listed. Takes effect on the next `start_recording`.
+    fn set_host(&self, name: Option<&str>) -> Result<(), AudioError>;
+    /// Whether the OS lets the app record (always granted outside macOS)
+    fn microphone_permission(&self) -> PermissionStatus;
+    /// Ask the user for microphone access if they haven't been asked, returning the answer
+    fn request_microphone_permission(&self) -> PermissionStatus;
 }
 
 /// Command sent to the audio capture thread
@@ -51,6 +1090,9 @@ enum AudioCommand {
     Start {
         device_name: Option<String>,
         callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
+        wav_path: Option<PathBuf>,
+        /// Recording generation this start belongs to
+        generation: u64,
     },
     Stop,
 }
@@ -58,132 +1100,505 @@ enum AudioCommand {
 /// Real audio capture implementation using cpal.
 /// Uses a dedicated thread to manage the stream since cpal::Stream is not Send+Sync.
 pub struct CpalAudioCapture {
-    command_sender: Mutex<Option<Sender<AudioCommand>>>,
-    is_recording: Arc<AtomicBool>,
+    gate: RecordingGate,
     thread_handle: Mutex<Option<JoinHandle<()>>>,
+    wav_output: Mutex<Option<PathBuf>>,
+    device_fallback: Arc<AtomicBool>,
+    device_callback: Arc<Mutex<Option<Arc<dyn Fn(DeviceEvent) + Send + Sync>>>>,
+    active_device: Arc<Mutex<Option<AudioDeviceInfo>>>,
+    buffer_frames: Arc<Mutex<Option<u32>>>,
+    /// Host chosen with `set_host`; None is the platform default
+    host_id: Arc<Mutex<Option<HostId>>>,
 }
 
 impl CpalAudioCapture {
     pub fn new() -> Self {
         Self {
-            command_sender: Mutex::new(None),
-            is_recording: Arc::new(AtomicBool::new(false)),
+            gate: RecordingGate::new(),
             thread_handle: Mutex::new(None),
+            wav_output: Mutex::new(None),
+            device_fallback: Arc::new(AtomicBool::new(true)),
+            device_callback: Arc::new(Mutex::new(None)),
+            active_device: Arc::new(Mutex::new(None)),
+            buffer_frames: Arc::new(Mutex::new(None)),
+            host_id: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Open the chosen host, falling back to the default if it has gone away
+    fn open_host(id: Option<HostId>) -> Host {
+        id.and_then(|id| cpal::host_from_id(id).ok())
+            .unwrap_or_else(cpal::default_host)
+    }
+
+    fn host(&self) -> Host {
+        Self::open_host(*self.host_id.lock())
+    }
+
+    fn enumerate_devices(host: &Host) -> Result<Vec<AudioDeviceInfo>, AudioError> {
+        let default_name = host.default_input_device().and_then(|d| d.name().ok());
+
+        let mut devices: Vec<AudioDeviceInfo> = host
+            .input_devices()
+            .map_err(|e| AudioError::ConfigError(e.to_string()))?
+            .filter_map(|device| {
+                device.name().ok().map(|name| AudioDeviceInfo {
+                    is_default: default_name.as_ref() == Some(&name),
+                    name,
+                    is_loopback: false,
+                })
+            })
+            .collect();
+
+        if LOOPBACK_SUPPORTED {
+            let loopback: Vec<AudioDeviceInfo> = host
+                .output_devices()
+                .map_err(|e| AudioError::ConfigError(e.to_string()))?
+                .filter_map(|device| device.name().ok())
+                // A name shared with an input would be ambiguous when selected
+                .filter(|name| !devices.iter().any(|d| &d.name == name))
+                .map(|name| AudioDeviceInfo {
+                    name,
+                    is_default: false,
+                    is_loopback: true,
+                })
+                .collect();
+            devices.extend(loopback);
+        }
+
+        Ok(devices)
+    }
+
+    fn is_input_device(host: &Host, name: &str) -> bool {
+        host.input_devices()
+            .map(|mut devices| devices.any(|d| d.name().map(|n| n == name).unwrap_or(false)))
+            .unwrap_or(false)
+    }
+
     fn get_device(host: &Host, device_name: Option<&str>) -> Result<Device, AudioError> {
         match device_name {
-            Some(name) => host
-                .input_devices()
-                .map_err(|e| AudioError::ConfigError(e.to_string()))?
-                .find(|d| d.name().map(|n| n == name).unwrap_or(false))
-                .ok_or_else(|| AudioError::DeviceNotFound(name.to_string())),
+            Some(name) => {
+                let input = host
+                    .input_devices()
+                    .map_err(|e| AudioError::ConfigError(e.to_string()))?
+                    .find(|d| d.name().map(|n| n == name).unwrap_or(false));
+
+                match input {
+                    Some(device) => Ok(device),
+                    None if LOOPBACK_SUPPORTED => host
+                        .output_devices()
+                        .map_err(|e| AudioError::ConfigError(e.to_string()))?
+                        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+                        .ok_or_else(|| AudioError::DeviceNotFound(name.to_string())),
+                    None => Err(AudioError::DeviceNotFound(name.to_string())),
+                }
+            }
             None => host
                 .default_input_device()
                 .ok_or(AudioError::NoInputDevice),
         }
     }
 
-    fn create_config(_device: &Device) -> Result<StreamConfig, AudioError> {
-        // Vosk requires 16kHz mono
-        Ok(StreamConfig {
-            channels: 1,
-            sample_rate: cpal::SampleRate(16000),
-            buffer_size: cpal::BufferSize::Default,
-        })
-    }
+    fn create_config(
+        device: &Device,
+        buffer_frames: Option<u32>,
+    ) -> Result<(StreamConfig, SampleFormat), AudioError> {
+        // Open at the device's native rate; buffers are downmixed and resampled to 16kHz for Vosk
+        let default_config = match device.default_input_config() {
+            Ok(config) => config,
+            // Loopback devices only describe their output side
+            Err(_) if LOOPBACK_SUPPORTED => device
+                .default_output_config()
+                .map_err(|e| AudioError::ConfigError(e.to_string()))?,
+            Err(e) => return Err(AudioError::ConfigError(e.to_string())),
+        };
+
+        Ok((
+            stream_config(&default_config, buffer_frames),
+            default_config.sample_format(),
+        ))
+    }
+
+    /// Build and start an input stream that delivers 16kHz buffers to `callback`.
+    /// `device_lost` is set if the stream reports the device as unavailable, and
+    /// other stream failures go to `report`.
+    /// A fixed `buffer_frames` the device rejects falls back to its default size,
+    /// reported as `None` in the returned `StreamInfo`.
+    fn open_stream(
+        device: &Device,
+        callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
+        device_lost: Arc<AtomicBool>,
+        report: Arc<dyn Fn(DeviceEvent) + Send + Sync>,
+        buffer_frames: Option<u32>,
+    ) -> Result<(cpal::Stream, StreamInfo), AudioError> {
+        let (mut config, sample_format) = Self::create_config(device, buffer_frames)?;
+
+        let build = |config: &StreamConfig| {
+            let on_error = stream_error_handler(device_lost.clone(), report.clone());
+            Self::build_stream(device, config, sample_format, callback.clone(), on_error)
+        };
+        // The caller reports the fallback as `BufferSizeRejected`
+        let stream = match build(&config) {
+            Err(AudioError::StreamError(_)) if config.buffer_size != cpal::BufferSize::Default => {
+                config.buffer_size = cpal::BufferSize::Default;
+                build(&config)
+            }
+            result => result,
+        }?;
+
+        stream
+            .play()
+            .map_err(|e| AudioError::PlayError(e.to_string()))?;
+
+        let info = StreamInfo {
+            device: device.name().unwrap_or_default(),
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+            buffer_frames: match config.buffer_size {
+                cpal::BufferSize::Fixed(frames) => Some(frames),
+                cpal::BufferSize::Default => None,
+            },
+        };
+        Ok((stream, info))
+    }
+
+    fn build_stream(
+        device: &Device,
+        config: &StreamConfig,
+        sample_format: SampleFormat,
+        callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
+        err_fn: impl FnMut(cpal::StreamError) + Send + 'static,
+    ) -> Result<cpal::Stream, AudioError> {
+        let src_rate = config.sample_rate.0;
+        let channels = config.channels;
+        let mut resampler = StreamResampler::new(src_rate, TARGET_SAMPLE_RATE);
+
+        match sample_format {
+            SampleFormat::I16 => device.build_input_stream(
+                config,
+                move |data: &[i16], _| {
+                    let mono = downmix_i16_to_mono(data, channels);
+                    callback(resampler.process(&mono));
+                },
+                err_fn,
+                None,
+            ),
+            SampleFormat::F32 => device.build_input_stream(
+                config,
+                move |data: &[f32], _| {
+                    let samples: Vec<i16> = downmix_to_mono(data, channels)
+                        .into_iter()
+                        .map(f32_to_i16)
+                        .collect();
+                    callback(resampler.process(&samples));
+                },
+                err_fn,
+                None,
+            ),
+            SampleFormat::I32 => device.build_input_stream(
+                config,
+                move |data: &[i32], _| {
+                    let samples: Vec<i16> = data.iter().map(|&s| i32_to_i16(s)).collect();
+                    let mono = downmix_i16_to_mono(&samples, channels);
+                    callback(resampler.process(&mono));
+                },
+                err_fn,
+                None,
+            ),
+            SampleFormat::U16 => device.build_input_stream(
+                config,
+                move |data: &[u16], _| {
+                    let samples: Vec<i16> = data.iter().map(|&s| u16_to_i16(s)).collect();
+                    let mono = downmix_i16_to_mono(&samples, channels);
+                    callback(resampler.process(&mono));
+                },
+                err_fn,
+                None,
+            ),
+            _ => {
+                return Err(AudioError::ConfigError(format!(
+                    "Unsupported sample format: {:?}",
+                    sample_format
+                )))
+            }
+        }
+        .map_err(|e| AudioError::StreamError(e.to_string()))
+    }
+
+    fn start_audio_thread(&self) -> Sender<AudioCommand> {
+        let (tx, rx) = mpsc::channel::<AudioCommand>();
+        let state = self.gate.state.clone();
+        let device_fallback = self.device_fallback.clone();
+        let device_callback = self.device_callback.clone();
+        let active_device = self.active_device.clone();
+        let buffer_frames = self.buffer_frames.clone();
+        let host_id = self.host_id.clone();
+
+        let handle = thread::spawn(move || {
+            // Replaced when a start finds a different host chosen
+            let mut host_choice = *host_id.lock();
+            let host = RefCell::new(Self::open_host(host_choice));
+            let mut _current_stream: Option<cpal::Stream> = None;
+            let mut current_recorder: Option<Arc<WavRecorder>> = None;
+            // Name and recording generation of the active device, kept to reopen on fallback
+            let mut active: Option<(String, u64)> = None;
+            // Requested device and format of the open stream, kept to resume it
+            let mut opened: Option<(Option<String>, StreamInfo)> = None;
+            // When the open stream was stopped; it closes once RESTART_DEBOUNCE passes
+            let mut stopped_at: Option<Instant> = None;
+            let device_lost = Arc::new(AtomicBool::new(false));
+
+            // Streams deliver through `sink`, so a resumed stream can switch callbacks
+            let sink: Arc<Mutex<Option<Arc<dyn Fn(Vec<i16>) + Send + Sync>>>> =
+                Arc::new(Mutex::new(None));
+            let forward: Arc<dyn Fn(Vec<i16>) + Send + Sync> = {
+                let sink = sink.clone();
+                Arc::new(move |samples: Vec<i16>| {
+                    // Some drivers deliver empty buffers at stream start
+                    if samples.is_empty() {
+                        return;
+                    }
+                    let callback = sink.lock().clone();
+                    if let Some(cb) = callback {
+                        cb(samples);
+                    }
+                })
+            };
+
+            let emit_device_event: Arc<dyn Fn(DeviceEvent) + Send + Sync> =
+                Arc::new(move |event| {
+                    let callback = device_callback.lock().clone();
+                    if let Some(cb) = callback {
+                        cb(event);
+                    }
+                });
+
+            let finish_recording = |recorder: &mut Option<Arc<WavRecorder>>| {
+                if let Some(r) = recorder.take() {
+                    if let Err(e) = r.finalize() {
+                        emit_device_event(DeviceEvent::from(&e));
+                    }
+                }
+            };
+
+            let record_to_wav = |callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
+                                 wav_path: Option<PathBuf>,
+                                 recorder: &mut Option<Arc<WavRecorder>>|
+             -> Arc<dyn Fn(Vec<i16>) + Send + Sync> {
+                let Some(path) = wav_path else {
+                    return callback;
+                };
+
+                match WavRecorder::create(&path) {
+                    Ok(created) => {
+                        let created = Arc::new(created);
+                        *recorder = Some(created.clone());
+                        let report = emit_device_event.clone();
+                        // A full disk fails every buffer; report it once
+                        let write_failed = AtomicBool::new(false);
+                        Arc::new(move |samples: Vec<i16>| {
+                            if let Err(e) = created.write_samples(&samples) {
+                                if !write_failed.swap(true, Ordering::SeqCst) {
+                                    report(DeviceEvent::from(&e));
+                                }
+                            }
+                            callback(samples);
+                        })
+                    }
+                    Err(e) => {
+                        emit_device_event(DeviceEvent::from(&e));
+                        callback
+                    }
+                }
+            };
+
+            // Publish the device a stream is now playing on
+            let stream_started = |info: StreamInfo, requested_frames: Option<u32>| {
+                if let (Some(requested), None) = (requested_frames, info.buffer_frames) {
+                    emit_device_event(DeviceEvent::BufferSizeRejected { requested });
+                }
 
-    fn start_audio_thread(&self) -> Sender<AudioCommand> {
-        let (tx, rx) = mpsc::channel::<AudioCommand>();
-        let is_recording = self.is_recording.clone();
+                let host = host.borrow();
+                let is_default = host
+                    .default_input_device()
+                    .and_then(|d| d.name().ok())
+                    .is_some_and(|name| name == info.device);
+                let is_loopback = LOOPBACK_SUPPORTED && !Self::is_input_device(&host, &info.device);
+                *active_device.lock() = Some(AudioDeviceInfo {
+                    name: info.device.clone(),
+                    is_default,
+                    is_loopback,
+                });
+                emit_device_event(DeviceEvent::Started(info));
+            };
 
-        let handle = thread::spawn(move || {
-            let host = cpal::default_host();
-            let mut _current_stream: Option<cpal::Stream> = None;
+            loop {
+                let timeout = if stopped_at.is_some() {
+                    RESTART_DEBOUNCE
+                } else {
+                    DEVICE_POLL_INTERVAL
+                };
 
-            while let Ok(cmd) = rx.recv() {
-                match cmd {
-                    AudioCommand::Start { device_name, callback } => {
-                        // Stop any existing stream
-                        _current_stream = None;
+                let cmd = match rx.recv_timeout(timeout) {
+                    Ok(cmd) => cmd,
+                    Err(RecvTimeoutError::Timeout) => {
+                        if stopped_at.is_some_and(|at| at.elapsed() >= RESTART_DEBOUNCE) {
+                            _current_stream = None;
+                            opened = None;
+                            stopped_at = None;
+                        }
 
-                        let device = match Self::get_device(&host, device_name.as_deref()) {
-                            Ok(d) => d,
-                            Err(e) => {
-                                eprintln!("Failed to get audio device: {}", e);
-                                continue;
-                            }
+                        let Some((name, generation)) = active.clone() else {
+                            continue;
                         };
 
-                        let config = match Self::create_config(&device) {
-                            Ok(c) => c,
-                            Err(e) => {
-                                eprintln!("Failed to create config: {}", e);
-                                continue;
-                            }
+                        let devices = Self::enumerate_devices(&host.borrow()).unwrap_or_default();
+                        let check = check_active_device(
+                            &name,
+                            &devices,
+                            device_lost.swap(false, Ordering::SeqCst),
+                            device_fallback.load(Ordering::SeqCst),
+                        );
+
+                        let DeviceCheck::Lost { fallback } = check else {
+                            continue;
                         };
 
-                        let sample_format = match device.default_input_config() {
-                            Ok(c) => c.sample_format(),
-                            Err(e) => {
-                                eprintln!("Failed to get input config: {}", e);
-                                continue;
+                        eprintln!("Audio device disconnected: {}", name);
+                        _current_stream = None;
+                        opened = None;
+                        active = None;
+                        *active_device.lock() = None;
+                        emit_device_event(DeviceEvent::Disconnected(name.clone()));
+
+                        let requested_frames = *buffer_frames.lock();
+                        let switched = fallback.and_then(|to| {
+                            let device = Self::get_device(&host.borrow(), Some(&to)).ok()?;
+                            match Self::open_stream(
+                                &device,
+                                forward.clone(),
+                                device_lost.clone(),
+                                emit_device_event.clone(),
+                                requested_frames,
+                            ) {
+                                Ok(opened) => Some((to, opened)),
+                                Err(e) => {
+                                    emit_device_event(DeviceEvent::from(&e));
+                                    None
+                                }
                             }
-                        };
+                        });
 
-                        let err_fn = |err| eprintln!("Audio stream error: {}", err);
-
-                        let stream = match sample_format {
-                            SampleFormat::I16 => {
-                                let cb = callback.clone();
-                                device.build_input_stream(
-                                    &config,
-                                    move |data: &[i16], _| {
-                                        cb(data.to_vec());
-                                    },
-                                    err_fn,
-                                    None,
-                                )
+                        match switched {
+                            Some((to, (stream, info))) => {
+                                _current_stream = Some(stream);
+                                active = Some((to.clone(), generation));
+                                emit_device_event(DeviceEvent::Switched { from: name, to });
+                                stream_started(info, requested_frames);
                             }
-                            SampleFormat::F32 => {
-                                let cb = callback.clone();
-                                device.build_input_stream(
-                                    &config,
-                                    move |data: &[f32], _| {
-                                        let samples: Vec<i16> = data
-                                            .iter()
-                                            .map(|&s| (s * i16::MAX as f32) as i16)
-                                            .collect();
-                                        cb(samples);
-                                    },
-                                    err_fn,
-                                    None,
-                                )
+                            None => {
+                                *sink.lock() = None;
+                                finish_recording(&mut current_recorder);
+                                state.fail(generation);
                             }
-                            _ => {
-                                eprintln!("Unsupported sample format: {:?}", sample_format);
+                        }
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                match cmd {
+                    AudioCommand::Start {
+                        device_name,
+                        callback,
+                        wav_path,
+                        generation,
+                    } => {
+                        let chosen = *host_id.lock();
+                        if chosen != host_choice {
+                            // Streams from the old host can't be resumed
+                            _current_stream = None;
+                            opened = None;
+                            stopped_at = None;
+                            host_choice = chosen;
+                            *host.borrow_mut() = Self::open_host(chosen);
+                        }
+
+                        // A start right after a stop keeps the stream that is still open
+                        let resumable = stopped_at
+                            .take()
+                            .zip(opened.as_ref())
+                            .filter(|(at, (request, _))| {
+                                can_resume(
+                                    *at,
+                                    request.as_deref(),
+                                    device_name.as_deref(),
+                                    Instant::now(),
+                                )
+                            })
+                            .map(|(_, (_, info))| info.clone());
+
+                        if let Some(info) = resumable {
+                            *sink.lock() =
+                                Some(record_to_wav(callback, wav_path, &mut current_recorder));
+                            active = Some((info.device.clone(), generation));
+                            stream_started(info, None);
+                            continue;
+                        }
+
+                        // Stop any existing stream
+                        _current_stream = None;
+                        opened = None;
+                        active = None;
+                        *active_device.lock() = None;
+                        *sink.lock() = None;
+                        finish_recording(&mut current_recorder);
+                        device_lost.store(false, Ordering::SeqCst);
+
+                        let device = Self::get_device(&host.borrow(), device_name.as_deref());
+                        let device = match device {
+                            Ok(d) => d,
+                            Err(e) => {
+                                emit_device_event(DeviceEvent::from(&e));
+                                state.fail(generation);
                                 continue;
                             }
                         };
 
-                        match stream {
-                            Ok(s) => {
-                                if let Err(e) = s.play() {
-                                    eprintln!("Failed to start stream: {}", e);
-                                    continue;
-                                }
+                        *sink.lock() =
+                            Some(record_to_wav(callback, wav_path, &mut current_recorder));
+
+                        let requested_frames = *buffer_frames.lock();
+                        match Self::open_stream(
+                            &device,
+                            forward.clone(),
+                            device_lost.clone(),
+                            emit_device_event.clone(),
+                            requested_frames,
+                        ) {
+                            Ok((s, info)) => {
                                 _current_stream = Some(s);
-                                is_recording.store(true, Ordering::SeqCst);
+                                active = device.name().ok().map(|name| (name, generation));
+                                opened = Some((device_name, info.clone()));
+                                stream_started(info, requested_frames);
                             }
                             Err(e) => {
-                                eprintln!("Failed to build stream: {}", e);
+                                emit_device_event(DeviceEvent::from(&e));
+                                *sink.lock() = None;
+                                finish_recording(&mut current_recorder);
+                                state.fail(generation);
                             }
                         }
                     }
                     AudioCommand::Stop => {
-                        _current_stream = None;
-                        is_recording.store(false, Ordering::SeqCst);
+                        // Detach rather than close, so a quick restart can resume the stream
+                        *sink.lock() = None;
+                        active = None;
+                        *active_device.lock() = None;
+                        finish_recording(&mut current_recorder);
+                        if _current_stream.is_some() {
+                            stopped_at = Some(Instant::now());
+                        }
                     }
                 }
             }
@@ -202,23 +1617,29 @@ impl Default for CpalAudioCapture {
 
 impl AudioCapture for CpalAudioCapture {
     fn list_devices(&self) -> Result<Vec<AudioDeviceInfo>, AudioError> {
-        let host = cpal::default_host();
-        let default_name = host
-            .default_input_device()
-            .and_then(|d| d.name().ok());
+        Self::enumerate_devices(&self.host())
+    }
 
-        let devices = host
-            .input_devices()
+    fn list_device_configs(&self, device_name: &str) -> Result<Vec<DeviceConfig>, AudioError> {
+        let host = self.host();
+        let device = Self::get_device(&host, Some(device_name))?;
+
+        if LOOPBACK_SUPPORTED && !Self::is_input_device(&host, device_name) {
+            let configs = device
+                .supported_output_configs()
+                .map_err(|e| AudioError::ConfigError(e.to_string()))?
+                .map(|range| DeviceConfig::from(&range))
+                .collect();
+            return Ok(configs);
+        }
+
+        let configs = device
+            .supported_input_configs()
             .map_err(|e| AudioError::ConfigError(e.to_string()))?
-            .filter_map(|device| {
-                device.name().ok().map(|name| AudioDeviceInfo {
-                    is_default: default_name.as_ref() == Some(&name),
-                    name,
-                })
-            })
+            .map(|range| DeviceConfig::from(&range))
             .collect();
 
-        Ok(devices)
+        Ok(configs)
     }
 
     fn start_recording(
@@ -226,76 +1647,189 @@ impl AudioCapture for CpalAudioCapture {
         device_name: Option<&str>,
         callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
     ) -> Result<(), AudioError> {
-        if self.is_recording.load(Ordering::SeqCst) {
-            return Ok(());
-        }
-
-        // Ensure thread is running and get/create sender
-        let mut sender_guard = self.command_sender.lock();
-        let sender = match sender_guard.as_ref() {
-            Some(s) => s.clone(),
-            None => {
-                let s = self.start_audio_thread();
-                *sender_guard = Some(s.clone());
-                s
-            }
-        };
-
-        sender
-            .send(AudioCommand::Start {
+        // Spawns the audio thread on first use
+        self.gate.start(
+            || self.start_audio_thread(),
+            |generation| AudioCommand::Start {
                 device_name: device_name.map(|s| s.to_string()),
                 callback,
-            })
-            .map_err(|e| AudioError::StreamError(e.to_string()))?;
-
-        Ok(())
+                wav_path: self.wav_output.lock().clone(),
+                generation,
+            },
+        )
     }
 
     fn stop_recording(&self) {
-        if let Some(sender) = self.command_sender.lock().as_ref() {
-            let _ = sender.send(AudioCommand::Stop);
-        }
+        self.gate.stop();
     }
 
     fn is_recording(&self) -> bool {
-        self.is_recording.load(Ordering::SeqCst)
+        self.gate.state.is_recording()
+    }
+
+    fn set_wav_output(&self, path: Option<PathBuf>) {
+        *self.wav_output.lock() = path;
+    }
+
+    fn set_device_fallback(&self, enabled: bool) {
+        self.device_fallback.store(enabled, Ordering::SeqCst);
+    }
+
+    fn set_buffer_frames(&self, frames: Option<u32>) {
+        *self.buffer_frames.lock() = frames;
+    }
+
+    fn set_device_event_callback(&self, callback: Arc<dyn Fn(DeviceEvent) + Send + Sync>) {
+        *self.device_callback.lock() = Some(callback);
+    }
+
+    fn get_active_device(&self) -> Option<AudioDeviceInfo> {
+        self.active_device.lock().clone()
+    }
+
+    fn microphone_permission(&self) -> PermissionStatus {
+        #[cfg(target_os = "macos")]
+        return macos_permission::status();
+        #[cfg(not(target_os = "macos"))]
+        PermissionStatus::Granted
+    }
+
+    fn request_microphone_permission(&self) -> PermissionStatus {
+        #[cfg(target_os = "macos")]
+        return macos_permission::request();
+        #[cfg(not(target_os = "macos"))]
+        PermissionStatus::Granted
+    }
+
+    fn list_hosts(&self) -> Vec<String> {
+        cpal::available_hosts()
+            .into_iter()
+            .map(|id| id.name().to_string())
+            .collect()
+    }
+
+    fn set_host(&self, name: Option<&str>) -> Result<(), AudioError> {
+        let id = match name {
+            Some(name) => {
+                let id = cpal::available_hosts()
+                    .into_iter()
+                    .find(|id| id.name().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| AudioError::HostUnavailable(name.to_string()))?;
+                // Listed hosts can still fail to start, e.g. JACK without a running server
+                cpal::host_from_id(id).map_err(|e| AudioError::HostUnavailable(e.to_string()))?;
+                Some(id)
+            }
+            None => None,
+        };
+
+        *self.host_id.lock() = id;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
     /// Mock audio capture for testing
     pub struct MockAudioCapture {
-        devices: Vec<AudioDeviceInfo>,
+        devices: Mutex<Vec<AudioDeviceInfo>>,
         is_recording: AtomicBool,
         start_called: AtomicUsize,
         stop_called: AtomicUsize,
         should_fail: AtomicBool,
+        wav_output: Mutex<Option<PathBuf>>,
+        callback: Mutex<Option<Arc<dyn Fn(Vec<i16>) + Send + Sync>>>,
+        active_device: Mutex<Option<String>>,
+        device_fallback: AtomicBool,
+        device_callback: Mutex<Option<Arc<dyn Fn(DeviceEvent) + Send + Sync>>>,
+        device_configs: Mutex<Vec<DeviceConfig>>,
+        buffer_frames: Mutex<Option<u32>>,
+        samples_on_start: Mutex<Option<Vec<i16>>>,
+        /// Host names in listing order; the first is the default
+        hosts: Mutex<Vec<String>>,
+        host: Mutex<String>,
+        /// Devices of the hosts not currently selected
+        host_devices: Mutex<HashMap<String, Vec<AudioDeviceInfo>>>,
+        permission: Mutex<PermissionStatus>,
+        /// What the user answers when asked for microphone access
+        grant_on_request: AtomicBool,
     }
 
     impl MockAudioCapture {
         pub fn new() -> Self {
             Self {
-                devices: vec![
+                devices: Mutex::new(vec![
                     AudioDeviceInfo {
                         name: "Test Microphone".to_string(),
                         is_default: true,
+                        is_loopback: false,
                     },
                     AudioDeviceInfo {
                         name: "Secondary Mic".to_string(),
                         is_default: false,
+                        is_loopback: false,
                     },
-                ],
+                ]),
                 is_recording: AtomicBool::new(false),
                 start_called: AtomicUsize::new(0),
                 stop_called: AtomicUsize::new(0),
                 should_fail: AtomicBool::new(false),
+                wav_output: Mutex::new(None),
+                callback: Mutex::new(None),
+                active_device: Mutex::new(None),
+                device_fallback: AtomicBool::new(true),
+                device_callback: Mutex::new(None),
+                device_configs: Mutex::new(vec![
+                    DeviceConfig {
+                        min_rate: 8000,
+                        max_rate: 96000,
+                        channels: 1,
+                        format: "i16".to_string(),
+                        usable: true,
+                    },
+                    DeviceConfig {
+                        min_rate: 48000,
+                        max_rate: 48000,
+                        channels: 2,
+                        format: "f32".to_string(),
+                        usable: true,
+                    },
+                ]),
+                buffer_frames: Mutex::new(None),
+                samples_on_start: Mutex::new(None),
+                hosts: Mutex::new(vec![MOCK_HOST.to_string()]),
+                host: Mutex::new(MOCK_HOST.to_string()),
+                host_devices: Mutex::new(HashMap::new()),
+                permission: Mutex::new(PermissionStatus::Granted),
+                grant_on_request: AtomicBool::new(true),
             }
         }
 
+        /// Simulate the microphone permission, and whether a request grants it
+        pub fn set_permission(&self, status: PermissionStatus, grant_on_request: bool) {
+            *self.permission.lock() = status;
+            self.grant_on_request
+                .store(grant_on_request, Ordering::SeqCst);
+        }
+
+        /// Add a host that lists `devices` once selected
+        pub fn add_host(&self, name: &str, devices: Vec<AudioDeviceInfo>) {
+            self.hosts.lock().push(name.to_string());
+            self.host_devices.lock().insert(name.to_string(), devices);
+        }
+
+        pub fn host(&self) -> String {
+            self.host.lock().clone()
+        }
+
+        /// Configurations reported for every mock device
+        pub fn set_device_configs(&self, configs: Vec<DeviceConfig>) {
+            *self.device_configs.lock() = configs;
+        }
+
         pub fn set_should_fail(&self, fail: bool) {
             self.should_fail.store(fail, Ordering::SeqCst);
         }
@@ -307,6 +1841,114 @@ pub(crate) mod tests {
         pub fn stop_call_count(&self) -> usize {
             self.stop_called.load(Ordering::SeqCst)
         }
+
+        pub fn wav_output(&self) -> Option<PathBuf> {
+            self.wav_output.lock().clone()
+        }
+
+        /// Feed a synthetic buffer to the active recording callback
+        pub fn push_samples(&self, samples: Vec<i16>) {
+            let callback = self.callback.lock().clone();
+            if let Some(cb) = callback {
+                cb(samples);
+            }
+        }
+
+        pub fn active_device(&self) -> Option<String> {
+            self.active_device.lock().clone()
+        }
+
+        /// Deliver `samples` to the callback as soon as recording starts
+        pub fn set_samples_on_start(&self, samples: Option<Vec<i16>>) {
+            *self.samples_on_start.lock() = samples;
+        }
+
+        pub fn buffer_frames(&self) -> Option<u32> {
+            *self.buffer_frames.lock()
+        }
+
+        /// Report a stream start, rejecting buffers larger than the mock supports
+        fn emit_started(&self, emit: impl Fn(DeviceEvent), device: &str) {
+            let requested = self.buffer_frames();
+            let accepted = requested.filter(|&frames| frames <= MOCK_MAX_BUFFER_FRAMES);
+            if let (Some(requested), None) = (requested, accepted) {
+                emit(DeviceEvent::BufferSizeRejected { requested });
+            }
+
+            emit(DeviceEvent::Started(StreamInfo {
+                buffer_frames: accepted,
+                ..mock_stream_info(device)
+            }));
+        }
+
+        /// Deliver an event as if the capture thread raised it
+        pub fn emit_device_event(&self, event: DeviceEvent) {
+            let callback = self.device_callback.lock().clone();
+            if let Some(cb) = callback {
+                cb(event);
+            }
+        }
+
+        /// Report `error` through the same handler a live stream uses,
+        /// returning whether it flagged the device as lost
+        pub fn fail_stream(&self, error: cpal::StreamError) -> bool {
+            let callback = self.device_callback.lock().clone();
+            let device_lost = Arc::new(AtomicBool::new(false));
+            let mut on_error = stream_error_handler(
+                device_lost.clone(),
+                Arc::new(move |event| {
+                    if let Some(cb) = &callback {
+                        cb(event);
+                    }
+                }),
+            );
+            on_error(error);
+            device_lost.load(Ordering::SeqCst)
+        }
+
+        /// Simulate a hot-plug change, reacting like the audio thread's device poll
+        pub fn set_devices(&self, devices: Vec<AudioDeviceInfo>) {
+            *self.devices.lock() = devices.clone();
+
+            let Some(active) = self.active_device() else {
+                return;
+            };
+
+            let check = check_active_device(
+                &active,
+                &devices,
+                false,
+                self.device_fallback.load(Ordering::SeqCst),
+            );
+            let DeviceCheck::Lost { fallback } = check else {
+                return;
+            };
+
+            let device_callback = self.device_callback.lock().clone();
+            let emit = |event: DeviceEvent| {
+                if let Some(cb) = &device_callback {
+                    cb(event);
+                }
+            };
+
+            emit(DeviceEvent::Disconnected(active.clone()));
+
+            match fallback {
+                Some(to) => {
+                    *self.active_device.lock() = Some(to.clone());
+                    emit(DeviceEvent::Switched {
+                        from: active,
+                        to: to.clone(),
+                    });
+                    self.emit_started(emit, &to);
+                }
+                None => {
+                    *self.active_device.lock() = None;
+                    *self.callback.lock() = None;
+                    self.is_recording.store(false, Ordering::SeqCst);
+                }
+            }
+        }
     }
 
     impl AudioCapture for MockAudioCapture {
@@ -314,13 +1956,20 @@ pub(crate) mod tests {
             if self.should_fail.load(Ordering::SeqCst) {
                 return Err(AudioError::NoInputDevice);
             }
-            Ok(self.devices.clone())
+            Ok(self.devices.lock().clone())
+        }
+
+        fn list_device_configs(&self, device_name: &str) -> Result<Vec<DeviceConfig>, AudioError> {
+            if !self.devices.lock().iter().any(|d| d.name == device_name) {
+                return Err(AudioError::DeviceNotFound(device_name.to_string()));
+            }
+            Ok(self.device_configs.lock().clone())
         }
 
         fn start_recording(
             &self,
             device_name: Option<&str>,
-            _callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
+            callback: Arc<dyn Fn(Vec<i16>) + Send + Sync>,
         ) -> Result<(), AudioError> {
             self.start_called.fetch_add(1, Ordering::SeqCst);
 
@@ -328,24 +1977,404 @@ pub(crate) mod tests {
                 return Err(AudioError::NoInputDevice);
             }
 
-            if let Some(name) = device_name {
-                if !self.devices.iter().any(|d| d.name == name) {
-                    return Err(AudioError::DeviceNotFound(name.to_string()));
-                }
-            }
+            let devices = self.devices.lock().clone();
+            let device = match device_name {
+                Some(name) => devices
+                    .iter()
+                    .find(|d| d.name == name)
+                    .ok_or_else(|| AudioError::DeviceNotFound(name.to_string()))?,
+                None => devices
+                    .iter()
+                    .find(|d| d.is_default)
+                    .ok_or(AudioError::NoInputDevice)?,
+            };
 
+            *self.active_device.lock() = Some(device.name.clone());
+            *self.callback.lock() = Some(callback);
             self.is_recording.store(true, Ordering::SeqCst);
+
+            let device_callback = self.device_callback.lock().clone();
+            if let Some(cb) = device_callback {
+                self.emit_started(|event| cb(event), &device.name);
+            }
+
+            let samples = self.samples_on_start.lock().clone();
+            if let Some(samples) = samples {
+                self.push_samples(samples);
+            }
             Ok(())
         }
 
         fn stop_recording(&self) {
             self.stop_called.fetch_add(1, Ordering::SeqCst);
+            *self.callback.lock() = None;
+            *self.active_device.lock() = None;
             self.is_recording.store(false, Ordering::SeqCst);
         }
 
-        fn is_recording(&self) -> bool {
-            self.is_recording.load(Ordering::SeqCst)
-        }
+        fn is_recording(&self) -> bool {
+            self.is_recording.load(Ordering::SeqCst)
+        }
+
+        fn set_wav_output(&self, path: Option<PathBuf>) {
+            *self.wav_output.lock() = path;
+        }
+
+        fn set_device_fallback(&self, enabled: bool) {
+            self.device_fallback.store(enabled, Ordering::SeqCst);
+        }
+
+        fn set_buffer_frames(&self, frames: Option<u32>) {
+            *self.buffer_frames.lock() = frames;
+        }
+
+        fn set_device_event_callback(&self, callback: Arc<dyn Fn(DeviceEvent) + Send + Sync>) {
+            *self.device_callback.lock() = Some(callback);
+        }
+
+        fn get_active_device(&self) -> Option<AudioDeviceInfo> {
+            let active = self.active_device()?;
+            self.devices
+                .lock()
+                .iter()
+                .find(|d| d.name == active)
+                .cloned()
+        }
+
+        fn list_hosts(&self) -> Vec<String> {
+            self.hosts.lock().clone()
+        }
+
+        fn microphone_permission(&self) -> PermissionStatus {
+            *self.permission.lock()
+        }
+
+        fn request_microphone_permission(&self) -> PermissionStatus {
+            let mut permission = self.permission.lock();
+            if *permission == PermissionStatus::Undetermined {
+                *permission = if self.grant_on_request.load(Ordering::SeqCst) {
+                    PermissionStatus::Granted
+                } else {
+                    PermissionStatus::Denied
+                };
+            }
+            *permission
+        }
+
+        fn set_host(&self, name: Option<&str>) -> Result<(), AudioError> {
+            let hosts = self.hosts.lock().clone();
+            let name = match name {
+                Some(name) => hosts
+                    .iter()
+                    .find(|host| host.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| AudioError::HostUnavailable(name.to_string()))?,
+                None => &hosts[0],
+            };
+
+            let mut current = self.host.lock();
+            if *current == *name {
+                return Ok(());
+            }
+            let mut host_devices = self.host_devices.lock();
+            let devices = host_devices.remove(name).unwrap_or_default();
+            let previous = std::mem::replace(&mut *self.devices.lock(), devices);
+            host_devices.insert(current.clone(), previous);
+            *current = name.clone();
+            Ok(())
+        }
+    }
+
+    /// Name of the host every mock starts on
+    pub const MOCK_HOST: &str = "Mock";
+
+    /// Largest fixed buffer the mock devices accept
+    const MOCK_MAX_BUFFER_FRAMES: u32 = 4096;
+
+    /// Mock devices capture 48kHz stereo
+    fn mock_stream_info(device: &str) -> StreamInfo {
+        StreamInfo {
+            device: device.to_string(),
+            sample_rate: 48000,
+            channels: 2,
+            buffer_frames: None,
+        }
+    }
+
+    fn device(name: &str, is_default: bool) -> AudioDeviceInfo {
+        AudioDeviceInfo {
+            name: name.to_string(),
+            is_default,
+            is_loopback: false,
+        }
+    }
+
+    fn record_device_events(capture: &MockAudioCapture) -> Arc<Mutex<Vec<DeviceEvent>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        capture.set_device_event_callback(Arc::new(move |event| {
+            events_clone.lock().push(event);
+        }));
+        events
+    }
+
+    #[test]
+    fn test_check_active_device_present() {
+        let devices = vec![device("USB Mic", false), device("Built-in", true)];
+        assert_eq!(
+            check_active_device("USB Mic", &devices, false, true),
+            DeviceCheck::Present
+        );
+    }
+
+    #[test]
+    fn test_check_active_device_lost() {
+        let devices = vec![device("Built-in", true)];
+        assert_eq!(
+            check_active_device("USB Mic", &devices, false, true),
+            DeviceCheck::Lost {
+                fallback: Some("Built-in".to_string())
+            }
+        );
+        assert_eq!(
+            check_active_device("USB Mic", &devices, false, false),
+            DeviceCheck::Lost { fallback: None }
+        );
+    }
+
+    #[test]
+    fn test_check_active_device_stream_lost() {
+        // Still listed, but the stream reported it unavailable; don't fall back to itself
+        let devices = vec![device("USB Mic", true)];
+        assert_eq!(
+            check_active_device("USB Mic", &devices, true, true),
+            DeviceCheck::Lost { fallback: None }
+        );
+    }
+
+    #[test]
+    fn test_mock_device_unplugged_falls_back() {
+        let capture = MockAudioCapture::new();
+        let events = record_device_events(&capture);
+
+        capture
+            .start_recording(Some("Secondary Mic"), Arc::new(|_| {}))
+            .unwrap();
+        assert_eq!(capture.active_device().as_deref(), Some("Secondary Mic"));
+
+        capture.set_devices(vec![device("Test Microphone", true)]);
+
+        assert!(capture.is_recording());
+        assert_eq!(capture.active_device().as_deref(), Some("Test Microphone"));
+        assert_eq!(
+            *events.lock(),
+            vec![
+                DeviceEvent::Started(mock_stream_info("Secondary Mic")),
+                DeviceEvent::Disconnected("Secondary Mic".to_string()),
+                DeviceEvent::Switched {
+                    from: "Secondary Mic".to_string(),
+                    to: "Test Microphone".to_string(),
+                },
+                DeviceEvent::Started(mock_stream_info("Test Microphone")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mock_active_device_tracked_across_start_stop() {
+        let capture = MockAudioCapture::new();
+        let events = record_device_events(&capture);
+        assert_eq!(capture.get_active_device(), None);
+
+        // No device name picks the default
+        capture.start_recording(None, Arc::new(|_| {})).unwrap();
+        assert_eq!(
+            capture.get_active_device(),
+            Some(device("Test Microphone", true))
+        );
+
+        capture.stop_recording();
+        assert_eq!(capture.get_active_device(), None);
+
+        capture
+            .start_recording(Some("Secondary Mic"), Arc::new(|_| {}))
+            .unwrap();
+        assert_eq!(
+            capture.get_active_device(),
+            Some(device("Secondary Mic", false))
+        );
+
+        // A fallback switch moves the active device
+        capture.set_devices(vec![device("Test Microphone", true)]);
+        assert_eq!(
+            capture.get_active_device(),
+            Some(device("Test Microphone", true))
+        );
+
+        let started: Vec<String> = events
+            .lock()
+            .iter()
+            .filter_map(|e| match e {
+                DeviceEvent::Started(info) => Some(info.device.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            started,
+            vec!["Test Microphone", "Secondary Mic", "Test Microphone"]
+        );
+    }
+
+    #[test]
+    fn test_mock_device_unplugged_without_fallback_stops() {
+        let capture = MockAudioCapture::new();
+        let events = record_device_events(&capture);
+        capture.set_device_fallback(false);
+
+        capture
+            .start_recording(Some("Secondary Mic"), Arc::new(|_| {}))
+            .unwrap();
+        capture.set_devices(vec![device("Test Microphone", true)]);
+
+        assert!(!capture.is_recording());
+        assert_eq!(
+            *events.lock(),
+            vec![
+                DeviceEvent::Started(mock_stream_info("Secondary Mic")),
+                DeviceEvent::Disconnected("Secondary Mic".to_string()),
+            ]
+        );
+        assert_eq!(capture.get_active_device(), None);
+    }
+
+    #[test]
+    fn test_mock_unrelated_device_change_ignored() {
+        let capture = MockAudioCapture::new();
+        let events = record_device_events(&capture);
+
+        capture.start_recording(None, Arc::new(|_| {})).unwrap();
+        events.lock().clear();
+        capture.set_devices(vec![device("Test Microphone", true)]);
+
+        assert!(capture.is_recording());
+        assert!(events.lock().is_empty());
+    }
+
+    #[test]
+    fn test_device_config_from_cpal_range() {
+        let range = cpal::SupportedStreamConfigRange::new(
+            2,
+            cpal::SampleRate(44100),
+            cpal::SampleRate(48000),
+            cpal::SupportedBufferSize::Unknown,
+            SampleFormat::F32,
+        );
+
+        assert_eq!(
+            DeviceConfig::from(&range),
+            DeviceConfig {
+                min_rate: 44100,
+                max_rate: 48000,
+                channels: 2,
+                format: "f32".to_string(),
+                usable: true,
+            }
+        );
+
+        let range = cpal::SupportedStreamConfigRange::new(
+            1,
+            cpal::SampleRate(8000),
+            cpal::SampleRate(8000),
+            cpal::SupportedBufferSize::Unknown,
+            SampleFormat::U8,
+        );
+        let config = DeviceConfig::from(&range);
+        assert_eq!(config.format, "u8");
+        assert!(!config.usable);
+    }
+
+    #[test]
+    fn test_stream_config_buffer_size() {
+        let supported = cpal::SupportedStreamConfig::new(
+            2,
+            cpal::SampleRate(48000),
+            cpal::SupportedBufferSize::Range { min: 64, max: 4096 },
+            SampleFormat::F32,
+        );
+
+        let config = stream_config(&supported, Some(256));
+        assert_eq!(config.buffer_size, cpal::BufferSize::Fixed(256));
+        assert_eq!(config.channels, 2);
+        assert_eq!(config.sample_rate, cpal::SampleRate(48000));
+
+        assert_eq!(
+            stream_config(&supported, None).buffer_size,
+            cpal::BufferSize::Default
+        );
+        // Outside the advertised range
+        assert_eq!(
+            stream_config(&supported, Some(32)).buffer_size,
+            cpal::BufferSize::Default
+        );
+        assert_eq!(
+            stream_config(&supported, Some(8192)).buffer_size,
+            cpal::BufferSize::Default
+        );
+
+        // Without a known range the fixed size is attempted
+        let unknown = cpal::SupportedStreamConfig::new(
+            1,
+            cpal::SampleRate(16000),
+            cpal::SupportedBufferSize::Unknown,
+            SampleFormat::I16,
+        );
+        assert_eq!(
+            stream_config(&unknown, Some(512)).buffer_size,
+            cpal::BufferSize::Fixed(512)
+        );
+    }
+
+    #[test]
+    fn test_device_error_event_from_audio_error() {
+        let error = AudioError::DeviceNotFound("USB Mic".to_string());
+        assert_eq!(
+            DeviceEvent::from(&error),
+            DeviceEvent::Error {
+                kind: "device_not_found",
+                message: "Device not found: USB Mic".to_string(),
+            }
+        );
+        assert_eq!(AudioError::NoInputDevice.kind(), "no_input_device");
+    }
+
+    #[test]
+    fn test_mock_list_device_configs() {
+        let capture = MockAudioCapture::new();
+
+        let configs = capture.list_device_configs("Test Microphone").unwrap();
+        assert_eq!(configs.len(), 2);
+
+        let json = serde_json::to_value(&configs[1]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "min_rate": 48000,
+                "max_rate": 48000,
+                "channels": 2,
+                "format": "f32",
+                "usable": true,
+            })
+        );
+
+        assert!(matches!(
+            capture.list_device_configs("Missing Mic"),
+            Err(AudioError::DeviceNotFound(_))
+        ));
+
+        capture.set_device_configs(Vec::new());
+        assert!(capture
+            .list_device_configs("Secondary Mic")
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
@@ -358,6 +2387,85 @@ pub(crate) mod tests {
         assert!(!devices[1].is_default);
     }
 
+    #[test]
+    fn test_mock_switch_host_changes_devices() {
+        let capture = MockAudioCapture::new();
+        capture.add_host("JACK", vec![device("system:capture_1", true)]);
+        assert_eq!(capture.list_hosts(), vec![MOCK_HOST, "JACK"]);
+
+        capture.set_host(Some("jack")).unwrap();
+        assert_eq!(capture.host(), "JACK");
+        let names: Vec<_> = capture
+            .list_devices()
+            .unwrap()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, vec!["system:capture_1"]);
+
+        // None returns to the default host and its devices
+        capture.set_host(None).unwrap();
+        assert_eq!(capture.list_devices().unwrap().len(), 2);
+        assert!(matches!(
+            capture.set_host(Some("ASIO")),
+            Err(AudioError::HostUnavailable(_))
+        ));
+        assert_eq!(capture.host(), MOCK_HOST);
+    }
+
+    #[test]
+    fn test_cpal_hosts_selectable() {
+        let capture = CpalAudioCapture::new();
+        let hosts = capture.list_hosts();
+        assert!(!hosts.is_empty());
+
+        // Hosts whose runtime is missing (e.g. no JACK server) may refuse to open
+        for host in &hosts {
+            match capture.set_host(Some(host)) {
+                Ok(()) => assert!(capture.host_id.lock().is_some()),
+                Err(e) => assert_eq!(e.kind(), "host_unavailable"),
+            }
+        }
+        capture.set_host(None).unwrap();
+        assert!(capture.host_id.lock().is_none());
+        assert!(capture.set_host(Some("NoSuchHost")).is_err());
+    }
+
+    #[test]
+    fn test_permission_status() {
+        assert_eq!(
+            serde_json::to_value(PermissionStatus::Granted).unwrap(),
+            "granted"
+        );
+        assert_eq!(
+            serde_json::to_value(PermissionStatus::Undetermined).unwrap(),
+            "undetermined"
+        );
+        assert_eq!(
+            AudioError::PermissionDenied.kind(),
+            "microphone_permission_denied"
+        );
+
+        // Only an unanswered prompt changes on request
+        let capture = MockAudioCapture::new();
+        capture.set_permission(PermissionStatus::Undetermined, false);
+        assert_eq!(
+            capture.request_microphone_permission(),
+            PermissionStatus::Denied
+        );
+        capture.set_permission(PermissionStatus::Denied, true);
+        assert_eq!(
+            capture.request_microphone_permission(),
+            PermissionStatus::Denied
+        );
+
+        #[cfg(not(target_os = "macos"))]
+        assert_eq!(
+            CpalAudioCapture::new().microphone_permission(),
+            PermissionStatus::Granted
+        );
+    }
+
     #[test]
     fn test_mock_list_devices_fails() {
         let capture = MockAudioCapture::new();
@@ -413,11 +2521,112 @@ pub(crate) mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_recording_state_transitions() {
+        let state = RecordingState::default();
+        assert!(!state.is_recording());
+
+        assert_eq!(state.transition(true), Some(1));
+        assert_eq!(state.transition(true), None);
+        assert!(state.is_recording());
+
+        assert_eq!(state.transition(false), Some(2));
+        assert_eq!(state.transition(false), None);
+        assert!(!state.is_recording());
+    }
+
+    #[test]
+    fn test_recording_state_ignores_stale_failure() {
+        let state = RecordingState::default();
+        let first = state.transition(true).unwrap();
+        state.transition(false);
+        state.transition(true);
+
+        // The first start failing must not clear the newer one
+        state.fail(first);
+        assert!(state.is_recording());
+
+        state.fail(first + 2);
+        assert!(!state.is_recording());
+    }
+
+    #[test]
+    fn test_can_resume_within_debounce() {
+        let stopped = Instant::now();
+
+        assert!(can_resume(stopped, None, None, stopped));
+        assert!(can_resume(
+            stopped,
+            Some("Mic"),
+            Some("Mic"),
+            stopped + RESTART_DEBOUNCE / 2
+        ));
+        assert!(!can_resume(stopped, Some("Mic"), None, stopped));
+        assert!(!can_resume(stopped, None, None, stopped + RESTART_DEBOUNCE));
+    }
+
+    #[test]
+    fn test_recording_gate_concurrent_toggles() {
+        let gate = Arc::new(RecordingGate::new());
+        let (tx, rx) = mpsc::channel();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let gate = gate.clone();
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for j in 0..200 {
+                        if (i + j) % 2 == 0 {
+                            gate.start(
+                                || tx.clone(),
+                                |generation| AudioCommand::Start {
+                                    device_name: None,
+                                    callback: Arc::new(|_| {}),
+                                    wav_path: None,
+                                    generation,
+                                },
+                            )
+                            .unwrap();
+                        } else {
+                            gate.stop();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        drop(tx);
+        drop(gate.sender.lock().take());
+
+        // Replay the commands as the audio thread would see them
+        let mut streams = 0usize;
+        let mut last_generation = 0;
+        for cmd in rx {
+            match cmd {
+                AudioCommand::Start { generation, .. } => {
+                    assert_eq!(streams, 0, "start while a stream is open");
+                    assert!(generation > last_generation);
+                    last_generation = generation;
+                    streams += 1;
+                }
+                AudioCommand::Stop => {
+                    assert_eq!(streams, 1, "stop without an open stream");
+                    streams -= 1;
+                }
+            }
+        }
+
+        assert_eq!(streams == 1, gate.state.is_recording());
+    }
+
     #[test]
     fn test_audio_device_info_serialization() {
         let info = AudioDeviceInfo {
             name: "Test Device".to_string(),
             is_default: true,
+            is_loopback: false,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -425,6 +2634,601 @@ pub(crate) mod tests {
         assert!(json.contains("true"));
     }
 
+    #[test]
+    fn test_audio_device_info_serializes_loopback_flag() {
+        let info = AudioDeviceInfo {
+            name: "Speakers".to_string(),
+            is_default: false,
+            is_loopback: true,
+        };
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["is_loopback"], true);
+        assert_eq!(json["is_default"], false);
+    }
+
+    #[test]
+    fn test_mock_loopback_device() {
+        let capture = MockAudioCapture::new();
+        let speakers = AudioDeviceInfo {
+            is_loopback: true,
+            ..device("Speakers", false)
+        };
+        capture.set_devices(vec![device("Test Microphone", true), speakers.clone()]);
+
+        let devices = capture.list_devices().unwrap();
+        assert_eq!(devices.iter().filter(|d| d.is_loopback).count(), 1);
+
+        capture
+            .start_recording(Some("Speakers"), Arc::new(|_: Vec<i16>| {}))
+            .unwrap();
+        assert_eq!(capture.get_active_device(), Some(speakers));
+    }
+
+    fn sine_wave(freq: f32, rate: u32, len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                ((2.0 * std::f32::consts::PI * freq * t).sin() * 16000.0) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resample_48k_sine_to_16k() {
+        // 100ms of a 440Hz tone at 48kHz
+        let input = sine_wave(440.0, 48000, 4800);
+        let output = resample_to_16k(&input, 48000);
+
+        assert_eq!(output.len(), 1600);
+
+        // Every third source sample lines up exactly with an output sample
+        assert_eq!(output[0], input[0]);
+        assert_eq!(output[100], input[300]);
+        assert_eq!(output[1599], input[4797]);
+    }
+
+    #[test]
+    fn test_resample_44k1_to_16k_length() {
+        let input = sine_wave(440.0, 44100, 44100);
+        let output = resample_to_16k(&input, 44100);
+
+        assert_eq!(output.len(), 16000);
+    }
+
+    #[test]
+    fn test_resample_16k_passthrough() {
+        let input = sine_wave(440.0, 16000, 1600);
+        assert_eq!(resample_to_16k(&input, 16000), input);
+    }
+
+    #[test]
+    fn test_resample_16k_to_8k() {
+        let input = sine_wave(440.0, 16000, 1600);
+        let output = resample(&input, 16000, 8000);
+
+        assert_eq!(output.len(), 800);
+        assert_eq!(output[100], input[200]);
+        assert_eq!(resample(&input, 16000, 0), input);
+    }
+
+    #[test]
+    fn test_stream_resampler_is_continuous_across_buffers() {
+        let input = sine_wave(440.0, 44100, 44100);
+        let one_shot = StreamResampler::new(44100, 16000).process(&input);
+
+        let mut resampler = StreamResampler::new(44100, 16000);
+        let mut chunked = Vec::new();
+        let mut rest = &input[..];
+        for size in [512, 333, 1, 0, 1021, 7].into_iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at(size.min(rest.len()));
+            chunked.extend(resampler.process(chunk));
+            rest = tail;
+        }
+
+        assert_eq!(chunked, one_shot);
+        // A second of audio is still a second, so speech isn't sped up
+        assert_eq!(chunked.len(), 16000);
+    }
+
+    #[test]
+    fn test_stream_resampler_filters_aliasing() {
+        let rms = |samples: &[i16]| {
+            let sum: f64 = samples.iter().map(|&s| (s as f64).powi(2)).sum();
+            (sum / samples.len() as f64).sqrt()
+        };
+
+        let speech = StreamResampler::new(48000, 16000).process(&sine_wave(1000.0, 48000, 4800));
+        // 12kHz is above 16kHz audio's Nyquist frequency and would fold down to 4kHz
+        let aliased = StreamResampler::new(48000, 16000).process(&sine_wave(12000.0, 48000, 4800));
+
+        assert!(rms(&speech[100..]) > 10000.0);
+        assert!(rms(&aliased[100..]) < rms(&speech[100..]) / 20.0);
+
+        // Upsampling and passthrough aren't filtered
+        let input = sine_wave(440.0, 16000, 1600);
+        assert_eq!(StreamResampler::new(16000, 16000).process(&input), input);
+        assert_eq!(
+            StreamResampler::new(8000, 16000).process(&[0, 100]),
+            vec![0, 50]
+        );
+    }
+
+    #[test]
+    fn test_resample_empty_input() {
+        assert!(resample_to_16k(&[], 48000).is_empty());
+    }
+
+    #[test]
+    fn test_helpers_handle_empty_and_single_sample() {
+        for rate in [8000, 16000, 44100, 48000, 0] {
+            assert!(resample_to_16k(&[], rate).is_empty());
+        }
+        assert_eq!(resample_to_16k(&[100], 16000), vec![100]);
+        assert_eq!(resample_to_16k(&[100], 8000), vec![100, 100]);
+        // Less than one output sample's worth of input
+        assert!(resample_to_16k(&[100], 48000).is_empty());
+
+        assert!(downmix_to_mono(&[], 2).is_empty());
+        assert!(downmix_i16_to_mono(&[], 2).is_empty());
+        assert_eq!(downmix_to_mono(&[0.5], 1), vec![0.5]);
+        assert_eq!(downmix_i16_to_mono(&[100], 0), vec![100]);
+        assert!(downmix_to_mono(&[0.5], 2).is_empty());
+        assert!(downmix_i16_to_mono(&[100], 2).is_empty());
+
+        assert_eq!(compute_level(&[]), AudioLevel::default());
+        let level = compute_level(&[i16::MIN]);
+        assert_eq!((level.rms, level.peak), (1.0, 1.0));
+
+        let mut empty: Vec<i16> = Vec::new();
+        apply_gain(&mut empty, 2.0);
+        let mut single = vec![100];
+        apply_gain(&mut single, 2.0);
+        assert_eq!(single, vec![200]);
+
+        assert!(apply_noise_gate(&[], TEST_GATE_THRESHOLD, 100).is_empty());
+        assert_eq!(apply_noise_gate(&[5], TEST_GATE_THRESHOLD, 0), vec![0]);
+        let gate = NoiseGate::new();
+        gate.set(TEST_GATE_THRESHOLD, 100);
+        gate.process(&mut empty);
+        assert!(empty.is_empty());
+
+        let gain = GainControl::new();
+        gain.set_agc(true);
+        gain.process(&mut [i16::MAX / 10]);
+        let tracked = *gain.agc_level.lock();
+        gain.process(&mut empty);
+        assert_eq!(*gain.agc_level.lock(), tracked);
+
+        let chunker = Chunker::new(0);
+        assert!(chunker.push(&[]).is_empty());
+        let chunker = Chunker::new(4);
+        assert!(chunker.push(&[]).is_empty());
+        assert!(chunker.push(&[1]).is_empty());
+        assert_eq!(chunker.flush(), vec![1]);
+    }
+
+    #[test]
+    fn test_wav_recorder_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("capture.wav");
+        let input = sine_wave(440.0, 16000, 1600);
+
+        let recorder = WavRecorder::create(&path).unwrap();
+        recorder.write_samples(&input[..800]).unwrap();
+        recorder.write_samples(&input[800..]).unwrap();
+        recorder.finalize().unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.sample_rate, 16000);
+        assert_eq!(spec.bits_per_sample, 16);
+
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn test_wav_recorder_writes_after_finalize_are_ignored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("capture.wav");
+
+        let recorder = WavRecorder::create(&path).unwrap();
+        recorder.write_samples(&[1, 2, 3]).unwrap();
+        recorder.finalize().unwrap();
+        recorder.write_samples(&[4, 5, 6]).unwrap();
+        recorder.finalize().unwrap();
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 3);
+    }
+
+    #[test]
+    fn test_mock_set_wav_output() {
+        let capture = MockAudioCapture::new();
+        assert!(capture.wav_output().is_none());
+
+        capture.set_wav_output(Some(PathBuf::from("/tmp/out.wav")));
+        assert_eq!(capture.wav_output(), Some(PathBuf::from("/tmp/out.wav")));
+
+        capture.set_wav_output(None);
+        assert!(capture.wav_output().is_none());
+    }
+
+    #[test]
+    fn test_f32_to_i16() {
+        assert_eq!(f32_to_i16(0.0), 0);
+        assert_eq!(f32_to_i16(1.0), i16::MAX);
+        assert_eq!(f32_to_i16(-1.0), -i16::MAX);
+        assert_eq!(f32_to_i16(0.5), i16::MAX / 2);
+        // Out-of-range input saturates instead of wrapping
+        assert_eq!(f32_to_i16(2.0), i16::MAX);
+        assert_eq!(f32_to_i16(-2.0), i16::MIN);
+    }
+
+    #[test]
+    fn test_i32_to_i16() {
+        assert_eq!(i32_to_i16(0), 0);
+        assert_eq!(i32_to_i16(i32::MAX), i16::MAX);
+        assert_eq!(i32_to_i16(i32::MIN), i16::MIN);
+        assert_eq!(i32_to_i16(0x1234_5678), 0x1234);
+        assert_eq!(i32_to_i16(-65536), -1);
+    }
+
+    #[test]
+    fn test_u16_to_i16() {
+        assert_eq!(u16_to_i16(0x8000), 0);
+        assert_eq!(u16_to_i16(0), i16::MIN);
+        assert_eq!(u16_to_i16(u16::MAX), i16::MAX);
+        assert_eq!(u16_to_i16(0x8001), 1);
+        assert_eq!(u16_to_i16(0x7fff), -1);
+    }
+
+    #[test]
+    fn test_downmix_i16_stereo_to_mono() {
+        let stereo = vec![100, 300, -200, 200, i16::MAX, i16::MAX];
+        assert_eq!(downmix_i16_to_mono(&stereo, 2), vec![200, 0, i16::MAX]);
+    }
+
+    #[test]
+    fn test_downmix_i16_mono_passthrough() {
+        let mono = vec![1, 2, 3];
+        assert_eq!(downmix_i16_to_mono(&mono, 1), mono);
+    }
+
+    #[test]
+    fn test_downmix_stereo_to_mono() {
+        let stereo = [0.5, -0.5, 1.0, 0.0, -1.0, -1.0];
+        assert_eq!(downmix_to_mono(&stereo, 2), vec![0.0, 0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_downmix_four_channels() {
+        let quad = [0.1, 0.2, 0.3, 0.4, 1.0, 1.0, -1.0, -1.0];
+        let mono = downmix_to_mono(&quad, 4);
+
+        assert_eq!(mono.len(), 2);
+        assert!((mono[0] - 0.25).abs() < 1e-6);
+        assert_eq!(mono[1], 0.0);
+    }
+
+    #[test]
+    fn test_downmix_drops_partial_frame() {
+        let stereo = [0.5, 0.5, 0.25];
+        assert_eq!(downmix_to_mono(&stereo, 2), vec![0.5]);
+    }
+
+    #[test]
+    fn test_downmix_float_mono_passthrough() {
+        let mono = [0.1, 0.2];
+        assert_eq!(downmix_to_mono(&mono, 1), mono.to_vec());
+    }
+
+    #[test]
+    fn test_compute_level_silence() {
+        let level = compute_level(&[0i16; 1600]);
+        assert_eq!(level, AudioLevel::default());
+        assert_eq!(compute_level(&[]), AudioLevel::default());
+    }
+
+    #[test]
+    fn test_compute_level_full_scale() {
+        let level = compute_level(&[i16::MIN, i16::MAX, i16::MIN, i16::MAX]);
+        assert!(level.peak > 0.999 && level.peak <= 1.0);
+        assert!(level.rms > 0.999 && level.rms <= 1.0);
+    }
+
+    #[test]
+    fn test_compute_level_sine() {
+        let level = compute_level(&sine_wave(440.0, 16000, 16000));
+        let expected_peak = 16000.0 / 32768.0;
+
+        assert!((level.peak - expected_peak).abs() < 0.001);
+        // RMS of a sine is peak / sqrt(2)
+        assert!((level.rms - expected_peak / 2f32.sqrt()).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_level_meter_throttles_updates() {
+        let meter = LevelMeter::new();
+        let loud = sine_wave(440.0, 16000, 800);
+
+        assert!(meter.update(&loud).is_some());
+        assert!(
+            meter.update(&loud).is_none(),
+            "Second update within interval should be throttled"
+        );
+        assert!(
+            meter.level().rms > 0.0,
+            "Level is tracked even when throttled"
+        );
+
+        meter.reset();
+        assert_eq!(meter.level(), AudioLevel::default());
+        assert!(meter.update(&loud).is_some());
+    }
+
+    #[test]
+    fn test_downsample_minmax() {
+        // Shorter than the point count: every sample is its own point
+        assert_eq!(
+            downsample_minmax(&[3, -2, 7], 128),
+            vec![(3, 3), (-2, -2), (7, 7)]
+        );
+        assert!(downsample_minmax(&[], 128).is_empty());
+        assert!(downsample_minmax(&[1, 2, 3], 0).is_empty());
+
+        let buf = [0, 5, -3, 2, 9, -8, 1, 1];
+        assert_eq!(
+            downsample_minmax(&buf, 4),
+            vec![(0, 5), (-3, 2), (-8, 9), (1, 1)]
+        );
+        // Uneven slices still cover every sample
+        assert_eq!(downsample_minmax(&buf, 3), vec![(0, 5), (-3, 9), (-8, 1)]);
+        assert_eq!(downsample_minmax(&buf, 1), vec![(-8, 9)]);
+
+        let long = sine_wave(440.0, 16000, 4000);
+        let points = downsample_minmax(&long, WAVEFORM_POINTS);
+        assert_eq!(points.len(), WAVEFORM_POINTS);
+        assert!(points.iter().all(|(min, max)| min <= max));
+    }
+
+    #[test]
+    fn test_waveform_meter_keeps_recent_window() {
+        let meter = WaveformMeter::new();
+
+        assert_eq!(meter.update(&[1, 2]), Some(vec![(1, 1), (2, 2)]));
+        assert_eq!(meter.update(&[3]), None, "Throttled within the interval");
+
+        meter.reset();
+        let mut samples = vec![i16::MIN; 4000];
+        samples[4000 - WAVEFORM_WINDOW_SAMPLES..].fill(100);
+        let points = meter.update(&samples).unwrap();
+        assert_eq!(points.len(), WAVEFORM_POINTS);
+        assert!(points.iter().all(|&point| point == (100, 100)));
+    }
+
+    #[test]
+    fn test_apply_gain_scales_samples() {
+        let mut samples = vec![0, 100, -100, 1000, -1000];
+        apply_gain(&mut samples, 2.0);
+        assert_eq!(samples, vec![0, 200, -200, 2000, -2000]);
+
+        apply_gain(&mut samples, 0.5);
+        assert_eq!(samples, vec![0, 100, -100, 1000, -1000]);
+
+        let quiet: Vec<i16> = sine_wave(440.0, 16000, 1600)
+            .iter()
+            .map(|&s| s / 10)
+            .collect();
+        let mut boosted = quiet.clone();
+        apply_gain(&mut boosted, 3.0);
+        let ratio = compute_level(&boosted).rms / compute_level(&quiet).rms;
+        assert!((ratio - 3.0).abs() < 0.01, "ratio was {}", ratio);
+    }
+
+    #[test]
+    fn test_apply_gain_clamps_instead_of_wrapping() {
+        let mut samples = vec![20000, -20000, i16::MAX, i16::MIN, 16384];
+        apply_gain(&mut samples, 4.0);
+        assert_eq!(
+            samples,
+            vec![i16::MAX, i16::MIN, i16::MAX, i16::MIN, i16::MAX]
+        );
+    }
+
+    #[test]
+    fn test_gain_control_manual_gain() {
+        let gain = GainControl::new();
+        let mut samples = vec![100, -100];
+        gain.process(&mut samples);
+        assert_eq!(samples, vec![100, -100], "Unity gain by default");
+
+        gain.set_gain(2.5);
+        gain.process(&mut samples);
+        assert_eq!(samples, vec![250, -250]);
+
+        gain.set_gain(100.0);
+        assert_eq!(gain.gain(), MAX_INPUT_GAIN);
+        gain.set_gain(-1.0);
+        assert_eq!(gain.gain(), 0.0);
+        gain.set_gain(f32::NAN);
+        assert_eq!(gain.gain(), 1.0);
+    }
+
+    #[test]
+    fn test_agc_boosts_quiet_input_towards_target() {
+        let gain = GainControl::new();
+        gain.set_agc(true);
+
+        // About -40 dBFS
+        let quiet: Vec<i16> = sine_wave(440.0, 16000, 1600)
+            .iter()
+            .map(|&s| s / 100)
+            .collect();
+        let mut samples = quiet.clone();
+        gain.process(&mut samples);
+
+        let rms = compute_level(&samples).rms;
+        assert!(rms > compute_level(&quiet).rms * 5.0);
+        assert!(rms <= AGC_TARGET_RMS * 1.01, "rms was {}", rms);
+    }
+
+    #[test]
+    fn test_agc_does_not_clip_or_blow_up_silence() {
+        let gain = GainControl::new();
+        gain.set_agc(true);
+
+        let mut silence = vec![0i16; 1600];
+        gain.process(&mut silence);
+        assert!(silence.iter().all(|&s| s == 0));
+
+        // Loud input with sharp peaks is never pushed past full scale
+        let mut loud = sine_wave(440.0, 16000, 1600);
+        gain.process(&mut loud);
+        assert!(compute_level(&loud).peak <= 1.0);
+        assert!(loud.iter().all(|&s| s != i16::MIN));
+
+        // Faint noise after silence is boosted at most AGC_MAX_GAIN times
+        gain.reset();
+        let mut hiss = vec![3i16, -3, 3, -3];
+        gain.process(&mut hiss);
+        assert!(hiss.iter().all(|&s| s.abs() <= (3.0 * AGC_MAX_GAIN) as i16));
+    }
+
+    /// Gate threshold used by the noise gate tests (about -30 dBFS)
+    const TEST_GATE_THRESHOLD: f32 = 0.03;
+
+    /// Low-level noise well under the test threshold
+    fn hiss(len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| if i % 2 == 0 { 200 } else { -200 })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunker_emits_fixed_chunks() {
+        let chunker = Chunker::new(4);
+
+        assert!(chunker.push(&[1, 2, 3]).is_empty());
+        assert_eq!(chunker.push(&[4, 5]), vec![vec![1, 2, 3, 4]]);
+        assert_eq!(
+            chunker.push(&[6, 7, 8, 9, 10, 11, 12]),
+            vec![vec![5, 6, 7, 8], vec![9, 10, 11, 12]]
+        );
+        assert!(chunker.push(&[13]).is_empty());
+        assert_eq!(chunker.flush(), vec![13]);
+        assert!(chunker.flush().is_empty());
+
+        // Passing through also releases whatever was pending
+        chunker.push(&[1, 2]);
+        chunker.set_frames(0);
+        assert_eq!(chunker.push(&[3]), vec![vec![1, 2, 3]]);
+        assert!(chunker.push(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_noise_gate_silent_loud_silent() {
+        let mut buf = hiss(3200);
+        buf.extend(sine_wave(440.0, 16000, 3200));
+        buf.extend(hiss(3200));
+
+        let gated = apply_noise_gate(&buf, TEST_GATE_THRESHOLD, 50);
+        assert_eq!(gated.len(), buf.len());
+
+        // Leading noise is gated
+        assert!(gated[..3200].iter().all(|&s| s == 0));
+
+        // Speech passes unchanged once the gate has opened (1ms attack)
+        let attack = ms_to_samples(GATE_ATTACK_MS);
+        assert_eq!(&gated[3200 + attack..6400], &buf[3200 + attack..6400]);
+        // ...and the onset isn't clipped: the gate starts opening on the first sample
+        assert_ne!(gated[3201], 0);
+
+        // The release keeps word endings for 50ms, then the trailing noise is gated
+        let release = ms_to_samples(50);
+        assert_eq!(&gated[6400..6400 + release], &buf[6400..6400 + release]);
+        let closed = 6400 + release + GATE_WINDOW_SAMPLES + ms_to_samples(GATE_FADE_MS);
+        assert!(gated[closed..].iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn test_noise_gate_fades_instead_of_cutting() {
+        let mut buf = sine_wave(440.0, 16000, 1600);
+        buf.extend(hiss(1600));
+
+        let gated = apply_noise_gate(&buf, TEST_GATE_THRESHOLD, 0);
+        let fade = &gated[1600..1600 + ms_to_samples(GATE_FADE_MS)];
+
+        // Samples shrink relative to the input during the fade
+        let first = fade[0] as f32 / buf[1600] as f32;
+        let last = fade[fade.len() - 2] as f32 / buf[1600 + fade.len() - 2] as f32;
+        assert!(first > last && last > 0.0, "first {} last {}", first, last);
+    }
+
+    #[test]
+    fn test_noise_gate_zero_threshold_passes_everything() {
+        let buf = hiss(800);
+        assert_eq!(apply_noise_gate(&buf, 0.0, 0), buf);
+    }
+
+    #[test]
+    fn test_noise_gate_keeps_state_between_buffers() {
+        let gate = NoiseGate::new();
+        let mut quiet = hiss(1600);
+        gate.process(&mut quiet);
+        assert_eq!(quiet, hiss(1600), "Disabled by default");
+
+        gate.set(TEST_GATE_THRESHOLD, 100);
+        let mut speech = sine_wave(440.0, 16000, 1600);
+        gate.process(&mut speech);
+
+        // Still within the release time of the previous buffer
+        let mut tail = hiss(800);
+        gate.process(&mut tail);
+        assert_eq!(tail, hiss(800));
+
+        // The remaining 50ms of release, then the fade
+        let mut later = hiss(1600);
+        gate.process(&mut later);
+        assert!(later[ms_to_samples(50) + ms_to_samples(GATE_FADE_MS)..]
+            .iter()
+            .all(|&s| s == 0));
+
+        gate.set(f32::NAN, 100);
+        assert_eq!(gate.threshold(), 0.0);
+    }
+
+    #[test]
+    fn test_mock_push_samples_drives_meter() {
+        let capture = MockAudioCapture::new();
+        let meter = Arc::new(LevelMeter::new());
+
+        let meter_clone = meter.clone();
+        capture
+            .start_recording(
+                None,
+                Arc::new(move |samples: Vec<i16>| {
+                    meter_clone.update(&samples);
+                }),
+            )
+            .unwrap();
+
+        capture.push_samples(sine_wave(440.0, 16000, 1600));
+        assert!(meter.level().peak > 0.4);
+
+        capture.stop_recording();
+        capture.push_samples(vec![0; 1600]);
+        assert!(
+            meter.level().peak > 0.4,
+            "Samples after stop should not reach the callback"
+        );
+    }
+
     #[test]
     fn test_audio_error_display() {
         let err = AudioError::NoInputDevice;
@@ -433,4 +3237,33 @@ pub(crate) mod tests {
         let err = AudioError::DeviceNotFound("test".to_string());
         assert_eq!(err.to_string(), "Device not found: test");
     }
+
+    #[test]
+    fn test_stream_error_handler() {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let mut on_error = stream_error_handler(
+            device_lost.clone(),
+            Arc::new(move |event| sink.lock().push(event)),
+        );
+
+        // A vanished device is left to the device poll
+        on_error(cpal::StreamError::DeviceNotAvailable);
+        assert!(device_lost.load(Ordering::SeqCst));
+        assert!(events.lock().is_empty());
+
+        on_error(cpal::StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: "xrun".to_string(),
+            },
+        });
+        assert_eq!(
+            *events.lock(),
+            vec![DeviceEvent::Error {
+                kind: "stream_failed",
+                message: "Audio stream failed: xrun".to_string(),
+            }]
+        );
+    }
 }
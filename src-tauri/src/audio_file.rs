@@ -0,0 +1,244 @@
+//! Audio file decoding module.
+//!
+//! Decodes recorded audio files into 16kHz mono samples so they can be
+//! run through the speech recognizer without a microphone.
+//! WAV files are read with hound; other formats go through symphonia.
+
+use crate::audio::{downmix_i16_to_mono, resample_to_16k, AudioError};
+use std::fs::File;
+use std::path::Path;
+
+/// Decode an audio file and convert it to 16kHz mono i16 samples
+pub fn decode_to_16k_mono(path: &Path) -> Result<Vec<i16>, AudioError> {
+    if !path.exists() {
+        return Err(AudioError::DecodeError(format!(
+            "File not found: {}",
+            path.to_string_lossy()
+        )));
+    }
+
+    let is_wav = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false);
+
+    let (samples, sample_rate, channels) = if is_wav {
+        decode_wav(path)?
+    } else {
+        decode_with_symphonia(path)?
+    };
+
+    let mono = downmix_i16_to_mono(&samples, channels);
+    Ok(resample_to_16k(&mono, sample_rate))
+}
+
+/// Read a WAV file, returning interleaved samples, sample rate and channel count
+fn decode_wav(path: &Path) -> Result<(Vec<i16>, u32, u16), AudioError> {
+    let mut reader =
+        hound::WavReader::open(path).map_err(|e| AudioError::DecodeError(e.to_string()))?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Int => {
+            // Scale any integer bit depth to 16 bits
+            let shift = spec.bits_per_sample as i32 - 16;
+            reader
+                .samples::<i32>()
+                .map(|s| {
+                    s.map(|v| {
+                        if shift >= 0 {
+                            (v >> shift) as i16
+                        } else {
+                            (v << -shift) as i16
+                        }
+                    })
+                })
+                .collect::<Result<Vec<i16>, _>>()
+        }
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.map(|v| (v.clamp(-1.0, 1.0) * i16::MAX as f32) as i16))
+            .collect::<Result<Vec<i16>, _>>(),
+    }
+    .map_err(|e| AudioError::DecodeError(e.to_string()))?;
+
+    Ok((samples, spec.sample_rate, spec.channels))
+}
+
+/// Decode any symphonia-supported format (MP3, FLAC, OGG, ...)
+fn decode_with_symphonia(path: &Path) -> Result<(Vec<i16>, u32, u16), AudioError> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::errors::Error as SymphoniaError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = File::open(path).map_err(|e| AudioError::DecodeError(e.to_string()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| AudioError::DecodeError(e.to_string()))?;
+    let mut format = probed.format;
+
+    let track = format
+        .default_track()
+        .ok_or_else(|| AudioError::DecodeError("No audio track found".to_string()))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = track
+        .codec_params
+        .channels
+        .map(|c| c.count() as u16)
+        .unwrap_or(1);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| AudioError::DecodeError(e.to_string()))?;
+
+    let mut samples = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(AudioError::DecodeError(e.to_string())),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                sample_rate = spec.rate;
+                channels = spec.channels.count() as u16;
+
+                let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, spec);
+                buffer.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buffer.samples());
+            }
+            // Skip corrupt frames rather than failing the whole file
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(AudioError::DecodeError(e.to_string())),
+        }
+    }
+
+    Ok((samples, sample_rate, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, sample_rate: u32, channels: u16, frames: usize) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for i in 0..frames {
+            let t = i as f32 / sample_rate as f32;
+            let sample = ((2.0 * std::f32::consts::PI * 440.0 * t).sin() * 8000.0) as i16;
+            for _ in 0..channels {
+                writer.write_sample(sample).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_decode_16k_mono_wav() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mono.wav");
+        write_wav(&path, 16000, 1, 16000);
+
+        let samples = decode_to_16k_mono(&path).unwrap();
+        assert_eq!(samples.len(), 16000);
+    }
+
+    #[test]
+    fn test_decode_converts_48k_stereo_wav() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("stereo.wav");
+        write_wav(&path, 48000, 2, 48000);
+
+        let samples = decode_to_16k_mono(&path).unwrap();
+        assert_eq!(
+            samples.len(),
+            16000,
+            "1 second of audio should yield 16000 samples"
+        );
+        assert!(
+            samples.iter().any(|&s| s > 7000),
+            "Signal should survive downmixing"
+        );
+    }
+
+    #[test]
+    fn test_decode_float_wav() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("float.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for v in [0.0f32, 0.5, -0.5, 1.0] {
+            writer.write_sample(v).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let samples = decode_to_16k_mono(&path).unwrap();
+        assert_eq!(samples, vec![0, i16::MAX / 2, -(i16::MAX / 2), i16::MAX]);
+    }
+
+    #[test]
+    fn test_symphonia_matches_hound() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("both.wav");
+        write_wav(&path, 16000, 1, 4000);
+
+        let (hound_samples, _, _) = decode_wav(&path).unwrap();
+        let (symphonia_samples, rate, channels) = decode_with_symphonia(&path).unwrap();
+
+        assert_eq!(rate, 16000);
+        assert_eq!(channels, 1);
+        assert_eq!(symphonia_samples, hound_samples);
+    }
+
+    #[test]
+    fn test_decode_missing_file() {
+        let result = decode_to_16k_mono(Path::new("/nonexistent/audio.wav"));
+        assert!(matches!(result, Err(AudioError::DecodeError(_))));
+    }
+
+    #[test]
+    fn test_decode_invalid_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("garbage.mp3");
+        std::fs::write(&path, b"not really audio").unwrap();
+
+        assert!(decode_to_16k_mono(&path).is_err());
+    }
+}
@@ -4,10 +4,17 @@
 
 use parking_lot::Mutex;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::io::{BufReader, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 /// Claude Code process errors
@@ -23,6 +30,34 @@ pub enum ClaudeError {
     ReadError(String),
     #[error("Claude Code not found in PATH")]
     ClaudeNotFound,
+    #[error("Failed to write session log: {0}")]
+    LogError(String),
+    #[error("Working directory does not exist: {0}")]
+    WorkingDirNotFound(String),
+    #[error("Invalid working directory: {0}")]
+    InvalidWorkingDir(String),
+    #[error("Timed out waiting for Claude Code to report its version")]
+    VersionTimeout,
+    #[error("Failed to detect Claude Code version: {0}")]
+    VersionError(String),
+}
+
+impl ClaudeError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClaudeError::PtySpawnError(_) => "pty_spawn_error",
+            ClaudeError::NotRunning => "not_running",
+            ClaudeError::WriteError(_) => "write_error",
+            ClaudeError::ReadError(_) => "read_error",
+            ClaudeError::ClaudeNotFound => "claude_not_found",
+            ClaudeError::LogError(_) => "log_error",
+            ClaudeError::WorkingDirNotFound(_) => "working_dir_not_found",
+            ClaudeError::InvalidWorkingDir(_) => "invalid_working_dir",
+            ClaudeError::VersionTimeout => "version_timeout",
+            ClaudeError::VersionError(_) => "version_error",
+        }
+    }
 }
 
 /// Output event from Claude Code
@@ -30,6 +65,393 @@ pub enum ClaudeError {
 pub struct OutputEvent {
     pub data: String,
     pub is_error: bool,
+    /// `data` with ANSI escape sequences removed (only set when stripping is enabled)
+    #[serde(default)]
+    pub plain: Option<String>,
+}
+
+/// Remove ANSI escape sequences from terminal output
+pub fn strip_ansi(data: &str) -> String {
+    strip_ansi_escapes::strip_str(data)
+}
+
+/// Key sequence that submits a line to Claude Code (Enter in a terminal)
+pub const DEFAULT_SUBMIT_SEQUENCE: &str = "\r";
+
+/// Submit sequences a user can configure
+pub const SUBMIT_SEQUENCES: &[&str] = &["\r", "\n", "\r\n"];
+
+/// Terminate `text` with exactly one `submit` sequence. Surrounding whitespace
+/// is trimmed first, so a trailing line break doesn't submit the line twice.
+pub fn submit_line(text: &str, submit: &str) -> String {
+    format!("{}{}", text.trim(), submit)
+}
+
+/// Keys that can be sent by name, with the bytes a terminal sends for them
+pub const NAMED_KEYS: &[(&str, &[u8])] = &[
+    ("up", b"\x1b[A"),
+    ("down", b"\x1b[B"),
+    ("right", b"\x1b[C"),
+    ("left", b"\x1b[D"),
+    ("enter", b"\r"),
+    ("tab", b"\t"),
+    ("shift-tab", b"\x1b[Z"),
+    ("esc", b"\x1b"),
+    ("backspace", b"\x7f"),
+    ("ctrl-c", CTRL_C),
+    ("ctrl-d", b"\x04"),
+];
+
+/// Byte sequence for a named key such as "up" or "ctrl-c" (case-insensitive)
+pub fn key_to_bytes(name: &str) -> Option<Vec<u8>> {
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, bytes)| bytes.to_vec())
+}
+
+/// Sent before pasted text when the terminal is in bracketed paste mode
+pub const PASTE_START: &str = "\x1b[200~";
+
+/// Sent after pasted text in bracketed paste mode
+pub const PASTE_END: &str = "\x1b[201~";
+
+/// Wrap `text` in bracketed paste markers so a TUI treats it as pasted rather
+/// than typed. An end marker inside the text is removed so it can't end the paste early.
+pub fn bracketed_paste(text: &str) -> String {
+    let text = text.replace(PASTE_END, "");
+    format!("{}{}{}", PASTE_START, text, PASTE_END)
+}
+
+/// How often buffered log entries are flushed to disk
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default size at which a session log is rotated
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default number of rotated session logs kept
+pub const DEFAULT_LOG_KEEP: usize = 5;
+
+/// When a session log is rotated and how many old logs are kept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogRotation {
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_LOG_MAX_BYTES,
+            keep: DEFAULT_LOG_KEEP,
+        }
+    }
+}
+
+/// The active log file and how many bytes it holds
+struct LogFile {
+    /// None only while the file is being rotated
+    writer: Option<BufWriter<File>>,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            writer: Some(BufWriter::new(file)),
+            size,
+        })
+    }
+}
+
+/// Append-only log of the PTY traffic for a Claude session.
+///
+/// Once the file reaches `LogRotation::max_bytes` it is renamed to
+/// `<name>.<unix millis>` and a fresh file is started.
+pub struct SessionLog {
+    path: PathBuf,
+    file: Mutex<LogFile>,
+    rotation: Mutex<LogRotation>,
+    last_flush: Mutex<Instant>,
+}
+
+impl SessionLog {
+    pub fn open(path: &Path, rotation: LogRotation) -> Result<Self, ClaudeError> {
+        let file = LogFile::open(path).map_err(|e| ClaudeError::LogError(e.to_string()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            rotation: Mutex::new(rotation),
+            last_flush: Mutex::new(Instant::now()),
+        })
+    }
+
+    pub fn set_rotation(&self, rotation: LogRotation) {
+        *self.rotation.lock() = rotation;
+    }
+
+    pub fn log_output(&self, data: &str) {
+        self.write_entry("OUT", data);
+    }
+
+    pub fn log_input(&self, data: &str) {
+        self.write_entry("IN", data);
+    }
+
+    pub fn flush(&self) {
+        if let Some(writer) = self.file.lock().writer.as_mut() {
+            let _ = writer.flush();
+        }
+        *self.last_flush.lock() = Instant::now();
+    }
+
+    fn write_entry(&self, tag: &str, data: &str) {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        // One line per entry; control characters are escaped so the log stays readable
+        let line = format!(
+            "[{}.{:03}] {} {}\n",
+            since_epoch.as_secs(),
+            since_epoch.subsec_millis(),
+            tag,
+            data.escape_debug()
+        );
+        {
+            let mut file = self.file.lock();
+            if let Some(writer) = file.writer.as_mut() {
+                if writer.write_all(line.as_bytes()).is_ok() {
+                    file.size += line.len() as u64;
+                }
+            }
+
+            let rotation = *self.rotation.lock();
+            if file.size >= rotation.max_bytes {
+                let _ = self.rotate(&mut file, rotation.keep);
+            }
+        }
+
+        if self.last_flush.lock().elapsed() >= LOG_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Move the full log aside, start a new one and delete rotated logs beyond `keep`
+    fn rotate(&self, file: &mut LogFile, keep: usize) -> std::io::Result<()> {
+        // Close the file first; Windows can't rename a file that is open
+        if let Some(mut writer) = file.writer.take() {
+            writer.flush()?;
+        }
+        let renamed = std::fs::rename(&self.path, rotated_log_path(&self.path));
+        *file = LogFile::open(&self.path)?;
+        renamed?;
+
+        for old in rotated_logs(&self.path).into_iter().skip(keep) {
+            std::fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+}
+
+/// `<log>.<unix millis>`, bumped until it doesn't clash with an earlier rotation
+fn rotated_log_path(path: &Path) -> PathBuf {
+    let mut millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    loop {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", millis));
+        let rotated = PathBuf::from(name);
+        if !rotated.exists() {
+            return rotated;
+        }
+        millis += 1;
+    }
+}
+
+/// Rotated copies of the log at `path`, newest first
+pub fn rotated_logs(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut logs: Vec<(u128, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let millis = file_name.strip_prefix(&prefix)?.parse().ok()?;
+            Some((millis, entry.path()))
+        })
+        .collect();
+    logs.sort_by_key(|(millis, _)| std::cmp::Reverse(*millis));
+    logs.into_iter().map(|(_, path)| path).collect()
+}
+
+impl Drop for SessionLog {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Default size of each read from the PTY
+pub const DEFAULT_READ_BUFFER_BYTES: usize = 4096;
+
+/// Decodes PTY output read in arbitrary chunks, holding back a multi-byte
+/// character split across reads until the rest of it arrives
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// Text of `bytes` up to any incomplete trailing character; invalid bytes
+    /// are replaced with U+FFFD
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let complete = self.pending.len() - incomplete_tail(&self.pending);
+        let text = String::from_utf8_lossy(&self.pending[..complete]).to_string();
+        self.pending.drain(..complete);
+        text
+    }
+
+    /// Whatever is still held back, e.g. when the output ends mid-character
+    pub fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).to_string();
+        self.pending.clear();
+        text
+    }
+}
+
+/// Length of a UTF-8 sequence cut off at the end of `bytes`
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for len in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - len];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let expected = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if expected > len { len } else { 0 };
+    }
+    0
+}
+
+/// Default amount of recent output kept for late-joining UIs
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 256 * 1024;
+
+/// Bounded buffer of the most recent PTY output, trimmed from the front
+pub struct Scrollback {
+    buffer: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl Scrollback {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, data: &str) {
+        self.buffer.extend(data.as_bytes());
+        self.trim();
+    }
+
+    /// Change the cap, dropping the oldest output if the buffer is now too large
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Buffered output as text, skipping a UTF-8 sequence cut off by trimming
+    pub fn contents(&self) -> String {
+        let start = self
+            .buffer
+            .iter()
+            .position(|&b| (b & 0xC0) != 0x80)
+            .unwrap_or(self.buffer.len());
+        let bytes: Vec<u8> = self.buffer.iter().skip(start).copied().collect();
+        String::from_utf8_lossy(&bytes).to_string()
+    }
+
+    fn trim(&mut self) {
+        if self.buffer.len() > self.capacity {
+            let excess = self.buffer.len() - self.capacity;
+            self.buffer.drain(..excess);
+        }
+    }
+}
+
+/// One hit from searching Claude's output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutputMatch {
+    /// The whole line containing the match
+    pub line: String,
+    /// Zero-based index of the line within the searched output
+    pub line_number: usize,
+    /// Byte offset of the match within the searched output
+    pub offset: usize,
+    /// Length of the match in bytes
+    pub len: usize,
+}
+
+/// Find `pattern` in terminal output, line by line.
+///
+/// Escape codes are stripped first so colored text still matches, and offsets
+/// refer to the stripped text. Empty matches (e.g. `^`) are skipped.
+pub fn search_output(
+    output: &str,
+    pattern: &str,
+    regex: bool,
+) -> Result<Vec<OutputMatch>, regex::Error> {
+    let matcher = if regex {
+        Regex::new(pattern)?
+    } else {
+        Regex::new(&regex::escape(pattern))?
+    };
+
+    let text = strip_ansi(output);
+    let mut matches = Vec::new();
+    let mut line_start = 0;
+    for (line_number, line) in text.split('\n').enumerate() {
+        let line_text = line.strip_suffix('\r').unwrap_or(line);
+        for found in matcher.find_iter(line_text).filter(|m| !m.is_empty()) {
+            matches.push(OutputMatch {
+                line: line_text.to_string(),
+                line_number,
+                offset: line_start + found.start(),
+                len: found.len(),
+            });
+        }
+        line_start += line.len() + 1;
+    }
+    Ok(matches)
 }
 
 /// Process status
@@ -38,38 +460,472 @@ pub enum ProcessStatus {
     Starting,
     Running,
     Stopped,
+    /// The process exited on its own with this exit code
+    Exited(i32),
     Error(String),
 }
 
+/// Options for launching Claude Code
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClaudeStartOptions {
+    pub working_dir: Option<String>,
+    /// Extra command-line arguments passed to Claude Code
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables (e.g. `ANTHROPIC_API_KEY`)
+    #[serde(default)]
+    pub env: Vec<(String, String)>,
+    /// Initial terminal width, so the TUI renders correctly before the first resize
+    #[serde(default)]
+    pub cols: Option<u16>,
+    /// Initial terminal height
+    #[serde(default)]
+    pub rows: Option<u16>,
+}
+
+/// PTY size (cols, rows) used when no initial size is given
+pub const DEFAULT_PTY_SIZE: (u16, u16) = (80, 24);
+
+impl ClaudeStartOptions {
+    /// Initial PTY size as (cols, rows), falling back to 80x24
+    pub fn pty_size(&self) -> (u16, u16) {
+        (
+            self.cols.unwrap_or(DEFAULT_PTY_SIZE.0),
+            self.rows.unwrap_or(DEFAULT_PTY_SIZE.1),
+        )
+    }
+}
+
+/// Check a working directory before spawning, so a bad path fails with a clear
+/// error rather than an opaque PTY one. Returns the canonical path.
+pub fn validate_working_dir(dir: &str) -> Result<PathBuf, ClaudeError> {
+    let path = Path::new(dir);
+    if !path.is_absolute() {
+        return Err(ClaudeError::InvalidWorkingDir(format!(
+            "{} is a relative path",
+            dir
+        )));
+    }
+
+    let canonical = path
+        .canonicalize()
+        .map_err(|_| ClaudeError::WorkingDirNotFound(dir.to_string()))?;
+    if !canonical.is_dir() {
+        return Err(ClaudeError::InvalidWorkingDir(format!(
+            "{} is not a directory",
+            dir
+        )));
+    }
+
+    Ok(canonical)
+}
+
+/// Time allowed for `claude --version` before it is killed
+pub const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs short-lived commands outside the PTY (enables testing)
+pub trait CommandRunner: Send + Sync {
+    /// Stdout of `program` run with `args`, killing it after `timeout`
+    fn run(&self, program: &str, args: &[&str], timeout: Duration) -> Result<String, ClaudeError>;
+}
+
+/// Real command runner using std::process
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(&self, program: &str, args: &[&str], timeout: Duration) -> Result<String, ClaudeError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => ClaudeError::ClaudeNotFound,
+                _ => ClaudeError::VersionError(e.to_string()),
+            })?;
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(ClaudeError::VersionTimeout);
+                }
+                Ok(None) => std::thread::sleep(SHUTDOWN_POLL_INTERVAL),
+                Err(e) => return Err(ClaudeError::VersionError(e.to_string())),
+            }
+        };
+
+        if !status.success() {
+            return Err(ClaudeError::VersionError(format!(
+                "{} exited with {}",
+                program, status
+            )));
+        }
+
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)
+                .map_err(|e| ClaudeError::VersionError(e.to_string()))?;
+        }
+        Ok(stdout)
+    }
+}
+
+/// CPU and memory use of a process
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    pub pid: u32,
+    /// Percent of one core; above 100 when several cores are busy
+    pub cpu_percent: f32,
+    /// Resident memory
+    pub rss_bytes: u64,
+}
+
+/// Samples the resource use of processes (enables testing)
+pub trait ProcessMonitor: Send + Sync {
+    /// Current use of process `pid`, or None if it no longer exists
+    fn usage(&self, pid: u32) -> Option<ResourceUsage>;
+}
+
+/// Real process monitor using sysinfo. CPU use is measured between samples,
+/// so the first sample of a process reports 0%.
+#[derive(Default)]
+pub struct SysinfoProcessMonitor {
+    system: Mutex<sysinfo::System>,
+}
+
+impl ProcessMonitor for SysinfoProcessMonitor {
+    fn usage(&self, pid: u32) -> Option<ResourceUsage> {
+        let mut system = self.system.lock();
+        let sys_pid = sysinfo::Pid::from_u32(pid);
+        if !system.refresh_process(sys_pid) {
+            return None;
+        }
+        let process = system.process(sys_pid)?;
+        Some(ResourceUsage {
+            pid,
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+        })
+    }
+}
+
+/// Version number from `claude --version` output such as "1.0.17 (Claude Code)"
+pub fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|token| token.trim_start_matches('v'))
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()) && token.contains('.'))
+        .map(str::to_string)
+}
+
+/// Run `program --version` and parse the reported version
+pub fn query_version(runner: &dyn CommandRunner, program: &str) -> Result<String, ClaudeError> {
+    let output = runner.run(program, &["--version"], VERSION_TIMEOUT)?;
+    parse_version(&output).ok_or_else(|| {
+        ClaudeError::VersionError(format!("Unrecognized version output: {}", output.trim()))
+    })
+}
+
 /// Trait for Claude Code process management (enables testing)
 pub trait ClaudeProcess: Send + Sync {
-    fn start(&self, working_dir: Option<&str>) -> Result<(), ClaudeError>;
+    fn start(&self, working_dir: Option<&str>) -> Result<(), ClaudeError> {
+        self.start_with(ClaudeStartOptions {
+            working_dir: working_dir.map(|d| d.to_string()),
+            ..Default::default()
+        })
+    }
+    fn start_with(&self, options: ClaudeStartOptions) -> Result<(), ClaudeError>;
     fn stop(&self) -> Result<(), ClaudeError>;
     fn send_input(&self, input: &str) -> Result<(), ClaudeError>;
     fn resize(&self, cols: u16, rows: u16) -> Result<(), ClaudeError>;
     fn status(&self) -> ProcessStatus;
+    /// Exit code of the most recently reaped process, if any
+    fn exit_code(&self) -> Option<i32>;
     fn set_output_callback(&self, callback: Arc<dyn Fn(OutputEvent) + Send + Sync>);
+    /// Populate `OutputEvent.plain` with ANSI-stripped text
+    fn set_strip_ansi(&self, enabled: bool);
+    /// Append session output and input to a log file (None disables logging)
+    fn set_log_path(&self, path: Option<PathBuf>) -> Result<(), ClaudeError>;
+    /// Size at which the session log is rotated and how many old logs to keep
+    fn set_log_rotation(&self, rotation: LogRotation);
+    /// Respawn with the same working directory when the process exits with a failure
+    fn set_auto_restart(&self, enabled: bool);
+    /// Called with the attempt number each time the process is restarted automatically
+    fn set_restart_callback(&self, callback: Arc<dyn Fn(u32) + Send + Sync>);
+    /// Called with the number of output bytes dropped because the output
+    /// callback fell behind
+    fn set_output_dropped_callback(&self, callback: Arc<dyn Fn(u64) + Send + Sync>);
+    /// Use this binary instead of searching PATH (None restores the PATH search)
+    fn set_binary_path(&self, path: Option<PathBuf>);
+    /// How long `stop` waits for Claude to exit after Ctrl-C before killing it (0 kills immediately)
+    fn set_shutdown_grace_ms(&self, ms: u32);
+    /// How long writing input may block before failing with a timeout, e.g.
+    /// when Claude stops reading (0 waits forever)
+    fn set_write_timeout_ms(&self, ms: u32);
+    /// Bytes read from the PTY at a time (at least 1). Larger reads mean fewer,
+    /// bigger output events.
+    fn set_read_buffer_size(&self, bytes: usize);
+    /// Most recent output, so a reloaded UI can repaint the terminal
+    fn get_recent_output(&self) -> String;
+    /// Maximum number of bytes kept by `get_recent_output`
+    fn set_scrollback_limit(&self, bytes: usize);
+    /// Non-blocking check that the process hasn't exited. An exit the output
+    /// reader hasn't noticed yet is recorded and the status updated.
+    fn is_alive(&self) -> bool;
+    /// Seconds since the current process was spawned (0 when not running)
+    fn uptime_secs(&self) -> u64;
+    /// OS process id of the running Claude process
+    fn pid(&self) -> Option<u32>;
+    /// Version of the Claude binary this process would launch, from `--version`
+    fn detect_version(&self) -> Result<String, ClaudeError>;
+}
+
+/// Output chunks queued between the PTY reader and the emitter thread, about
+/// 1MB of 4KB reads
+pub const OUTPUT_QUEUE_CHUNKS: usize = 256;
+
+/// How soon dropped output is reported when no more output arrives
+const DROP_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bounded queue from the PTY reader to a thread that runs the output callback.
+///
+/// A slow consumer (e.g. a busy webview) can't stall the reader or grow memory
+/// without limit: output that doesn't fit is dropped, and the dropped byte count
+/// is passed to `on_dropped` once the emitter catches up.
+pub struct OutputPump {
+    sender: SyncSender<OutputEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl OutputPump {
+    pub fn new(
+        capacity: usize,
+        deliver: impl Fn(OutputEvent) + Send + 'static,
+        on_dropped: impl Fn(u64) + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let pending = dropped.clone();
+
+        std::thread::spawn(move || {
+            // Drops in a row are coalesced into one report
+            let report = || {
+                let bytes = pending.swap(0, Ordering::SeqCst);
+                if bytes > 0 {
+                    on_dropped(bytes);
+                }
+            };
+
+            loop {
+                match receiver.recv_timeout(DROP_REPORT_INTERVAL) {
+                    Ok(event) => {
+                        deliver(event);
+                        report();
+                    }
+                    Err(RecvTimeoutError::Timeout) => report(),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        report();
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self { sender, dropped }
+    }
+
+    /// Queue `event` without blocking, dropping it if the queue is full
+    pub fn send(&self, event: OutputEvent) {
+        if let Err(TrySendError::Full(event)) = self.sender.try_send(event) {
+            self.dropped
+                .fetch_add(event.data.len() as u64, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Maximum number of automatic restarts before giving up
+pub const MAX_AUTO_RESTARTS: u32 = 3;
+
+/// Default time allowed for Claude to exit after Ctrl-C
+pub const DEFAULT_SHUTDOWN_GRACE_MS: u32 = 2000;
+
+/// Ctrl-C as written to the PTY
+const CTRL_C: &[u8] = b"\x03";
+
+/// Polling interval while waiting for a graceful exit
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Default time a write to Claude's input may block before it fails
+pub const DEFAULT_WRITE_TIMEOUT_MS: u32 = 5000;
+
+/// Why writes fail after one timed out
+const POISONED_WRITE: &str = "an earlier write timed out; restart Claude to send more input";
+
+/// Bytes to write and where to report how the write went
+type WriteJob = (Vec<u8>, mpsc::Sender<std::io::Result<()>>);
+
+/// Writes to the PTY on a worker thread, so a write Claude isn't draining
+/// fails after a timeout instead of blocking the caller forever
+struct PtyWriter {
+    jobs: mpsc::Sender<WriteJob>,
+    /// Set once a write times out; the writer then refuses everything else
+    poisoned: Arc<AtomicBool>,
+}
+
+impl PtyWriter {
+    fn new(mut writer: Box<dyn Write + Send>) -> Self {
+        let (jobs, queue) = mpsc::channel::<WriteJob>();
+        let poisoned = Arc::new(AtomicBool::new(false));
+        let skip = poisoned.clone();
+        std::thread::spawn(move || {
+            for (data, done) in queue {
+                let written = if skip.load(Ordering::SeqCst) {
+                    Err(std::io::Error::other(POISONED_WRITE))
+                } else {
+                    writer.write_all(&data).and_then(|()| writer.flush())
+                };
+                let _ = done.send(written);
+            }
+        });
+        Self { jobs, poisoned }
+    }
+
+    /// Write and flush `data`, waiting at most `timeout` (zero waits forever).
+    /// A write that timed out still lands if the PTY drains, so after a
+    /// timeout every other write fails until Claude is restarted; otherwise
+    /// retrying the input would type it twice.
+    fn write(&self, data: &[u8], timeout: Duration) -> Result<(), ClaudeError> {
+        if self.poisoned.load(Ordering::SeqCst) {
+            return Err(ClaudeError::WriteError(POISONED_WRITE.to_string()));
+        }
+        let (done, result) = mpsc::channel();
+        self.jobs
+            .send((data.to_vec(), done))
+            .map_err(|_| ClaudeError::NotRunning)?;
+
+        let result = if timeout.is_zero() {
+            result.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            result.recv_timeout(timeout)
+        };
+        match result {
+            Ok(written) => written.map_err(|e| ClaudeError::WriteError(e.to_string())),
+            Err(RecvTimeoutError::Timeout) => {
+                self.poisoned.store(true, Ordering::SeqCst);
+                Err(ClaudeError::WriteError("timed out".to_string()))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(ClaudeError::NotRunning),
+        }
+    }
 }
 
 /// Real Claude Code process implementation
 pub struct ClaudeCodeProcess {
+    inner: Arc<ProcessInner>,
+    command_runner: Arc<dyn CommandRunner>,
+}
+
+/// Process state shared with the output reader thread.
+///
+/// Callbacks and output settings live here rather than with a spawned process,
+/// so the reader thread of an automatic restart keeps delivering to them.
+struct ProcessInner {
     master: Mutex<Option<Box<dyn portable_pty::MasterPty + Send>>>,
     child: Mutex<Option<Box<dyn portable_pty::Child + Send + Sync>>>,
-    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    writer: Mutex<Option<PtyWriter>>,
+    write_timeout_ms: AtomicU32,
+    read_buffer_size: AtomicUsize,
     status: Mutex<ProcessStatus>,
-    running: Arc<AtomicBool>,
+    exit_code: Mutex<Option<i32>>,
+    running: AtomicBool,
     output_callback: Mutex<Option<Arc<dyn Fn(OutputEvent) + Send + Sync>>>,
+    strip_ansi: AtomicBool,
+    session_log: Mutex<Option<SessionLog>>,
+    log_rotation: Mutex<LogRotation>,
+    options: Mutex<ClaudeStartOptions>,
+    binary_path: Mutex<Option<PathBuf>>,
+    auto_restart: AtomicBool,
+    restart_count: AtomicU32,
+    restart_callback: Mutex<Option<Arc<dyn Fn(u32) + Send + Sync>>>,
+    output_dropped_callback: Mutex<Option<Arc<dyn Fn(u64) + Send + Sync>>>,
+    /// Incremented on every spawn so stale reader threads can tell they are outdated
+    generation: AtomicU64,
+    shutdown_grace_ms: AtomicU32,
+    scrollback: Mutex<Scrollback>,
+    started_at: Mutex<Option<Instant>>,
 }
 
 impl ClaudeCodeProcess {
     pub fn new() -> Self {
         Self {
-            master: Mutex::new(None),
-            child: Mutex::new(None),
-            writer: Mutex::new(None),
-            status: Mutex::new(ProcessStatus::Stopped),
-            running: Arc::new(AtomicBool::new(false)),
-            output_callback: Mutex::new(None),
+            inner: Arc::new(ProcessInner {
+                master: Mutex::new(None),
+                child: Mutex::new(None),
+                writer: Mutex::new(None),
+                write_timeout_ms: AtomicU32::new(DEFAULT_WRITE_TIMEOUT_MS),
+                read_buffer_size: AtomicUsize::new(DEFAULT_READ_BUFFER_BYTES),
+                status: Mutex::new(ProcessStatus::Stopped),
+                exit_code: Mutex::new(None),
+                running: AtomicBool::new(false),
+                output_callback: Mutex::new(None),
+                strip_ansi: AtomicBool::new(false),
+                session_log: Mutex::new(None),
+                log_rotation: Mutex::new(LogRotation::default()),
+                options: Mutex::new(ClaudeStartOptions::default()),
+                binary_path: Mutex::new(None),
+                auto_restart: AtomicBool::new(false),
+                restart_count: AtomicU32::new(0),
+                restart_callback: Mutex::new(None),
+                output_dropped_callback: Mutex::new(None),
+                generation: AtomicU64::new(0),
+                shutdown_grace_ms: AtomicU32::new(DEFAULT_SHUTDOWN_GRACE_MS),
+                scrollback: Mutex::new(Scrollback::new(DEFAULT_SCROLLBACK_BYTES)),
+                started_at: Mutex::new(None),
+            }),
+            command_runner: Arc::new(SystemCommandRunner),
+        }
+    }
+
+    /// Use a different runner for short-lived commands like `--version`
+    pub fn with_command_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.command_runner = runner;
+        self
+    }
+
+    /// Check that an explicitly configured Claude binary exists and is executable
+    pub fn validate_binary_path(path: &Path) -> Result<(), ClaudeError> {
+        if !path.is_file() {
+            return Err(ClaudeError::ClaudeNotFound);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path)
+                .map_err(|_| ClaudeError::ClaudeNotFound)?
+                .permissions()
+                .mode();
+            if mode & 0o111 == 0 {
+                return Err(ClaudeError::ClaudeNotFound);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Use the explicit binary path if configured, otherwise search PATH
+    fn resolve_command(binary_path: Option<&Path>) -> Result<String, ClaudeError> {
+        match binary_path {
+            Some(path) => {
+                Self::validate_binary_path(path)?;
+                Ok(path.to_string_lossy().to_string())
+            }
+            None => Self::find_claude_command(),
         }
     }
 
@@ -98,39 +954,78 @@ impl ClaudeCodeProcess {
 
         Err(ClaudeError::ClaudeNotFound)
     }
-}
 
-impl Default for ClaudeCodeProcess {
-    fn default() -> Self {
-        Self::new()
+    fn build_command(program: &str, options: &ClaudeStartOptions) -> CommandBuilder {
+        let mut cmd = CommandBuilder::new(program);
+
+        cmd.args(&options.args);
+
+        for (key, value) in &options.env {
+            cmd.env(key, value);
+        }
+
+        if let Some(dir) = &options.working_dir {
+            cmd.cwd(dir);
+        }
+
+        cmd
     }
 }
 
-impl ClaudeProcess for ClaudeCodeProcess {
-    fn start(&self, working_dir: Option<&str>) -> Result<(), ClaudeError> {
-        if self.running.load(Ordering::SeqCst) {
-            return Ok(());
+impl ProcessInner {
+    /// Write Ctrl-C to the PTY and wait up to the grace period for the child to exit.
+    /// Returns None if it is still running (or on Windows, where we kill directly).
+    fn interrupt_and_wait(
+        &self,
+        child: &mut (dyn portable_pty::Child + Send + Sync),
+    ) -> Option<portable_pty::ExitStatus> {
+        let grace = Duration::from_millis(self.shutdown_grace_ms.load(Ordering::SeqCst) as u64);
+        if cfg!(windows) || grace.is_zero() {
+            return None;
+        }
+
+        // Claude Code exits on the second Ctrl-C; the first clears the prompt
+        for _ in 0..2 {
+            if let Some(writer) = self.writer.lock().as_ref() {
+                let _ = writer.write(CTRL_C, self.write_timeout());
+            }
+            std::thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            match child.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) => std::thread::sleep(SHUTDOWN_POLL_INTERVAL),
+                Err(_) => return None,
+            }
         }
 
+        None
+    }
+
+    fn write_timeout(&self) -> Duration {
+        Duration::from_millis(self.write_timeout_ms.load(Ordering::SeqCst) as u64)
+    }
+
+    fn spawn(self: &Arc<Self>, options: ClaudeStartOptions) -> Result<(), ClaudeError> {
         *self.status.lock() = ProcessStatus::Starting;
 
-        let claude_cmd = Self::find_claude_command()?;
+        let binary_path = self.binary_path.lock().clone();
+        let claude_cmd = ClaudeCodeProcess::resolve_command(binary_path.as_deref())?;
 
+        let (cols, rows) = options.pty_size();
         let pty_system = native_pty_system();
         let pair = pty_system
             .openpty(PtySize {
-                rows: 24,
-                cols: 80,
+                rows,
+                cols,
                 pixel_width: 0,
                 pixel_height: 0,
             })
             .map_err(|e| ClaudeError::PtySpawnError(e.to_string()))?;
 
-        let mut cmd = CommandBuilder::new(&claude_cmd);
-
-        if let Some(dir) = working_dir {
-            cmd.cwd(dir);
-        }
+        let cmd = ClaudeCodeProcess::build_command(&claude_cmd, &options);
 
         let child = pair
             .slave
@@ -149,84 +1044,228 @@ impl ClaudeProcess for ClaudeCodeProcess {
 
         *self.master.lock() = Some(pair.master);
         *self.child.lock() = Some(child);
-        *self.writer.lock() = Some(writer);
+        *self.writer.lock() = Some(PtyWriter::new(writer));
+        *self.options.lock() = options;
+        *self.exit_code.lock() = None;
+        *self.started_at.lock() = Some(Instant::now());
         *self.status.lock() = ProcessStatus::Running;
         self.running.store(true, Ordering::SeqCst);
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
 
         // Start output reader thread
-        let callback = self.output_callback.lock().clone();
-        let running = self.running.clone();
-
-        std::thread::spawn(move || {
-            let mut reader = BufReader::new(reader);
-            let mut buffer = [0u8; 4096];
-
-            while running.load(Ordering::SeqCst) {
-                match std::io::Read::read(&mut reader, &mut buffer) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        let data = String::from_utf8_lossy(&buffer[..n]).to_string();
-                        if let Some(ref cb) = callback {
-                            cb(OutputEvent {
-                                data,
-                                is_error: false,
-                            });
-                        }
-                    }
-                    Err(e) => {
-                        if let Some(ref cb) = callback {
-                            cb(OutputEvent {
-                                data: format!("Read error: {}", e),
-                                is_error: true,
-                            });
-                        }
-                        break;
-                    }
-                }
-            }
-        });
+        let inner = self.clone();
+        std::thread::spawn(move || inner.read_output(reader, generation));
 
         Ok(())
     }
 
-    fn stop(&self) -> Result<(), ClaudeError> {
-        self.running.store(false, Ordering::SeqCst);
-
-        if let Some(mut child) = self.child.lock().take() {
-            let _ = child.kill();
-            let _ = child.wait();
-        }
+    fn read_output(self: Arc<Self>, reader: Box<dyn std::io::Read + Send>, generation: u64) {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        let mut decoder = Utf8Decoder::default();
+
+        // The callbacks are looked up on delivery so they can be replaced while running
+        let output = self.clone();
+        let dropped = self.clone();
+        let pump = OutputPump::new(
+            OUTPUT_QUEUE_CHUNKS,
+            move |event| {
+                let callback = output.output_callback.lock().clone();
+                if let Some(cb) = callback {
+                    cb(event);
+                }
+            },
+            move |bytes| {
+                let callback = dropped.output_dropped_callback.lock().clone();
+                if let Some(cb) = callback {
+                    cb(bytes);
+                }
+            },
+        );
+
+        while self.running.load(Ordering::SeqCst) {
+            buffer.resize(self.read_buffer_size.load(Ordering::SeqCst).max(1), 0);
+            match std::io::Read::read(&mut reader, &mut buffer) {
+                Ok(0) => {
+                    // EOF
+                    self.deliver_output(&pump, decoder.finish());
+                    break;
+                }
+                Ok(n) => self.deliver_output(&pump, decoder.decode(&buffer[..n])),
+                Err(e) => {
+                    pump.send(OutputEvent {
+                        data: format!("Read error: {}", e),
+                        is_error: true,
+                        plain: None,
+                    });
+                    break;
+                }
+            }
+        }
+
+        // The stream ended without `stop` being called: the process exited on its own
+        if self.generation.load(Ordering::SeqCst) == generation
+            && self.running.swap(false, Ordering::SeqCst)
+        {
+            self.handle_unexpected_exit();
+        }
+    }
+
+    fn deliver_output(&self, pump: &OutputPump, data: String) {
+        if data.is_empty() {
+            return;
+        }
+
+        if let Some(log) = self.session_log.lock().as_ref() {
+            log.log_output(&data);
+        }
+        self.scrollback.lock().push(&data);
+        let plain = self
+            .strip_ansi
+            .load(Ordering::SeqCst)
+            .then(|| strip_ansi(&data));
+        pump.send(OutputEvent {
+            data,
+            is_error: false,
+            plain,
+        });
+    }
+
+    /// Poll the child without blocking, handling an exit if it has quit
+    fn check_alive(self: &Arc<Self>) -> bool {
+        if !self.running.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let exited = match self.child.lock().as_mut() {
+            Some(child) => matches!(child.try_wait(), Ok(Some(_))),
+            None => return false,
+        };
+        if !exited {
+            return true;
+        }
+
+        // The PTY can stay open after Claude exits (e.g. a leftover subprocess
+        // holds it), in which case the reader thread never sees EOF
+        if self.running.swap(false, Ordering::SeqCst) {
+            self.handle_unexpected_exit();
+        }
+        false
+    }
+
+    fn handle_unexpected_exit(self: &Arc<Self>) {
+        let child = self.child.lock().take();
+        let exit_code = child
+            .and_then(|mut c| c.wait().ok())
+            .map(|s| s.exit_code() as i32);
 
         *self.master.lock() = None;
         *self.writer.lock() = None;
-        *self.status.lock() = ProcessStatus::Stopped;
+        *self.exit_code.lock() = exit_code;
+
+        if let Some(log) = self.session_log.lock().as_ref() {
+            log.flush();
+        }
+
+        *self.status.lock() = match exit_code {
+            Some(code) => ProcessStatus::Exited(code),
+            None => ProcessStatus::Error("Claude Code exited unexpectedly".to_string()),
+        };
+
+        // A clean exit means the user quit Claude; only failures are restarted
+        if exit_code == Some(0) {
+            return;
+        }
+
+        if !self.auto_restart.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let attempt = self.restart_count.fetch_add(1, Ordering::SeqCst) + 1;
+        if attempt > MAX_AUTO_RESTARTS {
+            return;
+        }
+
+        let options = self.options.lock().clone();
+        match self.spawn(options) {
+            Ok(()) => {
+                let callback = self.restart_callback.lock().clone();
+                if let Some(cb) = callback {
+                    cb(attempt);
+                }
+            }
+            Err(e) => *self.status.lock() = ProcessStatus::Error(e.to_string()),
+        }
+    }
+}
+
+impl Default for ClaudeCodeProcess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClaudeProcess for ClaudeCodeProcess {
+    fn start_with(&self, options: ClaudeStartOptions) -> Result<(), ClaudeError> {
+        if self.inner.running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        self.inner.restart_count.store(0, Ordering::SeqCst);
+        self.inner.scrollback.lock().clear();
+        self.inner.spawn(options)
+    }
+
+    fn stop(&self) -> Result<(), ClaudeError> {
+        self.inner.running.store(false, Ordering::SeqCst);
+
+        let child = self.inner.child.lock().take();
+        if let Some(mut child) = child {
+            // Give Claude a chance to finish in-progress edits before force-killing
+            let status = match self.inner.interrupt_and_wait(child.as_mut()) {
+                Some(status) => Some(status),
+                None => {
+                    let _ = child.kill();
+                    child.wait().ok()
+                }
+            };
+
+            if let Some(status) = status {
+                *self.inner.exit_code.lock() = Some(status.exit_code() as i32);
+            }
+        }
+
+        *self.inner.master.lock() = None;
+        *self.inner.writer.lock() = None;
+        *self.inner.status.lock() = ProcessStatus::Stopped;
+
+        if let Some(log) = self.inner.session_log.lock().as_ref() {
+            log.flush();
+        }
 
         Ok(())
     }
 
     fn send_input(&self, input: &str) -> Result<(), ClaudeError> {
-        if !self.running.load(Ordering::SeqCst) {
+        if !self.inner.running.load(Ordering::SeqCst) {
             return Err(ClaudeError::NotRunning);
         }
 
-        let mut writer_guard = self.writer.lock();
-        let writer = writer_guard
-            .as_mut()
-            .ok_or(ClaudeError::NotRunning)?;
-
+        let writer = self.inner.writer.lock();
         writer
-            .write_all(input.as_bytes())
-            .map_err(|e| ClaudeError::WriteError(e.to_string()))?;
+            .as_ref()
+            .ok_or(ClaudeError::NotRunning)?
+            .write(input.as_bytes(), self.inner.write_timeout())?;
 
-        writer
-            .flush()
-            .map_err(|e| ClaudeError::WriteError(e.to_string()))?;
+        if let Some(log) = self.inner.session_log.lock().as_ref() {
+            log.log_input(input);
+        }
 
         Ok(())
     }
 
     fn resize(&self, cols: u16, rows: u16) -> Result<(), ClaudeError> {
-        let master_guard = self.master.lock();
+        let master_guard = self.inner.master.lock();
         let master = master_guard.as_ref().ok_or(ClaudeError::NotRunning)?;
 
         master
@@ -240,11 +1279,96 @@ impl ClaudeProcess for ClaudeCodeProcess {
     }
 
     fn status(&self) -> ProcessStatus {
-        self.status.lock().clone()
+        self.inner.status.lock().clone()
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        *self.inner.exit_code.lock()
     }
 
     fn set_output_callback(&self, callback: Arc<dyn Fn(OutputEvent) + Send + Sync>) {
-        *self.output_callback.lock() = Some(callback);
+        *self.inner.output_callback.lock() = Some(callback);
+    }
+
+    fn set_strip_ansi(&self, enabled: bool) {
+        self.inner.strip_ansi.store(enabled, Ordering::SeqCst);
+    }
+
+    fn set_log_path(&self, path: Option<PathBuf>) -> Result<(), ClaudeError> {
+        let rotation = *self.inner.log_rotation.lock();
+        let log = path.map(|p| SessionLog::open(&p, rotation)).transpose()?;
+        *self.inner.session_log.lock() = log;
+        Ok(())
+    }
+
+    fn set_log_rotation(&self, rotation: LogRotation) {
+        *self.inner.log_rotation.lock() = rotation;
+        if let Some(log) = self.inner.session_log.lock().as_ref() {
+            log.set_rotation(rotation);
+        }
+    }
+
+    fn set_auto_restart(&self, enabled: bool) {
+        self.inner.auto_restart.store(enabled, Ordering::SeqCst);
+    }
+
+    fn set_restart_callback(&self, callback: Arc<dyn Fn(u32) + Send + Sync>) {
+        *self.inner.restart_callback.lock() = Some(callback);
+    }
+
+    fn set_output_dropped_callback(&self, callback: Arc<dyn Fn(u64) + Send + Sync>) {
+        *self.inner.output_dropped_callback.lock() = Some(callback);
+    }
+
+    fn set_binary_path(&self, path: Option<PathBuf>) {
+        *self.inner.binary_path.lock() = path;
+    }
+
+    fn set_shutdown_grace_ms(&self, ms: u32) {
+        self.inner.shutdown_grace_ms.store(ms, Ordering::SeqCst);
+    }
+
+    fn set_write_timeout_ms(&self, ms: u32) {
+        self.inner.write_timeout_ms.store(ms, Ordering::SeqCst);
+    }
+
+    fn set_read_buffer_size(&self, bytes: usize) {
+        self.inner.read_buffer_size.store(bytes, Ordering::SeqCst);
+    }
+
+    fn get_recent_output(&self) -> String {
+        self.inner.scrollback.lock().contents()
+    }
+
+    fn set_scrollback_limit(&self, bytes: usize) {
+        self.inner.scrollback.lock().set_capacity(bytes);
+    }
+
+    fn is_alive(&self) -> bool {
+        self.inner.check_alive()
+    }
+
+    fn uptime_secs(&self) -> u64 {
+        if !self.inner.running.load(Ordering::SeqCst) {
+            return 0;
+        }
+        self.inner
+            .started_at
+            .lock()
+            .map_or(0, |started| started.elapsed().as_secs())
+    }
+
+    fn pid(&self) -> Option<u32> {
+        if !self.inner.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.inner.child.lock().as_ref()?.process_id()
+    }
+
+    fn detect_version(&self) -> Result<String, ClaudeError> {
+        let binary_path = self.inner.binary_path.lock().clone();
+        let program = Self::resolve_command(binary_path.as_deref())?;
+        query_version(self.command_runner.as_ref(), &program)
     }
 }
 
@@ -256,6 +1380,7 @@ pub(crate) mod tests {
     /// Mock Claude process for testing
     pub struct MockClaudeProcess {
         status: Mutex<ProcessStatus>,
+        exit_code: Mutex<Option<i32>>,
         running: AtomicBool,
         input_history: Mutex<Vec<String>>,
         output_callback: Mutex<Option<Arc<dyn Fn(OutputEvent) + Send + Sync>>>,
@@ -263,22 +1388,127 @@ pub(crate) mod tests {
         start_count: AtomicUsize,
         stop_count: AtomicUsize,
         current_size: Mutex<(u16, u16)>,
+        strip_ansi: AtomicBool,
+        session_log: Mutex<Option<SessionLog>>,
+        log_rotation: Mutex<LogRotation>,
+        last_options: Mutex<Option<ClaudeStartOptions>>,
+        binary_path: Mutex<Option<PathBuf>>,
+        resolved_command: Mutex<Option<String>>,
+        path_lookup_count: AtomicUsize,
+        auto_restart: AtomicBool,
+        restart_count: AtomicU32,
+        restart_callback: Mutex<Option<Arc<dyn Fn(u32) + Send + Sync>>>,
+        output_dropped_callback: Mutex<Option<Arc<dyn Fn(u64) + Send + Sync>>>,
+        shutdown_grace_ms: AtomicU32,
+        write_timeout_ms: AtomicU32,
+        read_buffer_size: AtomicUsize,
+        ignore_interrupt: AtomicBool,
+        stop_actions: Mutex<Vec<StopAction>>,
+        scrollback: Mutex<Scrollback>,
+        started_at: Mutex<Option<Instant>>,
+        /// Exit code of a process that quit without the reader seeing EOF
+        pending_exit: Mutex<Option<i32>>,
+        /// Reported by `detect_version`; None simulates a missing binary
+        version: Mutex<Option<String>>,
+    }
+
+    /// Steps taken by the mock while stopping
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum StopAction {
+        Interrupt,
+        ExitedGracefully,
+        Kill,
+    }
+
+    /// Process id the mock reports while running
+    pub const MOCK_PID: u32 = 4242;
+
+    /// Process monitor reporting fixed usage for the processes it was given
+    #[derive(Default)]
+    pub struct MockProcessMonitor {
+        processes: Mutex<Vec<ResourceUsage>>,
+    }
+
+    impl MockProcessMonitor {
+        pub fn add(&self, usage: ResourceUsage) {
+            self.processes.lock().push(usage);
+        }
+    }
+
+    impl ProcessMonitor for MockProcessMonitor {
+        fn usage(&self, pid: u32) -> Option<ResourceUsage> {
+            self.processes
+                .lock()
+                .iter()
+                .find(|usage| usage.pid == pid)
+                .cloned()
+        }
     }
 
     impl MockClaudeProcess {
         pub fn new() -> Self {
             Self {
                 status: Mutex::new(ProcessStatus::Stopped),
+                exit_code: Mutex::new(None),
                 running: AtomicBool::new(false),
                 input_history: Mutex::new(Vec::new()),
                 output_callback: Mutex::new(None),
                 should_fail: AtomicBool::new(false),
                 start_count: AtomicUsize::new(0),
                 stop_count: AtomicUsize::new(0),
-                current_size: Mutex::new((80, 24)),
+                current_size: Mutex::new(DEFAULT_PTY_SIZE),
+                strip_ansi: AtomicBool::new(false),
+                session_log: Mutex::new(None),
+                log_rotation: Mutex::new(LogRotation::default()),
+                last_options: Mutex::new(None),
+                binary_path: Mutex::new(None),
+                resolved_command: Mutex::new(None),
+                path_lookup_count: AtomicUsize::new(0),
+                auto_restart: AtomicBool::new(false),
+                restart_count: AtomicU32::new(0),
+                restart_callback: Mutex::new(None),
+                output_dropped_callback: Mutex::new(None),
+                shutdown_grace_ms: AtomicU32::new(DEFAULT_SHUTDOWN_GRACE_MS),
+                write_timeout_ms: AtomicU32::new(DEFAULT_WRITE_TIMEOUT_MS),
+                read_buffer_size: AtomicUsize::new(DEFAULT_READ_BUFFER_BYTES),
+                ignore_interrupt: AtomicBool::new(false),
+                stop_actions: Mutex::new(Vec::new()),
+                scrollback: Mutex::new(Scrollback::new(DEFAULT_SCROLLBACK_BYTES)),
+                started_at: Mutex::new(None),
+                pending_exit: Mutex::new(None),
+                version: Mutex::new(Some("1.0.0".to_string())),
             }
         }
 
+        pub fn set_version(&self, version: Option<&str>) {
+            *self.version.lock() = version.map(str::to_string);
+        }
+
+        /// Simulate a process that keeps running after Ctrl-C
+        pub fn set_ignore_interrupt(&self, ignore: bool) {
+            self.ignore_interrupt.store(ignore, Ordering::SeqCst);
+        }
+
+        pub fn stop_actions(&self) -> Vec<StopAction> {
+            self.stop_actions.lock().clone()
+        }
+
+        pub fn shutdown_grace_ms(&self) -> u32 {
+            self.shutdown_grace_ms.load(Ordering::SeqCst)
+        }
+
+        pub fn write_timeout_ms(&self) -> u32 {
+            self.write_timeout_ms.load(Ordering::SeqCst)
+        }
+
+        pub fn read_buffer_size(&self) -> usize {
+            self.read_buffer_size.load(Ordering::SeqCst)
+        }
+
+        pub fn log_rotation(&self) -> LogRotation {
+            *self.log_rotation.lock()
+        }
+
         pub fn set_should_fail(&self, fail: bool) {
             self.should_fail.store(fail, Ordering::SeqCst);
         }
@@ -299,18 +1529,64 @@ pub(crate) mod tests {
             *self.current_size.lock()
         }
 
-        pub fn simulate_output(&self, data: &str, is_error: bool) {
-            if let Some(cb) = self.output_callback.lock().as_ref() {
-                cb(OutputEvent {
-                    data: data.to_string(),
-                    is_error,
-                });
+        pub fn working_dir(&self) -> Option<String> {
+            self.last_options
+                .lock()
+                .as_ref()
+                .and_then(|o| o.working_dir.clone())
+        }
+
+        pub fn last_options(&self) -> Option<ClaudeStartOptions> {
+            self.last_options.lock().clone()
+        }
+
+        pub fn resolved_command(&self) -> Option<String> {
+            self.resolved_command.lock().clone()
+        }
+
+        pub fn path_lookup_count(&self) -> usize {
+            self.path_lookup_count.load(Ordering::SeqCst)
+        }
+
+        /// Simulate the process exiting on its own, mirroring the real reader thread
+        pub fn simulate_exit(&self, code: i32) {
+            if !self.running.swap(false, Ordering::SeqCst) {
+                return;
+            }
+
+            *self.exit_code.lock() = Some(code);
+            *self.status.lock() = ProcessStatus::Exited(code);
+
+            if code == 0 || !self.auto_restart.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let attempt = self.restart_count.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt > MAX_AUTO_RESTARTS {
+                return;
+            }
+
+            let options = self.last_options.lock().clone().unwrap_or_default();
+            if self.start_session(options).is_ok() {
+                let callback = self.restart_callback.lock().clone();
+                if let Some(cb) = callback {
+                    cb(attempt);
+                }
             }
         }
-    }
 
-    impl ClaudeProcess for MockClaudeProcess {
-        fn start(&self, _working_dir: Option<&str>) -> Result<(), ClaudeError> {
+        /// Simulate the process exiting while the PTY stays open, so the exit
+        /// is only noticed by `is_alive`
+        pub fn simulate_silent_exit(&self, code: i32) {
+            *self.pending_exit.lock() = Some(code);
+        }
+
+        /// Pretend the process was spawned this long ago
+        pub fn set_started_ago(&self, elapsed: Duration) {
+            *self.started_at.lock() = Instant::now().checked_sub(elapsed);
+        }
+
+        fn start_session(&self, options: ClaudeStartOptions) -> Result<(), ClaudeError> {
             self.start_count.fetch_add(1, Ordering::SeqCst);
 
             if self.should_fail.load(Ordering::SeqCst) {
@@ -318,13 +1594,77 @@ pub(crate) mod tests {
                 return Err(ClaudeError::ClaudeNotFound);
             }
 
+            let binary_path = self.binary_path.lock().clone();
+            let command = match binary_path {
+                Some(path) => {
+                    ClaudeCodeProcess::validate_binary_path(&path)?;
+                    path.to_string_lossy().to_string()
+                }
+                None => {
+                    self.path_lookup_count.fetch_add(1, Ordering::SeqCst);
+                    "claude".to_string()
+                }
+            };
+
+            *self.resolved_command.lock() = Some(command);
+            *self.current_size.lock() = options.pty_size();
+            *self.last_options.lock() = Some(options);
+            *self.exit_code.lock() = None;
+            *self.pending_exit.lock() = None;
+            *self.started_at.lock() = Some(Instant::now());
             *self.status.lock() = ProcessStatus::Running;
             self.running.store(true, Ordering::SeqCst);
             Ok(())
         }
 
+        pub fn simulate_output(&self, data: &str, is_error: bool) {
+            if let Some(log) = self.session_log.lock().as_ref() {
+                log.log_output(data);
+            }
+            self.scrollback.lock().push(data);
+            if let Some(cb) = self.output_callback.lock().as_ref() {
+                cb(OutputEvent {
+                    data: data.to_string(),
+                    is_error,
+                    plain: self
+                        .strip_ansi
+                        .load(Ordering::SeqCst)
+                        .then(|| strip_ansi(data)),
+                });
+            }
+        }
+
+        /// Report `bytes` of output dropped by a slow consumer
+        pub fn simulate_output_dropped(&self, bytes: u64) {
+            if let Some(cb) = self.output_dropped_callback.lock().as_ref() {
+                cb(bytes);
+            }
+        }
+    }
+
+    impl ClaudeProcess for MockClaudeProcess {
+        fn start_with(&self, options: ClaudeStartOptions) -> Result<(), ClaudeError> {
+            self.restart_count.store(0, Ordering::SeqCst);
+            self.scrollback.lock().clear();
+            self.start_session(options)
+        }
+
         fn stop(&self) -> Result<(), ClaudeError> {
             self.stop_count.fetch_add(1, Ordering::SeqCst);
+
+            if self.running.load(Ordering::SeqCst) {
+                let mut actions = self.stop_actions.lock();
+                let graceful = self.shutdown_grace_ms.load(Ordering::SeqCst) > 0;
+                if graceful {
+                    actions.push(StopAction::Interrupt);
+                }
+                if graceful && !self.ignore_interrupt.load(Ordering::SeqCst) {
+                    actions.push(StopAction::ExitedGracefully);
+                } else {
+                    actions.push(StopAction::Kill);
+                }
+            }
+
             *self.status.lock() = ProcessStatus::Stopped;
             self.running.store(false, Ordering::SeqCst);
             Ok(())
@@ -339,6 +1679,9 @@ pub(crate) mod tests {
                 return Err(ClaudeError::WriteError("Mock write error".to_string()));
             }
 
+            if let Some(log) = self.session_log.lock().as_ref() {
+                log.log_input(input);
+            }
             self.input_history.lock().push(input.to_string());
             Ok(())
         }
@@ -356,9 +1699,152 @@ pub(crate) mod tests {
             self.status.lock().clone()
         }
 
+        fn exit_code(&self) -> Option<i32> {
+            *self.exit_code.lock()
+        }
+
         fn set_output_callback(&self, callback: Arc<dyn Fn(OutputEvent) + Send + Sync>) {
             *self.output_callback.lock() = Some(callback);
         }
+
+        fn set_strip_ansi(&self, enabled: bool) {
+            self.strip_ansi.store(enabled, Ordering::SeqCst);
+        }
+
+        fn set_log_path(&self, path: Option<PathBuf>) -> Result<(), ClaudeError> {
+            let rotation = *self.log_rotation.lock();
+            let log = path.map(|p| SessionLog::open(&p, rotation)).transpose()?;
+            *self.session_log.lock() = log;
+            Ok(())
+        }
+
+        fn set_log_rotation(&self, rotation: LogRotation) {
+            *self.log_rotation.lock() = rotation;
+            if let Some(log) = self.session_log.lock().as_ref() {
+                log.set_rotation(rotation);
+            }
+        }
+
+        fn set_auto_restart(&self, enabled: bool) {
+            self.auto_restart.store(enabled, Ordering::SeqCst);
+        }
+
+        fn set_restart_callback(&self, callback: Arc<dyn Fn(u32) + Send + Sync>) {
+            *self.restart_callback.lock() = Some(callback);
+        }
+
+        fn set_output_dropped_callback(&self, callback: Arc<dyn Fn(u64) + Send + Sync>) {
+            *self.output_dropped_callback.lock() = Some(callback);
+        }
+
+        fn set_binary_path(&self, path: Option<PathBuf>) {
+            *self.binary_path.lock() = path;
+        }
+
+        fn set_shutdown_grace_ms(&self, ms: u32) {
+            self.shutdown_grace_ms.store(ms, Ordering::SeqCst);
+        }
+
+        fn set_write_timeout_ms(&self, ms: u32) {
+            self.write_timeout_ms.store(ms, Ordering::SeqCst);
+        }
+
+        fn set_read_buffer_size(&self, bytes: usize) {
+            self.read_buffer_size.store(bytes, Ordering::SeqCst);
+        }
+
+        fn get_recent_output(&self) -> String {
+            self.scrollback.lock().contents()
+        }
+
+        fn set_scrollback_limit(&self, bytes: usize) {
+            self.scrollback.lock().set_capacity(bytes);
+        }
+
+        fn is_alive(&self) -> bool {
+            if !self.running.load(Ordering::SeqCst) {
+                return false;
+            }
+
+            let pending = self.pending_exit.lock().take();
+            match pending {
+                Some(code) => {
+                    self.simulate_exit(code);
+                    false
+                }
+                None => true,
+            }
+        }
+
+        fn uptime_secs(&self) -> u64 {
+            if !self.running.load(Ordering::SeqCst) {
+                return 0;
+            }
+            self.started_at
+                .lock()
+                .map_or(0, |started| started.elapsed().as_secs())
+        }
+
+        fn pid(&self) -> Option<u32> {
+            self.running.load(Ordering::SeqCst).then_some(MOCK_PID)
+        }
+
+        fn detect_version(&self) -> Result<String, ClaudeError> {
+            if let Some(path) = self.binary_path.lock().as_deref() {
+                ClaudeCodeProcess::validate_binary_path(path)?;
+            }
+            self.version
+                .lock()
+                .clone()
+                .ok_or(ClaudeError::ClaudeNotFound)
+        }
+    }
+
+    /// Command runner returning fixed output and recording what it was asked to run
+    struct StubCommandRunner {
+        output: Result<String, fn() -> ClaudeError>,
+        calls: Mutex<Vec<(String, Vec<String>)>>,
+    }
+
+    impl StubCommandRunner {
+        fn new(output: Result<&str, fn() -> ClaudeError>) -> Self {
+            Self {
+                output: output.map(str::to_string),
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl CommandRunner for StubCommandRunner {
+        fn run(
+            &self,
+            program: &str,
+            args: &[&str],
+            _timeout: Duration,
+        ) -> Result<String, ClaudeError> {
+            self.calls.lock().push((
+                program.to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+            ));
+            self.output.clone().map_err(|error| error())
+        }
+    }
+
+    /// Create a fake Claude binary in a temp dir
+    fn fake_binary(dir: &Path, executable: bool) -> PathBuf {
+        let path = dir.join("claude");
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = if executable { 0o755 } else { 0o644 };
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = executable;
+
+        path
     }
 
     #[test]
@@ -389,19 +1875,135 @@ pub(crate) mod tests {
         ));
     }
 
-    #[test]
-    fn test_mock_send_input() {
-        let process = MockClaudeProcess::new();
-        process.start(None).unwrap();
+    /// Input Claude never reads: writes block until the test drops the sender
+    struct StalledWriter(mpsc::Receiver<()>);
 
-        process.send_input("Hello Claude").unwrap();
-        process.send_input("How are you?").unwrap();
+    impl Write for StalledWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.0.recv();
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
 
-        let history = process.input_history();
-        assert_eq!(history.len(), 2);
-        assert_eq!(history[0], "Hello Claude");
-        assert_eq!(history[1], "How are you?");
-    }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pty_write_times_out() {
+        let (unblock, stalled) = mpsc::channel();
+        let writer = PtyWriter::new(Box::new(StalledWriter(stalled)));
+
+        let started = Instant::now();
+        let result = writer.write(b"list files\r", Duration::from_millis(50));
+        assert!(matches!(result, Err(ClaudeError::WriteError(ref e)) if e == "timed out"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Later writes fail rather than queue up behind the stalled one
+        drop(unblock);
+        let result = writer.write(b"y", Duration::from_secs(5));
+        assert!(matches!(result, Err(ClaudeError::WriteError(_))));
+
+        let writer = PtyWriter::new(Box::new(std::io::sink()));
+        writer.write(b"ok", Duration::from_millis(50)).unwrap();
+        writer.write(b"ok", Duration::ZERO).unwrap();
+    }
+
+    /// Input Claude reads late: writes block until the test sends, then land
+    struct SlowWriter {
+        drain: mpsc::Receiver<()>,
+        written: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.drain.recv();
+            self.written.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pty_write_retry_after_timeout_is_not_duplicated() {
+        let (drain_tx, drain) = mpsc::channel();
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let writer = PtyWriter::new(Box::new(SlowWriter {
+            drain,
+            written: written.clone(),
+        }));
+
+        let result = writer.write(b"list files\r", Duration::from_millis(50));
+        assert!(matches!(result, Err(ClaudeError::WriteError(ref e)) if e == "timed out"));
+
+        // The retry fails at once instead of queueing a second copy
+        let result = writer.write(b"list files\r", Duration::from_secs(5));
+        assert!(matches!(result, Err(ClaudeError::WriteError(ref e)) if e == POISONED_WRITE));
+
+        // The PTY drains and only the original write lands
+        for _ in 0..2 {
+            let _ = drain_tx.send(());
+        }
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while written.lock().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(*written.lock(), b"list files\r");
+    }
+
+    #[test]
+    fn test_mock_graceful_stop() {
+        let process = MockClaudeProcess::new();
+        process.start(None).unwrap();
+        process.stop().unwrap();
+
+        assert_eq!(
+            process.stop_actions(),
+            vec![StopAction::Interrupt, StopAction::ExitedGracefully]
+        );
+    }
+
+    #[test]
+    fn test_mock_interrupt_precedes_force_kill() {
+        let process = MockClaudeProcess::new();
+        process.set_ignore_interrupt(true);
+        process.start(None).unwrap();
+        process.stop().unwrap();
+
+        assert_eq!(
+            process.stop_actions(),
+            vec![StopAction::Interrupt, StopAction::Kill]
+        );
+        assert_eq!(process.status(), ProcessStatus::Stopped);
+    }
+
+    #[test]
+    fn test_mock_zero_grace_kills_immediately() {
+        let process = MockClaudeProcess::new();
+        process.set_shutdown_grace_ms(0);
+        process.start(None).unwrap();
+        process.stop().unwrap();
+
+        assert_eq!(process.stop_actions(), vec![StopAction::Kill]);
+    }
+
+    #[test]
+    fn test_mock_send_input() {
+        let process = MockClaudeProcess::new();
+        process.start(None).unwrap();
+
+        process.send_input("Hello Claude").unwrap();
+        process.send_input("How are you?").unwrap();
+
+        let history = process.input_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], "Hello Claude");
+        assert_eq!(history[1], "How are you?");
+    }
 
     #[test]
     fn test_mock_send_input_not_running() {
@@ -420,6 +2022,34 @@ pub(crate) mod tests {
         assert_eq!(process.current_size(), (120, 40));
     }
 
+    #[test]
+    fn test_mock_initial_size_before_resize() {
+        let process = MockClaudeProcess::new();
+        process
+            .start_with(ClaudeStartOptions {
+                cols: Some(132),
+                rows: Some(43),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Recorded at spawn time, no resize call needed
+        assert_eq!(process.current_size(), (132, 43));
+    }
+
+    #[test]
+    fn test_initial_size_defaults() {
+        let process = MockClaudeProcess::new();
+        process.start(None).unwrap();
+        assert_eq!(process.current_size(), DEFAULT_PTY_SIZE);
+
+        let options = ClaudeStartOptions {
+            cols: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(options.pty_size(), (100, 24));
+    }
+
     #[test]
     fn test_mock_resize_not_running() {
         let process = MockClaudeProcess::new();
@@ -449,11 +2079,757 @@ pub(crate) mod tests {
         assert!(events[1].is_error);
     }
 
+    #[test]
+    fn test_output_pump_drops_instead_of_blocking() {
+        let (release, gate) = std::sync::mpsc::channel::<()>();
+        let gate = Mutex::new(gate);
+        let delivered = Arc::new(AtomicU64::new(0));
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let delivered_bytes = delivered.clone();
+        let dropped_bytes = dropped.clone();
+        let pump = OutputPump::new(
+            2,
+            move |event| {
+                // A consumer that stalls until the test releases it
+                let _ = gate.lock().recv();
+                delivered_bytes.fetch_add(event.data.len() as u64, Ordering::SeqCst);
+            },
+            move |bytes| {
+                dropped_bytes.fetch_add(bytes, Ordering::SeqCst);
+            },
+        );
+
+        let chunk = "x".repeat(4096);
+        let started = Instant::now();
+        for _ in 0..20 {
+            pump.send(OutputEvent {
+                data: chunk.clone(),
+                is_error: false,
+                plain: None,
+            });
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Let the consumer through and wait for the emitter to finish
+        for _ in 0..20 {
+            let _ = release.send(());
+        }
+        drop(pump);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while delivered.load(Ordering::SeqCst) + dropped.load(Ordering::SeqCst) < 20 * 4096 {
+            assert!(Instant::now() < deadline, "emitter did not catch up");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // At most the queue plus the chunk in the consumer got through
+        assert!(delivered.load(Ordering::SeqCst) <= 3 * 4096);
+        assert!(dropped.load(Ordering::SeqCst) >= 17 * 4096);
+    }
+
+    #[test]
+    fn test_mock_start_with_options() {
+        let process = MockClaudeProcess::new();
+        let options = ClaudeStartOptions {
+            working_dir: Some("/projects/app".to_string()),
+            args: vec!["--model".to_string(), "opus".to_string()],
+            env: vec![("ANTHROPIC_API_KEY".to_string(), "sk-test".to_string())],
+            ..Default::default()
+        };
+
+        process.start_with(options.clone()).unwrap();
+        assert_eq!(process.status(), ProcessStatus::Running);
+        assert_eq!(process.last_options(), Some(options));
+    }
+
+    #[test]
+    fn test_start_uses_default_options() {
+        let process = MockClaudeProcess::new();
+        process.start(Some("/projects/app")).unwrap();
+
+        let options = process.last_options().unwrap();
+        assert_eq!(options.working_dir.as_deref(), Some("/projects/app"));
+        assert!(options.args.is_empty());
+        assert!(options.env.is_empty());
+    }
+
+    #[test]
+    fn test_build_command_applies_options() {
+        let options = ClaudeStartOptions {
+            working_dir: Some("/projects/app".to_string()),
+            args: vec!["--model".to_string(), "opus".to_string()],
+            env: vec![
+                ("ANTHROPIC_API_KEY".to_string(), "sk-test".to_string()),
+                ("CLAUDE_DEBUG".to_string(), "1".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let cmd = ClaudeCodeProcess::build_command("claude", &options);
+
+        assert_eq!(cmd.get_argv(), &vec!["claude", "--model", "opus"]);
+        assert_eq!(cmd.get_env("ANTHROPIC_API_KEY").unwrap(), "sk-test");
+        assert_eq!(cmd.get_env("CLAUDE_DEBUG").unwrap(), "1");
+        assert_eq!(cmd.get_cwd().unwrap(), "/projects/app");
+    }
+
+    #[test]
+    fn test_mock_explicit_binary_bypasses_path_lookup() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let binary = fake_binary(temp_dir.path(), true);
+
+        let process = MockClaudeProcess::new();
+        process.set_binary_path(Some(binary.clone()));
+        process.start(None).unwrap();
+
+        assert_eq!(process.path_lookup_count(), 0);
+        assert_eq!(
+            process.resolved_command(),
+            Some(binary.to_string_lossy().to_string())
+        );
+
+        process.stop().unwrap();
+        process.set_binary_path(None);
+        process.start(None).unwrap();
+        assert_eq!(process.path_lookup_count(), 1);
+    }
+
+    #[test]
+    fn test_mock_missing_binary_path() {
+        let process = MockClaudeProcess::new();
+        process.set_binary_path(Some(PathBuf::from("/nonexistent/claude")));
+
+        let result = process.start(None);
+        assert!(matches!(result, Err(ClaudeError::ClaudeNotFound)));
+    }
+
+    #[test]
+    fn test_resolve_explicit_binary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let binary = fake_binary(temp_dir.path(), true);
+
+        let resolved = ClaudeCodeProcess::resolve_command(Some(&binary)).unwrap();
+        assert_eq!(resolved, binary.to_string_lossy());
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("1.0.17 (Claude Code)\n"),
+            Some("1.0.17".to_string())
+        );
+        assert_eq!(parse_version("claude v2.1.0"), Some("2.1.0".to_string()));
+        assert_eq!(parse_version("Claude Code"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn test_query_version_with_stub_runner() {
+        let runner = StubCommandRunner::new(Ok("1.0.17 (Claude Code)\n"));
+        assert_eq!(query_version(&runner, "claude").unwrap(), "1.0.17");
+        assert_eq!(
+            runner.calls.lock().clone(),
+            vec![("claude".to_string(), vec!["--version".to_string()])]
+        );
+
+        let runner = StubCommandRunner::new(Ok("unknown option"));
+        assert_eq!(
+            query_version(&runner, "claude").unwrap_err().kind(),
+            "version_error"
+        );
+
+        let runner = StubCommandRunner::new(Err(|| ClaudeError::VersionTimeout));
+        assert_eq!(
+            query_version(&runner, "claude").unwrap_err().kind(),
+            "version_timeout"
+        );
+    }
+
+    #[test]
+    fn test_detect_version_uses_binary_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let runner = Arc::new(StubCommandRunner::new(Ok("1.0.17 (Claude Code)")));
+        let process = ClaudeCodeProcess::new().with_command_runner(runner.clone());
+
+        process.set_binary_path(Some(temp_dir.path().join("missing")));
+        assert!(matches!(
+            process.detect_version(),
+            Err(ClaudeError::ClaudeNotFound)
+        ));
+        assert!(runner.calls.lock().is_empty());
+
+        let binary = fake_binary(temp_dir.path(), true);
+        process.set_binary_path(Some(binary.clone()));
+        assert_eq!(process.detect_version().unwrap(), "1.0.17");
+        assert_eq!(runner.calls.lock()[0].0, binary.to_string_lossy());
+    }
+
+    #[test]
+    fn test_system_runner_missing_program() {
+        let result = SystemCommandRunner.run(
+            "icanhastool-missing-binary",
+            &["--version"],
+            VERSION_TIMEOUT,
+        );
+        assert!(matches!(result, Err(ClaudeError::ClaudeNotFound)));
+    }
+
+    #[test]
+    fn test_validate_binary_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            ClaudeCodeProcess::validate_binary_path(temp_dir.path()),
+            Err(ClaudeError::ClaudeNotFound)
+        ));
+        assert!(matches!(
+            ClaudeCodeProcess::validate_binary_path(&temp_dir.path().join("missing")),
+            Err(ClaudeError::ClaudeNotFound)
+        ));
+
+        #[cfg(unix)]
+        {
+            let not_executable = fake_binary(temp_dir.path(), false);
+            assert!(matches!(
+                ClaudeCodeProcess::validate_binary_path(&not_executable),
+                Err(ClaudeError::ClaudeNotFound)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_mock_unexpected_exit_updates_status() {
+        let process = MockClaudeProcess::new();
+        process.start(None).unwrap();
+
+        process.simulate_exit(1);
+        assert_eq!(process.status(), ProcessStatus::Exited(1));
+        assert_eq!(process.exit_code(), Some(1));
+        assert!(matches!(
+            process.send_input("hello"),
+            Err(ClaudeError::NotRunning)
+        ));
+        assert_eq!(process.start_count(), 1, "Should not restart when disabled");
+    }
+
+    #[test]
+    fn test_mock_is_alive_detects_silent_exit() {
+        let process = MockClaudeProcess::new();
+        assert!(!process.is_alive());
+
+        process.start(None).unwrap();
+        assert!(process.is_alive());
+
+        // The exit isn't visible until the process is polled
+        process.simulate_silent_exit(2);
+        assert_eq!(process.status(), ProcessStatus::Running);
+
+        assert!(!process.is_alive());
+        assert_eq!(process.status(), ProcessStatus::Exited(2));
+        assert_eq!(process.exit_code(), Some(2));
+        assert_eq!(process.uptime_secs(), 0);
+    }
+
+    #[test]
+    fn test_mock_pid_only_while_running() {
+        let process = MockClaudeProcess::new();
+        assert_eq!(process.pid(), None);
+
+        process.start(None).unwrap();
+        assert_eq!(process.pid(), Some(MOCK_PID));
+
+        process.simulate_exit(1);
+        assert_eq!(process.pid(), None);
+        assert_eq!(ClaudeCodeProcess::new().pid(), None);
+    }
+
+    #[test]
+    fn test_sysinfo_monitor_reports_own_process() {
+        let monitor = SysinfoProcessMonitor::default();
+        let usage = monitor.usage(std::process::id()).unwrap();
+
+        assert_eq!(usage.pid, std::process::id());
+        assert!(usage.rss_bytes > 0);
+        assert_eq!(monitor.usage(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_mock_uptime() {
+        let process = MockClaudeProcess::new();
+        assert_eq!(process.uptime_secs(), 0);
+
+        process.start(None).unwrap();
+        process.set_started_ago(Duration::from_secs(90));
+        assert_eq!(process.uptime_secs(), 90);
+
+        process.stop().unwrap();
+        assert_eq!(process.uptime_secs(), 0);
+    }
+
+    #[test]
+    fn test_mock_clean_exit_is_stopped() {
+        let process = MockClaudeProcess::new();
+        process.set_auto_restart(true);
+        process.start(None).unwrap();
+
+        process.simulate_exit(0);
+        assert_eq!(process.status(), ProcessStatus::Exited(0));
+        assert_eq!(process.exit_code(), Some(0));
+        assert_eq!(process.start_count(), 1, "Clean exits are not restarted");
+    }
+
+    #[test]
+    fn test_mock_auto_restart() {
+        let process = MockClaudeProcess::new();
+        let restarts = Arc::new(Mutex::new(Vec::new()));
+
+        let restarts_clone = restarts.clone();
+        process.set_restart_callback(Arc::new(move |attempt| {
+            restarts_clone.lock().push(attempt);
+        }));
+        process.set_auto_restart(true);
+        process.start(Some("/projects/app")).unwrap();
+
+        process.simulate_exit(1);
+        assert_eq!(process.status(), ProcessStatus::Running);
+        assert_eq!(process.exit_code(), None, "Exit code is cleared on restart");
+        assert_eq!(process.start_count(), 2);
+        assert_eq!(process.working_dir().as_deref(), Some("/projects/app"));
+
+        process.simulate_exit(1);
+        assert_eq!(process.start_count(), 3);
+        assert_eq!(*restarts.lock(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_mock_restart_keeps_output_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_path = temp_dir.path().join("session.log");
+        let process = MockClaudeProcess::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let events_clone = events.clone();
+        process.set_output_callback(Arc::new(move |event| {
+            events_clone.lock().push(event);
+        }));
+        process.set_strip_ansi(true);
+        process.set_log_path(Some(log_path.clone())).unwrap();
+        process.set_auto_restart(true);
+        process.start(None).unwrap();
+
+        process.simulate_output("before", false);
+        process.simulate_exit(1);
+        assert_eq!(process.start_count(), 2);
+        process.simulate_output("\x1b[1mafter\x1b[0m", false);
+
+        let events = events.lock();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].plain.as_deref(), Some("after"));
+
+        process.set_log_path(None).unwrap();
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.lines().nth(1).unwrap().contains("after"));
+    }
+
+    #[test]
+    fn test_mock_auto_restart_gives_up() {
+        let process = MockClaudeProcess::new();
+        process.set_auto_restart(true);
+        process.start(None).unwrap();
+
+        for _ in 0..=MAX_AUTO_RESTARTS {
+            process.simulate_exit(1);
+        }
+
+        assert_eq!(process.status(), ProcessStatus::Exited(1));
+        assert_eq!(process.start_count(), 1 + MAX_AUTO_RESTARTS as usize);
+
+        // A manual start resets the retry budget
+        process.start(None).unwrap();
+        process.simulate_exit(1);
+        assert_eq!(process.status(), ProcessStatus::Running);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restart_keeps_output_callback() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        // Exits with an error straight away, numbering each run
+        let binary = fake_binary(temp_dir.path(), true);
+        let runs = temp_dir.path().join("runs");
+        std::fs::write(
+            &binary,
+            format!(
+                "#!/bin/sh\necho x >> '{0}'\necho \"run $(wc -l < '{0}' | tr -d ' ')\"\nexit 1\n",
+                runs.display()
+            ),
+        )
+        .unwrap();
+
+        let process = ClaudeCodeProcess::new();
+        process.set_binary_path(Some(binary));
+        process.set_auto_restart(true);
+        let output = Arc::new(Mutex::new(String::new()));
+        let seen = output.clone();
+        process.set_output_callback(Arc::new(move |event| seen.lock().push_str(&event.data)));
+        let restarts = Arc::new(Mutex::new(Vec::new()));
+        let attempts = restarts.clone();
+        process.set_restart_callback(Arc::new(move |attempt| attempts.lock().push(attempt)));
+
+        process.start(None).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !output.lock().contains("run 2") && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(output.lock().contains("run 2"), "got {:?}", output.lock());
+        assert_eq!(restarts.lock().first(), Some(&1));
+        let _ = process.stop();
+    }
+
+    #[test]
+    fn test_scrollback_caps_and_keeps_recent() {
+        let mut scrollback = Scrollback::new(10);
+        scrollback.push("hello ");
+        assert_eq!(scrollback.contents(), "hello ");
+
+        scrollback.push("world!");
+        assert_eq!(scrollback.len(), 10);
+        assert_eq!(scrollback.contents(), "llo world!");
+    }
+
+    #[test]
+    fn test_scrollback_single_push_larger_than_cap() {
+        let mut scrollback = Scrollback::new(4);
+        scrollback.push("abcdefgh");
+        assert_eq!(scrollback.contents(), "efgh");
+    }
+
+    #[test]
+    fn test_scrollback_skips_split_utf8() {
+        let mut scrollback = Scrollback::new(4);
+        // "é" is two bytes; trimming to 4 bytes cuts it in half
+        scrollback.push("aé123");
+        assert_eq!(scrollback.len(), 4);
+        assert_eq!(scrollback.contents(), "123");
+    }
+
+    #[test]
+    fn test_utf8_decoder_joins_split_characters() {
+        let mut decoder = Utf8Decoder::default();
+        let text = "café 😀";
+        let bytes = text.as_bytes();
+
+        // Split inside "é" and again inside the emoji
+        assert_eq!(decoder.decode(&bytes[..4]), "caf");
+        assert_eq!(decoder.decode(&bytes[4..8]), "é ");
+        assert_eq!(decoder.decode(&bytes[8..9]), "");
+        assert_eq!(decoder.decode(&bytes[9..]), "😀");
+        assert_eq!(decoder.finish(), "");
+
+        // Invalid bytes don't hold back the rest of the output
+        assert_eq!(decoder.decode(b"a\xFFb"), "a\u{FFFD}b");
+        assert_eq!(decoder.decode(&bytes[..4]), "caf");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+    }
+
+    /// Output arriving a few bytes per read
+    struct ChunkedReader(VecDeque<Vec<u8>>);
+
+    impl Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some(chunk) = self.0.pop_front() else {
+                return Ok(0);
+            };
+            let n = chunk.len().min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            if n < chunk.len() {
+                self.0.push_front(chunk[n..].to_vec());
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_output_reader_keeps_split_characters_intact() {
+        let process = ClaudeCodeProcess::new();
+        process.set_read_buffer_size(3);
+        let text = "naïve ✓ 日本";
+        let chunks = text.as_bytes().chunks(5).map(<[u8]>::to_vec).collect();
+        process.inner.running.store(true, Ordering::SeqCst);
+
+        process
+            .inner
+            .clone()
+            .read_output(Box::new(ChunkedReader(chunks)), 0);
+
+        assert_eq!(process.get_recent_output(), text);
+    }
+
+    #[test]
+    fn test_scrollback_shrinking_capacity_trims() {
+        let mut scrollback = Scrollback::new(100);
+        scrollback.push("0123456789");
+        scrollback.set_capacity(3);
+        assert_eq!(scrollback.contents(), "789");
+    }
+
+    #[test]
+    fn test_search_output_literal() {
+        let output = "Compiling app\nerror[E0308]: mismatched types\n  --> src/main.rs:4:5\nerror: aborting\n";
+
+        let matches = search_output(output, "error", false).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line, "error[E0308]: mismatched types");
+        assert_eq!(matches[0].line_number, 1);
+        assert_eq!(matches[0].offset, 14);
+        assert_eq!(matches[1].line, "error: aborting");
+        assert_eq!(matches[1].line_number, 3);
+        assert_eq!(matches[1].offset, 67);
+        assert_eq!(&output[matches[1].offset..][..matches[1].len], "error");
+
+        // Regex metacharacters are literal in this mode
+        let matches = search_output(output, "[E0308]", false).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].offset, 19);
+        assert!(search_output(output, "warning", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_output_regex() {
+        let output = "\x1b[31merror\x1b[0m: first\nok\nsrc/lib.rs:10:2 src/main.rs:4:5\n";
+
+        // Escape codes are stripped before matching
+        let matches = search_output(output, r"^error: \w+$", true).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, "error: first");
+        assert_eq!(matches[0].offset, 0);
+        assert_eq!(matches[0].len, 12);
+
+        let matches = search_output(output, r"\w+\.rs:\d+", true).unwrap();
+        let offsets: Vec<_> = matches.iter().map(|m| (m.offset, m.len)).collect();
+        assert_eq!(offsets, vec![(20, 9), (36, 9)]);
+        assert!(matches.iter().all(|m| m.line_number == 2));
+
+        assert!(search_output(output, "^", true).unwrap().is_empty());
+        assert!(search_output(output, "(unclosed", true).is_err());
+    }
+
+    #[test]
+    fn test_mock_recent_output() {
+        let process = MockClaudeProcess::new();
+        process.start(None).unwrap();
+        process.set_scrollback_limit(8);
+
+        process.simulate_output("\x1b[1mbold\x1b[0m", false);
+        process.simulate_output(" text", false);
+        assert_eq!(process.get_recent_output(), "[0m text");
+
+        // A new session starts with empty scrollback
+        process.stop().unwrap();
+        process.start(None).unwrap();
+        assert_eq!(process.get_recent_output(), "");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[31mred\x1b[0m text"), "red text");
+        assert_eq!(strip_ansi("\x1b[1;32m> \x1b[0mHello"), "> Hello");
+        assert_eq!(strip_ansi("\x1b[2J\x1b[H\x1b[?25lcleared"), "cleared");
+        assert_eq!(strip_ansi("\x1b]0;window title\x07prompt"), "prompt");
+        assert_eq!(strip_ansi("no escapes here"), "no escapes here");
+    }
+
+    #[test]
+    fn test_validate_working_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().to_str().unwrap();
+
+        assert_eq!(
+            validate_working_dir(dir).unwrap(),
+            temp_dir.path().canonicalize().unwrap()
+        );
+
+        let missing = temp_dir.path().join("missing");
+        let error = validate_working_dir(missing.to_str().unwrap()).unwrap_err();
+        assert!(matches!(error, ClaudeError::WorkingDirNotFound(_)));
+        assert!(error.to_string().contains("does not exist"));
+
+        let file = temp_dir.path().join("file.txt");
+        std::fs::write(&file, "").unwrap();
+        assert!(matches!(
+            validate_working_dir(file.to_str().unwrap()),
+            Err(ClaudeError::InvalidWorkingDir(_))
+        ));
+
+        assert!(matches!(
+            validate_working_dir("projects/app"),
+            Err(ClaudeError::InvalidWorkingDir(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_to_bytes() {
+        let cases: [(&str, &[u8]); 11] = [
+            ("up", b"\x1b[A"),
+            ("down", b"\x1b[B"),
+            ("right", b"\x1b[C"),
+            ("left", b"\x1b[D"),
+            ("enter", b"\r"),
+            ("tab", b"\t"),
+            ("shift-tab", b"\x1b[Z"),
+            ("esc", b"\x1b"),
+            ("backspace", b"\x7f"),
+            ("ctrl-c", b"\x03"),
+            ("ctrl-d", b"\x04"),
+        ];
+        assert_eq!(cases.len(), NAMED_KEYS.len());
+        for (name, bytes) in cases {
+            assert_eq!(key_to_bytes(name).as_deref(), Some(bytes), "{}", name);
+        }
+
+        assert_eq!(key_to_bytes(" Ctrl-C "), Some(b"\x03".to_vec()));
+        assert_eq!(key_to_bytes("f13"), None);
+        assert_eq!(key_to_bytes(""), None);
+    }
+
+    #[test]
+    fn test_bracketed_paste() {
+        assert_eq!(bracketed_paste("ls -la"), "\x1b[200~ls -la\x1b[201~");
+        assert_eq!(
+            bracketed_paste("a\x1b[201~rm -rf /\r"),
+            "\x1b[200~arm -rf /\r\x1b[201~"
+        );
+    }
+
+    #[test]
+    fn test_submit_line() {
+        let cases = [
+            ("list files", "\r", "list files\r"),
+            ("  list files  ", "\r", "list files\r"),
+            ("list files\r", "\r", "list files\r"),
+            ("list files\n", "\r", "list files\r"),
+            ("list files\r\n", "\n", "list files\n"),
+            ("list files", "\r\n", "list files\r\n"),
+            ("", "\r", "\r"),
+        ];
+
+        for (text, submit, expected) in cases {
+            assert_eq!(submit_line(text, submit), expected, "text: {:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_mock_output_plain_only_when_stripping() {
+        let process = MockClaudeProcess::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let received_clone = received.clone();
+        process.set_output_callback(Arc::new(move |event| {
+            received_clone.lock().push(event);
+        }));
+
+        process.simulate_output("\x1b[33mwarning\x1b[0m", false);
+        process.set_strip_ansi(true);
+        process.simulate_output("\x1b[33mwarning\x1b[0m", false);
+
+        let events = received.lock();
+        assert_eq!(events[0].plain, None);
+        assert_eq!(events[1].data, "\x1b[33mwarning\x1b[0m");
+        assert_eq!(events[1].plain.as_deref(), Some("warning"));
+    }
+
+    #[test]
+    fn test_mock_session_log() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_path = temp_dir.path().join("session.log");
+
+        let process = MockClaudeProcess::new();
+        process.set_log_path(Some(log_path.clone())).unwrap();
+        process.start(None).unwrap();
+
+        process.simulate_output("Welcome to Claude\r\n", false);
+        process.send_input("list files\r").unwrap();
+        process.simulate_output("file.txt", false);
+
+        // Dropping the log flushes it
+        process.set_log_path(None).unwrap();
+
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with('['));
+        assert!(lines[0].ends_with("OUT Welcome to Claude\\r\\n"));
+        assert!(lines[1].ends_with("IN list files\\r"));
+        assert!(lines[2].ends_with("OUT file.txt"));
+    }
+
+    #[test]
+    fn test_session_log_appends() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_path = temp_dir.path().join("session.log");
+
+        SessionLog::open(&log_path, LogRotation::default())
+            .unwrap()
+            .log_output("first");
+        SessionLog::open(&log_path, LogRotation::default())
+            .unwrap()
+            .log_output("second");
+
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.contains("OUT first"));
+        assert!(contents.contains("OUT second"));
+    }
+
+    #[test]
+    fn test_session_log_invalid_path() {
+        let result = SessionLog::open(
+            Path::new("/nonexistent/dir/session.log"),
+            LogRotation::default(),
+        );
+        assert!(matches!(result, Err(ClaudeError::LogError(_))));
+    }
+
+    #[test]
+    fn test_session_log_rotation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_path = temp_dir.path().join("session.log");
+        let log = SessionLog::open(
+            &log_path,
+            LogRotation {
+                max_bytes: 100,
+                keep: 2,
+            },
+        )
+        .unwrap();
+
+        // Each entry is 30 bytes, so every fourth one rotates the log
+        for i in 0..12 {
+            log.log_output(&format!("entry {:02}", i));
+        }
+        log.flush();
+
+        let rotated = rotated_logs(&log_path);
+        assert_eq!(rotated.len(), 2);
+        let newest = std::fs::read_to_string(&rotated[0]).unwrap();
+        let older = std::fs::read_to_string(&rotated[1]).unwrap();
+        assert!(newest.contains("entry 11"));
+        assert!(older.contains("entry 07"));
+        assert!(!older.contains("entry 03"));
+
+        // The active file was started fresh after the last rotation
+        assert!(log_path.exists());
+        assert!(std::fs::metadata(&log_path).unwrap().len() < 100);
+    }
+
     #[test]
     fn test_output_event_serialization() {
         let event = OutputEvent {
             data: "Test data".to_string(),
             is_error: false,
+            plain: None,
         };
 
         let json = serde_json::to_string(&event).unwrap();
@@ -471,6 +2847,10 @@ pub(crate) mod tests {
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("Running"));
 
+        let status = ProcessStatus::Exited(1);
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(json, r#"{"Exited":1}"#);
+
         let status = ProcessStatus::Error("test error".to_string());
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("Error"));
@@ -2,254 +2,6623 @@
 //!
 //! Exposes Rust functionality to the Svelte frontend via Tauri commands.
 
-use crate::audio::{AudioCapture, AudioDeviceInfo, CpalAudioCapture};
-use crate::claude::{ClaudeCodeProcess, ClaudeProcess, ProcessStatus};
-use crate::vosk_stt::{ModelInfo, ModelManager, RecognitionResult, SpeechRecognizer, VoskRecognizer};
+use crate::audio::{
+    compute_level, resample, AudioCapture, AudioDeviceInfo, AudioError, AudioLevel, Chunker,
+    CpalAudioCapture, DeviceConfig, DeviceEvent, GainControl, LevelMeter, NoiseGate,
+    PermissionStatus, StreamResampler, WaveformMeter, TARGET_SAMPLE_RATE,
+};
+use crate::audio_file;
+use crate::claude::{
+    bracketed_paste, key_to_bytes, search_output, submit_line, validate_working_dir,
+    ClaudeCodeProcess, ClaudeError, ClaudeProcess, ClaudeStartOptions, LogRotation, OutputMatch,
+    ProcessMonitor, ProcessStatus, ResourceUsage, SysinfoProcessMonitor, DEFAULT_SUBMIT_SEQUENCE,
+    SUBMIT_SEQUENCES,
+};
+use crate::scripted_stt::ScriptedRecognizer;
+use crate::settings::{Settings, SettingsError, SETTINGS_FILE};
+use crate::text_process::{
+    load_symbols, to_owned_pairs, SymbolDictionaryError, TextProcessor, DEFAULT_SPOKEN_COMMANDS,
+    DEFAULT_SYMBOLS,
+};
+use crate::transcript::{full_text, write_transcript, TranscriptError};
+use crate::voice_commands::{VoiceAction, VoiceCommands};
+use crate::vosk_stt::{
+    benchmark_audio, benchmark_recognizer, transcribe_samples, BenchmarkResult, CancelToken,
+    ModelInfo, ModelLanguage, ModelManager, RecognitionResult, RecognizerDebug, SpeechError,
+    SpeechRecognizer, VoskRecognizer,
+};
+use crate::wake_word::WakeGate;
 use parking_lot::Mutex;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, State};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_opener::OpenerExt;
 
-/// Application state shared across commands
-pub struct AppState {
-    pub audio: Arc<dyn AudioCapture>,
-    pub recognizer: Arc<dyn SpeechRecognizer>,
-    pub claude: Arc<dyn ClaudeProcess>,
-    pub model_manager: ModelManager,
-    audio_callback: Mutex<Option<Arc<dyn Fn(Vec<i16>) + Send + Sync>>>,
+/// Session used by commands called without a session id, and by dictation
+pub const DEFAULT_CLAUDE_SESSION: &str = "default";
+
+/// Claude options set from the frontend that apply to every session.
+/// Kept so sessions opened later are configured like the existing ones.
+#[derive(Debug, Clone, Default)]
+struct ClaudeConfig {
+    auto_restart: Option<bool>,
+    strip_ansi: Option<bool>,
+    shutdown_grace_ms: Option<u32>,
+    write_timeout_ms: Option<u32>,
+    read_buffer_size: Option<usize>,
+    scrollback_limit: Option<usize>,
+    log_rotation: Option<LogRotation>,
 }
 
-impl AppState {
-    pub fn new(app_data_dir: PathBuf) -> Self {
-        let models_dir = app_data_dir.join("models");
+type ClaudeFactory = Box<dyn Fn() -> Arc<dyn ClaudeProcess> + Send + Sync>;
 
-        Self {
-            audio: Arc::new(CpalAudioCapture::new()),
-            recognizer: Arc::new(VoskRecognizer::new()),
-            claude: Arc::new(ClaudeCodeProcess::new()),
-            model_manager: ModelManager::new(models_dir),
-            audio_callback: Mutex::new(None),
+type RecognizerFactory = Box<dyn Fn() -> Arc<dyn SpeechRecognizer> + Send + Sync>;
+
+/// Drops recognition results whose confidence is below a threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ConfidenceFilter {
+    min_confidence: f32,
+    /// Partial results are exempt unless this is set
+    filter_partials: bool,
+}
+
+impl ConfidenceFilter {
+    /// Results without a confidence score are never rejected
+    fn rejects(&self, result: &RecognitionResult) -> bool {
+        if !result.is_final && !self.filter_partials {
+            return false;
         }
+        matches!(result.confidence, Some(confidence) if confidence < self.min_confidence)
     }
+}
 
-    #[cfg(test)]
-    pub fn with_mocks(
-        audio: Arc<dyn AudioCapture>,
-        recognizer: Arc<dyn SpeechRecognizer>,
-        claude: Arc<dyn ClaudeProcess>,
-    ) -> Self {
+/// Window in which a repeated final is treated as a duplicate, unless configured
+const DEFAULT_DUPLICATE_WINDOW_MS: u64 = 1500;
+
+/// Suppresses a final that repeats the previous one, which Vosk occasionally
+/// emits twice in quick succession
+#[derive(Debug, Clone, PartialEq)]
+struct DuplicateFilter {
+    /// Audio time after the previous final in which a repeat is dropped; 0 disables
+    window_ms: u64,
+    /// Character edit distance up to which two finals count as the same text
+    max_distance: usize,
+    /// Text and audio position of the previous final
+    last_final: Option<(String, u64)>,
+}
+
+impl Default for DuplicateFilter {
+    fn default() -> Self {
         Self {
-            audio,
-            recognizer,
-            claude,
-            model_manager: ModelManager::new(PathBuf::from("/test/models")),
-            audio_callback: Mutex::new(None),
+            window_ms: DEFAULT_DUPLICATE_WINDOW_MS,
+            max_distance: 0,
+            last_final: None,
         }
     }
 }
 
-// ============================================================================
-// Audio Commands
-// ============================================================================
+impl DuplicateFilter {
+    /// Record a final heard at `now_ms`, returning whether it repeats the previous one
+    fn is_duplicate(&mut self, text: &str, now_ms: u64) -> bool {
+        if text.is_empty() {
+            return false;
+        }
 
-#[tauri::command]
-pub fn list_audio_devices(state: State<AppState>) -> Result<Vec<AudioDeviceInfo>, String> {
-    state.audio.list_devices().map_err(|e| e.to_string())
+        let duplicate = self.window_ms > 0
+            && matches!(&self.last_final, Some((last, at))
+                if now_ms.saturating_sub(*at) <= self.window_ms
+                    && edit_distance(last, text) <= self.max_distance);
+        self.last_final = Some((text.to_string(), now_ms));
+        duplicate
+    }
 }
 
-#[tauri::command]
-pub fn start_recording(
-    app: AppHandle,
-    state: State<AppState>,
-    device_name: Option<String>,
-) -> Result<(), String> {
-    let recognizer = state.recognizer.clone();
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
 
-    // Create callback that processes audio through Vosk and emits events
-    let callback: Arc<dyn Fn(Vec<i16>) + Send + Sync> = Arc::new(move |samples| {
-        match recognizer.process_audio(&samples) {
-            Ok(Some(result)) => {
-                let _ = app.emit("transcription", &result);
-            }
-            Ok(None) => {}
-            Err(e) => {
-                eprintln!("Recognition error: {}", e);
+/// Notices when the shown partial result hasn't changed for a while, e.g. when
+/// the speaker pauses mid-sentence and Vosk keeps repeating the same partial
+#[derive(Debug, Default)]
+struct StalePartial {
+    text: String,
+    /// Audio position when `text` was first seen
+    since_ms: u64,
+    cleared: bool,
+}
+
+impl StalePartial {
+    /// Track a buffer's result, returning the partial's text when it has gone
+    /// `timeout_ms` without changing (0 never times out). Reported once per partial.
+    fn update(
+        &mut self,
+        result: Option<&RecognitionResult>,
+        now_ms: u64,
+        timeout_ms: u64,
+    ) -> Option<String> {
+        match result {
+            // A final replaces whatever partial was shown
+            Some(result) if result.is_final => *self = Self::default(),
+            Some(result) if result.text != self.text => {
+                *self = Self {
+                    text: result.text.clone(),
+                    since_ms: now_ms,
+                    cleared: false,
+                };
             }
+            _ => {}
         }
-    });
-
-    *state.audio_callback.lock() = Some(callback.clone());
 
-    state
-        .audio
-        .start_recording(device_name.as_deref(), callback)
-        .map_err(|e| e.to_string())
+        if timeout_ms == 0
+            || self.text.is_empty()
+            || self.cleared
+            || now_ms.saturating_sub(self.since_ms) < timeout_ms
+        {
+            return None;
+        }
+        self.cleared = true;
+        Some(self.text.clone())
+    }
 }
 
-#[tauri::command]
-pub fn stop_recording(app: AppHandle, state: State<AppState>) -> Result<RecognitionResult, String> {
-    state.audio.stop_recording();
-    *state.audio_callback.lock() = None;
-
-    // Get final transcription
-    let result = state.recognizer.get_final_result().map_err(|e| e.to_string())?;
+/// Coalesces partial results so at most one reaches the UI per interval,
+/// keeping only the latest
+#[derive(Debug, Default)]
+struct PartialThrottle {
+    /// Audio position the last partial was emitted at
+    last_emit_ms: Option<u64>,
+    /// Newest partial held back since then
+    pending: Option<RecognitionResult>,
+}
 
-    // Emit final result
-    let _ = app.emit("transcription-final", &result);
+impl PartialThrottle {
+    /// Track a buffer's result, returning the partial due to be emitted now.
+    /// An interval of 0 passes every partial through.
+    fn update(
+        &mut self,
+        result: Option<&RecognitionResult>,
+        now_ms: u64,
+        interval_ms: u64,
+    ) -> Option<RecognitionResult> {
+        match result {
+            // A final supersedes the held-back partial, and the next
+            // utterance's first partial shows without waiting
+            Some(result) if result.is_final => {
+                *self = Self::default();
+                return None;
+            }
+            Some(result) => self.pending = Some(result.clone()),
+            None => {}
+        }
 
-    Ok(result)
+        let due = self
+            .last_emit_ms
+            .is_none_or(|last| now_ms.saturating_sub(last) >= interval_ms);
+        if !due {
+            return None;
+        }
+        let partial = self.pending.take()?;
+        self.last_emit_ms = Some(now_ms);
+        Some(partial)
+    }
 }
 
-#[tauri::command]
-pub fn is_recording(state: State<AppState>) -> bool {
-    state.audio.is_recording()
+/// What a terminal sends for the backspace key
+const BACKSPACE: char = '\x7f';
+
+/// Tracks the partial typed into Claude's input in live-type mode, so the next
+/// partial can be typed over it
+#[derive(Debug, Default)]
+struct LiveTyper {
+    /// Text typed since the last final
+    typed: String,
 }
 
-// ============================================================================
-// Speech Recognition Commands
-// ============================================================================
+impl LiveTyper {
+    /// Input turning the typed text into `text`: a backspace for each typed
+    /// character after the part both share, then the rest of `text`
+    fn replace(&mut self, text: &str) -> String {
+        let shared = self
+            .typed
+            .chars()
+            .zip(text.chars())
+            .take_while(|(typed, new)| typed == new)
+            .count();
+        let erase = self.typed.chars().count() - shared;
 
-#[tauri::command]
-pub fn list_models(state: State<AppState>) -> Vec<ModelInfo> {
-    state.model_manager.list_available_models()
-}
+        let mut input: String = std::iter::repeat_n(BACKSPACE, erase).collect();
+        input.extend(text.chars().skip(shared));
+        self.typed = text.to_string();
+        input
+    }
 
-#[tauri::command]
-pub fn list_installed_models(state: State<AppState>) -> Vec<ModelInfo> {
-    state.model_manager.list_installed_models()
+    /// Input replacing the typed partial with the final `text` followed by
+    /// `append`, which stays typed. An empty final just erases the partial.
+    fn commit(&mut self, text: &str, append: &str) -> String {
+        let mut input = self.replace(text);
+        if !text.is_empty() {
+            input.push_str(append);
+        }
+        self.typed.clear();
+        input
+    }
 }
 
-#[tauri::command]
-pub fn load_model(state: State<AppState>, model_path: String) -> Result<(), String> {
-    state
-        .recognizer
-        .load_model(std::path::Path::new(&model_path))
-        .map_err(|e| e.to_string())
+/// What `shutdown` tore down
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShutdownSummary {
+    /// Whether a recording was in progress
+    pub stopped_recording: bool,
+    /// Claude sessions that were running, sorted by id
+    pub stopped_sessions: Vec<String>,
 }
 
-#[tauri::command]
-pub fn is_model_loaded(state: State<AppState>) -> bool {
-    state.recognizer.is_model_loaded()
+/// Audio processed during the current (or last) recording session
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionStats {
+    /// Samples fed to the recognizer, at 16kHz
+    pub total_samples: u64,
+    pub duration_secs: f64,
+    /// Non-empty final results committed to the transcript
+    pub final_count: usize,
 }
 
-#[tauri::command]
-pub fn reset_recognizer(state: State<AppState>) {
-    eprintln!("[DEBUG] reset_recognizer called");
-    state.recognizer.reset();
-    eprintln!("[DEBUG] reset_recognizer completed");
+/// Speech recognition engine chosen with `set_backend`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechBackend {
+    /// Streaming recognition with partial results (the default)
+    Vosk,
+    /// whisper.cpp, more accurate but transcribes after each pause; needs the
+    /// `whisper` cargo feature
+    Whisper,
 }
 
-// ============================================================================
-// Claude Code Commands
-// ============================================================================
+/// How long the self test records for
+const SELF_TEST_DURATION: Duration = Duration::from_secs(1);
 
-#[tauri::command]
-pub fn start_claude(
-    app: AppHandle,
-    state: State<AppState>,
-    working_dir: Option<String>,
-) -> Result<(), String> {
-    // Set up output callback to emit events
-    let app_clone = app.clone();
-    state.claude.set_output_callback(Arc::new(move |event| {
-        let _ = app_clone.emit("claude-output", &event);
-    }));
+/// RMS level (0.0–1.0) below which the self test treats the microphone as
+/// silent, about -60 dBFS
+const SELF_TEST_MIN_RMS: f32 = 0.001;
 
-    state
-        .claude
-        .start(working_dir.as_deref())
-        .map_err(|e| e.to_string())
+/// Stage of the self test, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestStage {
+    Devices,
+    Model,
+    Capture,
+    Level,
+    Recognition,
 }
 
-#[tauri::command]
-pub fn stop_claude(state: State<AppState>) -> Result<(), String> {
-    state.claude.stop().map_err(|e| e.to_string())
+/// Outcome of `self_test`. Stages after the failed one are not run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SelfTestReport {
+    pub devices: Vec<AudioDeviceInfo>,
+    /// Device the test recorded from
+    pub device: Option<String>,
+    pub model_loaded: bool,
+    pub samples_captured: usize,
+    pub rms: f32,
+    /// What the recognizer heard; often empty when nobody spoke
+    pub transcript: Option<String>,
+    /// First stage that failed, None when every stage passed
+    pub failed_stage: Option<SelfTestStage>,
+    pub message: Option<String>,
 }
 
-#[tauri::command]
-pub fn send_to_claude(state: State<AppState>, input: String) -> Result<(), String> {
-    // Send input directly - xterm.js already sends appropriate characters
-    // (Enter sends \r, arrow keys send escape sequences like \x1b[A, etc.)
-    state
-        .claude
-        .send_input(&input)
-        .map_err(|e| e.to_string())
+impl SelfTestReport {
+    fn fail(mut self, stage: SelfTestStage, message: impl Into<String>) -> Self {
+        self.failed_stage = Some(stage);
+        self.message = Some(message.into());
+        self
+    }
 }
 
-#[tauri::command]
-pub fn resize_claude(state: State<AppState>, cols: u16, rows: u16) -> Result<(), String> {
-    state.claude.resize(cols, rows).map_err(|e| e.to_string())
+/// Precondition checked by `run_diagnostics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Diagnostic {
+    ModelsDir,
+    InputDevice,
+    VoskRuntime,
+    ClaudeBinary,
 }
 
-#[tauri::command]
-pub fn claude_status(state: State<AppState>) -> ProcessStatus {
-    state.claude.status()
+/// Outcome of one diagnostic check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticCheck {
+    pub check: Diagnostic,
+    pub passed: bool,
+    /// What was found, or why the check failed
+    pub detail: String,
 }
 
-// ============================================================================
-// Utility Commands
-// ============================================================================
+impl DiagnosticCheck {
+    fn new(check: Diagnostic, result: Result<String, String>) -> Self {
+        let passed = result.is_ok();
+        Self {
+            check,
+            passed,
+            detail: result.unwrap_or_else(|e| e),
+        }
+    }
+}
 
-#[tauri::command]
-pub fn get_app_info() -> serde_json::Value {
-    serde_json::json!({
-        "name": "icanhastool",
-        "version": env!("CARGO_PKG_VERSION"),
-        "description": env!("CARGO_PKG_DESCRIPTION"),
-    })
+/// Application state shared across commands
+pub struct AppState {
+    pub audio: Arc<dyn AudioCapture>,
+    /// Swappable so a scripted demo recognizer can stand in for Vosk. Shared with
+    /// the audio pipeline, so a running recording follows a language switch.
+    recognizer: Arc<Mutex<Arc<dyn SpeechRecognizer>>>,
+    /// Creates the recognizers models for other languages are kept warm in
+    recognizer_factory: RecognizerFactory,
+    /// Loaded models of inactive languages, keyed by language code, so
+    /// `set_active_language` can switch without reloading
+    warm_models: Mutex<HashMap<String, (Arc<dyn SpeechRecognizer>, ModelInfo)>>,
+    /// Claude sessions keyed by session id
+    pub claude: Mutex<HashMap<String, Arc<dyn ClaudeProcess>>>,
+    claude_factory: ClaudeFactory,
+    claude_config: Mutex<ClaudeConfig>,
+    pub model_manager: ModelManager,
+    /// Samples the CPU and memory use of Claude processes
+    process_monitor: Arc<dyn ProcessMonitor>,
+    /// Model currently loaded into the recognizer
+    loaded_model: Mutex<Option<ModelInfo>>,
+    pub level_meter: Arc<LevelMeter>,
+    /// Recent audio for the `audio-waveform` visualizer
+    waveform: Arc<WaveformMeter>,
+    /// Input gain and AGC applied before samples reach the recognizer
+    pub gain: Arc<GainControl>,
+    /// Silences background noise between utterances before recognition
+    pub noise_gate: Arc<NoiseGate>,
+    pub settings: Mutex<Settings>,
+    app_data_dir: PathBuf,
+    /// Owns a mock state's own app data directory, deleted with the state, so
+    /// tests that save settings don't share a file
+    #[cfg(test)]
+    _test_data_dir: Option<tempfile::TempDir>,
+    audio_callback: Mutex<Option<Arc<dyn Fn(Vec<i16>) + Send + Sync>>>,
+    /// Recognizes audio pushed with `feed_pcm`, kept between calls
+    pcm_pipeline: Mutex<Option<RecognitionPipeline>>,
+    /// Regroups captured audio into fixed-size chunks for the recognizer
+    chunker: Arc<Chunker>,
+    /// Feeds the chunker's remainder to the recognizer when recording stops
+    flush_audio: Mutex<Option<Box<dyn Fn() + Send + Sync>>>,
+    /// When the current recording started, None while not recording
+    recording_started: Mutex<Option<Instant>>,
+    /// Recordings longer than this many seconds are stopped (0 is unlimited)
+    max_recording_secs: AtomicU64,
+    /// Source of the current time for recording durations, replaced in tests
+    clock: Box<dyn Fn() -> Instant + Send + Sync>,
+    /// Whether the push-to-talk hotkey is currently held
+    ptt_active: AtomicBool,
+    /// Drops captured audio while the stream keeps running
+    muted: Arc<AtomicBool>,
+    /// How long a partial may go unchanged before it is reported stale (0 disables)
+    partial_timeout_ms: Arc<AtomicU64>,
+    /// Minimum audio time between partial results sent to the UI (0 sends all)
+    partial_emit_interval_ms: Arc<AtomicU64>,
+    /// Suffix sent after final transcriptions in dictation mode (None disables)
+    auto_send: Arc<Mutex<Option<String>>>,
+    /// Types partials into Claude's input as they change (None disables)
+    live_type: Arc<Mutex<Option<LiveTyper>>>,
+    /// Formats final transcriptions when auto-punctuation is enabled
+    text_processor: Arc<Mutex<TextProcessor>>,
+    auto_punctuation: Arc<AtomicBool>,
+    /// Reset the recognizer when decoding fails instead of leaving it broken
+    auto_recover: Arc<AtomicBool>,
+    /// Suppresses low-confidence results before they are emitted
+    confidence_filter: Arc<Mutex<ConfidenceFilter>>,
+    /// Drops a final repeating the previous one; holds the last final's text and time
+    duplicate_filter: Arc<Mutex<DuplicateFilter>>,
+    /// Trigger phrases checked before a final transcription is treated as dictation
+    voice_commands: Arc<Mutex<VoiceCommands>>,
+    /// Holds back transcriptions until a wake phrase is heard; None forwards everything
+    wake_gate: Arc<Mutex<Option<WakeGate>>>,
+    /// Samples fed to the recognizer since recording started, for result offsets
+    samples_processed: Arc<AtomicU64>,
+    /// Finalized results of the current recording session, for export
+    transcript: Arc<Mutex<Vec<RecognitionResult>>>,
+    /// Models currently being downloaded, with the token that cancels each
+    downloads: Arc<Mutex<HashMap<String, CancelToken>>>,
+    /// Detected Claude Code version, cleared when the binary path changes
+    claude_version: Mutex<Option<String>>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::audio::tests::MockAudioCapture;
-    use crate::claude::tests::MockClaudeProcess;
-    use crate::vosk_stt::tests::MockSpeechRecognizer;
+impl AppState {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let models_dir = app_data_dir.join("models");
 
-    fn create_test_state() -> AppState {
-        AppState::with_mocks(
-            Arc::new(MockAudioCapture::new()),
-            Arc::new(MockSpeechRecognizer::new()),
-            Arc::new(MockClaudeProcess::new()),
-        )
+        let state = Self {
+            audio: Arc::new(CpalAudioCapture::new()),
+            recognizer: Arc::new(Mutex::new(Arc::new(VoskRecognizer::new()))),
+            recognizer_factory: Box::new(|| Arc::new(VoskRecognizer::new())),
+            warm_models: Mutex::new(HashMap::new()),
+            claude: Mutex::new(HashMap::new()),
+            claude_factory: Box::new(|| Arc::new(ClaudeCodeProcess::new())),
+            claude_config: Mutex::new(ClaudeConfig::default()),
+            // Development builds also find the repo's models folder
+            model_manager: ModelManager::new(models_dir, cfg!(debug_assertions)),
+            process_monitor: Arc::new(SysinfoProcessMonitor::default()),
+            loaded_model: Mutex::new(None),
+            level_meter: Arc::new(LevelMeter::new()),
+            waveform: Arc::new(WaveformMeter::new()),
+            gain: Arc::new(GainControl::new()),
+            noise_gate: Arc::new(NoiseGate::new()),
+            settings: Mutex::new(Settings::load(&app_data_dir)),
+            app_data_dir,
+            #[cfg(test)]
+            _test_data_dir: None,
+            audio_callback: Mutex::new(None),
+            pcm_pipeline: Mutex::new(None),
+            chunker: Arc::new(Chunker::default()),
+            flush_audio: Mutex::new(None),
+            recording_started: Mutex::new(None),
+            max_recording_secs: AtomicU64::new(0),
+            clock: Box::new(Instant::now),
+            ptt_active: AtomicBool::new(false),
+            muted: Arc::new(AtomicBool::new(false)),
+            partial_timeout_ms: Arc::new(AtomicU64::new(0)),
+            partial_emit_interval_ms: Arc::new(AtomicU64::new(0)),
+            auto_send: Arc::new(Mutex::new(None)),
+            live_type: Arc::new(Mutex::new(None)),
+            text_processor: Arc::new(Mutex::new(TextProcessor::default())),
+            auto_punctuation: Arc::new(AtomicBool::new(false)),
+            auto_recover: Arc::new(AtomicBool::new(true)),
+            confidence_filter: Arc::new(Mutex::new(ConfidenceFilter::default())),
+            duplicate_filter: Arc::new(Mutex::new(DuplicateFilter::default())),
+            voice_commands: Arc::new(Mutex::new(VoiceCommands::default())),
+            wake_gate: Arc::new(Mutex::new(None)),
+            samples_processed: Arc::new(AtomicU64::new(0)),
+            transcript: Arc::new(Mutex::new(Vec::new())),
+            downloads: Arc::new(Mutex::new(HashMap::new())),
+            claude_version: Mutex::new(None),
+        };
+
+        state.apply_settings();
+        state
     }
 
-    #[test]
-    fn test_app_state_creation() {
-        let state = create_test_state();
-        assert!(!state.audio.is_recording());
-        assert!(!state.recognizer.is_model_loaded());
-        assert_eq!(state.claude.status(), ProcessStatus::Stopped);
+    /// Apply persisted settings to the backends
+    pub fn apply_settings(&self) {
+        let settings = self.settings.lock().clone();
+
+        for claude in self.claude_sessions() {
+            claude.set_binary_path(settings.claude_path.as_ref().map(PathBuf::from));
+        }
+
+        self.audio.set_buffer_frames(settings.buffer_frames);
+        if let Err(e) = self.audio.set_host(settings.audio_host.as_deref()) {
+            eprintln!("Using the default audio host: {}", e);
+        }
+        self.gain.set_gain(settings.input_gain.unwrap_or(1.0));
+        self.gain.set_agc(settings.agc);
+        self.noise_gate.set(
+            settings.noise_gate_threshold,
+            settings.noise_gate_release_ms,
+        );
+        *self.confidence_filter.lock() = ConfidenceFilter {
+            min_confidence: settings.min_confidence,
+            filter_partials: settings.filter_partials,
+        };
+        {
+            let mut duplicate_filter = self.duplicate_filter.lock();
+            duplicate_filter.window_ms = settings
+                .duplicate_window_ms
+                .map_or(DEFAULT_DUPLICATE_WINDOW_MS, u64::from);
+            duplicate_filter.max_distance = settings.duplicate_max_distance;
+        }
+        self.recognizer()
+            .set_keep_unknown_words(settings.keep_unknown_words);
+        self.partial_timeout_ms
+            .store(settings.partial_timeout_ms as u64, Ordering::SeqCst);
+        self.partial_emit_interval_ms
+            .store(settings.partial_emit_interval_ms as u64, Ordering::SeqCst);
+        self.max_recording_secs
+            .store(settings.max_recording_secs as u64, Ordering::SeqCst);
+        if let Some(frames) = settings.chunk_frames {
+            self.chunker.set_frames(frames);
+        }
+        let model_dirs: Vec<PathBuf> = settings.model_dirs.iter().map(PathBuf::from).collect();
+        self.model_manager.set_user_model_dirs(&model_dirs);
     }
 
-    #[test]
-    fn test_model_manager_in_state() {
-        let state = create_test_state();
-        let models = state.model_manager.list_available_models();
-        assert!(!models.is_empty());
+    /// Load the saved preferred model, or else the first installed one.
+    /// Emits `model-loaded` with the model info, or `model-load-error`.
+    pub fn load_default_model(&self, events: &dyn EventSink) -> Option<ModelInfo> {
+        let preferred = self
+            .settings
+            .lock()
+            .model_path
+            .as_ref()
+            .map(PathBuf::from)
+            .filter(|path| path.exists());
+
+        let model = match preferred {
+            Some(path) => ModelManager::model_info(&path),
+            None => self.model_manager.get_default_model()?,
+        };
+
+        match self.recognizer().load_model(&model.path) {
+            Ok(()) => {
+                *self.loaded_model.lock() = Some(model.clone());
+                events.emit_event("model-loaded", json!(model));
+                warn_sample_rate_mismatch(events, self.recognizer().as_ref());
+                Some(model)
+            }
+            Err(e) => {
+                events.emit_event(
+                    "model-load-error",
+                    json!({ "path": model.path, "kind": e.kind(), "message": e.to_string() }),
+                );
+                None
+            }
+        }
     }
 
-    #[test]
-    fn test_audio_callback_storage() {
-        let state = create_test_state();
+    /// Load the model at `path` and remember it as the preferred model.
+    /// Emits `low-memory-warning` first if the model likely won't fit in free RAM.
+    pub fn load_model(
+        &self,
+        events: &dyn EventSink,
+        path: &Path,
+    ) -> Result<ModelInfo, CommandError> {
+        warn_low_memory(events, &self.model_manager, path);
+        self.recognizer().load_model(path)?;
+        warn_sample_rate_mismatch(events, self.recognizer().as_ref());
 
-        assert!(state.audio_callback.lock().is_none());
+        let model = ModelManager::model_info(path);
+        *self.loaded_model.lock() = Some(model.clone());
 
-        let callback: Arc<dyn Fn(Vec<i16>) + Send + Sync> = Arc::new(|_| {});
-        *state.audio_callback.lock() = Some(callback);
+        // Remember the model so it is loaded again on next launch
+        self.update_settings(|s| s.model_path = Some(path.to_string_lossy().to_string()))?;
+        Ok(model)
+    }
 
-        assert!(state.audio_callback.lock().is_some());
+    /// Load the model at `path` like `load_model`, but without stopping a
+    /// recording in progress: the old model's pending speech is emitted as a
+    /// final result and later audio goes to the new model
+    pub fn switch_model(
+        &self,
+        events: &dyn EventSink,
+        path: &Path,
+    ) -> Result<ModelInfo, CommandError> {
+        if !self.audio.is_recording() {
+            return self.load_model(events, path);
+        }
 
-        *state.audio_callback.lock() = None;
-        assert!(state.audio_callback.lock().is_none());
+        let result = self.recognizer().switch_model(path)?;
+        emit_final(events, self, result);
+        warn_sample_rate_mismatch(events, self.recognizer().as_ref());
+
+        let model = ModelManager::model_info(path);
+        *self.loaded_model.lock() = Some(model.clone());
+        self.update_settings(|s| s.model_path = Some(path.to_string_lossy().to_string()))?;
+        Ok(model)
     }
 
-    #[test]
-    fn test_get_app_info() {
-        let info = get_app_info();
+    /// Recognizer audio is currently fed to
+    pub fn recognizer(&self) -> Arc<dyn SpeechRecognizer> {
+        self.recognizer.lock().clone()
+    }
 
-        assert_eq!(info["name"], "icanhastool");
-        assert!(info["version"].is_string());
-        assert!(info["description"].is_string());
+    /// Load the model at `path` into a recognizer of its own and keep it warm
+    /// for `set_active_language`, replacing a model kept for the same language
+    pub fn load_language_model(&self, path: &Path) -> Result<ModelInfo, CommandError> {
+        let model = ModelManager::model_info(path);
+        let code = model.language.code.clone();
+        if self
+            .loaded_model()
+            .is_some_and(|active| active.language.code == code)
+        {
+            return Err(CommandError::new(
+                "language_active",
+                format!(
+                    "{} is the active language; use load_model to replace its model",
+                    code
+                ),
+            ));
+        }
+
+        let recognizer = (self.recognizer_factory)();
+        recognizer.set_keep_unknown_words(self.settings.lock().keep_unknown_words);
+        recognizer.load_model(path)?;
+        self.warm_models
+            .lock()
+            .insert(code, (recognizer, model.clone()));
+        Ok(model)
+    }
+
+    /// Send audio to the warm model for `code`, keeping the previous model warm
+    /// in its place. Takes effect immediately: during a recording the previous
+    /// model's pending speech is emitted as a final result and the next chunk
+    /// goes to the new model.
+    pub fn set_active_language(
+        &self,
+        events: &dyn EventSink,
+        code: &str,
+    ) -> Result<ModelInfo, CommandError> {
+        let active = self.loaded_model();
+        if let Some(model) = active.as_ref().filter(|m| m.language.code == code) {
+            return Ok(model.clone());
+        }
+
+        let (recognizer, model) = self.warm_models.lock().remove(code).ok_or_else(|| {
+            CommandError::new(
+                "language_not_loaded",
+                format!("No model loaded for language: {}", code),
+            )
+        })?;
+        recognizer.reset();
+
+        let previous = std::mem::replace(&mut *self.recognizer.lock(), recognizer);
+        *self.loaded_model.lock() = Some(model.clone());
+        if let Some(previous_model) = active {
+            self.warm_models.lock().insert(
+                previous_model.language.code.clone(),
+                (previous.clone(), previous_model),
+            );
+        }
+
+        if self.audio.is_recording() {
+            emit_final(events, self, previous.get_final_result()?);
+        }
+        let path = model.path.to_string_lossy().to_string();
+        self.update_settings(|s| s.model_path = Some(path))?;
+        Ok(model)
+    }
+
+    /// Languages with a loaded model, active or warm, sorted by code
+    pub fn loaded_languages(&self) -> Vec<ModelLanguage> {
+        let mut languages: Vec<ModelLanguage> = self
+            .warm_models
+            .lock()
+            .values()
+            .map(|(_, model)| model.language.clone())
+            .chain(self.loaded_model().map(|model| model.language))
+            .collect();
+        languages.sort_by(|a, b| a.code.cmp(&b.code));
+        languages
+    }
+
+    /// Counters are reset when a recording starts
+    pub fn session_stats(&self) -> SessionStats {
+        let total_samples = self.samples_processed.load(Ordering::SeqCst);
+        SessionStats {
+            total_samples,
+            duration_secs: total_samples as f64 / TARGET_SAMPLE_RATE as f64,
+            final_count: self.transcript.lock().len(),
+        }
+    }
+
+    /// Replace the recognizer, including for a recording already running
+    pub fn set_recognizer(&self, recognizer: Arc<dyn SpeechRecognizer>) {
+        recognizer.set_keep_unknown_words(self.settings.lock().keep_unknown_words);
+        *self.recognizer.lock() = recognizer;
+        *self.loaded_model.lock() = None;
+    }
+
+    /// Ask the running download of `name` to stop
+    pub fn cancel_download(&self, name: &str) -> Result<(), CommandError> {
+        match self.downloads.lock().get(name) {
+            Some(cancel) => {
+                cancel.cancel();
+                Ok(())
+            }
+            None => Err(CommandError::new(
+                "no_active_download",
+                format!("{} is not downloading", name),
+            )),
+        }
+    }
+
+    /// Scan `dir` for models too, remember it in the settings and emit
+    /// `models-updated` with the installed models
+    pub fn add_model_dir(&self, events: &dyn EventSink, dir: &Path) -> Result<(), CommandError> {
+        self.model_manager.add_model_dir(dir)?;
+        self.model_dirs_changed(events)
+    }
+
+    pub fn remove_model_dir(&self, events: &dyn EventSink, dir: &Path) -> Result<(), CommandError> {
+        self.model_manager.remove_model_dir(dir)?;
+        self.model_dirs_changed(events)
+    }
+
+    fn model_dirs_changed(&self, events: &dyn EventSink) -> Result<(), CommandError> {
+        let model_dirs = self
+            .model_manager
+            .user_model_dirs()
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect();
+        let saved = self.update_settings(|s| s.model_dirs = model_dirs);
+
+        events.emit_event(
+            "models-updated",
+            json!(self.model_manager.list_installed_models()),
+        );
+        saved
+    }
+
+    /// Free the loaded model's memory. The preferred model stays in the
+    /// settings, so `load_default_model` loads it again.
+    pub fn unload_model(&self) -> Result<(), CommandError> {
+        if self.audio.is_recording() {
+            return Err(CommandError::new(
+                "recording_in_progress",
+                "Cannot unload the model while recording",
+            ));
+        }
+
+        self.recognizer().unload_model();
+        *self.loaded_model.lock() = None;
+        Ok(())
+    }
+
+    /// Info for the model currently loaded into the recognizer
+    pub fn loaded_model(&self) -> Option<ModelInfo> {
+        self.loaded_model.lock().clone()
+    }
+
+    /// Version of the configured Claude binary, detected once and then cached
+    pub fn claude_version(&self) -> Result<String, CommandError> {
+        let mut cached = self.claude_version.lock();
+        if let Some(version) = cached.as_ref() {
+            return Ok(version.clone());
+        }
+
+        let version = self.default_claude().detect_version()?;
+        *cached = Some(version.clone());
+        Ok(version)
+    }
+
+    /// Update settings in memory and write them to disk
+    pub fn update_settings(&self, update: impl FnOnce(&mut Settings)) -> Result<(), CommandError> {
+        let mut settings = self.settings.lock();
+        update(&mut settings);
+        settings
+            .save(&self.app_data_dir)
+            .map_err(CommandError::from)
+    }
+
+    /// Stop recording, reset the recognizer and gracefully stop every running
+    /// Claude session. Safe to call repeatedly; later calls find nothing to stop.
+    pub fn shutdown(&self) -> ShutdownSummary {
+        // Claimed like a stop, so a racing stop or auto-stop can't finalize it too
+        let stopped_recording = claim_recording(self);
+        if stopped_recording {
+            self.audio.stop_recording();
+            *self.audio_callback.lock() = None;
+            *self.flush_audio.lock() = None;
+            self.chunker.reset();
+            self.level_meter.reset();
+            self.waveform.reset();
+            self.ptt_active.store(false, Ordering::SeqCst);
+        }
+        self.recognizer().reset();
+
+        let mut stopped_sessions = Vec::new();
+        for session_id in self.claude_session_ids() {
+            let Ok(claude) = self.claude_session(&session_id) else {
+                continue;
+            };
+            if !matches!(
+                claude.status(),
+                ProcessStatus::Starting | ProcessStatus::Running
+            ) {
+                continue;
+            }
+
+            if let Err(e) = claude.stop() {
+                eprintln!("Failed to stop Claude session {}: {}", session_id, e);
+            }
+            stopped_sessions.push(session_id);
+        }
+
+        ShutdownSummary {
+            stopped_recording,
+            stopped_sessions,
+        }
+    }
+
+    /// Sequence appended to lines sent to Claude
+    pub fn submit_sequence(&self) -> String {
+        self.settings
+            .lock()
+            .submit_sequence
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SUBMIT_SEQUENCE.to_string())
+    }
+
+    /// The session used by dictation and commands without a session id
+    pub fn default_claude(&self) -> Arc<dyn ClaudeProcess> {
+        self.open_claude_session(DEFAULT_CLAUDE_SESSION)
+    }
+
+    /// Look up an existing Claude session
+    pub fn claude_session(&self, session_id: &str) -> Result<Arc<dyn ClaudeProcess>, CommandError> {
+        self.claude.lock().get(session_id).cloned().ok_or_else(|| {
+            CommandError::new(
+                "session_not_found",
+                format!("Unknown Claude session: {}", session_id),
+            )
+        })
+    }
+
+    /// Get a Claude session, creating and configuring it if it doesn't exist
+    pub fn open_claude_session(&self, session_id: &str) -> Arc<dyn ClaudeProcess> {
+        let mut sessions = self.claude.lock();
+        if let Some(claude) = sessions.get(session_id) {
+            return claude.clone();
+        }
+
+        let claude = (self.claude_factory)();
+        let claude_path = self.settings.lock().claude_path.clone();
+        claude.set_binary_path(claude_path.map(PathBuf::from));
+        self.configure_claude(claude.as_ref());
+
+        sessions.insert(session_id.to_string(), claude.clone());
+        claude
+    }
+
+    /// Forget a session. The default session is kept so its settings survive a restart.
+    pub fn close_claude_session(&self, session_id: &str) {
+        if session_id != DEFAULT_CLAUDE_SESSION {
+            self.claude.lock().remove(session_id);
+        }
+    }
+
+    /// Ids of all open sessions, sorted
+    pub fn claude_session_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.claude.lock().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    fn claude_sessions(&self) -> Vec<Arc<dyn ClaudeProcess>> {
+        self.claude.lock().values().cloned().collect()
+    }
+
+    /// Change a session-wide Claude option and apply it to every open session
+    fn update_claude_config(&self, update: impl FnOnce(&mut ClaudeConfig)) {
+        update(&mut self.claude_config.lock());
+        for claude in self.claude_sessions() {
+            self.configure_claude(claude.as_ref());
+        }
+    }
+
+    fn configure_claude(&self, claude: &dyn ClaudeProcess) {
+        let config = self.claude_config.lock().clone();
+        if let Some(enabled) = config.auto_restart {
+            claude.set_auto_restart(enabled);
+        }
+        if let Some(enabled) = config.strip_ansi {
+            claude.set_strip_ansi(enabled);
+        }
+        if let Some(ms) = config.shutdown_grace_ms {
+            claude.set_shutdown_grace_ms(ms);
+        }
+        if let Some(ms) = config.write_timeout_ms {
+            claude.set_write_timeout_ms(ms);
+        }
+        if let Some(bytes) = config.read_buffer_size {
+            claude.set_read_buffer_size(bytes);
+        }
+        if let Some(bytes) = config.scrollback_limit {
+            claude.set_scrollback_limit(bytes);
+        }
+        if let Some(rotation) = config.log_rotation {
+            claude.set_log_rotation(rotation);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_mocks(
+        audio: Arc<dyn AudioCapture>,
+        recognizer: Arc<dyn SpeechRecognizer>,
+        claude: Arc<dyn ClaudeProcess>,
+    ) -> Self {
+        let sessions = HashMap::from([(DEFAULT_CLAUDE_SESSION.to_string(), claude)]);
+        let data_dir = tempfile::tempdir().expect("Failed to create test data directory");
+
+        Self {
+            audio,
+            recognizer: Arc::new(Mutex::new(recognizer)),
+            recognizer_factory: Box::new(|| {
+                Arc::new(crate::vosk_stt::tests::MockSpeechRecognizer::new())
+            }),
+            warm_models: Mutex::new(HashMap::new()),
+            claude: Mutex::new(sessions),
+            claude_factory: Box::new(|| Arc::new(crate::claude::tests::MockClaudeProcess::new())),
+            claude_config: Mutex::new(ClaudeConfig::default()),
+            model_manager: ModelManager::new_isolated(PathBuf::from("/test/models")),
+            process_monitor: Arc::new(crate::claude::tests::MockProcessMonitor::default()),
+            loaded_model: Mutex::new(None),
+            level_meter: Arc::new(LevelMeter::new()),
+            waveform: Arc::new(WaveformMeter::new()),
+            gain: Arc::new(GainControl::new()),
+            noise_gate: Arc::new(NoiseGate::new()),
+            settings: Mutex::new(Settings::default()),
+            app_data_dir: data_dir.path().to_path_buf(),
+            _test_data_dir: Some(data_dir),
+            audio_callback: Mutex::new(None),
+            pcm_pipeline: Mutex::new(None),
+            // Tests push buffers straight through to the recognizer
+            chunker: Arc::new(Chunker::new(0)),
+            flush_audio: Mutex::new(None),
+            recording_started: Mutex::new(None),
+            max_recording_secs: AtomicU64::new(0),
+            clock: Box::new(Instant::now),
+            ptt_active: AtomicBool::new(false),
+            muted: Arc::new(AtomicBool::new(false)),
+            partial_timeout_ms: Arc::new(AtomicU64::new(0)),
+            partial_emit_interval_ms: Arc::new(AtomicU64::new(0)),
+            auto_send: Arc::new(Mutex::new(None)),
+            live_type: Arc::new(Mutex::new(None)),
+            text_processor: Arc::new(Mutex::new(TextProcessor::default())),
+            auto_punctuation: Arc::new(AtomicBool::new(false)),
+            auto_recover: Arc::new(AtomicBool::new(true)),
+            confidence_filter: Arc::new(Mutex::new(ConfidenceFilter::default())),
+            // Mock recognizers repeat the same final text
+            duplicate_filter: Arc::new(Mutex::new(DuplicateFilter {
+                window_ms: 0,
+                ..DuplicateFilter::default()
+            })),
+            voice_commands: Arc::new(Mutex::new(VoiceCommands::default())),
+            wake_gate: Arc::new(Mutex::new(None)),
+            samples_processed: Arc::new(AtomicU64::new(0)),
+            transcript: Arc::new(Mutex::new(Vec::new())),
+            downloads: Arc::new(Mutex::new(HashMap::new())),
+            claude_version: Mutex::new(None),
+        }
+    }
+
+    /// Sample process resource use with `monitor` instead of the test default
+    #[cfg(test)]
+    pub fn with_process_monitor(mut self, monitor: Arc<dyn ProcessMonitor>) -> Self {
+        self.process_monitor = monitor;
+        self
+    }
+
+    /// Look for models in `models_dir` instead of the test default
+    #[cfg(test)]
+    pub fn with_model_manager(mut self, model_manager: ModelManager) -> Self {
+        self.model_manager = model_manager;
+        self
+    }
+
+    /// Replace the factory used to create recognizers for warm language models
+    #[cfg(test)]
+    pub fn with_recognizer_factory(
+        mut self,
+        factory: impl Fn() -> Arc<dyn SpeechRecognizer> + Send + Sync + 'static,
+    ) -> Self {
+        self.recognizer_factory = Box::new(factory);
+        self
+    }
+
+    /// Replace the factory used to create new Claude sessions
+    #[cfg(test)]
+    pub fn with_claude_factory(
+        mut self,
+        factory: impl Fn() -> Arc<dyn ClaudeProcess> + Send + Sync + 'static,
+    ) -> Self {
+        self.claude_factory = Box::new(factory);
+        self
+    }
+}
+
+/// Destination for backend events.
+/// Implemented by `AppHandle`; tests substitute a recorder.
+pub trait EventSink: Send + Sync + 'static {
+    fn emit_event(&self, event: &str, payload: serde_json::Value);
+}
+
+impl<R: Runtime> EventSink for AppHandle<R> {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.emit(event, payload);
+    }
+}
+
+/// Shows files and directories in the OS file manager.
+/// Implemented by `AppHandle`; tests substitute a recorder.
+pub trait PathRevealer {
+    fn reveal(&self, path: &Path) -> Result<(), String>;
+}
+
+impl<R: Runtime> PathRevealer for AppHandle<R> {
+    fn reveal(&self, path: &Path) -> Result<(), String> {
+        // Open directories themselves; select files in their folder
+        let opened = if path.is_dir() {
+            self.opener()
+                .open_path(path.to_string_lossy(), None::<&str>)
+        } else {
+            self.opener().reveal_item_in_dir(path)
+        };
+        opened.map_err(|e| e.to_string())
+    }
+}
+
+/// Error returned by commands: a stable snake_case `kind` the frontend can
+/// branch on, e.g. "model_not_found" or "device_not_found", and a readable message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandError {
+    pub kind: String,
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(kind: &str, message: impl Into<String>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<AudioError> for CommandError {
+    fn from(error: AudioError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<SpeechError> for CommandError {
+    fn from(error: SpeechError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<ClaudeError> for CommandError {
+    fn from(error: ClaudeError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<SettingsError> for CommandError {
+    fn from(error: SettingsError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<TranscriptError> for CommandError {
+    fn from(error: TranscriptError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<SymbolDictionaryError> for CommandError {
+    fn from(error: SymbolDictionaryError) -> Self {
+        Self::new(error.kind(), error.to_string())
+    }
+}
+
+// ============================================================================
+// Audio Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn list_audio_devices(state: State<AppState>) -> Result<Vec<AudioDeviceInfo>, CommandError> {
+    state.audio.list_devices().map_err(CommandError::from)
+}
+
+/// Audio backends that can be chosen with `set_audio_host`
+#[tauri::command]
+pub fn list_audio_hosts(state: State<AppState>) -> Vec<String> {
+    state.audio.list_hosts()
+}
+
+/// Capture through another audio backend (None is the platform default) and
+/// return its devices. Takes effect on the next recording.
+#[tauri::command]
+pub fn set_audio_host(
+    state: State<AppState>,
+    name: Option<String>,
+) -> Result<Vec<AudioDeviceInfo>, CommandError> {
+    switch_audio_host(&state, name)
+}
+
+fn switch_audio_host(
+    state: &AppState,
+    name: Option<String>,
+) -> Result<Vec<AudioDeviceInfo>, CommandError> {
+    state.audio.set_host(name.as_deref())?;
+    state.update_settings(|s| s.audio_host = name)?;
+    state.audio.list_devices().map_err(CommandError::from)
+}
+
+/// Start recording. Fails without a loaded model unless `allow_without_model`
+/// is set, e.g. when only capturing to a WAV file.
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    state: State<AppState>,
+    device_name: Option<String>,
+    allow_without_model: Option<bool>,
+) -> Result<(), CommandError> {
+    try_begin_recording(
+        Arc::new(app),
+        &state,
+        device_name,
+        allow_without_model.unwrap_or(false),
+    )
+}
+
+/// Whether the app may record from the microphone (always granted outside macOS)
+#[tauri::command]
+pub fn check_microphone_permission(state: State<AppState>) -> PermissionStatus {
+    state.audio.microphone_permission()
+}
+
+/// Show the system's microphone prompt if the user hasn't answered it yet.
+/// Waiting for the answer blocks, so it runs off the main thread.
+#[tauri::command]
+pub async fn request_microphone_permission(
+    state: State<'_, AppState>,
+) -> Result<PermissionStatus, CommandError> {
+    let audio = state.audio.clone();
+    tauri::async_runtime::spawn_blocking(move || audio.request_microphone_permission())
+        .await
+        .map_err(|e| CommandError::new("permission_request_error", e.to_string()))
+}
+
+#[tauri::command]
+pub fn stop_recording(
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<RecognitionResult, CommandError> {
+    finish_recording(&app, &state)
+}
+
+/// Seconds since recording started, for a UI timer (0 while not recording)
+#[tauri::command]
+pub fn recording_elapsed_secs(state: State<AppState>) -> f64 {
+    recording_elapsed(&state).map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+/// Stop recording on its own after `secs` seconds, in case the microphone is
+/// left open by mistake; 0 records without limit
+#[tauri::command]
+pub fn set_max_recording_secs(state: State<AppState>, secs: u32) -> Result<(), CommandError> {
+    state
+        .max_recording_secs
+        .store(secs as u64, Ordering::SeqCst);
+    state.update_settings(|s| s.max_recording_secs = secs)
+}
+
+fn recording_elapsed(state: &AppState) -> Option<Duration> {
+    let started = (*state.recording_started.lock())?;
+    Some((state.clock)().saturating_duration_since(started))
+}
+
+/// Finalize a recording that has run past the maximum duration like
+/// `stop_recording`, after emitting `recording-auto-stopped`. Returns true if
+/// the recording was stopped.
+fn enforce_max_recording(events: &dyn EventSink, state: &AppState) -> bool {
+    let max_secs = state.max_recording_secs.load(Ordering::SeqCst);
+    let Some(elapsed) = recording_elapsed(state) else {
+        return false;
+    };
+    if max_secs == 0 || elapsed < Duration::from_secs(max_secs) || !state.audio.is_recording() {
+        return false;
+    }
+    // The user may have stopped the recording since it was checked
+    if !claim_recording(state) {
+        return false;
+    }
+    // A push-to-talk recording is over; its key release has nothing to stop
+    state.ptt_active.store(false, Ordering::SeqCst);
+
+    events.emit_event(
+        "recording-auto-stopped",
+        json!({ "elapsed_secs": elapsed.as_secs_f64(), "max_secs": max_secs }),
+    );
+    if let Err(e) = finalize_recording(events, state) {
+        eprintln!("Failed to finalize auto-stopped recording: {}", e);
+    }
+    true
+}
+
+/// How often the watchdog checks the recording duration
+const RECORDING_WATCHDOG_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Start the background thread that stops recordings at the maximum duration
+pub fn spawn_recording_watchdog<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(RECORDING_WATCHDOG_INTERVAL);
+        let state = app.state::<AppState>();
+        enforce_max_recording(&app, &state);
+    });
+}
+
+/// Start a new transcription session: result offsets count from zero and the
+/// transcript is empty
+fn reset_session(state: &AppState) {
+    state.samples_processed.store(0, Ordering::SeqCst);
+    state.transcript.lock().clear();
+    state.duplicate_filter.lock().last_final = None;
+    if let Some(gate) = state.wake_gate.lock().as_mut() {
+        gate.disarm();
+    }
+    if let Some(typer) = state.live_type.lock().as_mut() {
+        typer.typed.clear();
+    }
+}
+
+/// Recognize audio captured outside the app, e.g. by a browser tab, instead of
+/// a local device. Samples must be 16kHz mono i16. They go to the recognizer
+/// as given, without gain, noise gate or chunking, and results are emitted
+/// with the same events as live recording. Returns the result emitted for
+/// this buffer, if any. The first call starts a new session.
+#[tauri::command]
+pub fn feed_pcm(
+    app: AppHandle,
+    state: State<AppState>,
+    samples: Vec<i16>,
+) -> Result<Option<RecognitionResult>, CommandError> {
+    feed_samples(Arc::new(app), &state, samples)
+}
+
+fn feed_samples(
+    events: Arc<dyn EventSink>,
+    state: &AppState,
+    samples: Vec<i16>,
+) -> Result<Option<RecognitionResult>, CommandError> {
+    if !state.recognizer().is_model_loaded() {
+        return Err(CommandError::new(
+            "model_not_loaded",
+            "No speech model loaded",
+        ));
+    }
+    // Fed audio would be interleaved with the microphone's
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot feed audio while recording",
+        ));
+    }
+    if samples.is_empty() {
+        return Ok(None);
+    }
+
+    let pipeline = state
+        .pcm_pipeline
+        .lock()
+        .get_or_insert_with(|| {
+            reset_session(state);
+            recognition_pipeline(events, state)
+        })
+        .clone();
+    Ok(pipeline(samples))
+}
+
+/// Refuse to open a stream whose every buffer would fail recognition
+fn try_begin_recording(
+    events: Arc<dyn EventSink>,
+    state: &AppState,
+    device_name: Option<String>,
+    allow_without_model: bool,
+) -> Result<(), CommandError> {
+    if !allow_without_model && !state.recognizer().is_model_loaded() {
+        return Err(CommandError::new(
+            "model_not_loaded",
+            "No speech model loaded",
+        ));
+    }
+    // Without access macOS records silence, which looks like a broken model.
+    // The prompt blocks until answered, so the frontend asks for it through
+    // `request_microphone_permission` and tries again.
+    match state.audio.microphone_permission() {
+        PermissionStatus::Granted => {}
+        PermissionStatus::Undetermined => return Err(AudioError::PermissionUndetermined.into()),
+        PermissionStatus::Denied => return Err(AudioError::PermissionDenied.into()),
+    }
+
+    begin_recording(events, state, device_name)
+}
+
+/// Runs one chunk of 16kHz mono audio through the recognizer and emits the
+/// result, as described on `recognition_pipeline`
+type RecognitionPipeline = Arc<dyn Fn(Vec<i16>) -> Option<RecognitionResult> + Send + Sync>;
+
+/// Build the recognition stage shared by live capture and `feed_pcm`. Each
+/// chunk goes through these stages in order:
+///
+/// 1. Resample to the recognizer's rate and decode. Failures emit
+///    `recognition-error` once per run of failing chunks; with auto-recover on,
+///    the recognizer is reset and `recognizer-recovered` fires once it decodes again.
+/// 2. Emit `transcription-partial-cleared` when a partial has stayed unchanged
+///    for the partial timeout.
+/// 3. Drop results below the confidence threshold (`transcription-rejected`) and
+///    finals repeating the previous one (`transcription-duplicate-suppressed`).
+/// 4. With a wake word set, hold results back until `wake-detected`, and again
+///    after `wake-ended` or `wake-timeout`.
+/// 5. Emit partials as `transcription-partial`, at most one per emit interval.
+/// 6. Emit finals matching a voice command as `voice-command` instead of dictating them.
+/// 7. Polish other finals if auto-punctuation is on, send them to Claude, and emit
+///    `transcription-final` (plus the deprecated `transcription`), then
+///    `transcription-committed` with the session's text and `utterance-end` if the
+///    recognizer detected an endpoint on its own.
+///
+/// The pipeline returns the result it emitted, or None when there was nothing to
+/// show or the result was filtered out, handled as a voice command, or failed.
+fn recognition_pipeline(events: Arc<dyn EventSink>, state: &AppState) -> RecognitionPipeline {
+    let active_recognizer = state.recognizer.clone();
+    let claude = state.default_claude();
+    let auto_send = state.auto_send.clone();
+    let live_type = state.live_type.clone();
+    let text_processor = state.text_processor.clone();
+    let auto_punctuation = state.auto_punctuation.clone();
+    let auto_recover = state.auto_recover.clone();
+    let partial_timeout_ms = state.partial_timeout_ms.clone();
+    let partial_emit_interval_ms = state.partial_emit_interval_ms.clone();
+    let confidence_filter = state.confidence_filter.clone();
+    let duplicate_filter = state.duplicate_filter.clone();
+    let voice_commands = state.voice_commands.clone();
+    let wake_gate = state.wake_gate.clone();
+    let samples_processed = state.samples_processed.clone();
+    let transcript = state.transcript.clone();
+    // Report a failing recognizer once rather than for every buffer
+    let recognition_failing = AtomicBool::new(false);
+    // Set after an automatic reset until the recognizer processes audio again
+    let recovering = AtomicBool::new(false);
+    let stale_partial = Mutex::new(StalePartial::default());
+    let partial_throttle = Mutex::new(PartialThrottle::default());
+    let recognizer_rate = Mutex::new(RecognizerRate::default());
+
+    Arc::new(move |samples| {
+        let recognizer = active_recognizer.lock().clone();
+        let count = samples.len() as u64;
+        let processed = samples_processed.fetch_add(count, Ordering::SeqCst) + count;
+
+        let samples = recognizer_rate
+            .lock()
+            .convert(recognizer.sample_rate(), samples);
+        let result = recognizer.process_audio(&samples);
+        if result.is_ok() && recovering.swap(false, Ordering::SeqCst) {
+            events.emit_event("recognizer-recovered", json!(null));
+        }
+
+        let shown = result.as_ref().ok().and_then(|result| result.as_ref());
+        let now_ms = samples_to_ms(processed);
+        let timeout_ms = partial_timeout_ms.load(Ordering::SeqCst);
+        if let Some(text) = stale_partial.lock().update(shown, now_ms, timeout_ms) {
+            events.emit_event(
+                "transcription-partial-cleared",
+                json!({ "text": text, "offset_ms": now_ms }),
+            );
+        }
+
+        if let Some(gate) = wake_gate.lock().as_mut() {
+            if gate.check_timeout(now_ms) {
+                events.emit_event("wake-timeout", json!({ "offset_ms": now_ms }));
+            }
+        }
+
+        let interval_ms = partial_emit_interval_ms.load(Ordering::SeqCst);
+        match result {
+            Ok(Some(mut result)) => {
+                recognition_failing.store(false, Ordering::SeqCst);
+                result.offset_ms = samples_to_ms(processed);
+                if result.is_final {
+                    partial_throttle
+                        .lock()
+                        .update(Some(&result), now_ms, interval_ms);
+                }
+                if reject_low_confidence(events.as_ref(), &confidence_filter, &result)
+                    || suppress_duplicate(events.as_ref(), &duplicate_filter, &result)
+                    || !pass_wake_gate(events.as_ref(), &wake_gate, &mut result)
+                {
+                    if result.is_final {
+                        live_type_final(claude.as_ref(), &live_type, &auto_send, "");
+                    }
+                    return None;
+                }
+                if !result.is_final {
+                    let due = partial_throttle
+                        .lock()
+                        .update(Some(&result), now_ms, interval_ms);
+                    if let Some(partial) = due {
+                        emit_partial(events.as_ref(), &partial);
+                        live_type_partial(claude.as_ref(), &live_type, &partial);
+                    }
+                    return Some(result);
+                }
+                if route_voice_command(events.as_ref(), &voice_commands, &result) {
+                    live_type_final(claude.as_ref(), &live_type, &auto_send, "");
+                    return None;
+                }
+                if auto_punctuation.load(Ordering::SeqCst) {
+                    result.text = text_processor.lock().polish(&result.text);
+                }
+                if !live_type_final(claude.as_ref(), &live_type, &auto_send, &result.text) {
+                    send_final_to_claude(claude.as_ref(), &auto_send, &result);
+                }
+
+                events.emit_event("transcription-final", json!(result));
+                events.emit_event("transcription", json!(result));
+                commit_final(events.as_ref(), &transcript, &result);
+
+                if result.endpoint {
+                    events.emit_event("utterance-end", json!(result));
+                }
+                Some(result)
+            }
+            Ok(None) => {
+                recognition_failing.store(false, Ordering::SeqCst);
+                // Send a held-back partial once its interval is up
+                let due = partial_throttle.lock().update(None, now_ms, interval_ms);
+                if let Some(partial) = due {
+                    emit_partial(events.as_ref(), &partial);
+                    live_type_partial(claude.as_ref(), &live_type, &partial);
+                }
+                None
+            }
+            Err(e) => {
+                if !recognition_failing.swap(true, Ordering::SeqCst) {
+                    events.emit_event(
+                        "recognition-error",
+                        json!({ "kind": e.kind(), "message": e.to_string() }),
+                    );
+                }
+                // Vosk keeps failing after a failed decode until it is reset
+                if matches!(e, SpeechError::RecognitionError(_))
+                    && auto_recover.load(Ordering::SeqCst)
+                {
+                    recognizer.reset();
+                    recovering.store(true, Ordering::SeqCst);
+                }
+                None
+            }
+        }
+    })
+}
+
+/// Start capturing and feed fixed-size chunks of audio (see `set_chunk_frames`)
+/// through `recognition_pipeline`. Capture itself emits `audio-level`,
+/// `audio-waveform`, `recording-started` once the stream is live, and
+/// `audio-warning` or `audio-error` when the device misbehaves.
+fn begin_recording(
+    events: Arc<dyn EventSink>,
+    state: &AppState,
+    device_name: Option<String>,
+) -> Result<(), CommandError> {
+    let level_meter = state.level_meter.clone();
+    let waveform = state.waveform.clone();
+    let gain = state.gain.clone();
+    let noise_gate = state.noise_gate.clone();
+    let muted = state.muted.clone();
+    let chunker = state.chunker.clone();
+    let device_events = events.clone();
+    let chunk_events = events.clone();
+    let recognize = recognition_pipeline(events, state);
+
+    // Create callback that conditions captured audio and passes it on in chunks
+    let chunk_recognize = recognize.clone();
+    let callback: Arc<dyn Fn(Vec<i16>) + Send + Sync> = Arc::new(move |mut samples| {
+        // Some drivers deliver empty buffers at stream start
+        if samples.is_empty() || muted.load(Ordering::SeqCst) {
+            return;
+        }
+
+        gain.process(&mut samples);
+
+        if let Some(level) = level_meter.update(&samples) {
+            chunk_events.emit_event("audio-level", json!(level));
+        }
+        if let Some(points) = waveform.update(&samples) {
+            chunk_events.emit_event("audio-waveform", json!(points));
+        }
+
+        // Gate after metering so the level display still shows background noise
+        noise_gate.process(&mut samples);
+
+        for chunk in chunker.push(&samples) {
+            chunk_recognize(chunk);
+        }
+    });
+
+    let flush_chunker = state.chunker.clone();
+    *state.flush_audio.lock() = Some(Box::new(move || {
+        let remainder = flush_chunker.flush();
+        if !remainder.is_empty() {
+            recognize(remainder);
+        }
+    }));
+    *state.audio_callback.lock() = Some(callback.clone());
+    *state.pcm_pipeline.lock() = None;
+    state.chunker.reset();
+    state.gain.reset();
+    state.noise_gate.reset();
+    reset_session(state);
+
+    state
+        .audio
+        .set_device_event_callback(Arc::new(move |event| match event {
+            DeviceEvent::Started(info) => {
+                device_events.emit_event("recording-started", json!(info));
+            }
+            DeviceEvent::Disconnected(device) => {
+                device_events.emit_event("device-disconnected", json!(device));
+            }
+            DeviceEvent::Switched { from, to } => {
+                device_events.emit_event("device-switched", json!({ "from": from, "to": to }));
+            }
+            DeviceEvent::Error { kind, message } => {
+                device_events
+                    .emit_event("audio-error", json!({ "kind": kind, "message": message }));
+            }
+            DeviceEvent::BufferSizeRejected { requested } => {
+                device_events.emit_event(
+                    "audio-warning",
+                    json!({
+                        "message": format!(
+                            "Device rejected a {} frame buffer; using its default size",
+                            requested
+                        ),
+                        "requested_frames": requested,
+                    }),
+                );
+            }
+        }));
+
+    let device_name = device_name.or_else(|| preferred_device(state));
+
+    state
+        .audio
+        .start_recording(device_name.as_deref(), callback)?;
+    *state.recording_started.lock() = Some((state.clock)());
+    Ok(())
+}
+
+/// Stop capturing and emit the final transcription, followed by `session-ended`
+/// with the session's stats.
+/// A rejected low-confidence result or a trailing voice command is returned as
+/// an empty result instead.
+fn finish_recording(
+    events: &dyn EventSink,
+    state: &AppState,
+) -> Result<RecognitionResult, CommandError> {
+    if !claim_recording(state) {
+        return Err(CommandError::new(
+            "not_recording",
+            "No recording in progress",
+        ));
+    }
+    finalize_recording(events, state)
+}
+
+/// Mark the recording as ending, returning false if it already was. Stopping,
+/// releasing push-to-talk and the max-duration watchdog can race, and only
+/// the caller that claims the recording may finalize it.
+fn claim_recording(state: &AppState) -> bool {
+    state.recording_started.lock().take().is_some()
+}
+
+/// Stop capture and emit the final result of a claimed recording
+fn finalize_recording(
+    events: &dyn EventSink,
+    state: &AppState,
+) -> Result<RecognitionResult, CommandError> {
+    state.audio.stop_recording();
+    *state.audio_callback.lock() = None;
+    state.level_meter.reset();
+    state.waveform.reset();
+
+    // The last partial chunk still holds speech
+    if let Some(flush) = state.flush_audio.lock().take() {
+        flush();
+    }
+
+    let result = final_result(events, state);
+    events.emit_event("session-ended", json!(state.session_stats()));
+    result
+}
+
+/// Flush the recognizer and emit its final result
+fn final_result(
+    events: &dyn EventSink,
+    state: &AppState,
+) -> Result<RecognitionResult, CommandError> {
+    let result = state.recognizer().get_final_result()?;
+    Ok(emit_final(events, state, result))
+}
+
+/// Filter, polish and emit a final result flushed from the recognizer
+fn emit_final(
+    events: &dyn EventSink,
+    state: &AppState,
+    mut result: RecognitionResult,
+) -> RecognitionResult {
+    result.offset_ms = samples_to_ms(state.samples_processed.load(Ordering::SeqCst));
+
+    let claude = state.default_claude();
+    if reject_low_confidence(events, &state.confidence_filter, &result)
+        || suppress_duplicate(events, &state.duplicate_filter, &result)
+        || !pass_wake_gate(events, &state.wake_gate, &mut result)
+        || route_voice_command(events, &state.voice_commands, &result)
+    {
+        result.text.clear();
+        live_type_final(claude.as_ref(), &state.live_type, &state.auto_send, "");
+        return result;
+    }
+
+    if state.auto_punctuation.load(Ordering::SeqCst) {
+        result.text = state.text_processor.lock().polish(&result.text);
+    }
+
+    if !live_type_final(
+        claude.as_ref(),
+        &state.live_type,
+        &state.auto_send,
+        &result.text,
+    ) {
+        send_final_to_claude(claude.as_ref(), &state.auto_send, &result);
+    }
+
+    // Emit final result
+    events.emit_event("transcription-final", json!(result));
+    commit_final(events, &state.transcript, &result);
+
+    result
+}
+
+/// Add a final result to the session transcript and emit
+/// `transcription-committed` with the session's text so far
+fn commit_final(
+    events: &dyn EventSink,
+    transcript: &Mutex<Vec<RecognitionResult>>,
+    result: &RecognitionResult,
+) {
+    if result.text.trim().is_empty() {
+        return;
+    }
+
+    let full_text = {
+        let mut transcript = transcript.lock();
+        transcript.push(result.clone());
+        full_text(&transcript)
+    };
+    events.emit_event("transcription-committed", json!({ "full_text": full_text }));
+}
+
+/// The saved device if it is still connected. Otherwise recording falls back to
+/// the default device, which becomes the saved device.
+fn preferred_device(state: &AppState) -> Option<String> {
+    let saved = state.settings.lock().selected_device.clone()?;
+
+    // Without a device list, let start_recording report the problem
+    let Ok(devices) = state.audio.list_devices() else {
+        return Some(saved);
+    };
+    if devices.iter().any(|d| d.name == saved) {
+        return Some(saved);
+    }
+
+    let fallback = devices.into_iter().find(|d| d.is_default).map(|d| d.name);
+    if let Err(e) = state.update_settings(|s| s.selected_device = fallback.clone()) {
+        eprintln!("Failed to save fallback device: {}", e);
+    }
+    fallback
+}
+
+/// Warn when the model at `path` is estimated to need more memory than is free.
+/// Loading still goes ahead, since the estimate is rough and the OS can swap.
+fn warn_low_memory(events: &dyn EventSink, model_manager: &ModelManager, path: &Path) {
+    let Some((estimated_bytes, available_bytes)) = model_manager.check_memory_for_model(path)
+    else {
+        return;
+    };
+
+    events.emit_event(
+        "low-memory-warning",
+        json!({
+            "path": path,
+            "estimated_bytes": estimated_bytes,
+            "available_bytes": available_bytes,
+        }),
+    );
+}
+
+/// Warn when the loaded model was trained at a different rate than the
+/// recognizer is fed at; recognition still runs but accuracy suffers
+fn warn_sample_rate_mismatch(events: &dyn EventSink, recognizer: &dyn SpeechRecognizer) {
+    let pipeline_rate = recognizer.sample_rate();
+    let Some(model_rate) = recognizer.model_sample_rate() else {
+        return;
+    };
+    if model_rate == pipeline_rate {
+        return;
+    }
+
+    events.emit_event(
+        "sample-rate-warning",
+        json!({
+            "model_rate": model_rate,
+            "pipeline_rate": pipeline_rate,
+            "message": format!(
+                "Model expects {} Hz audio but recognition runs at {} Hz; accuracy may suffer",
+                model_rate, pipeline_rate
+            ),
+        }),
+    );
+}
+
+/// Converts the pipeline's 16kHz buffers to the rate the recognizer is fed
+/// at, carrying resampler state across buffers until that rate changes
+#[derive(Default)]
+struct RecognizerRate {
+    resampler: Option<(u32, StreamResampler)>,
+}
+
+impl RecognizerRate {
+    fn convert(&mut self, rate: u32, samples: Vec<i16>) -> Vec<i16> {
+        if rate == TARGET_SAMPLE_RATE {
+            self.resampler = None;
+            return samples;
+        }
+        if !matches!(self.resampler, Some((current, _)) if current == rate) {
+            self.resampler = Some((rate, StreamResampler::new(TARGET_SAMPLE_RATE, rate)));
+        }
+        match &mut self.resampler {
+            Some((_, resampler)) => resampler.process(&samples),
+            None => samples,
+        }
+    }
+}
+
+/// Convert a whole 16kHz clip to the rate the recognizer is fed at
+fn to_recognizer_rate(recognizer: &dyn SpeechRecognizer, samples: Vec<i16>) -> Vec<i16> {
+    match recognizer.sample_rate() {
+        TARGET_SAMPLE_RATE => samples,
+        rate => resample(&samples, TARGET_SAMPLE_RATE, rate),
+    }
+}
+
+/// Duration of `samples` 16kHz samples in milliseconds
+fn samples_to_ms(samples: u64) -> u64 {
+    samples * 1000 / TARGET_SAMPLE_RATE as u64
+}
+
+/// Text of the current (or last) recording session, finals joined by spaces
+#[tauri::command]
+pub fn get_current_transcript(state: State<AppState>) -> String {
+    full_text(&state.transcript.lock())
+}
+
+/// Write the finalized transcriptions of the last recording session to `path`,
+/// as SRT subtitles if it ends in `.srt` and as a JSON array otherwise
+#[tauri::command]
+pub fn export_transcript(state: State<AppState>, path: String) -> Result<(), CommandError> {
+    let results = state.transcript.lock().clone();
+    write_transcript(&results, Path::new(&path)).map_err(CommandError::from)
+}
+
+/// Remember `name` as the device to record from when none is given
+/// (None records from the system default)
+#[tauri::command]
+pub fn set_preferred_device(
+    state: State<AppState>,
+    name: Option<String>,
+) -> Result<(), CommandError> {
+    set_preferred(&state, name)
+}
+
+fn set_preferred(state: &AppState, name: Option<String>) -> Result<(), CommandError> {
+    if let Some(name) = &name {
+        if !state.audio.list_devices()?.iter().any(|d| &d.name == name) {
+            return Err(AudioError::DeviceNotFound(name.clone()).into());
+        }
+    }
+
+    state.update_settings(|s| s.selected_device = name)
+}
+
+#[tauri::command]
+pub fn is_recording(state: State<AppState>) -> bool {
+    state.audio.is_recording()
+}
+
+/// Fall back to the default input device if the recording device is unplugged
+#[tauri::command]
+pub fn set_device_fallback(state: State<AppState>, enabled: bool) {
+    state.audio.set_device_fallback(enabled);
+}
+
+/// Amplify the microphone before recognition (1.0 is unchanged)
+#[tauri::command]
+pub fn set_input_gain(state: State<AppState>, gain: f32) -> Result<(), CommandError> {
+    if !gain.is_finite() || gain < 0.0 {
+        return Err(CommandError::new(
+            "invalid_argument",
+            format!("Invalid input gain: {}", gain),
+        ));
+    }
+
+    state.gain.set_gain(gain);
+    let gain = state.gain.gain();
+    state.update_settings(|s| s.input_gain = Some(gain))
+}
+
+/// Automatically normalize quiet or loud input towards a comfortable level
+#[tauri::command]
+pub fn set_agc(state: State<AppState>, enabled: bool) -> Result<(), CommandError> {
+    state.gain.set_agc(enabled);
+    state.update_settings(|s| s.agc = enabled)
+}
+
+/// Zero input below `threshold` RMS (0.0–1.0, 0 disables) before recognition,
+/// staying open for `release_ms` after speech so word endings aren't cut
+#[tauri::command]
+pub fn set_noise_gate(
+    state: State<AppState>,
+    threshold: f32,
+    release_ms: u32,
+) -> Result<(), CommandError> {
+    if !threshold.is_finite() || !(0.0..=1.0).contains(&threshold) {
+        return Err(CommandError::new(
+            "invalid_argument",
+            format!("Invalid noise gate threshold: {}", threshold),
+        ));
+    }
+
+    state.noise_gate.set(threshold, release_ms);
+    state.update_settings(|s| {
+        s.noise_gate_threshold = threshold;
+        s.noise_gate_release_ms = release_ms;
+    })
+}
+
+/// Drop results whose average word confidence is below `min_confidence` (0 disables).
+/// Partial results are only filtered when `filter_partials` is set.
+#[tauri::command]
+pub fn set_min_confidence(
+    state: State<AppState>,
+    min_confidence: f32,
+    filter_partials: Option<bool>,
+) -> Result<(), CommandError> {
+    if !min_confidence.is_finite() || !(0.0..=1.0).contains(&min_confidence) {
+        return Err(CommandError::new(
+            "invalid_argument",
+            format!("Invalid confidence threshold: {}", min_confidence),
+        ));
+    }
+
+    let filter_partials = filter_partials.unwrap_or(false);
+    *state.confidence_filter.lock() = ConfidenceFilter {
+        min_confidence,
+        filter_partials,
+    };
+    state.update_settings(|s| {
+        s.min_confidence = min_confidence;
+        s.filter_partials = filter_partials;
+    })
+}
+
+/// Show Vosk's `[unk]` token for words it couldn't recognize instead of
+/// removing them from final results
+#[tauri::command]
+pub fn set_keep_unknown_words(state: State<AppState>, enabled: bool) -> Result<(), CommandError> {
+    state.recognizer().set_keep_unknown_words(enabled);
+    state.update_settings(|s| s.keep_unknown_words = enabled)
+}
+
+/// Fix the capture buffer size in frames (None uses the device default).
+/// Smaller buffers lower the latency of partial results but cost more CPU.
+#[tauri::command]
+pub fn set_buffer_frames(state: State<AppState>, frames: Option<u32>) -> Result<(), CommandError> {
+    if frames == Some(0) {
+        return Err(CommandError::new(
+            "invalid_argument",
+            "Buffer size must be at least one frame",
+        ));
+    }
+
+    state.audio.set_buffer_frames(frames);
+    state.update_settings(|s| s.buffer_frames = frames)
+}
+
+/// Feed the recognizer fixed chunks of `frames` samples (0 passes capture
+/// buffers through as they arrive). The default is 3200, 200ms of audio.
+#[tauri::command]
+pub fn set_chunk_frames(state: State<AppState>, frames: usize) -> Result<(), CommandError> {
+    if frames > TARGET_SAMPLE_RATE as usize {
+        return Err(CommandError::new(
+            "invalid_argument",
+            format!(
+                "Chunks can be at most one second ({} frames)",
+                TARGET_SAMPLE_RATE
+            ),
+        ));
+    }
+
+    state.chunker.set_frames(frames);
+    state.update_settings(|s| s.chunk_frames = Some(frames))
+}
+
+/// Input configurations a device supports, so the UI can warn about unusable devices
+#[tauri::command]
+pub fn list_device_configs(
+    state: State<AppState>,
+    device_name: String,
+) -> Result<Vec<DeviceConfig>, CommandError> {
+    state
+        .audio
+        .list_device_configs(&device_name)
+        .map_err(CommandError::from)
+}
+
+/// Device being recorded from, once the stream is live
+#[tauri::command]
+pub fn get_active_device(state: State<AppState>) -> Option<AudioDeviceInfo> {
+    state.audio.get_active_device()
+}
+
+#[tauri::command]
+pub fn get_audio_level(state: State<AppState>) -> AudioLevel {
+    state.level_meter.level()
+}
+
+/// Save captured audio to a WAV file on the next recording (None disables)
+#[tauri::command]
+pub fn record_to_file(state: State<AppState>, path: Option<String>) {
+    state.audio.set_wav_output(path.map(PathBuf::from));
+}
+
+/// Forward a finalized transcription to Claude when dictation mode is on.
+/// Partial results and empty text are never sent.
+fn send_final_to_claude(
+    claude: &dyn ClaudeProcess,
+    auto_send: &Mutex<Option<String>>,
+    result: &RecognitionResult,
+) {
+    if !result.is_final || result.text.trim().is_empty() {
+        return;
+    }
+
+    if let Some(append) = auto_send.lock().as_ref() {
+        if let Err(e) = claude.send_input(&format!("{}{}", result.text, append)) {
+            eprintln!("Failed to send transcription to Claude: {}", e);
+        }
+    }
+}
+
+/// In live-type mode, type a partial into Claude's input over the previous one
+fn live_type_partial(
+    claude: &dyn ClaudeProcess,
+    live_type: &Mutex<Option<LiveTyper>>,
+    result: &RecognitionResult,
+) {
+    let mut live_type = live_type.lock();
+    let Some(typer) = live_type.as_mut() else {
+        return;
+    };
+    send_live_input(claude, &typer.replace(&result.text));
+}
+
+/// In live-type mode, replace the typed partial with the final `text` followed
+/// by the auto-send suffix, or a space when dictation mode is off. Returns false
+/// when live typing is off and the final still needs sending.
+fn live_type_final(
+    claude: &dyn ClaudeProcess,
+    live_type: &Mutex<Option<LiveTyper>>,
+    auto_send: &Mutex<Option<String>>,
+    text: &str,
+) -> bool {
+    let mut live_type = live_type.lock();
+    let Some(typer) = live_type.as_mut() else {
+        return false;
+    };
+    let append = auto_send.lock().clone().unwrap_or_else(|| " ".to_string());
+    send_live_input(claude, &typer.commit(text.trim(), &append));
+    true
+}
+
+fn send_live_input(claude: &dyn ClaudeProcess, input: &str) {
+    if input.is_empty() {
+        return;
+    }
+    if let Err(e) = claude.send_input(input) {
+        eprintln!("Failed to type transcription into Claude: {}", e);
+    }
+}
+
+/// Emit an interim result, including as the deprecated `transcription` event
+fn emit_partial(events: &dyn EventSink, result: &RecognitionResult) {
+    events.emit_event("transcription-partial", json!(result));
+    events.emit_event("transcription", json!(result));
+}
+
+/// Emit `transcription-duplicate-suppressed` with the result when a final repeats
+/// the previous one. Returns true if the result must not be emitted or dictated.
+fn suppress_duplicate(
+    events: &dyn EventSink,
+    filter: &Mutex<DuplicateFilter>,
+    result: &RecognitionResult,
+) -> bool {
+    if !result.is_final || !filter.lock().is_duplicate(&result.text, result.offset_ms) {
+        return false;
+    }
+
+    events.emit_event("transcription-duplicate-suppressed", json!(result));
+    true
+}
+
+/// Emit `transcription-rejected` with the text and score when the filter drops a result.
+/// Returns true if the result must not be emitted or dictated.
+fn reject_low_confidence(
+    events: &dyn EventSink,
+    filter: &Mutex<ConfidenceFilter>,
+    result: &RecognitionResult,
+) -> bool {
+    if !filter.lock().rejects(result) {
+        return false;
+    }
+
+    events.emit_event(
+        "transcription-rejected",
+        json!({
+            "text": result.text,
+            "confidence": result.confidence,
+            "is_final": result.is_final,
+        }),
+    );
+    true
+}
+
+/// Hold back results while the wake-word gate is waiting for its wake phrase,
+/// emitting `wake-detected` and `wake-ended` with the utterance that contains
+/// the wake or end phrase. A forwarded final has those phrases removed.
+/// Returns false if the result must not be emitted or dictated.
+fn pass_wake_gate(
+    events: &dyn EventSink,
+    wake_gate: &Mutex<Option<WakeGate>>,
+    result: &mut RecognitionResult,
+) -> bool {
+    let mut wake_gate = wake_gate.lock();
+    let Some(gate) = wake_gate.as_mut() else {
+        return true;
+    };
+
+    if !result.is_final {
+        if !result.text.is_empty() {
+            gate.heard(result.offset_ms);
+        }
+        return gate.is_armed();
+    }
+
+    let gated = gate.filter_final(&result.text, result.offset_ms);
+    let payload = json!({ "text": result.text, "offset_ms": result.offset_ms });
+    if gated.woke {
+        events.emit_event("wake-detected", payload.clone());
+    }
+    if gated.ended {
+        events.emit_event("wake-ended", payload);
+    }
+    let Some(keep) = gated.keep else {
+        return false;
+    };
+
+    let words: Vec<&str> = result.text.split_whitespace().collect();
+    // Word timings line up with the text unless the recognizer split words differently
+    if result.words.len() == words.len() {
+        result.words = result.words[keep.clone()].to_vec();
+    }
+    result.text = words[keep].join(" ");
+    true
+}
+
+/// Emit `voice-command` when a final transcription is a trigger phrase.
+/// Returns true if the text was a command and must not be dictated.
+fn route_voice_command(
+    events: &dyn EventSink,
+    voice_commands: &Mutex<VoiceCommands>,
+    result: &RecognitionResult,
+) -> bool {
+    if !result.is_final {
+        return false;
+    }
+
+    match voice_commands.lock().match_action(&result.text) {
+        Some(action) => {
+            events.emit_event(
+                "voice-command",
+                json!({ "action": action, "text": result.text }),
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Dictate to Claude: send each final transcription followed by `append`
+/// (the configured submit sequence by default)
+#[tauri::command]
+pub fn set_auto_send(state: State<AppState>, enabled: bool, append: Option<String>) {
+    *state.auto_send.lock() = enabled.then(|| append.unwrap_or_else(|| state.submit_sequence()));
+}
+
+/// Type partial transcriptions into Claude's input as they change, erasing the
+/// previous partial with backspaces. Finals replace the partial and stay typed,
+/// followed by the auto-send suffix if dictation mode is on.
+#[tauri::command]
+pub fn set_live_type(state: State<AppState>, enabled: bool) {
+    *state.live_type.lock() = enabled.then(LiveTyper::default);
+}
+
+/// Capitalize and punctuate final transcriptions
+#[tauri::command]
+pub fn set_auto_punctuation(state: State<AppState>, enabled: bool) {
+    state.auto_punctuation.store(enabled, Ordering::SeqCst);
+}
+
+/// Report a partial result as stale (`transcription-partial-cleared`) once it has
+/// gone `timeout_ms` of audio without changing; 0 turns this off
+#[tauri::command]
+pub fn set_partial_timeout(state: State<AppState>, timeout_ms: u32) -> Result<(), CommandError> {
+    state
+        .partial_timeout_ms
+        .store(timeout_ms as u64, Ordering::SeqCst);
+    state.update_settings(|s| s.partial_timeout_ms = timeout_ms)
+}
+
+/// Drop a final that repeats the previous one within `window_ms` of audio (0 disables).
+/// Finals up to `max_distance` character edits apart count as repeats.
+#[tauri::command]
+pub fn set_duplicate_suppression(
+    state: State<AppState>,
+    window_ms: u32,
+    max_distance: usize,
+) -> Result<(), CommandError> {
+    {
+        let mut filter = state.duplicate_filter.lock();
+        filter.window_ms = window_ms as u64;
+        filter.max_distance = max_distance;
+    }
+    state.update_settings(|s| {
+        s.duplicate_window_ms = Some(window_ms);
+        s.duplicate_max_distance = max_distance;
+    })
+}
+
+/// Send at most one partial result per `interval_ms` of audio, the latest one,
+/// so a fast model doesn't flood the UI; 0 sends every partial
+#[tauri::command]
+pub fn set_partial_emit_interval_ms(
+    state: State<AppState>,
+    interval_ms: u32,
+) -> Result<(), CommandError> {
+    state
+        .partial_emit_interval_ms
+        .store(interval_ms as u64, Ordering::SeqCst);
+    state.update_settings(|s| s.partial_emit_interval_ms = interval_ms)
+}
+
+/// Discard captured audio without stopping the stream, which is quicker than
+/// stopping and restarting for a short interruption. Decode state is kept, so
+/// an utterance in progress continues after unmuting.
+#[tauri::command]
+pub fn set_muted(state: State<AppState>, muted: bool) {
+    state.muted.store(muted, Ordering::SeqCst);
+}
+
+/// Reset the recognizer automatically after a failed decode (on by default)
+#[tauri::command]
+pub fn set_auto_recover(state: State<AppState>, enabled: bool) {
+    state.auto_recover.store(enabled, Ordering::SeqCst);
+}
+
+/// Replace the spoken punctuation map, e.g. [("period", ".")] (None restores the defaults)
+#[tauri::command]
+pub fn set_spoken_commands(state: State<AppState>, commands: Option<Vec<(String, String)>>) {
+    let commands = commands.unwrap_or_else(|| {
+        DEFAULT_SPOKEN_COMMANDS
+            .iter()
+            .map(|(phrase, symbol)| (phrase.to_string(), symbol.to_string()))
+            .collect()
+    });
+    state.text_processor.lock().set_commands(commands);
+}
+
+/// Replace the programming symbol dictionary, e.g. [("double colon", "::")]
+/// (None turns symbols off). Symbols keep the spacing they're given.
+#[tauri::command]
+pub fn set_symbol_dictionary(state: State<AppState>, symbols: Option<Vec<(String, String)>>) {
+    state
+        .text_processor
+        .lock()
+        .set_symbols(symbols.unwrap_or_default());
+}
+
+/// Replace spoken programming symbols ("double colon", "arrow", ...) using the
+/// built-in Rust dictionary, or turn symbols off again. Off by default, since
+/// the same words turn up in prose.
+#[tauri::command]
+pub fn set_code_dictation(state: State<AppState>, enabled: bool) {
+    let symbols = if enabled {
+        to_owned_pairs(DEFAULT_SYMBOLS)
+    } else {
+        Vec::new()
+    };
+    state.text_processor.lock().set_symbols(symbols);
+}
+
+/// Replace the symbol dictionary with a JSON file of {"spoken phrase": "symbol"},
+/// returning how many symbols it has
+#[tauri::command]
+pub fn load_symbol_dictionary(state: State<AppState>, path: String) -> Result<usize, CommandError> {
+    let symbols = load_symbols(Path::new(&path))?;
+    let count = symbols.len();
+    state.text_processor.lock().set_symbols(symbols);
+    Ok(count)
+}
+
+/// Replace the voice command map, e.g. [("send message", "SendToClaude")] (None restores the defaults)
+#[tauri::command]
+pub fn set_voice_commands(state: State<AppState>, commands: Option<Vec<(String, VoiceAction)>>) {
+    *state.voice_commands.lock() = commands.map(VoiceCommands::new).unwrap_or_default();
+}
+
+/// Only forward transcriptions once `phrase` (e.g. "hey tool") is heard, until
+/// `end_phrase` is spoken or `timeout_ms` of audio passes without speech
+/// (0 never times out). None turns the gate off.
+#[tauri::command]
+pub fn set_wake_word(
+    state: State<AppState>,
+    phrase: Option<String>,
+    end_phrase: Option<String>,
+    timeout_ms: u32,
+) -> Result<(), CommandError> {
+    configure_wake_word(&state, phrase, end_phrase, timeout_ms)
+}
+
+fn configure_wake_word(
+    state: &AppState,
+    phrase: Option<String>,
+    end_phrase: Option<String>,
+    timeout_ms: u32,
+) -> Result<(), CommandError> {
+    let gate = match phrase {
+        Some(phrase) => Some(
+            WakeGate::new(&phrase, end_phrase.as_deref(), timeout_ms as u64).ok_or_else(|| {
+                CommandError::new("invalid_wake_word", "Wake phrase must contain a word")
+            })?,
+        ),
+        None => None,
+    };
+    *state.wake_gate.lock() = gate;
+    Ok(())
+}
+
+// ============================================================================
+// Push-to-Talk Commands
+// ============================================================================
+
+/// Bind a global hotkey that records while held and transcribes on release
+#[tauri::command]
+pub fn register_ptt_shortcut(
+    app: AppHandle,
+    state: State<AppState>,
+    accelerator: String,
+) -> Result<(), CommandError> {
+    let previous = state.settings.lock().ptt_shortcut.clone();
+    if let Some(previous) = previous {
+        unregister_ptt(&app, &previous)?;
+    }
+
+    register_ptt(&app, &accelerator)?;
+    state.update_settings(|s| s.ptt_shortcut = Some(accelerator))
+}
+
+#[tauri::command]
+pub fn unregister_ptt_shortcut(app: AppHandle, state: State<AppState>) -> Result<(), CommandError> {
+    let previous = state.settings.lock().ptt_shortcut.clone();
+    if let Some(previous) = previous {
+        unregister_ptt(&app, &previous)?;
+    }
+
+    state.update_settings(|s| s.ptt_shortcut = None)
+}
+
+/// Register the push-to-talk handler for `accelerator` (e.g. "CmdOrCtrl+Shift+Space")
+pub fn register_ptt<R: Runtime>(app: &AppHandle<R>, accelerator: &str) -> Result<(), CommandError> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+        CommandError::new(
+            "invalid_shortcut",
+            format!("Invalid shortcut {}: {}", accelerator, e),
+        )
+    })?;
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            let state = app.state::<AppState>();
+            handle_ptt(
+                Arc::new(app.clone()),
+                &state,
+                event.state() == ShortcutState::Pressed,
+            );
+        })
+        .map_err(|e| CommandError::new("shortcut_error", e.to_string()))
+}
+
+fn unregister_ptt<R: Runtime>(app: &AppHandle<R>, accelerator: &str) -> Result<(), CommandError> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+        CommandError::new(
+            "invalid_shortcut",
+            format!("Invalid shortcut {}: {}", accelerator, e),
+        )
+    })?;
+
+    app.global_shortcut()
+        .unregister(shortcut)
+        .map_err(|e| CommandError::new("shortcut_error", e.to_string()))
+}
+
+/// Start recording on press and send the final transcription on release.
+/// Key repeat while held is ignored.
+fn handle_ptt(events: Arc<dyn EventSink>, state: &AppState, pressed: bool) {
+    if pressed {
+        if state.ptt_active.load(Ordering::SeqCst) {
+            return;
+        }
+
+        match try_begin_recording(events.clone(), state, None, false) {
+            Ok(()) => {
+                state.ptt_active.store(true, Ordering::SeqCst);
+                events.emit_event("ptt-active", json!(null));
+            }
+            Err(e) => events.emit_event("ptt-error", json!(e)),
+        }
+    } else {
+        if !state.ptt_active.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        match finish_recording(events.as_ref(), state) {
+            Ok(result) => events.emit_event("ptt-released", json!(result)),
+            Err(e) => events.emit_event("ptt-error", json!(e)),
+        }
+    }
+}
+
+// ============================================================================
+// Speech Recognition Commands
+// ============================================================================
+
+/// Downloadable models from the Vosk model index, or a built-in list when offline.
+/// Fetching the index blocks, so it runs off the main thread.
+#[tauri::command]
+pub async fn list_models(state: State<'_, AppState>) -> Result<Vec<ModelInfo>, CommandError> {
+    let model_manager = state.model_manager.clone();
+    tauri::async_runtime::spawn_blocking(move || model_manager.fetch_model_catalog())
+        .await
+        .map_err(|e| CommandError::new("model_list_error", e.to_string()))
+}
+
+#[tauri::command]
+pub fn list_installed_models(state: State<AppState>) -> Vec<ModelInfo> {
+    state.model_manager.list_installed_models()
+}
+
+/// Groups of installed models with identical contents under different names.
+/// Confirming a match reads the whole model, so it runs off the main thread.
+#[tauri::command]
+pub async fn find_duplicate_models(
+    state: State<'_, AppState>,
+) -> Result<Vec<Vec<ModelInfo>>, CommandError> {
+    let model_manager = state.model_manager.clone();
+    tauri::async_runtime::spawn_blocking(move || model_manager.find_duplicate_models())
+        .await
+        .map_err(|e| CommandError::new("model_list_error", e.to_string()))
+}
+
+/// Look for models in another folder, e.g. a network share or external drive
+#[tauri::command]
+pub fn add_model_dir(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+) -> Result<(), CommandError> {
+    state.add_model_dir(&app, Path::new(&path))
+}
+
+#[tauri::command]
+pub fn remove_model_dir(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+) -> Result<(), CommandError> {
+    state.remove_model_dir(&app, Path::new(&path))
+}
+
+/// Download and install a model in the background, resuming an interrupted download.
+/// Emits `download-progress` while running, then `model-downloaded` with the
+/// model info or `download-error`.
+#[tauri::command]
+pub fn download_model(
+    app: AppHandle,
+    state: State<AppState>,
+    name: String,
+) -> Result<(), CommandError> {
+    start_download(Arc::new(app), &state, name, false).map(|_| ())
+}
+
+/// Continue an interrupted download; fails if there is nothing to resume
+#[tauri::command]
+pub fn resume_download(
+    app: AppHandle,
+    state: State<AppState>,
+    name: String,
+) -> Result<(), CommandError> {
+    start_download(Arc::new(app), &state, name, true).map(|_| ())
+}
+
+fn start_download(
+    events: Arc<dyn EventSink>,
+    state: &AppState,
+    name: String,
+    resume: bool,
+) -> Result<JoinHandle<()>, CommandError> {
+    if resume && !state.model_manager.has_partial_download(&name) {
+        return Err(CommandError::new(
+            "no_partial_download",
+            format!("No partial download of {}", name),
+        ));
+    }
+    let cancel = CancelToken::default();
+    {
+        let mut downloads = state.downloads.lock();
+        if downloads.contains_key(&name) {
+            return Err(CommandError::new(
+                "download_in_progress",
+                format!("{} is already downloading", name),
+            ));
+        }
+        downloads.insert(name.clone(), cancel.clone());
+    }
+
+    let manager = state.model_manager.clone();
+    let downloads = state.downloads.clone();
+
+    Ok(std::thread::spawn(move || {
+        let result = manager.download_model(&name, &cancel, &|progress| {
+            events.emit_event("download-progress", json!(progress));
+        });
+        downloads.lock().remove(&name);
+
+        match result {
+            Ok(model) => events.emit_event("model-downloaded", json!(model)),
+            Err(e) => events.emit_event(
+                "download-error",
+                json!({ "name": name, "kind": e.kind(), "message": e.to_string() }),
+            ),
+        }
+    }))
+}
+
+/// Stop a running download and delete its partial file. The download thread
+/// then emits `download-error` with kind `download_cancelled`.
+#[tauri::command]
+pub fn cancel_download(state: State<AppState>, name: String) -> Result<(), CommandError> {
+    state.cancel_download(&name)
+}
+
+/// Check an installed model against its known SHA-256, deleting it on mismatch.
+/// Returns false if no checksum is known for the model.
+#[tauri::command]
+pub fn verify_model(state: State<AppState>, name: String) -> Result<bool, CommandError> {
+    state
+        .model_manager
+        .verify_model(&name)
+        .map_err(CommandError::from)
+}
+
+/// Estimated memory in bytes the model `name` needs once loaded
+#[tauri::command]
+pub fn estimate_model_memory(state: State<AppState>, name: String) -> Result<u64, CommandError> {
+    state
+        .model_manager
+        .estimate_model_memory(&name)
+        .map_err(CommandError::from)
+}
+
+/// Fails with an insufficient disk space error if `name` won't fit in the models directory
+#[tauri::command]
+pub fn check_space_for_model(state: State<AppState>, name: String) -> Result<(), CommandError> {
+    state
+        .model_manager
+        .check_space_for_model(&name)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn load_model(
+    app: AppHandle,
+    state: State<AppState>,
+    model_path: String,
+) -> Result<(), CommandError> {
+    state.load_model(&app, Path::new(&model_path)).map(|_| ())
+}
+
+/// Change models mid-recording; see `AppState::switch_model`
+#[tauri::command]
+pub fn switch_model(
+    app: AppHandle,
+    state: State<AppState>,
+    model_path: String,
+) -> Result<ModelInfo, CommandError> {
+    state.switch_model(&app, Path::new(&model_path))
+}
+
+/// Keep a model for another language loaded alongside the active one
+#[tauri::command]
+pub fn load_language_model(
+    state: State<AppState>,
+    model_path: String,
+) -> Result<ModelInfo, CommandError> {
+    state.load_language_model(Path::new(&model_path))
+}
+
+/// Route audio to the loaded model for `code` (e.g. "es"); see `AppState::set_active_language`
+#[tauri::command]
+pub fn set_active_language(
+    app: AppHandle,
+    state: State<AppState>,
+    code: String,
+) -> Result<ModelInfo, CommandError> {
+    state.set_active_language(&app, &code)
+}
+
+#[tauri::command]
+pub fn list_loaded_languages(state: State<AppState>) -> Vec<ModelLanguage> {
+    state.loaded_languages()
+}
+
+/// The model loaded into the recognizer, e.g. to show the active model's language
+#[tauri::command]
+pub fn get_loaded_model(state: State<AppState>) -> Option<ModelInfo> {
+    state.loaded_model()
+}
+
+/// Load the preferred or first installed model, as done at startup
+#[tauri::command]
+pub fn load_default_model(app: AppHandle, state: State<AppState>) -> Option<ModelInfo> {
+    state.load_default_model(&app)
+}
+
+/// Unload the speech model to free memory without quitting
+#[tauri::command]
+pub fn unload_model(state: State<AppState>) -> Result<(), CommandError> {
+    state.unload_model()
+}
+
+/// Whether the Vosk native library can be used, so the UI can disable
+/// recording and explain what to install when it can't
+#[tauri::command]
+pub fn vosk_available() -> bool {
+    crate::vosk_stt::vosk_available()
+}
+
+#[tauri::command]
+pub fn is_model_loaded(state: State<AppState>) -> bool {
+    state.recognizer().is_model_loaded()
+}
+
+/// Samples, duration and final results of the current (or last) recording
+#[tauri::command]
+pub fn session_stats(state: State<AppState>) -> SessionStats {
+    state.session_stats()
+}
+
+/// Report what the recognizer has seen, to tell missing audio apart from a
+/// model that isn't producing text
+#[tauri::command]
+pub fn recognizer_debug_info(state: State<AppState>) -> RecognizerDebug {
+    state.recognizer().debug_info()
+}
+
+/// Rate in Hz the recognizer is fed at
+#[tauri::command]
+pub fn get_recognizer_sample_rate(state: State<AppState>) -> u32 {
+    state.recognizer().sample_rate()
+}
+
+/// Feed the recognizer audio at `sample_rate` Hz, e.g. 8000 for a model trained
+/// on telephone audio. Captured audio is resampled to match. Needs a loaded model;
+/// emits `sample-rate-warning` if the model was trained at another rate.
+#[tauri::command]
+pub fn set_recognizer_sample_rate(
+    app: AppHandle,
+    state: State<AppState>,
+    sample_rate: u32,
+) -> Result<(), CommandError> {
+    apply_recognizer_sample_rate(&app, &state, sample_rate)
+}
+
+fn apply_recognizer_sample_rate(
+    events: &dyn EventSink,
+    state: &AppState,
+    sample_rate: u32,
+) -> Result<(), CommandError> {
+    let recognizer = state.recognizer();
+    if !recognizer.is_model_loaded() {
+        return Err(CommandError::new(
+            "model_not_loaded",
+            "No speech model loaded",
+        ));
+    }
+
+    recognizer.set_sample_rate(sample_rate as f32)?;
+    warn_sample_rate_mismatch(events, recognizer.as_ref());
+    Ok(())
+}
+
+/// Transcribe a WAV/MP3/... file with the loaded model instead of the live mic
+#[tauri::command]
+pub fn transcribe_file(
+    state: State<AppState>,
+    path: String,
+) -> Result<RecognitionResult, CommandError> {
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot transcribe a file while recording",
+        ));
+    }
+
+    let recognizer = state.recognizer();
+    let samples = audio_file::decode_to_16k_mono(std::path::Path::new(&path))?;
+    let samples = to_recognizer_rate(recognizer.as_ref(), samples);
+
+    transcribe_samples(recognizer.as_ref(), &samples).map_err(CommandError::from)
+}
+
+/// Time the loaded model on a few seconds of synthetic audio, to tell whether
+/// it keeps up with live recording on this machine
+#[tauri::command]
+pub fn benchmark_model(state: State<AppState>) -> Result<BenchmarkResult, CommandError> {
+    run_benchmark(&state)
+}
+
+fn run_benchmark(state: &AppState) -> Result<BenchmarkResult, CommandError> {
+    if !state.recognizer().is_model_loaded() {
+        return Err(CommandError::new(
+            "model_not_loaded",
+            "No speech model loaded",
+        ));
+    }
+    // Benchmarking resets the recognizer, which would drop the live utterance
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot benchmark while recording",
+        ));
+    }
+
+    benchmark_recognizer(state.recognizer().as_ref(), &benchmark_audio())
+        .map_err(CommandError::from)
+}
+
+/// Check what the app needs to work: a writable models directory, an input
+/// device, the Vosk runtime and the Claude Code binary. Every check runs.
+#[tauri::command]
+pub fn run_diagnostics(state: State<AppState>) -> Vec<DiagnosticCheck> {
+    diagnose(&state, crate::vosk_stt::vosk_available())
+}
+
+/// Run the diagnostics and emit them as `startup-diagnostics`, logging failures
+pub fn emit_startup_diagnostics(events: &dyn EventSink, state: &AppState) {
+    let checks = diagnose(state, crate::vosk_stt::vosk_available());
+    for check in checks.iter().filter(|check| !check.passed) {
+        eprintln!("Startup check {:?} failed: {}", check.check, check.detail);
+    }
+    events.emit_event("startup-diagnostics", json!(checks));
+}
+
+fn diagnose(state: &AppState, vosk_available: bool) -> Vec<DiagnosticCheck> {
+    let vosk_runtime = if vosk_available {
+        Ok("Vosk library loaded".to_string())
+    } else {
+        Err("The Vosk library could not be loaded".to_string())
+    };
+
+    vec![
+        DiagnosticCheck::new(
+            Diagnostic::ModelsDir,
+            check_models_dir(state.model_manager.get_models_dir()),
+        ),
+        DiagnosticCheck::new(Diagnostic::InputDevice, check_input_device(state)),
+        DiagnosticCheck::new(Diagnostic::VoskRuntime, vosk_runtime),
+        DiagnosticCheck::new(
+            Diagnostic::ClaudeBinary,
+            state
+                .claude_version()
+                .map(|version| format!("Claude Code {}", version))
+                .map_err(|e| e.message),
+        ),
+    ]
+}
+
+fn check_models_dir(dir: &Path) -> Result<String, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} does not exist", dir.display()));
+    }
+
+    // Downloads fail late if the directory turns out to be read-only
+    let probe = dir.join(".write-test");
+    std::fs::write(&probe, b"").map_err(|e| format!("{} is not writable: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(dir.display().to_string())
+}
+
+fn check_input_device(state: &AppState) -> Result<String, String> {
+    let devices = state.audio.list_devices().map_err(|e| e.to_string())?;
+    let inputs: Vec<_> = devices.iter().filter(|d| !d.is_loopback).collect();
+    if inputs.is_empty() {
+        return Err("No input devices found".to_string());
+    }
+
+    let default = inputs.iter().find(|d| d.is_default).unwrap_or(&inputs[0]);
+    Ok(format!(
+        "{} input device(s), default {}",
+        inputs.len(),
+        default.name
+    ))
+}
+
+/// Check each stage between the microphone and a transcription: devices are
+/// listed, a model is loaded, a second of audio arrives from the recording
+/// device and isn't silent, and the recognizer accepts it
+#[tauri::command]
+pub fn self_test(state: State<AppState>) -> Result<SelfTestReport, CommandError> {
+    run_self_test(&state, SELF_TEST_DURATION)
+}
+
+fn run_self_test(state: &AppState, duration: Duration) -> Result<SelfTestReport, CommandError> {
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot run the self test while recording",
+        ));
+    }
+
+    let mut report = SelfTestReport::default();
+
+    match state.audio.list_devices() {
+        Ok(devices) if !devices.is_empty() => report.devices = devices,
+        Ok(_) => return Ok(report.fail(SelfTestStage::Devices, "No input devices found")),
+        Err(e) => return Ok(report.fail(SelfTestStage::Devices, e.to_string())),
+    }
+
+    let recognizer = state.recognizer();
+    report.model_loaded = recognizer.is_model_loaded();
+    if !report.model_loaded {
+        return Ok(report.fail(SelfTestStage::Model, "No speech model loaded"));
+    }
+
+    let device = preferred_device(state);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let sink = captured.clone();
+    let started = state.audio.start_recording(
+        device.as_deref(),
+        Arc::new(move |samples: Vec<i16>| sink.lock().extend(samples)),
+    );
+    if let Err(e) = started {
+        report.device = device;
+        return Ok(report.fail(SelfTestStage::Capture, e.to_string()));
+    }
+    std::thread::sleep(duration);
+    report.device = state.audio.get_active_device().map(|d| d.name).or(device);
+    state.audio.stop_recording();
+
+    let samples = std::mem::take(&mut *captured.lock());
+    report.samples_captured = samples.len();
+    if samples.is_empty() {
+        return Ok(report.fail(
+            SelfTestStage::Capture,
+            "No audio arrived from the input device",
+        ));
+    }
+
+    report.rms = compute_level(&samples).rms;
+    if report.rms < SELF_TEST_MIN_RMS {
+        return Ok(report.fail(
+            SelfTestStage::Level,
+            "The microphone is silent; check it isn't muted",
+        ));
+    }
+
+    let result = transcribe_samples(recognizer.as_ref(), &samples);
+    recognizer.reset();
+    match result {
+        Ok(result) => report.transcript = Some(result.text),
+        Err(e) => return Ok(report.fail(SelfTestStage::Recognition, e.to_string())),
+    }
+
+    Ok(report)
+}
+
+/// Replay the canned transcriptions in `script_path` instead of recognizing
+/// speech, for demos and UI work without a model. The app returns to Vosk
+/// on the next launch.
+#[tauri::command]
+pub fn use_demo_recognizer(
+    state: State<AppState>,
+    script_path: String,
+) -> Result<(), CommandError> {
+    use_scripted_recognizer(&state, Path::new(&script_path))
+}
+
+fn use_scripted_recognizer(state: &AppState, script_path: &Path) -> Result<(), CommandError> {
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot switch recognizers while recording",
+        ));
+    }
+
+    let recognizer = ScriptedRecognizer::from_file(script_path)?;
+    state.set_recognizer(Arc::new(recognizer));
+    Ok(())
+}
+
+/// Switch the speech recognition engine. The new backend starts without a
+/// model, so load one next (a ggml `.bin` file for whisper). The app returns
+/// to Vosk on the next launch.
+#[tauri::command]
+pub fn set_backend(state: State<AppState>, backend: SpeechBackend) -> Result<(), CommandError> {
+    switch_backend(&state, backend)
+}
+
+fn switch_backend(state: &AppState, backend: SpeechBackend) -> Result<(), CommandError> {
+    if state.audio.is_recording() {
+        return Err(CommandError::new(
+            "recording_in_progress",
+            "Cannot switch recognizers while recording",
+        ));
+    }
+
+    let recognizer: Arc<dyn SpeechRecognizer> = match backend {
+        SpeechBackend::Vosk => Arc::new(VoskRecognizer::new()),
+        #[cfg(feature = "whisper")]
+        SpeechBackend::Whisper => Arc::new(crate::whisper_stt::WhisperRecognizer::new()),
+        #[cfg(not(feature = "whisper"))]
+        SpeechBackend::Whisper => {
+            return Err(CommandError::new(
+                "backend_unavailable",
+                "This build does not include whisper support",
+            ))
+        }
+    };
+    state.set_recognizer(recognizer);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reset_recognizer(state: State<AppState>) {
+    log::trace!("reset_recognizer called");
+    state.recognizer().reset();
+    log::trace!("reset_recognizer completed");
+}
+
+// ============================================================================
+// Claude Code Commands
+// ============================================================================
+
+fn session_or_default(session_id: &Option<String>) -> &str {
+    session_id.as_deref().unwrap_or(DEFAULT_CLAUDE_SESSION)
+}
+
+/// Forward a session's output and restarts to the frontend.
+///
+/// `claude-output` carries the `OutputEvent` fields plus `session_id`;
+/// `claude-restarted` carries `{session_id, attempt}`.
+fn connect_claude_events(events: Arc<dyn EventSink>, session_id: &str, claude: &dyn ClaudeProcess) {
+    let output_events = events.clone();
+    let output_session = session_id.to_string();
+    claude.set_output_callback(Arc::new(move |event| {
+        let mut payload = json!(event);
+        payload["session_id"] = json!(output_session);
+        output_events.emit_event("claude-output", payload);
+    }));
+
+    let dropped_events = events.clone();
+    let dropped_session = session_id.to_string();
+    claude.set_output_dropped_callback(Arc::new(move |bytes| {
+        dropped_events.emit_event(
+            "claude-output-dropped",
+            json!({ "session_id": dropped_session, "bytes": bytes }),
+        );
+    }));
+
+    let restart_session = session_id.to_string();
+    claude.set_restart_callback(Arc::new(move |attempt| {
+        events.emit_event(
+            "claude-restarted",
+            json!({ "session_id": restart_session, "attempt": attempt }),
+        );
+    }));
+}
+
+/// Start Claude in a session, creating the session if needed.
+/// Without a session id the default session is used.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_claude(
+    app: AppHandle,
+    state: State<AppState>,
+    session_id: Option<String>,
+    working_dir: Option<String>,
+    args: Option<Vec<String>>,
+    env: Option<Vec<(String, String)>>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<(), CommandError> {
+    launch_claude(
+        Arc::new(app),
+        &state,
+        session_or_default(&session_id),
+        ClaudeStartOptions {
+            working_dir,
+            args: args.unwrap_or_default(),
+            env: env.unwrap_or_default(),
+            cols,
+            rows,
+        },
+    )
+}
+
+/// Validate the working directory, then start the session with events tagged by its id.
+/// A given directory is saved; without one, the last saved directory is reused.
+fn launch_claude(
+    events: Arc<dyn EventSink>,
+    state: &AppState,
+    session_id: &str,
+    mut options: ClaudeStartOptions,
+) -> Result<(), CommandError> {
+    let explicit_dir = options.working_dir.is_some();
+    match &options.working_dir {
+        Some(dir) => {
+            let dir = validate_working_dir(dir)?;
+            options.working_dir = Some(dir.to_string_lossy().to_string());
+        }
+        None => options.working_dir = last_working_dir(state),
+    }
+
+    let claude = state.open_claude_session(session_id);
+
+    // Set up callbacks to emit events tagged with the session
+    connect_claude_events(events, session_id, claude.as_ref());
+
+    claude.start_with(options.clone())?;
+
+    if explicit_dir {
+        if let Err(e) = state.update_settings(|s| s.last_working_dir = options.working_dir) {
+            eprintln!("Failed to save working directory: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// The saved working directory, if it still exists
+fn last_working_dir(state: &AppState) -> Option<String> {
+    let saved = state.settings.lock().last_working_dir.clone()?;
+    validate_working_dir(&saved)
+        .ok()
+        .map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// Directory Claude was last started in, to prefill the directory picker.
+/// None if nothing was saved or the directory no longer exists.
+#[tauri::command]
+pub fn get_last_working_dir(state: State<AppState>) -> Option<String> {
+    last_working_dir(&state)
+}
+
+/// Stop a session. Sessions other than the default one are closed afterwards.
+#[tauri::command]
+pub fn stop_claude(state: State<AppState>, session_id: Option<String>) -> Result<(), CommandError> {
+    let session_id = session_or_default(&session_id);
+    let result = state.claude_session(session_id)?.stop();
+    state.close_claude_session(session_id);
+    result.map_err(CommandError::from)
+}
+
+/// CPU and memory use of a session's Claude process, or None if it isn't running
+#[tauri::command]
+pub fn claude_resource_usage(
+    state: State<AppState>,
+    session_id: Option<String>,
+) -> Result<Option<ResourceUsage>, CommandError> {
+    resource_usage(&state, session_or_default(&session_id))
+}
+
+fn resource_usage(
+    state: &AppState,
+    session_id: &str,
+) -> Result<Option<ResourceUsage>, CommandError> {
+    let Some(pid) = state.claude_session(session_id)?.pid() else {
+        return Ok(None);
+    };
+    // The process may have exited since its pid was read
+    Ok(state.process_monitor.usage(pid))
+}
+
+/// Cancel Claude's current turn with Ctrl-C. Unlike `stop_claude` the
+/// session keeps running.
+#[tauri::command]
+pub fn interrupt_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+) -> Result<(), CommandError> {
+    interrupt(&state, session_or_default(&session_id))
+}
+
+fn interrupt(state: &AppState, session_id: &str) -> Result<(), CommandError> {
+    if state.claude_session(session_id)?.status() != ProcessStatus::Running {
+        return Err(ClaudeError::NotRunning.into());
+    }
+    send_key(state, session_id, "ctrl-c")
+}
+
+#[tauri::command]
+pub fn send_to_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+    input: String,
+) -> Result<(), CommandError> {
+    // Send input directly - xterm.js already sends appropriate characters
+    // (Enter sends \r, arrow keys send escape sequences like \x1b[A, etc.)
+    state
+        .claude_session(session_or_default(&session_id))?
+        .send_input(&input)
+        .map_err(CommandError::from)
+}
+
+/// Send `text` as one submitted line: trimmed and followed by the submit sequence
+#[tauri::command]
+pub fn send_line_to_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+    text: String,
+) -> Result<(), CommandError> {
+    send_line(&state, session_or_default(&session_id), &text)
+}
+
+fn send_line(state: &AppState, session_id: &str, text: &str) -> Result<(), CommandError> {
+    state
+        .claude_session(session_id)?
+        .send_input(&submit_line(text, &state.submit_sequence()))
+        .map_err(CommandError::from)
+}
+
+/// Send a named key such as "up", "esc" or "ctrl-c" as the bytes a terminal
+/// would, so callers don't need to know escape codes
+#[tauri::command]
+pub fn send_key_to_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+    key: String,
+) -> Result<(), CommandError> {
+    send_key(&state, session_or_default(&session_id), &key)
+}
+
+fn send_key(state: &AppState, session_id: &str, key: &str) -> Result<(), CommandError> {
+    let bytes = key_to_bytes(key)
+        .ok_or_else(|| CommandError::new("unknown_key", format!("Unknown key: {:?}", key)))?;
+
+    // Named keys are all ASCII
+    state
+        .claude_session(session_id)?
+        .send_input(&String::from_utf8_lossy(&bytes))
+        .map_err(CommandError::from)
+}
+
+/// Paste the system clipboard's text into a Claude session (the default one if
+/// None) as a bracketed paste. Returns false without sending anything when the
+/// clipboard holds no text.
+#[tauri::command]
+pub fn paste_to_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+) -> Result<bool, CommandError> {
+    let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => text,
+        Err(arboard::Error::ContentNotAvailable) => return Ok(false),
+        Err(e) => return Err(CommandError::new("clipboard_error", e.to_string())),
+    };
+    paste_text(&state, session_or_default(&session_id), &text)
+}
+
+fn paste_text(state: &AppState, session_id: &str, text: &str) -> Result<bool, CommandError> {
+    if text.is_empty() {
+        return Ok(false);
+    }
+
+    state
+        .claude_session(session_id)?
+        .send_input(&bracketed_paste(text))?;
+    Ok(true)
+}
+
+/// Choose the sequence that submits a line: "\r", "\n" or "\r\n" (None restores "\r")
+#[tauri::command]
+pub fn set_submit_sequence(
+    state: State<AppState>,
+    sequence: Option<String>,
+) -> Result<(), CommandError> {
+    if let Some(sequence) = &sequence {
+        if !SUBMIT_SEQUENCES.contains(&sequence.as_str()) {
+            return Err(CommandError::new(
+                "invalid_argument",
+                format!("Unsupported submit sequence: {:?}", sequence),
+            ));
+        }
+    }
+
+    state.update_settings(|s| s.submit_sequence = sequence)
+}
+
+#[tauri::command]
+pub fn resize_claude(
+    state: State<AppState>,
+    session_id: Option<String>,
+    cols: u16,
+    rows: u16,
+) -> Result<(), CommandError> {
+    state
+        .claude_session(session_or_default(&session_id))?
+        .resize(cols, rows)
+        .map_err(CommandError::from)
+}
+
+/// How often running Claude sessions are health-checked
+const CLAUDE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Health-check every running session and emit
+/// `claude-heartbeat {session_id, alive, uptime_secs}`. A session whose
+/// process has exited reports `alive: false` once and its status is updated.
+fn emit_claude_heartbeats(events: &dyn EventSink, state: &AppState) {
+    let sessions: Vec<(String, Arc<dyn ClaudeProcess>)> = state
+        .claude
+        .lock()
+        .iter()
+        .map(|(id, claude)| (id.clone(), claude.clone()))
+        .collect();
+
+    for (session_id, claude) in sessions {
+        if claude.status() != ProcessStatus::Running {
+            continue;
+        }
+
+        let uptime_secs = claude.uptime_secs();
+        let alive = claude.is_alive();
+        events.emit_event(
+            "claude-heartbeat",
+            json!({ "session_id": session_id, "alive": alive, "uptime_secs": uptime_secs }),
+        );
+    }
+}
+
+/// Start the background thread that emits Claude heartbeats
+pub fn spawn_claude_heartbeat<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CLAUDE_HEARTBEAT_INTERVAL);
+        let state = app.state::<AppState>();
+        emit_claude_heartbeats(&app, &state);
+    });
+}
+
+/// Ids of the open Claude sessions
+#[tauri::command]
+pub fn list_claude_sessions(state: State<AppState>) -> Vec<String> {
+    state.claude_session_ids()
+}
+
+/// Use an explicit Claude binary instead of searching PATH (None restores the search)
+#[tauri::command]
+pub fn set_claude_path(state: State<AppState>, path: Option<String>) -> Result<(), CommandError> {
+    let path = path.map(PathBuf::from);
+
+    if let Some(p) = &path {
+        ClaudeCodeProcess::validate_binary_path(p).map_err(|e| {
+            CommandError::new(
+                e.kind(),
+                format!("Not an executable file: {}", p.to_string_lossy()),
+            )
+        })?;
+    }
+
+    for claude in state.claude_sessions() {
+        claude.set_binary_path(path.clone());
+    }
+    *state.claude_version.lock() = None;
+    state.update_settings(|s| s.claude_path = path.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Version reported by `claude --version`, for diagnostics
+#[tauri::command]
+pub fn claude_version(state: State<AppState>) -> Result<String, CommandError> {
+    state.claude_version()
+}
+
+#[tauri::command]
+pub fn set_claude_auto_restart(state: State<AppState>, enabled: bool) {
+    state.update_claude_config(|c| c.auto_restart = Some(enabled));
+}
+
+/// Time to wait for Claude to exit after Ctrl-C before force-killing it
+#[tauri::command]
+pub fn set_shutdown_grace_ms(state: State<AppState>, ms: u32) {
+    state.update_claude_config(|c| c.shutdown_grace_ms = Some(ms));
+}
+
+/// Time a write to Claude's input may block before failing with a
+/// "timed out" write error (0 waits forever)
+#[tauri::command]
+pub fn set_claude_write_timeout_ms(state: State<AppState>, ms: u32) {
+    state.update_claude_config(|c| c.write_timeout_ms = Some(ms));
+}
+
+/// Bytes read from Claude's output at a time; takes effect on the next read
+#[tauri::command]
+pub fn set_claude_read_buffer_size(state: State<AppState>, bytes: usize) {
+    state.update_claude_config(|c| c.read_buffer_size = Some(bytes));
+}
+
+#[tauri::command]
+pub fn set_strip_ansi(state: State<AppState>, enabled: bool) {
+    state.update_claude_config(|c| c.strip_ansi = Some(enabled));
+}
+
+/// Log a Claude session to a file (None disables logging)
+#[tauri::command]
+pub fn set_claude_log(
+    state: State<AppState>,
+    session_id: Option<String>,
+    path: Option<String>,
+) -> Result<(), CommandError> {
+    state
+        .open_claude_session(session_or_default(&session_id))
+        .set_log_path(path.map(PathBuf::from))
+        .map_err(CommandError::from)
+}
+
+/// Rotate Claude session logs once they reach `max_bytes`, keeping the
+/// newest `keep` rotated files
+#[tauri::command]
+pub fn set_log_rotation(
+    state: State<AppState>,
+    max_bytes: u64,
+    keep: usize,
+) -> Result<(), CommandError> {
+    set_rotation(&state, max_bytes, keep)
+}
+
+fn set_rotation(state: &AppState, max_bytes: u64, keep: usize) -> Result<(), CommandError> {
+    if max_bytes == 0 {
+        return Err(CommandError::new(
+            "invalid_argument",
+            "Log size limit must be greater than zero",
+        ));
+    }
+
+    state.update_claude_config(|c| c.log_rotation = Some(LogRotation { max_bytes, keep }));
+    Ok(())
+}
+
+/// Recent Claude output for repainting the terminal after a reload
+#[tauri::command]
+pub fn claude_scrollback(state: State<AppState>, session_id: Option<String>) -> String {
+    state
+        .claude_session(session_or_default(&session_id))
+        .map(|claude| claude.get_recent_output())
+        .unwrap_or_default()
+}
+
+/// Find text in a session's retained output, for "find in terminal"
+#[tauri::command]
+pub fn search_claude_output(
+    state: State<AppState>,
+    session_id: Option<String>,
+    pattern: String,
+    regex: bool,
+) -> Result<Vec<OutputMatch>, CommandError> {
+    search_scrollback(&state, session_or_default(&session_id), &pattern, regex)
+}
+
+fn search_scrollback(
+    state: &AppState,
+    session_id: &str,
+    pattern: &str,
+    regex: bool,
+) -> Result<Vec<OutputMatch>, CommandError> {
+    if pattern.is_empty() {
+        return Err(CommandError::new(
+            "invalid_argument",
+            "Search pattern must not be empty",
+        ));
+    }
+
+    let output = state.claude_session(session_id)?.get_recent_output();
+    search_output(&output, pattern, regex)
+        .map_err(|e| CommandError::new("invalid_argument", format!("Invalid pattern: {}", e)))
+}
+
+#[tauri::command]
+pub fn set_claude_scrollback_limit(state: State<AppState>, bytes: usize) {
+    state.update_claude_config(|c| c.scrollback_limit = Some(bytes));
+}
+
+#[tauri::command]
+pub fn claude_status(state: State<AppState>, session_id: Option<String>) -> ProcessStatus {
+    state
+        .claude_session(session_or_default(&session_id))
+        .map(|claude| claude.status())
+        .unwrap_or(ProcessStatus::Stopped)
+}
+
+// ============================================================================
+// Utility Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn get_settings(state: State<AppState>) -> Settings {
+    state.settings.lock().clone()
+}
+
+#[tauri::command]
+pub fn update_settings(state: State<AppState>, settings: Settings) -> Result<(), CommandError> {
+    state.update_settings(|s| *s = settings)?;
+    state.apply_settings();
+    Ok(())
+}
+
+/// Tear down recording and Claude sessions, e.g. before the window closes
+#[tauri::command]
+pub fn shutdown(state: State<AppState>) -> ShutdownSummary {
+    state.shutdown()
+}
+
+/// Name and version of the app, plus the platform, compiled-in recognizer
+/// backends and models directory for bug reports
+#[tauri::command]
+pub fn get_app_info(state: State<AppState>) -> serde_json::Value {
+    app_info(&state)
+}
+
+fn app_info(state: &AppState) -> serde_json::Value {
+    let mut backends = vec!["vosk"];
+    if cfg!(feature = "whisper") {
+        backends.push("whisper");
+    }
+
+    serde_json::json!({
+        "name": "icanhastool",
+        "version": env!("CARGO_PKG_VERSION"),
+        "description": env!("CARGO_PKG_DESCRIPTION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "vosk_available": crate::vosk_stt::vosk_available(),
+        "backends": backends,
+        "models_dir": state.model_manager.get_models_dir(),
+    })
+}
+
+/// Directory under the app data directory for logs
+pub const LOGS_DIR: &str = "logs";
+
+/// Places on disk users go looking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AppLocation {
+    ModelsDir,
+    LogsDir,
+    SettingsFile,
+}
+
+/// Show the models directory in the file manager, returning its path
+#[tauri::command]
+pub fn open_models_dir(app: AppHandle, state: State<AppState>) -> Result<PathBuf, CommandError> {
+    reveal_location(&app, &state, AppLocation::ModelsDir)
+}
+
+/// Show the logs directory in the file manager, returning its path
+#[tauri::command]
+pub fn open_logs_dir(app: AppHandle, state: State<AppState>) -> Result<PathBuf, CommandError> {
+    reveal_location(&app, &state, AppLocation::LogsDir)
+}
+
+/// Show the settings file in the file manager, returning its path
+#[tauri::command]
+pub fn open_settings_file(app: AppHandle, state: State<AppState>) -> Result<PathBuf, CommandError> {
+    reveal_location(&app, &state, AppLocation::SettingsFile)
+}
+
+fn reveal_location(
+    revealer: &dyn PathRevealer,
+    state: &AppState,
+    location: AppLocation,
+) -> Result<PathBuf, CommandError> {
+    let path = resolve_location(state, location)?;
+    revealer.reveal(&path).map_err(|e| {
+        CommandError::new(
+            "open_failed",
+            format!("Could not open {}: {}", path.display(), e),
+        )
+    })?;
+    Ok(path)
+}
+
+/// Path of `location`, creating it first if it doesn't exist yet (the
+/// settings file is written with the current settings)
+fn resolve_location(state: &AppState, location: AppLocation) -> Result<PathBuf, CommandError> {
+    let path = match location {
+        AppLocation::ModelsDir => state.model_manager.get_models_dir().to_path_buf(),
+        AppLocation::LogsDir => state.app_data_dir.join(LOGS_DIR),
+        AppLocation::SettingsFile => state.app_data_dir.join(SETTINGS_FILE),
+    };
+
+    let created = match location {
+        AppLocation::SettingsFile if path.is_file() => Ok(()),
+        AppLocation::SettingsFile => state
+            .settings
+            .lock()
+            .save(&state.app_data_dir)
+            .map_err(|e| e.to_string()),
+        _ => std::fs::create_dir_all(&path).map_err(|e| e.to_string()),
+    };
+    created.map_err(|e| {
+        CommandError::new(
+            "path_unavailable",
+            format!("Could not create {}: {}", path.display(), e),
+        )
+    })?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::tests::{MockAudioCapture, MOCK_HOST};
+    use crate::audio::AudioError;
+    use crate::audio_file;
+    use crate::claude::tests::{MockClaudeProcess, MockProcessMonitor, MOCK_PID};
+    use crate::claude::{DEFAULT_READ_BUFFER_BYTES, DEFAULT_WRITE_TIMEOUT_MS};
+    use crate::vosk_stt::tests::{
+        write_fixture_model, MockModelFetcher, MockSpeechRecognizer, MockSystemMemory,
+    };
+
+    /// Records emitted events for assertions
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<(String, serde_json::Value)>>,
+    }
+
+    impl RecordingSink {
+        fn names(&self) -> Vec<String> {
+            self.events
+                .lock()
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect()
+        }
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit_event(&self, event: &str, payload: serde_json::Value) {
+            self.events.lock().push((event.to_string(), payload));
+        }
+    }
+
+    fn create_test_state() -> AppState {
+        AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        )
+    }
+
+    #[test]
+    fn test_app_state_creation() {
+        let state = create_test_state();
+        assert!(!state.audio.is_recording());
+        assert!(!state.recognizer().is_model_loaded());
+        assert_eq!(state.default_claude().status(), ProcessStatus::Stopped);
+    }
+
+    #[test]
+    fn test_model_manager_in_state() {
+        let state = create_test_state();
+        let models = state.model_manager.list_available_models();
+        assert!(!models.is_empty());
+    }
+
+    #[test]
+    fn test_audio_callback_storage() {
+        let state = create_test_state();
+
+        assert!(state.audio_callback.lock().is_none());
+
+        let callback: Arc<dyn Fn(Vec<i16>) + Send + Sync> = Arc::new(|_| {});
+        *state.audio_callback.lock() = Some(callback);
+
+        assert!(state.audio_callback.lock().is_some());
+
+        *state.audio_callback.lock() = None;
+        assert!(state.audio_callback.lock().is_none());
+    }
+
+    #[test]
+    fn test_level_meter_in_state() {
+        let state = create_test_state();
+        assert_eq!(state.level_meter.level(), AudioLevel::default());
+
+        state.level_meter.update(&[i16::MAX; 160]);
+        assert!(state.level_meter.level().peak > 0.99);
+    }
+
+    #[test]
+    fn test_transcribe_generated_wav() {
+        let state = create_test_state();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("speech.wav");
+
+        // 1 second of 44.1kHz stereo audio
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..44100 {
+            let sample = ((i % 100) as i16 - 50) * 100;
+            writer.write_sample(sample).unwrap();
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let samples = audio_file::decode_to_16k_mono(&path).unwrap();
+        assert_eq!(samples.len(), 16000);
+
+        let result = transcribe_samples(state.recognizer().as_ref(), &samples).unwrap();
+        assert_eq!(result.text, "Hello world");
+        assert!(result.is_final);
+    }
+
+    #[test]
+    fn test_load_default_model_prefers_saved_model() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let preferred = temp_dir.path().join("vosk-model-small-de-0.15");
+        write_fixture_model(&temp_dir.path().join("vosk-model-en-us-0.22"));
+        write_fixture_model(&preferred);
+
+        let state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        state.settings.lock().model_path = Some(preferred.to_string_lossy().to_string());
+        let sink = RecordingSink::default();
+
+        let loaded = state.load_default_model(&sink).unwrap();
+        assert_eq!(loaded.name, "vosk-model-small-de-0.15");
+        assert!(state.recognizer().is_model_loaded());
+        assert_eq!(state.loaded_model(), Some(loaded));
+        assert_eq!(sink.names(), vec!["model-loaded"]);
+    }
+
+    #[test]
+    fn test_load_model_warns_on_sample_rate_mismatch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("models/vosk-model-small-en-us-0.15");
+        write_fixture_model(&model_path);
+        std::fs::write(
+            model_path.join("conf/mfcc.conf"),
+            "--sample-frequency=8000\n",
+        )
+        .unwrap();
+        let mut state = create_test_state();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        let sink = RecordingSink::default();
+
+        state.load_model(&sink, &model_path).unwrap();
+
+        assert_eq!(state.recognizer().model_sample_rate(), Some(8000));
+        let warnings: Vec<_> = sink
+            .events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == "sample-rate-warning")
+            .map(|(_, payload)| payload.clone())
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["model_rate"], 8000);
+        assert_eq!(warnings[0]["pipeline_rate"], 16000);
+    }
+
+    #[test]
+    fn test_recognizer_sample_rate_can_match_model() {
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        let err = apply_recognizer_sample_rate(sink.as_ref(), &state, 8000).unwrap_err();
+        assert_eq!(err.kind, "model_not_loaded");
+        assert_eq!(recognizer.recreate_count(), 0);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        write_fixture_model(&model_path);
+        std::fs::write(
+            model_path.join("conf/mfcc.conf"),
+            "--sample-frequency=8000\n",
+        )
+        .unwrap();
+        recognizer.load_model(&model_path).unwrap();
+
+        apply_recognizer_sample_rate(sink.as_ref(), &state, 8000).unwrap();
+        assert_eq!(recognizer.recreate_count(), 1);
+        assert_eq!(state.recognizer().debug_info().sample_rate, 8000);
+        assert_eq!(count_events(&sink, "sample-rate-warning"), 0);
+
+        // 16kHz audio is resampled to the new rate
+        feed_samples(sink.clone(), &state, vec![0i16; 1600]).unwrap();
+        assert_eq!(recognizer.buffer_sizes(), vec![800]);
+
+        apply_recognizer_sample_rate(sink.as_ref(), &state, 16000).unwrap();
+        assert_eq!(count_events(&sink, "sample-rate-warning"), 1);
+    }
+
+    #[test]
+    fn test_audio_routed_to_active_language() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let audio = Arc::new(MockAudioCapture::new());
+        let english = Arc::new(MockSpeechRecognizer::new());
+        let created: Arc<Mutex<Vec<Arc<MockSpeechRecognizer>>>> = Arc::new(Mutex::new(Vec::new()));
+        let factory_created = created.clone();
+        let mut state = AppState::with_mocks(
+            audio.clone(),
+            english.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        )
+        .with_recognizer_factory(move || {
+            let recognizer = Arc::new(MockSpeechRecognizer::new());
+            factory_created.lock().push(recognizer.clone());
+            recognizer
+        });
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        let sink = Arc::new(RecordingSink::default());
+
+        state
+            .load_model(sink.as_ref(), Path::new("/models/vosk-model-en-us-0.22"))
+            .unwrap();
+        let spanish_path = Path::new("/models/vosk-model-small-es-0.42");
+        let model = state.load_language_model(spanish_path).unwrap();
+        assert_eq!(model.language.code, "es");
+        let spanish = created.lock()[0].clone();
+        assert_eq!(spanish.loaded_path().as_deref(), Some(spanish_path));
+        let codes: Vec<_> = state
+            .loaded_languages()
+            .into_iter()
+            .map(|language| language.code)
+            .collect();
+        assert_eq!(codes, vec!["en-us", "es"]);
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(english.buffer_sizes().len(), 1);
+
+        // The English utterance in progress is finalized by the switch
+        let model = state.set_active_language(sink.as_ref(), "es").unwrap();
+        assert_eq!(model.language.code, "es");
+        assert_eq!(state.loaded_model(), Some(model));
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(english.buffer_sizes().len(), 1);
+        assert_eq!(spanish.buffer_sizes().len(), 1);
+
+        // Switching back reuses the warm English model
+        state.set_active_language(sink.as_ref(), "en-us").unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(english.buffer_sizes().len(), 2);
+        assert_eq!(spanish.buffer_sizes().len(), 1);
+        assert_eq!(created.lock().len(), 1);
+        assert_eq!(state.loaded_languages().len(), 2);
+
+        let err = state.set_active_language(sink.as_ref(), "de").unwrap_err();
+        assert_eq!(err.kind, "language_not_loaded");
+        let err = state
+            .load_language_model(Path::new("/models/vosk-model-en-us-0.42"))
+            .unwrap_err();
+        assert_eq!(err.kind, "language_active");
+    }
+
+    #[test]
+    fn test_reveal_locations_in_app_data_dir() {
+        /// Records revealed paths, or fails every reveal
+        #[derive(Default)]
+        struct RecordingRevealer {
+            revealed: Mutex<Vec<PathBuf>>,
+            fail: bool,
+        }
+
+        impl PathRevealer for RecordingRevealer {
+            fn reveal(&self, path: &Path) -> Result<(), String> {
+                if self.fail {
+                    return Err("no file manager".to_string());
+                }
+                self.revealed.lock().push(path.to_path_buf());
+                Ok(())
+            }
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        let mut state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(data_dir.join("models")));
+        state.app_data_dir = data_dir.clone();
+
+        let revealer = RecordingRevealer::default();
+        let models = reveal_location(&revealer, &state, AppLocation::ModelsDir).unwrap();
+        let logs = reveal_location(&revealer, &state, AppLocation::LogsDir).unwrap();
+        let settings = reveal_location(&revealer, &state, AppLocation::SettingsFile).unwrap();
+
+        assert_eq!(models, data_dir.join("models"));
+        assert_eq!(logs, data_dir.join("logs"));
+        assert_eq!(settings, data_dir.join("settings.json"));
+        assert!(models.is_dir() && logs.is_dir());
+        assert_eq!(Settings::load(&data_dir), *state.settings.lock());
+        assert_eq!(*revealer.revealed.lock(), vec![models, logs, settings]);
+
+        let failing = RecordingRevealer {
+            fail: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            reveal_location(&failing, &state, AppLocation::LogsDir)
+                .unwrap_err()
+                .kind,
+            "open_failed"
+        );
+
+        // A file where the directory should be
+        state.app_data_dir = temp_dir.path().join("blocked");
+        std::fs::write(&state.app_data_dir, "").unwrap();
+        for location in [AppLocation::LogsDir, AppLocation::SettingsFile] {
+            assert_eq!(
+                reveal_location(&revealer, &state, location)
+                    .unwrap_err()
+                    .kind,
+                "path_unavailable"
+            );
+        }
+        assert_eq!(revealer.revealed.lock().len(), 3);
+    }
+
+    #[test]
+    fn test_load_model_warns_on_low_memory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("models/vosk-model-en-us-0.22");
+        write_fixture_model(&model_path);
+        let memory = |available| {
+            let mut state = create_test_state().with_model_manager(
+                ModelManager::new_isolated(temp_dir.path().join("models"))
+                    .with_memory(Arc::new(MockSystemMemory(available))),
+            );
+            state.app_data_dir = temp_dir.path().to_path_buf();
+            state
+        };
+
+        let sink = RecordingSink::default();
+        let state = memory(16);
+        state.load_model(&sink, &model_path).unwrap();
+        assert_eq!(sink.names(), vec!["low-memory-warning"]);
+        let (_, payload) = sink.events.lock()[0].clone();
+        assert_eq!(payload["available_bytes"], 16);
+        assert!(payload["estimated_bytes"].as_u64().unwrap() > 16);
+        // The warning doesn't stop the model loading
+        assert!(state.recognizer().is_model_loaded());
+
+        let sink = RecordingSink::default();
+        memory(1 << 30).load_model(&sink, &model_path).unwrap();
+        assert!(sink.names().is_empty());
+    }
+
+    #[test]
+    fn test_load_model_tracks_loaded_model() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("models/vosk-model-en-us-0.22");
+        write_fixture_model(&model_path);
+        let mut state = create_test_state();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        assert_eq!(state.loaded_model(), None);
+
+        let sink = RecordingSink::default();
+        let model = state.load_model(&sink, &model_path).unwrap();
+        assert!(sink.names().is_empty());
+        assert_eq!(model.name, "vosk-model-en-us-0.22");
+        assert_eq!(model.language.display_name, "English (US)");
+        assert_eq!(state.loaded_model(), Some(model));
+        assert_eq!(
+            Settings::load(temp_dir.path()).model_path,
+            Some(model_path.to_string_lossy().to_string())
+        );
+
+        // Resetting clears the recognizer's utterance, not the model
+        state.recognizer().reset();
+        assert!(state.loaded_model().is_some());
+
+        state.unload_model().unwrap();
+        assert!(!state.recognizer().is_model_loaded());
+        assert_eq!(state.loaded_model(), None);
+        assert_eq!(
+            state
+                .recognizer()
+                .process_audio(&[0i16; 1600])
+                .unwrap_err()
+                .kind(),
+            "recognizer_error"
+        );
+    }
+
+    #[test]
+    fn test_unload_model_refused_while_recording() {
+        let (state, audio, _claude) = create_dictation_state();
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+        assert!(audio.is_recording());
+
+        assert_eq!(
+            state.unload_model().unwrap_err().kind,
+            "recording_in_progress"
+        );
+    }
+
+    #[test]
+    fn test_load_default_model_uses_first_installed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_fixture_model(&temp_dir.path().join("vosk-model-small-de-0.15"));
+        write_fixture_model(&temp_dir.path().join("vosk-model-en-us-0.22"));
+
+        let state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        // A saved model that was since deleted falls back to the default
+        state.settings.lock().model_path = Some("/deleted/model".to_string());
+        let sink = RecordingSink::default();
+
+        state.load_default_model(&sink).unwrap();
+        assert!(state.recognizer().is_model_loaded());
+
+        let (name, payload) = sink.events.lock()[0].clone();
+        assert_eq!(name, "model-loaded");
+        assert_eq!(payload["name"], "vosk-model-en-us-0.22");
+    }
+
+    #[test]
+    fn test_load_default_model_reports_failure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_fixture_model(&temp_dir.path().join("vosk-model-en-us-0.22"));
+
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        recognizer.set_should_fail(true);
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        )
+        .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        let sink = RecordingSink::default();
+
+        assert!(state.load_default_model(&sink).is_none());
+        assert!(!recognizer.is_model_loaded());
+
+        let (name, payload) = sink.events.lock()[0].clone();
+        assert_eq!(name, "model-load-error");
+        assert_eq!(payload["kind"], "model_not_found");
+    }
+
+    #[test]
+    fn test_resume_download_requires_partial_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        let sink = Arc::new(RecordingSink::default());
+
+        let result = start_download(sink.clone(), &state, "vosk-model-en-us-0.22".into(), true);
+        assert_eq!(
+            result.err(),
+            Some(CommandError::new(
+                "no_partial_download",
+                "No partial download of vosk-model-en-us-0.22"
+            ))
+        );
+        assert!(sink.names().is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove_model_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let external = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(external.path().join("vosk-model-small-fr-0.22/am")).unwrap();
+        std::fs::create_dir_all(external.path().join("vosk-model-small-fr-0.22/graph")).unwrap();
+        let mut state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().join("models")));
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        let sink = RecordingSink::default();
+
+        state.add_model_dir(&sink, external.path()).unwrap();
+        let (name, models) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(name, "models-updated");
+        assert_eq!(models[0]["name"], "vosk-model-small-fr-0.22");
+        assert_eq!(
+            Settings::load(temp_dir.path()).model_dirs,
+            vec![external.path().to_string_lossy().to_string()]
+        );
+
+        assert_eq!(
+            state
+                .add_model_dir(&sink, external.path())
+                .unwrap_err()
+                .kind,
+            "duplicate_model_dir"
+        );
+        assert_eq!(
+            state
+                .add_model_dir(&sink, &external.path().join("missing"))
+                .unwrap_err()
+                .kind,
+            "model_dir_not_found"
+        );
+        assert_eq!(sink.names(), vec!["models-updated"]);
+
+        state.remove_model_dir(&sink, external.path()).unwrap();
+        assert!(state.model_manager.list_installed_models().is_empty());
+        assert!(Settings::load(temp_dir.path()).model_dirs.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_download() {
+        let state = create_test_state();
+        assert_eq!(
+            state
+                .cancel_download("vosk-model-en-us-0.22")
+                .unwrap_err()
+                .kind,
+            "no_active_download"
+        );
+
+        let cancel = CancelToken::default();
+        state
+            .downloads
+            .lock()
+            .insert("vosk-model-en-us-0.22".into(), cancel.clone());
+        state.cancel_download("vosk-model-en-us-0.22").unwrap();
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn test_download_failure_emits_error_and_allows_retry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fetcher = Arc::new(MockModelFetcher::new(Vec::new()));
+        fetcher.set_should_fail(true);
+        let manager =
+            ModelManager::new_isolated(temp_dir.path().to_path_buf()).with_fetcher(fetcher);
+        let state = create_test_state().with_model_manager(manager);
+        let sink = Arc::new(RecordingSink::default());
+
+        state
+            .downloads
+            .lock()
+            .insert("vosk-model-en-us-0.22".into(), CancelToken::default());
+        let duplicate = start_download(sink.clone(), &state, "vosk-model-en-us-0.22".into(), false);
+        assert!(duplicate.is_err());
+        state.downloads.lock().clear();
+
+        start_download(sink.clone(), &state, "vosk-model-en-us-0.22".into(), false)
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let (name, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(name, "download-error");
+        assert_eq!(payload["name"], "vosk-model-en-us-0.22");
+        assert_eq!(payload["kind"], "download_error");
+        assert!(state.downloads.lock().is_empty());
+    }
+
+    #[test]
+    fn test_load_default_model_without_models() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        let sink = RecordingSink::default();
+
+        assert!(state.load_default_model(&sink).is_none());
+        assert!(sink.names().is_empty());
+    }
+
+    #[test]
+    fn test_mock_states_have_own_data_dir() {
+        let (first, second) = (create_test_state(), create_test_state());
+        assert_ne!(first.app_data_dir, second.app_data_dir);
+
+        first
+            .update_settings(|s| s.max_recording_secs = 60)
+            .unwrap();
+        assert_eq!(Settings::load(&first.app_data_dir).max_recording_secs, 60);
+        assert_eq!(Settings::load(&second.app_data_dir).max_recording_secs, 0);
+
+        let dir = first.app_data_dir.clone();
+        drop(first);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_update_settings_persists() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut state = create_test_state();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+
+        state
+            .update_settings(|s| s.selected_device = Some("Secondary Mic".to_string()))
+            .unwrap();
+
+        let loaded = Settings::load(temp_dir.path());
+        assert_eq!(loaded.selected_device.as_deref(), Some("Secondary Mic"));
+    }
+
+    #[test]
+    fn test_preferred_device_reselected_when_present() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (mut state, audio, _claude) = create_dictation_state();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+
+        set_preferred(&state, Some("Secondary Mic".to_string())).unwrap();
+        assert_eq!(
+            set_preferred(&state, Some("Headset".to_string()))
+                .unwrap_err()
+                .kind,
+            "device_not_found"
+        );
+
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+        assert_eq!(audio.active_device().as_deref(), Some("Secondary Mic"));
+        assert_eq!(
+            Settings::load(temp_dir.path()).selected_device.as_deref(),
+            Some("Secondary Mic")
+        );
+    }
+
+    #[test]
+    fn test_preferred_device_falls_back_when_absent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (mut state, audio, _claude) = create_dictation_state();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        set_preferred(&state, Some("Secondary Mic".to_string())).unwrap();
+
+        // The headset was unplugged between sessions
+        audio.set_devices(vec![AudioDeviceInfo {
+            name: "Test Microphone".to_string(),
+            is_default: true,
+            is_loopback: false,
+        }]);
+
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+        assert_eq!(audio.active_device().as_deref(), Some("Test Microphone"));
+        assert_eq!(
+            Settings::load(temp_dir.path()).selected_device.as_deref(),
+            Some("Test Microphone")
+        );
+    }
+
+    #[test]
+    fn test_ptt_without_model_emits_error() {
+        let state = create_test_state();
+        let sink = Arc::new(RecordingSink::default());
+
+        handle_ptt(sink.clone(), &state, true);
+
+        assert!(!state.audio.is_recording());
+        assert_eq!(sink.names(), vec!["ptt-error"]);
+        let (_, payload) = sink.events.lock()[0].clone();
+        assert_eq!(payload["kind"], "model_not_loaded");
+        assert!(payload["message"].as_str().unwrap().contains("model"));
+
+        // Release without an active press is ignored
+        handle_ptt(sink.clone(), &state, false);
+        assert_eq!(sink.names().len(), 1);
+    }
+
+    #[test]
+    fn test_ptt_requires_microphone_permission() {
+        let (state, audio, _claude) = create_dictation_state();
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        audio.set_permission(PermissionStatus::Denied, false);
+        let sink = Arc::new(RecordingSink::default());
+
+        handle_ptt(sink.clone(), &state, true);
+
+        assert!(!state.audio.is_recording());
+        assert_eq!(audio.start_call_count(), 0);
+        assert_eq!(sink.names(), vec!["ptt-error"]);
+        let (_, payload) = sink.events.lock()[0].clone();
+        assert_eq!(payload["kind"], "microphone_permission_denied");
+
+        // The failed press leaves nothing to release
+        handle_ptt(sink.clone(), &state, false);
+        assert_eq!(sink.names().len(), 1);
+
+        // A press never waits on the system prompt
+        audio.set_permission(PermissionStatus::Undetermined, true);
+        handle_ptt(sink.clone(), &state, true);
+        assert_eq!(audio.start_call_count(), 0);
+        let (_, payload) = sink.events.lock()[1].clone();
+        assert_eq!(payload["kind"], "microphone_permission_undetermined");
+    }
+
+    #[test]
+    fn test_ptt_press_and_release() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        let sink = Arc::new(RecordingSink::default());
+
+        handle_ptt(sink.clone(), &state, true);
+        assert!(state.audio.is_recording());
+
+        // Key repeat doesn't restart recording
+        handle_ptt(sink.clone(), &state, true);
+        assert_eq!(sink.names(), vec!["recording-started", "ptt-active"]);
+
+        audio.push_samples(vec![0i16; 1600]);
+
+        handle_ptt(sink.clone(), &state, false);
+        assert!(!state.audio.is_recording());
+
+        let names = sink.names();
+        assert!(names.contains(&"transcription".to_string()));
+        assert_eq!(
+            &names[names.len() - 4..],
+            &[
+                "transcription-final".to_string(),
+                "transcription-committed".to_string(),
+                "session-ended".to_string(),
+                "ptt-released".to_string()
+            ]
+        );
+
+        let (_, released) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(released["is_final"], true);
+    }
+
+    fn create_dictation_state() -> (AppState, Arc<MockAudioCapture>, Arc<MockClaudeProcess>) {
+        let audio = Arc::new(MockAudioCapture::new());
+        let claude = Arc::new(MockClaudeProcess::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            claude.clone(),
+        );
+        claude.start(None).unwrap();
+        (state, audio, claude)
+    }
+
+    fn final_result(text: &str) -> RecognitionResult {
+        RecognitionResult {
+            text: text.to_string(),
+            is_final: true,
+            confidence: None,
+            endpoint: true,
+            offset_ms: 0,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_auto_send_final_with_suffix() {
+        let (state, _audio, claude) = create_dictation_state();
+        *state.auto_send.lock() = Some("\r".to_string());
+
+        send_final_to_claude(
+            state.default_claude().as_ref(),
+            &state.auto_send,
+            &final_result("list files"),
+        );
+
+        assert_eq!(claude.input_history(), vec!["list files\r"]);
+    }
+
+    #[test]
+    fn test_auto_send_skips_partials_and_empty() {
+        let (state, _audio, claude) = create_dictation_state();
+        *state.auto_send.lock() = Some("\n".to_string());
+
+        let partial = RecognitionResult {
+            text: "list".to_string(),
+            is_final: false,
+            confidence: None,
+            endpoint: false,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+        send_final_to_claude(state.default_claude().as_ref(), &state.auto_send, &partial);
+        send_final_to_claude(
+            state.default_claude().as_ref(),
+            &state.auto_send,
+            &final_result("  "),
+        );
+
+        assert!(claude.input_history().is_empty());
+    }
+
+    #[test]
+    fn test_live_typer_erases_only_what_changed() {
+        let mut typer = LiveTyper::default();
+
+        assert_eq!(typer.replace("list"), "list");
+        // Growing appends without erasing
+        assert_eq!(typer.replace("list the"), " the");
+        // A revised word erases back to where the texts differ
+        assert_eq!(typer.replace("list a"), "\x7f\x7f\x7fa");
+        // Shrinking only erases
+        assert_eq!(typer.replace("list"), "\x7f\x7f");
+        assert_eq!(typer.replace(""), "\x7f\x7f\x7f\x7f");
+        // Characters, not bytes, are counted
+        assert_eq!(typer.replace("café"), "café");
+        assert_eq!(typer.replace("cafe"), "\x7fe");
+
+        assert_eq!(typer.commit("cafes", "\r"), "s\r");
+        // The committed text is never erased
+        assert_eq!(typer.replace("next"), "next");
+        assert_eq!(typer.commit("", " "), "\x7f\x7f\x7f\x7f");
+        assert_eq!(typer.typed, "");
+    }
+
+    #[test]
+    fn test_live_type_streams_partials_to_claude() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(audio.clone(), recognizer.clone(), claude.clone());
+        *state.live_type.lock() = Some(LiveTyper::default());
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        for text in ["run", "run the", "run tea", "run the tests"] {
+            recognizer.set_mock_text(text);
+            audio.push_samples(vec![0i16; 1600]);
+        }
+        recognizer.set_mock_text("run the tests now");
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(false);
+        recognizer.set_mock_text("ok");
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(
+            claude.input_history(),
+            vec![
+                "run",
+                " the",
+                "\x7f\x7fea",
+                "\x7f\x7fhe tests",
+                " now ",
+                "ok",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_live_type_final_uses_auto_send_suffix() {
+        let (state, _audio, claude) = create_dictation_state();
+        *state.auto_send.lock() = Some("\r".to_string());
+
+        assert!(!live_type_final(
+            state.default_claude().as_ref(),
+            &state.live_type,
+            &state.auto_send,
+            "list files",
+        ));
+        assert!(claude.input_history().is_empty());
+
+        *state.live_type.lock() = Some(LiveTyper::default());
+        live_type_partial(
+            state.default_claude().as_ref(),
+            &state.live_type,
+            &RecognitionResult {
+                is_final: false,
+                ..final_result("list")
+            },
+        );
+        assert!(live_type_final(
+            state.default_claude().as_ref(),
+            &state.live_type,
+            &state.auto_send,
+            "list files",
+        ));
+        assert_eq!(claude.input_history(), vec!["list", " files\r"]);
+
+        // A rejected final erases the partial without sending anything
+        live_type_partial(
+            state.default_claude().as_ref(),
+            &state.live_type,
+            &RecognitionResult {
+                is_final: false,
+                ..final_result("send")
+            },
+        );
+        live_type_final(
+            state.default_claude().as_ref(),
+            &state.live_type,
+            &state.auto_send,
+            "",
+        );
+        assert_eq!(claude.input_history()[2..], ["send", "\x7f\x7f\x7f\x7f"]);
+    }
+
+    #[test]
+    fn test_auto_send_disabled() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        send_final_to_claude(
+            state.default_claude().as_ref(),
+            &state.auto_send,
+            &final_result("list files"),
+        );
+
+        assert!(claude.input_history().is_empty());
+    }
+
+    #[test]
+    fn test_stop_recording_auto_sends_final() {
+        let (state, audio, claude) = create_dictation_state();
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        *state.auto_send.lock() = Some("\r".to_string());
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        // Mock recognizer only produces partials while streaming
+        audio.push_samples(vec![0i16; 1600]);
+        assert!(claude.input_history().is_empty());
+
+        finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(claude.input_history(), vec!["Hello world\r"]);
+    }
+
+    #[test]
+    fn test_auto_punctuation_on_stop() {
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        recognizer.set_mock_text("run the tests period");
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            recognizer,
+            claude.clone(),
+        );
+        *state.auto_send.lock() = Some("\r".to_string());
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        let result = finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(result.text, "run the tests period");
+
+        state.auto_punctuation.store(true, Ordering::SeqCst);
+        begin_recording(sink.clone(), &state, None).unwrap();
+        let result = finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(result.text, "Run the tests.");
+        assert_eq!(
+            claude.input_history(),
+            vec!["run the tests period\r", "Run the tests.\r"]
+        );
+    }
+
+    #[test]
+    fn test_voice_command_fires_action_instead_of_dictating() {
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        recognizer.set_mock_text("Clear terminal.");
+        recognizer.set_mock_final(true);
+        let audio = Arc::new(MockAudioCapture::new());
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(audio.clone(), recognizer.clone(), claude.clone());
+        *state.auto_send.lock() = Some("\r".to_string());
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+
+        let (_, payload) = sink
+            .events
+            .lock()
+            .iter()
+            .find(|(name, _)| name == "voice-command")
+            .cloned()
+            .unwrap();
+        assert_eq!(payload["action"], "ClearTerminal");
+        assert_eq!(count_events(&sink, "transcription-final"), 0);
+        assert!(claude.input_history().is_empty());
+
+        // Ordinary speech is still dictated
+        recognizer.set_mock_text("clear the terminal buffer");
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "voice-command"), 1);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(claude.input_history(), vec!["clear the terminal buffer\r"]);
+    }
+
+    #[test]
+    fn test_voice_command_on_stop_is_not_dictated() {
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        recognizer.set_mock_text("over and out");
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            recognizer,
+            claude.clone(),
+        );
+        *state.auto_send.lock() = Some("\r".to_string());
+        *state.voice_commands.lock() =
+            VoiceCommands::new([("over and out".to_string(), VoiceAction::SendToClaude)]);
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        sink.events.lock().clear();
+        let result = finish_recording(sink.as_ref(), &state).unwrap();
+
+        assert_eq!(result.text, "");
+        assert_eq!(
+            sink.names(),
+            vec!["voice-command".to_string(), "session-ended".to_string()]
+        );
+        let (_, payload) = sink.events.lock()[0].clone();
+        assert_eq!(payload["action"], "SendToClaude");
+        assert!(claude.input_history().is_empty());
+    }
+
+    #[test]
+    fn test_wake_word_gates_transcriptions() {
+        let (state, _, claude) = create_dictation_state();
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        state.set_recognizer(Arc::new(recognizer));
+        *state.auto_send.lock() = Some("\r".to_string());
+        configure_wake_word(&state, Some("hey tool".to_string()), None, 300).unwrap();
+        let sink = Arc::new(RecordingSink::default());
+        let say = |text: &str, is_final: bool| {
+            let recognizer = MockSpeechRecognizer::new();
+            recognizer
+                .load_model(std::path::Path::new("/test/model"))
+                .unwrap();
+            recognizer.set_mock_text(text);
+            recognizer.set_mock_final(is_final);
+            state.set_recognizer(Arc::new(recognizer));
+            feed_samples(sink.clone(), &state, vec![0i16; 1600]).unwrap()
+        };
+
+        // Suppressed before the wake phrase
+        assert!(say("delete everything", false).is_none());
+        assert!(say("delete everything", true).is_none());
+        assert!(sink.names().is_empty());
+
+        let result = say("Hey tool, list files", true).unwrap();
+        assert_eq!(result.text, "list files");
+        assert_eq!(
+            sink.names(),
+            vec![
+                "wake-detected",
+                "transcription-final",
+                "transcription",
+                "transcription-committed",
+                "utterance-end"
+            ]
+        );
+        assert_eq!(say("run the tests", false).unwrap().text, "run the tests");
+        say("run the tests", true).unwrap();
+        assert_eq!(
+            claude.input_history(),
+            vec!["list files\r", "run the tests\r"]
+        );
+
+        // Disarmed after 300ms of audio without speech
+        say("", false);
+        say("", false);
+        assert_eq!(count_events(&sink, "wake-timeout"), 0);
+        say("", false);
+        assert_eq!(count_events(&sink, "wake-timeout"), 1);
+        assert!(say("and commit", true).is_none());
+        assert_eq!(claude.input_history().len(), 2);
+
+        let err = configure_wake_word(&state, Some("?".to_string()), None, 0).unwrap_err();
+        assert_eq!(err.kind, "invalid_wake_word");
+        configure_wake_word(&state, None, None, 0).unwrap();
+        assert!(say("and commit", true).is_some());
+    }
+
+    #[test]
+    fn test_recording_auto_stops_at_max_duration() {
+        let (mut state, audio, _) = create_dictation_state();
+        let base = Instant::now();
+        let now = Arc::new(Mutex::new(base));
+        let clock = now.clone();
+        state.clock = Box::new(move || *clock.lock());
+        let sink = Arc::new(RecordingSink::default());
+        let advance = |secs: u64| *now.lock() = base + Duration::from_secs(secs);
+
+        // Unlimited by default
+        begin_recording(sink.clone(), &state, None).unwrap();
+        advance(3600);
+        assert!(!enforce_max_recording(sink.as_ref(), &state));
+        assert_eq!(recording_elapsed(&state), Some(Duration::from_secs(3600)));
+        finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(recording_elapsed(&state), None);
+
+        state.max_recording_secs.store(10, Ordering::SeqCst);
+        advance(0);
+        begin_recording(sink.clone(), &state, None).unwrap();
+        sink.events.lock().clear();
+        advance(9);
+        assert!(!enforce_max_recording(sink.as_ref(), &state));
+        assert!(audio.is_recording());
+
+        advance(10);
+        assert!(enforce_max_recording(sink.as_ref(), &state));
+        assert!(!audio.is_recording());
+        assert_eq!(audio.stop_call_count(), 2);
+        let names = sink.names();
+        assert_eq!(names.first().unwrap(), "recording-auto-stopped");
+        assert_eq!(names.last().unwrap(), "session-ended");
+        let (_, payload) = sink.events.lock()[0].clone();
+        assert_eq!(payload["max_secs"], 10);
+        assert_eq!(recording_elapsed(&state), None);
+
+        advance(30);
+        assert!(!enforce_max_recording(sink.as_ref(), &state));
+        assert_eq!(count_events(&sink, "recording-auto-stopped"), 1);
+    }
+
+    #[test]
+    fn test_auto_stop_finalizes_once() {
+        let (mut state, audio, _) = create_dictation_state();
+        let base = Instant::now();
+        let now = Arc::new(Mutex::new(base));
+        let clock = now.clone();
+        state.clock = Box::new(move || *clock.lock());
+        state.max_recording_secs.store(10, Ordering::SeqCst);
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        let sink = Arc::new(RecordingSink::default());
+
+        handle_ptt(sink.clone(), &state, true);
+        assert!(audio.is_recording());
+        *now.lock() = base + Duration::from_secs(10);
+        assert!(enforce_max_recording(sink.as_ref(), &state));
+
+        // Neither the key release nor a stop finalizes again
+        handle_ptt(sink.clone(), &state, false);
+        let err = finish_recording(sink.as_ref(), &state).unwrap_err();
+        assert_eq!(err.kind, "not_recording");
+        assert_eq!(count_events(&sink, "session-ended"), 1);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(count_events(&sink, "ptt-released"), 0);
+        assert_eq!(audio.stop_call_count(), 1);
+    }
+
+    #[test]
+    fn test_claude_resource_usage() {
+        let claude = Arc::new(MockClaudeProcess::new());
+        let monitor = Arc::new(MockProcessMonitor::default());
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            Arc::new(MockSpeechRecognizer::new()),
+            claude.clone(),
+        )
+        .with_process_monitor(monitor.clone());
+        let usage = ResourceUsage {
+            pid: MOCK_PID,
+            cpu_percent: 87.5,
+            rss_bytes: 300 << 20,
+        };
+        monitor.add(usage.clone());
+
+        // Not running yet
+        assert_eq!(
+            resource_usage(&state, DEFAULT_CLAUDE_SESSION).unwrap(),
+            None
+        );
+
+        claude.start(None).unwrap();
+        assert_eq!(
+            resource_usage(&state, DEFAULT_CLAUDE_SESSION).unwrap(),
+            Some(usage)
+        );
+
+        let err = resource_usage(&state, "missing").unwrap_err();
+        assert_eq!(err.kind, "session_not_found");
+
+        // The process is gone but the session hasn't noticed yet
+        let state = create_test_state();
+        state.default_claude().start(None).unwrap();
+        assert_eq!(
+            resource_usage(&state, DEFAULT_CLAUDE_SESSION).unwrap(),
+            None
+        );
+    }
+
+    fn count_events(sink: &RecordingSink, name: &str) -> usize {
+        sink.names().iter().filter(|n| *n == name).count()
+    }
+
+    #[test]
+    fn test_recording_started_reports_device() {
+        let state = create_test_state();
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, Some("Secondary Mic".to_string())).unwrap();
+
+        let (name, payload) = sink.events.lock()[0].clone();
+        assert_eq!(name, "recording-started");
+        assert_eq!(payload["device"], "Secondary Mic");
+        assert!(payload["sample_rate"].as_u64().unwrap() > 0);
+        assert!(payload["channels"].as_u64().unwrap() > 0);
+        assert_eq!(
+            state.audio.get_active_device().map(|d| d.name).as_deref(),
+            Some("Secondary Mic")
+        );
+    }
+
+    #[test]
+    fn test_partial_and_final_events() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(false);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "transcription-partial"), 3);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        // Deprecated combined event still fires for every result
+        assert_eq!(count_events(&sink, "transcription"), 4);
+
+        finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(count_events(&sink, "transcription-final"), 2);
+        assert_eq!(count_events(&sink, "transcription-partial"), 3);
+    }
+
+    #[test]
+    fn test_low_confidence_results_are_rejected() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        recognizer.set_mock_confidence(0.4);
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(audio.clone(), recognizer.clone(), claude.clone());
+        *state.auto_send.lock() = Some("\r".to_string());
+        *state.confidence_filter.lock() = ConfidenceFilter {
+            min_confidence: 0.5,
+            filter_partials: false,
+        };
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        // Partials are exempt by default
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "transcription-partial"), 1);
+        assert_eq!(count_events(&sink, "transcription-final"), 0);
+        assert_eq!(count_events(&sink, "transcription"), 1);
+        assert_eq!(count_events(&sink, "transcription-rejected"), 1);
+        let (_, rejected) = sink
+            .events
+            .lock()
+            .iter()
+            .find(|(name, _)| name == "transcription-rejected")
+            .cloned()
+            .unwrap();
+        assert_eq!(rejected["text"], "Hello world");
+        assert!((rejected["confidence"].as_f64().unwrap() - 0.4).abs() < 1e-6);
+        assert!(claude.input_history().is_empty());
+
+        // Confident results pass through
+        recognizer.set_mock_confidence(0.9);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(claude.input_history(), vec!["Hello world\r"]);
+
+        // The final result on stop is filtered too
+        recognizer.set_mock_confidence(0.4);
+        let result = finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(result.text, "");
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(count_events(&sink, "transcription-rejected"), 2);
+    }
+
+    #[test]
+    fn test_switch_audio_host() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let jack_mic = AudioDeviceInfo {
+            name: "system:capture_1".to_string(),
+            is_default: true,
+            is_loopback: false,
+        };
+        let audio = Arc::new(MockAudioCapture::new());
+        audio.add_host("JACK", vec![jack_mic.clone()]);
+        let mut state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.app_data_dir = temp_dir.path().to_path_buf();
+
+        let devices = switch_audio_host(&state, Some("JACK".to_string())).unwrap();
+        assert_eq!(devices, vec![jack_mic]);
+        assert_eq!(
+            Settings::load(temp_dir.path()).audio_host.as_deref(),
+            Some("JACK")
+        );
+
+        // An unknown host leaves the current one selected
+        let err = switch_audio_host(&state, Some("ASIO".to_string())).unwrap_err();
+        assert_eq!(err.kind, "host_unavailable");
+        assert_eq!(audio.host(), "JACK");
+
+        let devices = switch_audio_host(&state, None).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(Settings::load(temp_dir.path()).audio_host, None);
+    }
+
+    #[test]
+    fn test_saved_audio_host_applied() {
+        let audio = Arc::new(MockAudioCapture::new());
+        audio.add_host("JACK", Vec::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+
+        // A saved host that is no longer available leaves the default in use
+        state.settings.lock().audio_host = Some("PulseAudio".to_string());
+        state.apply_settings();
+        assert_eq!(audio.host(), MOCK_HOST);
+
+        state.settings.lock().audio_host = Some("JACK".to_string());
+        state.apply_settings();
+        assert_eq!(audio.host(), "JACK");
+    }
+
+    #[test]
+    fn test_duplicate_finals_are_suppressed() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let claude = Arc::new(MockClaudeProcess::new());
+        claude.start(None).unwrap();
+        let state = AppState::with_mocks(audio.clone(), recognizer.clone(), claude.clone());
+        *state.auto_send.lock() = Some("\r".to_string());
+        state.duplicate_filter.lock().window_ms = DEFAULT_DUPLICATE_WINDOW_MS;
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(count_events(&sink, "transcription-duplicate-suppressed"), 1);
+        assert_eq!(claude.input_history(), vec!["Hello world\r"]);
+        assert_eq!(state.transcript.lock().len(), 1);
+
+        // The same text after the window is dictated again
+        audio.push_samples(vec![0i16; 32000]);
+        assert_eq!(count_events(&sink, "transcription-final"), 2);
+        assert_eq!(claude.input_history().len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_filter() {
+        let mut filter = DuplicateFilter::default();
+        assert!(!filter.is_duplicate("run the tests", 0));
+        assert!(filter.is_duplicate("run the tests", 200));
+        assert!(!filter.is_duplicate("run the test", 400));
+        assert!(!filter.is_duplicate("", 500));
+
+        filter.max_distance = 2;
+        assert!(filter.is_duplicate("run the tests", 600));
+        assert!(!filter.is_duplicate("run the tests", 600 + DEFAULT_DUPLICATE_WINDOW_MS + 1));
+
+        filter.window_ms = 0;
+        assert!(!filter.is_duplicate("run the tests", 3000));
+
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("héllo", "hello"), 1);
+    }
+
+    #[test]
+    fn test_confidence_filter() {
+        let result = |is_final, confidence| RecognitionResult {
+            text: "hello".to_string(),
+            is_final,
+            confidence,
+            endpoint: is_final,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+
+        // The default threshold filters nothing
+        let filter = ConfidenceFilter::default();
+        assert!(!filter.rejects(&result(true, Some(0.0))));
+
+        let filter = ConfidenceFilter {
+            min_confidence: 0.5,
+            filter_partials: false,
+        };
+        assert!(filter.rejects(&result(true, Some(0.49))));
+        assert!(!filter.rejects(&result(true, Some(0.5))));
+        assert!(!filter.rejects(&result(true, None)));
+        assert!(!filter.rejects(&result(false, Some(0.1))));
+
+        let filter = ConfidenceFilter {
+            filter_partials: true,
+            ..filter
+        };
+        assert!(filter.rejects(&result(false, Some(0.1))));
+    }
+
+    #[test]
+    fn test_input_gain_applied_before_recognition() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        state.gain.set_gain(4.0);
+        begin_recording(sink, &state, None).unwrap();
+        audio.push_samples(vec![1000i16; 1600]);
+
+        let expected = compute_level(&[4000i16; 1600]);
+        assert!((state.level_meter.level().rms - expected.rms).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_noise_gate_applied_after_metering() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        state.noise_gate.set(0.05, 100);
+        begin_recording(sink, &state, None).unwrap();
+        audio.push_samples(vec![300i16; 1600]);
+
+        assert!(recognizer.last_samples().iter().all(|&s| s == 0));
+        assert!(state.level_meter.level().rms > 0.0);
+    }
+
+    #[test]
+    fn test_transcript_accumulates_final_results() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_text("partial only");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(true);
+        recognizer.set_mock_text("first sentence");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_text("last words");
+        finish_recording(sink.as_ref(), &state).unwrap();
+
+        let texts: Vec<String> = state
+            .transcript
+            .lock()
+            .iter()
+            .map(|r| r.text.clone())
+            .collect();
+        assert_eq!(texts, vec!["first sentence", "last words"]);
+
+        // Starting a new session clears the previous transcript
+        begin_recording(sink, &state, None).unwrap();
+        assert!(state.transcript.lock().is_empty());
+    }
+
+    #[test]
+    fn test_result_offsets_track_samples_since_start() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+        recognizer.set_mock_text("hello");
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        for _ in 0..5 {
+            audio.push_samples(vec![0i16; 1600]);
+        }
+
+        let offsets: Vec<u64> = sink
+            .events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == "transcription-partial")
+            .map(|(_, payload)| payload["offset_ms"].as_u64().unwrap())
+            .collect();
+        assert_eq!(offsets, vec![100, 200, 300, 400, 500]);
+
+        let result = finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(result.offset_ms, 5 * 1600 / 16);
+
+        // Restarting begins counting from zero again
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(vec![0i16; 800]);
+        let (_, last) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(last["offset_ms"], 800 / 16);
+    }
+
+    #[test]
+    fn test_buffer_size_rejection_emits_warning() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        audio.set_buffer_frames(Some(1 << 20));
+        begin_recording(sink.clone(), &state, None).unwrap();
+
+        assert_eq!(sink.names(), vec!["audio-warning", "recording-started"]);
+        let (_, warning) = sink.events.lock()[0].clone();
+        assert_eq!(warning["requested_frames"], 1 << 20);
+        let (_, started) = sink.events.lock()[1].clone();
+        assert!(started["buffer_frames"].is_null());
+    }
+
+    #[test]
+    fn test_start_recording_requires_model() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        let result = try_begin_recording(sink.clone(), &state, None, false);
+        assert_eq!(
+            result,
+            Err(CommandError::new(
+                "model_not_loaded",
+                "No speech model loaded"
+            ))
+        );
+        assert_eq!(audio.start_call_count(), 0);
+        assert!(sink.names().is_empty());
+
+        // WAV-only capture can opt out of the check
+        try_begin_recording(sink.clone(), &state, None, true).unwrap();
+        assert_eq!(audio.start_call_count(), 1);
+        finish_recording(sink.as_ref(), &state).unwrap();
+
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        try_begin_recording(sink, &state, None, false).unwrap();
+        assert_eq!(audio.start_call_count(), 2);
+    }
+
+    #[test]
+    fn test_feed_pcm_emits_results() {
+        let (state, audio, claude) = create_dictation_state();
+        let recognizer = MockSpeechRecognizer::new();
+        let sink = Arc::new(RecordingSink::default());
+        *state.auto_send.lock() = Some("\r".to_string());
+
+        let err = feed_samples(sink.clone(), &state, vec![0i16; 1600]).unwrap_err();
+        assert_eq!(err.kind, "model_not_loaded");
+
+        recognizer.set_mock_text("list");
+        state.set_recognizer(Arc::new(recognizer));
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        assert!(feed_samples(sink.clone(), &state, Vec::new())
+            .unwrap()
+            .is_none());
+
+        let partial = feed_samples(sink.clone(), &state, vec![0i16; 1600])
+            .unwrap()
+            .unwrap();
+        assert!(!partial.is_final);
+        assert_eq!(partial.text, "list");
+        assert_eq!(partial.offset_ms, 100);
+        assert_eq!(sink.names(), vec!["transcription-partial", "transcription"]);
+
+        // Buffers reach the recognizer unchunked
+        let buffer = vec![0i16; 5000];
+        feed_samples(sink.clone(), &state, buffer).unwrap();
+        assert_eq!(state.session_stats().total_samples, 6600);
+
+        audio.start_recording(None, Arc::new(|_| {})).unwrap();
+        let err = feed_samples(sink.clone(), &state, vec![0i16; 1600]).unwrap_err();
+        assert_eq!(err.kind, "recording_in_progress");
+        audio.stop_recording();
+
+        // Finals are dictated and committed like live ones
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer.set_mock_text("list files");
+        recognizer.set_mock_final(true);
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        state.set_recognizer(Arc::new(recognizer));
+        let result = feed_samples(sink.clone(), &state, vec![0i16; 1600])
+            .unwrap()
+            .unwrap();
+        assert!(result.is_final);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        assert_eq!(count_events(&sink, "transcription-committed"), 1);
+        assert_eq!(claude.input_history(), vec!["list files\r"]);
+    }
+
+    #[test]
+    fn test_start_recording_requires_microphone_permission() {
+        let (state, audio, _claude) = create_dictation_state();
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        let sink = Arc::new(RecordingSink::default());
+
+        audio.set_permission(PermissionStatus::Denied, true);
+        let err = try_begin_recording(sink.clone(), &state, None, false).unwrap_err();
+        assert_eq!(err.kind, "microphone_permission_denied");
+        assert_eq!(err.message, "Microphone permission denied");
+        assert_eq!(audio.start_call_count(), 0);
+
+        // An unanswered prompt is left to the frontend rather than shown inline
+        audio.set_permission(PermissionStatus::Undetermined, true);
+        let err = try_begin_recording(sink.clone(), &state, None, false).unwrap_err();
+        assert_eq!(err.kind, "microphone_permission_undetermined");
+        assert_eq!(
+            audio.microphone_permission(),
+            PermissionStatus::Undetermined
+        );
+        assert_eq!(audio.start_call_count(), 0);
+
+        assert_eq!(
+            audio.request_microphone_permission(),
+            PermissionStatus::Granted
+        );
+        try_begin_recording(sink, &state, None, false).unwrap();
+        assert_eq!(audio.start_call_count(), 1);
+    }
+
+    #[test]
+    fn test_recognition_failure_emits_error_event() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_should_fail(true);
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "recognition-error"), 1);
+        let (_, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(payload["kind"], "recognition_error");
+        assert_eq!(payload["message"], "Recognition failed: Mock error");
+
+        // A recovery re-arms the event for the next failure
+        recognizer.set_should_fail(false);
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_should_fail(true);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "recognition-error"), 2);
+    }
+
+    #[test]
+    fn test_pipeline_resamples_across_buffers() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        state.recognizer().set_sample_rate(8000.0).unwrap();
+        let sink = Arc::new(RecordingSink::default());
+        begin_recording(sink, &state, None).unwrap();
+
+        let input: Vec<i16> = (0..16000)
+            .map(|i| ((i as f64 * 0.05).sin() * 8000.0) as i16)
+            .collect();
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(1600) {
+            audio.push_samples(chunk.to_vec());
+            chunked.extend(recognizer.last_samples());
+        }
+
+        let one_shot = StreamResampler::new(16000, 8000).process(&input);
+        assert_eq!(chunked.len(), 8000);
+        assert_eq!(chunked, one_shot);
+    }
+
+    #[test]
+    fn test_stale_partial_survives_clock_going_back() {
+        let partial = RecognitionResult {
+            text: "turn the".to_string(),
+            is_final: false,
+            confidence: None,
+            endpoint: false,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+        let mut stale = StalePartial::default();
+        assert_eq!(stale.update(Some(&partial), 1000, 500), None);
+
+        // The sample counter restarts with a new session
+        assert_eq!(stale.update(Some(&partial), 100, 500), None);
+        assert_eq!(stale.update(None, 1500, 500), Some("turn the".to_string()));
+    }
+
+    #[test]
+    fn test_stale_partial_is_cleared() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.partial_timeout_ms.store(500, Ordering::SeqCst);
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_text("turn the");
+        audio.push_samples(vec![0i16; 1600]);
+
+        // The same partial keeps coming back while the speaker pauses
+        for _ in 0..4 {
+            audio.push_samples(vec![0i16; 1600]);
+        }
+        assert_eq!(count_events(&sink, "transcription-partial-cleared"), 0);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "transcription-partial-cleared"), 1);
+        let (_, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(payload["text"], "turn the");
+        assert_eq!(payload["offset_ms"], 600);
+
+        // Reported once, then again for a newer partial that stalls
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_text("turn the page");
+        for _ in 0..6 {
+            audio.push_samples(vec![0i16; 1600]);
+        }
+        assert_eq!(count_events(&sink, "transcription-partial-cleared"), 2);
+    }
+
+    #[test]
+    fn test_rapid_partials_are_coalesced() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.partial_emit_interval_ms.store(500, Ordering::SeqCst);
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        // A partial every 100ms of audio
+        for i in 0..10 {
+            recognizer.set_mock_text(&format!("partial {}", i));
+            audio.push_samples(vec![0i16; 1600]);
+        }
+        // No new result, but the held-back partial is now due
+        recognizer.set_mock_text("");
+        audio.push_samples(vec![0i16; 1600]);
+
+        let partials = |sink: &RecordingSink| -> Vec<String> {
+            sink.events
+                .lock()
+                .iter()
+                .filter(|(name, _)| name == "transcription-partial")
+                .map(|(_, payload)| payload["text"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(partials(&sink), vec!["partial 0", "partial 5", "partial 9"]);
+        assert_eq!(count_events(&sink, "transcription"), 3);
+
+        // Finals are never held back, and drop the partial waiting behind them
+        recognizer.set_mock_text("partial 10");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_text("done");
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "transcription-final"), 1);
+        recognizer.set_mock_text("");
+        for _ in 0..6 {
+            audio.push_samples(vec![0i16; 1600]);
+        }
+        assert_eq!(partials(&sink).len(), 3);
+    }
+
+    #[test]
+    fn test_partial_timeout_off_by_default() {
+        let mut stale = StalePartial::default();
+        let partial = RecognitionResult {
+            text: "hello".to_string(),
+            is_final: false,
+            confidence: None,
+            endpoint: false,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+
+        assert_eq!(stale.update(Some(&partial), 0, 0), None);
+        assert_eq!(stale.update(None, 60_000, 0), None);
+
+        // A final result leaves nothing to clear
+        assert_eq!(stale.update(Some(&partial), 0, 100), None);
+        let final_result = RecognitionResult {
+            is_final: true,
+            ..partial.clone()
+        };
+        assert_eq!(stale.update(Some(&final_result), 50, 100), None);
+        assert_eq!(stale.update(None, 500, 100), None);
+    }
+
+    #[test]
+    fn test_switch_model_while_recording() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let mut state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+        let temp_dir = tempfile::tempdir().unwrap();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+        let english = temp_dir.path().join("vosk-model-en-us-0.22");
+        let german = temp_dir.path().join("vosk-model-de-0.21");
+        state.load_model(sink.as_ref(), &english).unwrap();
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_text("guten");
+        audio.push_samples(vec![0i16; 1600]);
+
+        let model = state.switch_model(sink.as_ref(), &german).unwrap();
+        assert_eq!(model.name, "vosk-model-de-0.21");
+        assert_eq!(recognizer.loaded_path(), Some(german.clone()));
+        assert!(state.audio.is_recording());
+
+        // The old model's pending speech is finalized
+        let finals: Vec<String> = sink
+            .events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == "transcription-final")
+            .map(|(_, payload)| payload["text"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(finals, vec!["guten"]);
+        assert_eq!(state.transcript.lock().len(), 1);
+
+        // Later buffers go to the new model without losing any
+        recognizer.set_mock_text("guten Tag");
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(recognizer.last_samples().len(), 1600);
+        assert_eq!(state.session_stats().total_samples, 3200);
+        assert_eq!(state.loaded_model().map(|m| m.path), Some(german.clone()));
+        assert_eq!(
+            state.settings.lock().model_path.as_deref(),
+            Some(german.to_str().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_switch_model_when_idle_loads() {
+        let (mut state, _audio, _claude) = create_dictation_state();
+        let sink = RecordingSink::default();
+        let temp_dir = tempfile::tempdir().unwrap();
+        state.app_data_dir = temp_dir.path().to_path_buf();
+
+        state
+            .switch_model(&sink, &temp_dir.path().join("vosk-model-fr-0.22"))
+            .unwrap();
+        assert!(state.recognizer().is_model_loaded());
+        assert!(sink.names().is_empty());
+    }
+
+    #[test]
+    fn test_audio_is_chunked_for_recognition() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.chunker.set_frames(3200);
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        for size in [1000, 441, 5000, 3200, 7, 1200] {
+            audio.push_samples(vec![0i16; size]);
+        }
+        assert_eq!(recognizer.buffer_sizes(), vec![3200, 3200, 3200]);
+
+        // The remainder is recognized before the final result
+        finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(recognizer.buffer_sizes(), vec![3200, 3200, 3200, 1248]);
+        assert_eq!(state.session_stats().total_samples, 10848);
+    }
+
+    #[test]
+    fn test_muted_audio_is_discarded() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(recognizer.process_count(), 1);
+
+        state.muted.store(true, Ordering::SeqCst);
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(recognizer.process_count(), 1);
+        assert!(state.audio.is_recording());
+        assert_eq!(state.session_stats().total_samples, 1600);
+
+        state.muted.store(false, Ordering::SeqCst);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(recognizer.process_count(), 2);
+    }
+
+    #[test]
+    fn test_empty_buffers_are_skipped() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(Vec::new());
+        assert!(recognizer.buffer_sizes().is_empty());
+        assert_eq!(count_events(&sink, "audio-level"), 0);
+        assert_eq!(count_events(&sink, "audio-waveform"), 0);
+
+        audio.push_samples(vec![0i16; 1]);
+        assert_eq!(recognizer.buffer_sizes(), vec![1]);
+        let events = sink.events.lock().clone();
+        let (_, waveform) = events
+            .iter()
+            .find(|(name, _)| name == "audio-waveform")
+            .unwrap();
+        assert_eq!(*waveform, json!([[0, 0]]));
+        assert_eq!(state.session_stats().total_samples, 1);
+    }
+
+    #[test]
+    fn test_recognizer_recovers_after_failed_decode() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_fail_until_reset();
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "recognition-error"), 1);
+        assert_eq!(count_events(&sink, "recognizer-recovered"), 1);
+        assert_eq!(count_events(&sink, "transcription-partial"), 1);
+        assert_eq!(recognizer.process_count(), 1);
+    }
+
+    #[test]
+    fn test_recognizer_stays_failed_without_auto_recover() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.auto_recover.store(false, Ordering::SeqCst);
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_fail_until_reset();
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+
+        assert_eq!(count_events(&sink, "recognition-error"), 1);
+        assert_eq!(count_events(&sink, "recognizer-recovered"), 0);
+        assert_eq!(recognizer.process_count(), 0);
+    }
+
+    #[test]
+    fn test_audio_thread_failure_emits_error_event() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.emit_device_event(DeviceEvent::from(&AudioError::StreamError(
+            "device busy".to_string(),
+        )));
+
+        let (name, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(name, "audio-error");
+        assert_eq!(payload["kind"], "stream_error");
+        assert_eq!(
+            payload["message"],
+            "Failed to build input stream: device busy"
+        );
+    }
+
+    #[test]
+    fn test_stream_failure_emits_error_event() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+        begin_recording(sink.clone(), &state, None).unwrap();
+        let before = sink.names().len();
+
+        let lost = audio.fail_stream(cpal::StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: "buffer overrun".to_string(),
+            },
+        });
+
+        assert!(!lost);
+        assert_eq!(sink.names()[before..], ["audio-error"]);
+        let (_, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(payload["kind"], "stream_failed");
+        assert_eq!(payload["message"], "Audio stream failed: buffer overrun");
+    }
+
+    #[test]
+    fn test_apply_settings_restores_buffer_frames() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        state.settings.lock().buffer_frames = Some(512);
+
+        state.apply_settings();
+        assert_eq!(audio.buffer_frames(), Some(512));
+
+        let sink = Arc::new(RecordingSink::default());
+        begin_recording(sink.clone(), &state, None).unwrap();
+        let (name, payload) = sink.events.lock()[0].clone();
+        assert_eq!(name, "recording-started");
+        assert_eq!(payload["buffer_frames"], 512);
+    }
+
+    #[test]
+    fn test_apply_settings_restores_gain() {
+        let state = create_test_state();
+        {
+            let mut settings = state.settings.lock();
+            settings.input_gain = Some(3.0);
+            settings.agc = true;
+            settings.noise_gate_threshold = 0.02;
+            settings.noise_gate_release_ms = 250;
+        }
+
+        state.apply_settings();
+        assert_eq!(state.gain.gain(), 3.0);
+        assert!(state.gain.agc_enabled());
+        assert_eq!(state.noise_gate.threshold(), 0.02);
+        assert_eq!(state.noise_gate.release_ms(), 250);
+    }
+
+    #[test]
+    fn test_utterance_end_only_on_endpoint() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "utterance-end"), 0);
+
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 1600]);
+        assert_eq!(count_events(&sink, "utterance-end"), 1);
+
+        // Stopping forces a final result but is not an endpoint
+        finish_recording(sink.as_ref(), &state).unwrap();
+        assert_eq!(count_events(&sink, "utterance-end"), 1);
+    }
+
+    #[test]
+    fn test_command_error_kinds() {
+        let s = || "x".to_string();
+        let cases: Vec<(CommandError, &str)> = vec![
+            (AudioError::NoInputDevice.into(), "no_input_device"),
+            (AudioError::ConfigError(s()).into(), "config_error"),
+            (AudioError::StreamError(s()).into(), "stream_error"),
+            (AudioError::PlayError(s()).into(), "play_error"),
+            (AudioError::DeviceNotFound(s()).into(), "device_not_found"),
+            (AudioError::WavError(s()).into(), "wav_error"),
+            (AudioError::DecodeError(s()).into(), "decode_error"),
+            (AudioError::HostUnavailable(s()).into(), "host_unavailable"),
+            (SpeechError::ModelNotFound(s()).into(), "model_not_found"),
+            (SpeechError::ModelInitError(s()).into(), "model_init_error"),
+            (SpeechError::RecognizerError(s()).into(), "recognizer_error"),
+            (
+                SpeechError::RecognitionError(s()).into(),
+                "recognition_error",
+            ),
+            (SpeechError::DownloadError(s()).into(), "download_error"),
+            (
+                SpeechError::DownloadCancelled(s()).into(),
+                "download_cancelled",
+            ),
+            (
+                SpeechError::InsufficientSpace(s()).into(),
+                "insufficient_space",
+            ),
+            (SpeechError::InvalidScript(s()).into(), "invalid_script"),
+            (
+                SpeechError::ModelDirNotFound(s()).into(),
+                "model_dir_not_found",
+            ),
+            (
+                SpeechError::DuplicateModelDir(s()).into(),
+                "duplicate_model_dir",
+            ),
+            (ClaudeError::PtySpawnError(s()).into(), "pty_spawn_error"),
+            (ClaudeError::NotRunning.into(), "not_running"),
+            (ClaudeError::WriteError(s()).into(), "write_error"),
+            (ClaudeError::ReadError(s()).into(), "read_error"),
+            (ClaudeError::ClaudeNotFound.into(), "claude_not_found"),
+            (ClaudeError::LogError(s()).into(), "log_error"),
+            (
+                ClaudeError::WorkingDirNotFound(s()).into(),
+                "working_dir_not_found",
+            ),
+            (
+                ClaudeError::InvalidWorkingDir(s()).into(),
+                "invalid_working_dir",
+            ),
+            (ClaudeError::VersionTimeout.into(), "version_timeout"),
+            (ClaudeError::VersionError(s()).into(), "version_error"),
+            (
+                SettingsError::WriteError(s()).into(),
+                "settings_write_error",
+            ),
+            (
+                SettingsError::SerializeError(s()).into(),
+                "settings_serialize_error",
+            ),
+            (
+                TranscriptError::WriteError(std::io::Error::other(s())).into(),
+                "transcript_write_error",
+            ),
+        ];
+
+        for (error, kind) in cases {
+            assert_eq!(error.kind, kind, "{}", error.message);
+        }
+
+        let error = CommandError::from(AudioError::DeviceNotFound("USB Mic".to_string()));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "kind": "device_not_found", "message": "Device not found: USB Mic" })
+        );
+    }
+
+    #[test]
+    fn test_command_errors_from_state() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        let error = state.claude_session("missing").err().unwrap();
+        assert_eq!(error.kind, "session_not_found");
+
+        claude.stop().unwrap();
+        let error = send_line(&state, DEFAULT_CLAUDE_SESSION, "hello").unwrap_err();
+        assert_eq!(error.kind, "not_running");
+    }
+
+    #[test]
+    fn test_shutdown_stops_everything_once() {
+        let (state, audio, default) = create_dictation_state();
+        let backend = Arc::new(MockClaudeProcess::new());
+        let factory_backend = backend.clone();
+        let state = state.with_claude_factory(move || factory_backend.clone());
+        state.open_claude_session("backend");
+        backend.start(None).unwrap();
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+
+        let summary = state.shutdown();
+        assert_eq!(
+            summary,
+            ShutdownSummary {
+                stopped_recording: true,
+                stopped_sessions: vec!["backend".to_string(), "default".to_string()],
+            }
+        );
+
+        // A second call finds nothing left running
+        assert_eq!(state.shutdown(), ShutdownSummary::default());
+        assert_eq!(audio.stop_call_count(), 1);
+        assert_eq!(default.stop_count(), 1);
+        assert_eq!(backend.stop_count(), 1);
+        assert!(!audio.is_recording());
+
+        // Nor is there a recording left to stop
+        assert!(recording_elapsed(&state).is_none());
+        let err = finish_recording(&RecordingSink::default(), &state).unwrap_err();
+        assert_eq!(err.kind, "not_recording");
+        assert_eq!(audio.stop_call_count(), 1);
+    }
+
+    #[test]
+    fn test_benchmark_requires_idle_loaded_model() {
+        let (state, audio, _claude) = create_dictation_state();
+        assert_eq!(run_benchmark(&state).unwrap_err().kind, "model_not_loaded");
+
+        state
+            .recognizer()
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        let result = run_benchmark(&state).unwrap();
+        assert!(result.rtf >= 0.0);
+
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+        assert!(audio.is_recording());
+        assert_eq!(
+            run_benchmark(&state).unwrap_err().kind,
+            "recording_in_progress"
+        );
+    }
+
+    #[test]
+    fn test_transcription_committed_carries_full_text() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+        let committed = |sink: &RecordingSink| -> Vec<String> {
+            sink.events
+                .lock()
+                .iter()
+                .filter(|(name, _)| name == "transcription-committed")
+                .map(|(_, payload)| payload["full_text"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_final(true);
+        recognizer.set_mock_text("open the readme");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_text("[unk]");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_final(false);
+        recognizer.set_mock_text("and run");
+        audio.push_samples(vec![0i16; 1600]);
+        recognizer.set_mock_text("and run the tests");
+        finish_recording(sink.as_ref(), &state).unwrap();
+
+        assert_eq!(
+            committed(&sink),
+            vec!["open the readme", "open the readme and run the tests"]
+        );
+        assert_eq!(
+            full_text(&state.transcript.lock()),
+            "open the readme and run the tests"
+        );
+
+        // A new recording starts from an empty transcript
+        begin_recording(sink.clone(), &state, None).unwrap();
+        assert_eq!(full_text(&state.transcript.lock()), "");
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        let sink = Arc::new(RecordingSink::default());
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_final(true);
+        audio.push_samples(vec![0i16; 16000]);
+        audio.push_samples(vec![0i16; 8000]);
+        recognizer.set_mock_final(false);
+        audio.push_samples(vec![0i16; 8000]);
+
+        let stats = state.session_stats();
+        assert_eq!(stats.total_samples, 32000);
+        assert_eq!(stats.duration_secs, 2.0);
+        assert_eq!(stats.final_count, 2);
+
+        finish_recording(sink.as_ref(), &state).unwrap();
+        let (name, payload) = sink.events.lock().last().cloned().unwrap();
+        assert_eq!(name, "session-ended");
+        assert_eq!(payload["total_samples"], 32000);
+        assert_eq!(payload["duration_secs"], 2.0);
+        assert_eq!(payload["final_count"], 3);
+
+        // A new recording starts from zero
+        begin_recording(sink, &state, None).unwrap();
+        assert_eq!(state.session_stats(), SessionStats::default());
+    }
+
+    #[test]
+    fn test_recognizer_debug_info_tracks_pipeline() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        let sink = Arc::new(RecordingSink::default());
+
+        let info = state.recognizer().debug_info();
+        assert!(info.model_loaded);
+        assert_eq!(info.sample_rate, 16000);
+        assert_eq!(info.samples_processed, 0);
+        assert_eq!(info.last_partial, None);
+
+        begin_recording(sink.clone(), &state, None).unwrap();
+        recognizer.set_mock_text("hello");
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 800]);
+
+        let info = state.recognizer().debug_info();
+        assert_eq!(info.samples_processed, 2400);
+        assert_eq!(info.last_partial.as_deref(), Some("hello"));
+        assert_eq!(info.last_final, None);
+
+        recognizer.set_mock_text("hello world");
+        finish_recording(sink.as_ref(), &state).unwrap();
+        let info = state.recognizer().debug_info();
+        assert_eq!(info.last_final.as_deref(), Some("hello world"));
+
+        state.unload_model().unwrap();
+        let info = state.recognizer().debug_info();
+        assert!(!info.model_loaded);
+        assert_eq!(info.samples_processed, 0);
+    }
+
+    /// Dictation state with a loaded model whose microphone delivers `samples`
+    fn create_self_test_state(
+        samples: Option<Vec<i16>>,
+    ) -> (AppState, Arc<MockAudioCapture>, Arc<MockSpeechRecognizer>) {
+        let audio = Arc::new(MockAudioCapture::new());
+        let recognizer = Arc::new(MockSpeechRecognizer::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            recognizer.clone(),
+            Arc::new(MockClaudeProcess::new()),
+        );
+        recognizer
+            .load_model(std::path::Path::new("/test/model"))
+            .unwrap();
+        audio.set_samples_on_start(samples);
+        (state, audio, recognizer)
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        let (state, audio, _recognizer) = create_self_test_state(Some(vec![4000i16; 1600]));
+
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, None);
+        assert_eq!(report.device.as_deref(), Some("Test Microphone"));
+        assert_eq!(report.samples_captured, 1600);
+        assert!(report.rms > SELF_TEST_MIN_RMS);
+        assert_eq!(report.transcript.as_deref(), Some("Hello world"));
+        assert!(!audio.is_recording());
+    }
+
+    #[test]
+    fn test_diagnostics_report_each_failed_check() {
+        let audio = Arc::new(MockAudioCapture::new());
+        let claude = Arc::new(MockClaudeProcess::new());
+        let state = AppState::with_mocks(
+            audio.clone(),
+            Arc::new(MockSpeechRecognizer::new()),
+            claude.clone(),
+        );
+        audio.set_devices(vec![AudioDeviceInfo {
+            name: "Speakers".to_string(),
+            is_default: true,
+            is_loopback: true,
+        }]);
+        claude.set_version(None);
+
+        let checks = diagnose(&state, false);
+        let failed: Vec<_> = checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.check)
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                Diagnostic::ModelsDir,
+                Diagnostic::InputDevice,
+                Diagnostic::VoskRuntime,
+                Diagnostic::ClaudeBinary
+            ]
+        );
+        assert_eq!(checks[1].detail, "No input devices found");
+
+        audio.set_should_fail(true);
+        assert!(!diagnose(&state, false)[1].passed);
+    }
+
+    #[test]
+    fn test_startup_diagnostics_pass() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state = create_test_state()
+            .with_model_manager(ModelManager::new_isolated(temp_dir.path().to_path_buf()));
+        let sink = RecordingSink::default();
+
+        emit_startup_diagnostics(&sink, &state);
+
+        let (name, payload) = sink.events.lock()[0].clone();
+        assert_eq!(name, "startup-diagnostics");
+        let checks = payload.as_array().unwrap();
+        assert_eq!(checks.len(), 4);
+        assert_eq!(checks[0]["check"], "models_dir");
+        assert_eq!(checks[0]["passed"], true);
+        assert_eq!(
+            checks[1]["detail"],
+            "2 input device(s), default Test Microphone"
+        );
+        assert_eq!(checks[3]["detail"], "Claude Code 1.0.0");
+        // The writability probe is cleaned up
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+
+        assert!(diagnose(&state, true).iter().all(|check| check.passed));
+    }
+
+    #[test]
+    fn test_self_test_flags_failed_stage() {
+        let speech = Some(vec![4000i16; 1600]);
+
+        let (state, audio, _) = create_self_test_state(speech.clone());
+        audio.set_devices(Vec::new());
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, Some(SelfTestStage::Devices));
+
+        let (state, _, recognizer) = create_self_test_state(speech.clone());
+        recognizer.unload_model();
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, Some(SelfTestStage::Model));
+        assert!(!report.model_loaded);
+
+        let (state, _, _) = create_self_test_state(None);
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, Some(SelfTestStage::Capture));
+        assert_eq!(report.samples_captured, 0);
+
+        let (state, _, _) = create_self_test_state(Some(vec![0i16; 1600]));
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, Some(SelfTestStage::Level));
+        assert_eq!(report.transcript, None);
+
+        let (state, _, recognizer) = create_self_test_state(speech);
+        recognizer.set_should_fail(true);
+        let report = run_self_test(&state, Duration::ZERO).unwrap();
+        assert_eq!(report.failed_stage, Some(SelfTestStage::Recognition));
+        assert!(report.message.is_some());
+    }
+
+    #[test]
+    fn test_self_test_refused_while_recording() {
+        let (state, audio, _claude) = create_dictation_state();
+        begin_recording(Arc::new(RecordingSink::default()), &state, None).unwrap();
+        assert!(audio.is_recording());
+
+        assert_eq!(
+            run_self_test(&state, Duration::ZERO).unwrap_err().kind,
+            "recording_in_progress"
+        );
+    }
+
+    #[test]
+    fn test_claude_version_is_cached() {
+        let claude = Arc::new(MockClaudeProcess::new());
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            Arc::new(MockSpeechRecognizer::new()),
+            claude.clone(),
+        );
+
+        claude.set_version(None);
+        assert_eq!(state.claude_version().unwrap_err().kind, "claude_not_found");
+
+        claude.set_version(Some("1.0.17"));
+        assert_eq!(state.claude_version().unwrap(), "1.0.17");
+        claude.set_version(Some("2.0.0"));
+        assert_eq!(state.claude_version().unwrap(), "1.0.17");
+    }
+
+    #[test]
+    fn test_demo_recognizer_replays_script() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = temp_dir.path().join("demo.txt");
+        std::fs::write(&script, "0.1 list the files\n0.1 open the readme\n").unwrap();
+        let (state, audio, _claude) = create_dictation_state();
+        let sink = Arc::new(RecordingSink::default());
+
+        assert_eq!(
+            use_scripted_recognizer(&state, &temp_dir.path().join("missing.txt"))
+                .unwrap_err()
+                .kind,
+            "invalid_script"
+        );
+
+        use_scripted_recognizer(&state, &script).unwrap();
+        assert!(state.recognizer().is_model_loaded());
+
+        try_begin_recording(sink.clone(), &state, None, false).unwrap();
+        audio.push_samples(vec![0i16; 1600]);
+        audio.push_samples(vec![0i16; 1600]);
+
+        let finals: Vec<String> = sink
+            .events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == "transcription-final")
+            .map(|(_, payload)| payload["text"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(finals, vec!["list the files", "open the readme"]);
+
+        assert_eq!(
+            use_scripted_recognizer(&state, &script).unwrap_err().kind,
+            "recording_in_progress"
+        );
+    }
+
+    #[test]
+    fn test_switch_backend() {
+        let (state, audio, _claude) = create_dictation_state();
+        state
+            .recognizer()
+            .load_model(Path::new("/test/model"))
+            .unwrap();
+
+        // The mock is replaced by a Vosk recognizer without a model
+        switch_backend(&state, SpeechBackend::Vosk).unwrap();
+        assert!(!state.recognizer().is_model_loaded());
+        assert!(state.loaded_model().is_none());
+        assert!(state.recognizer().process_audio(&[0i16; 160]).is_err());
+
+        audio.start_recording(None, Arc::new(|_| {})).unwrap();
+        assert_eq!(
+            switch_backend(&state, SpeechBackend::Vosk)
+                .unwrap_err()
+                .kind,
+            "recording_in_progress"
+        );
+    }
+
+    #[cfg(not(feature = "whisper"))]
+    #[test]
+    fn test_whisper_backend_unavailable() {
+        let (state, _audio, _claude) = create_dictation_state();
+        state
+            .recognizer()
+            .load_model(Path::new("/test/model"))
+            .unwrap();
+
+        let err = switch_backend(&state, SpeechBackend::Whisper).unwrap_err();
+        assert_eq!(err.kind, "backend_unavailable");
+        assert!(state.recognizer().is_model_loaded());
+    }
+
+    #[cfg(feature = "whisper")]
+    #[test]
+    fn test_whisper_backend() {
+        let (state, _audio, _claude) = create_dictation_state();
+
+        switch_backend(&state, SpeechBackend::Whisper).unwrap();
+        let recognizer = state.recognizer();
+        assert!(!recognizer.is_model_loaded());
+        assert_eq!(recognizer.sample_rate(), 16000);
+        assert_eq!(recognizer.get_final_result().unwrap().text, "");
+    }
+
+    #[test]
+    fn test_get_app_info() {
+        let state = create_test_state();
+        let info = app_info(&state);
+
+        assert_eq!(info["name"], "icanhastool");
+        assert!(info["version"].is_string());
+        assert!(info["description"].is_string());
+        assert_eq!(info["os"], std::env::consts::OS);
+        assert_eq!(info["arch"], std::env::consts::ARCH);
+        assert!(info["vosk_available"].is_boolean());
+        assert_eq!(info["backends"][0], "vosk");
+        assert_eq!(
+            info["backends"].as_array().unwrap().len(),
+            if cfg!(feature = "whisper") { 2 } else { 1 }
+        );
+        assert_eq!(
+            info["models_dir"],
+            json!(state.model_manager.get_models_dir())
+        );
+    }
+
+    /// State whose new Claude sessions are mocks the test can inspect
+    fn create_session_state() -> (
+        AppState,
+        Arc<MockClaudeProcess>,
+        Arc<Mutex<Vec<Arc<MockClaudeProcess>>>>,
+    ) {
+        let default = Arc::new(MockClaudeProcess::new());
+        let created: Arc<Mutex<Vec<Arc<MockClaudeProcess>>>> = Arc::new(Mutex::new(Vec::new()));
+        let factory_created = created.clone();
+
+        let state = AppState::with_mocks(
+            Arc::new(MockAudioCapture::new()),
+            Arc::new(MockSpeechRecognizer::new()),
+            default.clone(),
+        )
+        .with_claude_factory(move || {
+            let claude = Arc::new(MockClaudeProcess::new());
+            factory_created.lock().push(claude.clone());
+            claude
+        });
+
+        (state, default, created)
+    }
+
+    #[test]
+    fn test_claude_sessions_route_input_separately() {
+        let (state, default, created) = create_session_state();
+
+        let backend = state.open_claude_session("backend");
+        assert_eq!(created.lock().len(), 1);
+        let backend_mock = created.lock()[0].clone();
+
+        state.default_claude().start(None).unwrap();
+        backend.start(None).unwrap();
+
+        state
+            .claude_session(DEFAULT_CLAUDE_SESSION)
+            .unwrap()
+            .send_input("frontend task")
+            .unwrap();
+        state
+            .claude_session("backend")
+            .unwrap()
+            .send_input("backend task")
+            .unwrap();
+        state
+            .claude_session("backend")
+            .unwrap()
+            .resize(120, 40)
+            .unwrap();
+
+        assert_eq!(default.input_history(), vec!["frontend task"]);
+        assert_eq!(backend_mock.input_history(), vec!["backend task"]);
+        assert_eq!(backend_mock.current_size(), (120, 40));
+        assert_ne!(default.current_size(), (120, 40));
+
+        // Stopping one session leaves the other running
+        backend.stop().unwrap();
+        assert_eq!(backend_mock.status(), ProcessStatus::Stopped);
+        assert_eq!(default.status(), ProcessStatus::Running);
+    }
+
+    #[test]
+    fn test_claude_session_registry() {
+        let (state, _default, created) = create_session_state();
+
+        assert_eq!(state.claude_session_ids(), vec![DEFAULT_CLAUDE_SESSION]);
+        assert!(state.claude_session("missing").is_err());
+
+        // Opening an existing session reuses it
+        state.open_claude_session("backend");
+        state.open_claude_session("backend");
+        assert_eq!(created.lock().len(), 1);
+        assert_eq!(
+            state.claude_session_ids(),
+            vec!["backend", DEFAULT_CLAUDE_SESSION]
+        );
+
+        state.close_claude_session("backend");
+        assert!(state.claude_session("backend").is_err());
+
+        // The default session is never closed
+        state.close_claude_session(DEFAULT_CLAUDE_SESSION);
+        assert!(state.claude_session(DEFAULT_CLAUDE_SESSION).is_ok());
+    }
+
+    #[test]
+    fn test_claude_config_applies_to_all_sessions() {
+        let (state, default, created) = create_session_state();
+        state.open_claude_session("backend");
+
+        state.update_claude_config(|c| c.shutdown_grace_ms = Some(500));
+        assert_eq!(default.shutdown_grace_ms(), 500);
+        assert_eq!(created.lock()[0].shutdown_grace_ms(), 500);
+
+        // Sessions opened later inherit the configuration
+        state.open_claude_session("docs");
+        assert_eq!(created.lock()[1].shutdown_grace_ms(), 500);
+
+        assert_eq!(default.write_timeout_ms(), DEFAULT_WRITE_TIMEOUT_MS);
+        state.update_claude_config(|c| c.write_timeout_ms = Some(100));
+        assert_eq!(created.lock()[1].write_timeout_ms(), 100);
+
+        assert_eq!(default.read_buffer_size(), DEFAULT_READ_BUFFER_BYTES);
+        state.update_claude_config(|c| c.read_buffer_size = Some(16384));
+        assert_eq!(default.read_buffer_size(), 16384);
+        assert_eq!(created.lock()[1].read_buffer_size(), 16384);
+    }
+
+    #[test]
+    fn test_set_log_rotation() {
+        let (state, default, created) = create_session_state();
+        state.open_claude_session("backend");
+
+        assert_eq!(
+            set_rotation(&state, 0, 3).unwrap_err().kind,
+            "invalid_argument"
+        );
+        assert_eq!(default.log_rotation(), LogRotation::default());
+
+        set_rotation(&state, 1024, 3).unwrap();
+        let expected = LogRotation {
+            max_bytes: 1024,
+            keep: 3,
+        };
+        assert_eq!(default.log_rotation(), expected);
+        assert_eq!(created.lock()[0].log_rotation(), expected);
+    }
+
+    #[test]
+    fn test_claude_events_carry_session_id() {
+        let (state, default, created) = create_session_state();
+        let sink = Arc::new(RecordingSink::default());
+
+        let backend = state.open_claude_session("backend");
+        connect_claude_events(sink.clone(), DEFAULT_CLAUDE_SESSION, default.as_ref());
+        connect_claude_events(sink.clone(), "backend", backend.as_ref());
+
+        default.simulate_output("from default", false);
+        created.lock()[0].simulate_output("from backend", false);
+
+        let events = sink.events.lock().clone();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|(name, _)| name == "claude-output"));
+        assert_eq!(events[0].1["session_id"], DEFAULT_CLAUDE_SESSION);
+        assert_eq!(events[0].1["data"], "from default");
+        assert_eq!(events[1].1["session_id"], "backend");
+        assert_eq!(events[1].1["data"], "from backend");
+    }
+
+    #[test]
+    fn test_claude_output_survives_restart() {
+        let (state, default, _created) = create_session_state();
+        state.update_claude_config(|c| {
+            c.auto_restart = Some(true);
+            c.strip_ansi = Some(true);
+        });
+        let sink = Arc::new(RecordingSink::default());
+
+        launch_claude(
+            sink.clone(),
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions::default(),
+        )
+        .unwrap();
+        default.simulate_exit(1);
+        default.simulate_output("\x1b[32mback\x1b[0m", false);
+
+        let events = sink.events.lock().clone();
+        assert_eq!(sink.names(), vec!["claude-restarted", "claude-output"]);
+        assert_eq!(events[1].1["session_id"], DEFAULT_CLAUDE_SESSION);
+        assert_eq!(events[1].1["plain"], "back");
+    }
+
+    #[test]
+    fn test_claude_output_dropped_event() {
+        let (_state, default, _created) = create_session_state();
+        let sink = Arc::new(RecordingSink::default());
+
+        connect_claude_events(sink.clone(), DEFAULT_CLAUDE_SESSION, default.as_ref());
+        default.simulate_output_dropped(8192);
+
+        let events = sink.events.lock().clone();
+        assert_eq!(sink.names(), vec!["claude-output-dropped"]);
+        assert_eq!(events[0].1["session_id"], DEFAULT_CLAUDE_SESSION);
+        assert_eq!(events[0].1["bytes"], 8192);
+    }
+
+    #[test]
+    fn test_search_claude_output() {
+        let (state, default, _created) = create_session_state();
+        default.start(None).unwrap();
+        default.simulate_output("running tests\nFAILED: test_a\nok\nFAILED: test_b\n", false);
+
+        let matches = search_scrollback(&state, DEFAULT_CLAUDE_SESSION, "FAILED", false).unwrap();
+        let lines: Vec<_> = matches.iter().map(|m| m.line.as_str()).collect();
+        assert_eq!(lines, vec!["FAILED: test_a", "FAILED: test_b"]);
+
+        let matches = search_scrollback(&state, DEFAULT_CLAUDE_SESSION, r"test_\w$", true).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].offset, 22);
+
+        for pattern in ["", "("] {
+            let err = search_scrollback(&state, DEFAULT_CLAUDE_SESSION, pattern, true).unwrap_err();
+            assert_eq!(err.kind, "invalid_argument");
+        }
+        let err = search_scrollback(&state, "missing", "FAILED", false).unwrap_err();
+        assert_eq!(err.kind, "session_not_found");
+    }
+
+    #[test]
+    fn test_launch_claude_rejects_missing_working_dir() {
+        let (state, default, _created) = create_session_state();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let missing = temp_dir.path().join("missing");
+
+        let error = launch_claude(
+            Arc::new(RecordingSink::default()),
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions {
+                working_dir: Some(missing.to_string_lossy().to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind, "working_dir_not_found");
+        assert!(error.message.contains("does not exist"));
+        assert_eq!(default.start_count(), 0);
+    }
+
+    #[test]
+    fn test_launch_claude_in_valid_working_dir() {
+        let (state, default, _created) = create_session_state();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        launch_claude(
+            Arc::new(RecordingSink::default()),
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions {
+                working_dir: Some(temp_dir.path().to_string_lossy().to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(default.status(), ProcessStatus::Running);
+        assert_eq!(
+            default.working_dir().map(PathBuf::from),
+            Some(temp_dir.path().canonicalize().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_launch_claude_reuses_last_working_dir() {
+        let (mut state, default, _created) = create_session_state();
+        let data_dir = tempfile::tempdir().unwrap();
+        state.app_data_dir = data_dir.path().to_path_buf();
+        let project = tempfile::tempdir().unwrap();
+        let project_path = project.path().canonicalize().unwrap();
+        let sink: Arc<dyn EventSink> = Arc::new(RecordingSink::default());
+        assert_eq!(last_working_dir(&state), None);
+
+        launch_claude(
+            sink.clone(),
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions {
+                working_dir: Some(project.path().to_string_lossy().to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let saved = Settings::load(data_dir.path()).last_working_dir;
+        assert_eq!(saved.map(PathBuf::from), Some(project_path.clone()));
+        default.stop().unwrap();
+
+        launch_claude(
+            sink.clone(),
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(default.working_dir().map(PathBuf::from), Some(project_path));
+        default.stop().unwrap();
+
+        // A directory that has since been removed is not reused
+        drop(project);
+        assert_eq!(last_working_dir(&state), None);
+        launch_claude(
+            sink,
+            &state,
+            DEFAULT_CLAUDE_SESSION,
+            ClaudeStartOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(default.working_dir(), None);
+    }
+
+    #[test]
+    fn test_dictation_targets_default_session() {
+        let (state, default, created) = create_session_state();
+        let backend = state.open_claude_session("backend");
+        default.start(None).unwrap();
+        backend.start(None).unwrap();
+        *state.auto_send.lock() = Some("\r".to_string());
+
+        send_final_to_claude(
+            state.default_claude().as_ref(),
+            &state.auto_send,
+            &final_result("run tests"),
+        );
+
+        assert_eq!(default.input_history(), vec!["run tests\r"]);
+        assert!(created.lock()[0].input_history().is_empty());
+    }
+
+    #[test]
+    fn test_send_line_submits_exactly_once() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        send_line(&state, DEFAULT_CLAUDE_SESSION, "list files").unwrap();
+        send_line(&state, DEFAULT_CLAUDE_SESSION, "  list files \r").unwrap();
+        send_line(&state, DEFAULT_CLAUDE_SESSION, "list files\n").unwrap();
+
+        state.settings.lock().submit_sequence = Some("\r\n".to_string());
+        send_line(&state, DEFAULT_CLAUDE_SESSION, "run tests\r\n").unwrap();
+
+        state.settings.lock().submit_sequence = Some("\n".to_string());
+        send_line(&state, DEFAULT_CLAUDE_SESSION, "").unwrap();
+
+        assert_eq!(
+            claude.input_history(),
+            vec![
+                "list files\r",
+                "list files\r",
+                "list files\r",
+                "run tests\r\n",
+                "\n"
+            ]
+        );
+        assert!(send_line(&state, "missing", "hello").is_err());
+    }
+
+    #[test]
+    fn test_send_key() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        send_key(&state, DEFAULT_CLAUDE_SESSION, "up").unwrap();
+        send_key(&state, DEFAULT_CLAUDE_SESSION, "ctrl-c").unwrap();
+        send_key(&state, DEFAULT_CLAUDE_SESSION, "Esc").unwrap();
+        assert_eq!(claude.input_history(), vec!["\x1b[A", "\x03", "\x1b"]);
+
+        let err = send_key(&state, DEFAULT_CLAUDE_SESSION, "hyper").unwrap_err();
+        assert_eq!(err.kind, "unknown_key");
+        assert_eq!(claude.input_history().len(), 3);
+        assert!(send_key(&state, "missing", "up").is_err());
+    }
+
+    #[test]
+    fn test_interrupt_claude() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        interrupt(&state, DEFAULT_CLAUDE_SESSION).unwrap();
+        assert_eq!(claude.input_history(), vec!["\x03"]);
+        assert_eq!(claude.status(), ProcessStatus::Running);
+
+        claude.stop().unwrap();
+        let err = interrupt(&state, DEFAULT_CLAUDE_SESSION).unwrap_err();
+        assert_eq!(err.kind, "not_running");
+        assert_eq!(claude.input_history().len(), 1);
+    }
+
+    #[test]
+    fn test_paste_text() {
+        let (state, _audio, claude) = create_dictation_state();
+
+        assert!(paste_text(&state, DEFAULT_CLAUDE_SESSION, "fn main() {}\n").unwrap());
+        assert_eq!(
+            claude.input_history(),
+            vec!["\x1b[200~fn main() {}\n\x1b[201~"]
+        );
+
+        // Nothing is sent for an empty clipboard
+        assert!(!paste_text(&state, DEFAULT_CLAUDE_SESSION, "").unwrap());
+        assert_eq!(claude.input_history().len(), 1);
+        assert!(paste_text(&state, "missing", "text").is_err());
+    }
+
+    #[test]
+    fn test_claude_heartbeat() {
+        let (state, default, _created) = create_session_state();
+        let sink = RecordingSink::default();
+
+        // Sessions that aren't running are skipped
+        emit_claude_heartbeats(&sink, &state);
+        assert!(sink.events.lock().is_empty());
+
+        default.start(None).unwrap();
+        default.set_started_ago(Duration::from_secs(42));
+        state.open_claude_session("backend");
+
+        emit_claude_heartbeats(&sink, &state);
+        {
+            let events = sink.events.lock();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].0, "claude-heartbeat");
+            assert_eq!(
+                events[0].1,
+                json!({ "session_id": DEFAULT_CLAUDE_SESSION, "alive": true, "uptime_secs": 42 })
+            );
+        }
+    }
+
+    #[test]
+    fn test_claude_heartbeat_detects_exit() {
+        let (state, default, _created) = create_session_state();
+        let sink = RecordingSink::default();
+        default.start(None).unwrap();
+
+        default.simulate_silent_exit(1);
+        emit_claude_heartbeats(&sink, &state);
+
+        assert_eq!(default.status(), ProcessStatus::Exited(1));
+        assert_eq!(sink.events.lock()[0].1["alive"], false);
+
+        // An exited session is reported only once
+        emit_claude_heartbeats(&sink, &state);
+        assert_eq!(count_events(&sink, "claude-heartbeat"), 1);
     }
 }
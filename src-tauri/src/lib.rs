@@ -4,13 +4,22 @@
 //! to Claude Code using local speech recognition (Vosk).
 
 pub mod audio;
+pub mod audio_file;
 pub mod claude;
 pub mod commands;
+pub mod scripted_stt;
+pub mod settings;
+pub mod text_process;
+pub mod transcript;
+pub mod voice_commands;
 pub mod vosk_stt;
+pub mod wake_word;
+#[cfg(feature = "whisper")]
+pub mod whisper_stt;
 
 use commands::AppState;
 use std::path::PathBuf;
-use tauri::Manager;
+use tauri::{Manager, WindowEvent};
 
 /// Get the app data directory for storing models and settings
 fn get_app_data_dir(app: &tauri::App) -> PathBuf {
@@ -34,26 +43,152 @@ pub fn run() {
 
             // Initialize app state
             let state = AppState::new(app_data_dir);
+            let ptt_shortcut = state.settings.lock().ptt_shortcut.clone();
             app.manage(state);
 
+            commands::spawn_claude_heartbeat(app.handle().clone());
+            commands::spawn_recording_watchdog(app.handle().clone());
+
+            // Load the preferred model off the main thread; large models take seconds
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                handle.state::<AppState>().load_default_model(&handle);
+            });
+
+            // Log the Claude version and check the setup for diagnostics; the
+            // CLI can take a moment to answer
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                let state = handle.state::<AppState>();
+                match state.claude_version() {
+                    Ok(version) => eprintln!("Claude Code version: {}", version),
+                    Err(e) => eprintln!("Could not detect Claude Code version: {}", e),
+                }
+                commands::emit_startup_diagnostics(&handle, &state);
+            });
+
+            // Restore the saved push-to-talk hotkey
+            if let Some(accelerator) = ptt_shortcut {
+                if let Err(e) = commands::register_ptt(app.handle(), &accelerator) {
+                    eprintln!("Failed to register push-to-talk shortcut: {}", e);
+                }
+            }
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Don't leave PTY children and audio threads running after the window closes
+            if let WindowEvent::CloseRequested { .. } = event {
+                window.state::<AppState>().shutdown();
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::list_audio_devices,
+            commands::list_audio_hosts,
+            commands::set_audio_host,
             commands::start_recording,
+            commands::check_microphone_permission,
+            commands::request_microphone_permission,
             commands::stop_recording,
+            commands::feed_pcm,
             commands::is_recording,
+            commands::recording_elapsed_secs,
+            commands::set_max_recording_secs,
+            commands::set_preferred_device,
+            commands::set_device_fallback,
+            commands::list_device_configs,
+            commands::get_active_device,
+            commands::get_audio_level,
+            commands::set_input_gain,
+            commands::set_agc,
+            commands::set_noise_gate,
+            commands::set_buffer_frames,
+            commands::set_chunk_frames,
+            commands::set_min_confidence,
+            commands::set_keep_unknown_words,
+            commands::record_to_file,
+            commands::register_ptt_shortcut,
+            commands::unregister_ptt_shortcut,
             commands::list_models,
             commands::list_installed_models,
+            commands::find_duplicate_models,
+            commands::add_model_dir,
+            commands::remove_model_dir,
+            commands::download_model,
+            commands::resume_download,
+            commands::cancel_download,
+            commands::check_space_for_model,
+            commands::estimate_model_memory,
+            commands::verify_model,
             commands::load_model,
+            commands::switch_model,
+            commands::load_language_model,
+            commands::set_active_language,
+            commands::list_loaded_languages,
+            commands::load_default_model,
+            commands::get_loaded_model,
+            commands::unload_model,
+            commands::vosk_available,
             commands::is_model_loaded,
+            commands::recognizer_debug_info,
+            commands::get_recognizer_sample_rate,
+            commands::set_recognizer_sample_rate,
+            commands::session_stats,
+            commands::transcribe_file,
+            commands::get_current_transcript,
+            commands::export_transcript,
+            commands::benchmark_model,
+            commands::self_test,
+            commands::run_diagnostics,
+            commands::use_demo_recognizer,
+            commands::set_backend,
             commands::reset_recognizer,
             commands::start_claude,
+            commands::get_last_working_dir,
             commands::stop_claude,
             commands::send_to_claude,
+            commands::send_line_to_claude,
+            commands::send_key_to_claude,
+            commands::claude_resource_usage,
+            commands::interrupt_claude,
+            commands::paste_to_claude,
             commands::resize_claude,
+            commands::list_claude_sessions,
+            commands::set_claude_path,
+            commands::claude_version,
+            commands::set_submit_sequence,
+            commands::set_auto_send,
+            commands::set_live_type,
+            commands::set_auto_punctuation,
+            commands::set_auto_recover,
+            commands::set_muted,
+            commands::set_partial_timeout,
+            commands::set_partial_emit_interval_ms,
+            commands::set_duplicate_suppression,
+            commands::set_spoken_commands,
+            commands::set_symbol_dictionary,
+            commands::set_code_dictation,
+            commands::load_symbol_dictionary,
+            commands::set_voice_commands,
+            commands::set_wake_word,
+            commands::set_claude_auto_restart,
+            commands::set_shutdown_grace_ms,
+            commands::set_claude_write_timeout_ms,
+            commands::set_claude_read_buffer_size,
+            commands::set_strip_ansi,
+            commands::set_claude_log,
+            commands::set_log_rotation,
+            commands::claude_scrollback,
+            commands::search_claude_output,
+            commands::set_claude_scrollback_limit,
             commands::claude_status,
+            commands::get_settings,
+            commands::update_settings,
+            commands::shutdown,
             commands::get_app_info,
+            commands::open_models_dir,
+            commands::open_logs_dir,
+            commands::open_settings_file,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,312 @@
+//! Scripted speech recognizer for demos and UI development.
+//!
+//! Replays canned transcriptions from a script file instead of running a
+//! model. Each script line gives the seconds of audio to wait after the
+//! previous line, followed by the text:
+//!
+//! ```text
+//! # Lines starting with '#' are ignored
+//! 1.5 list the files in this directory
+//! 3 now open the readme
+//! ```
+//!
+//! While a line is pending its words are revealed as partial results, and it
+//! becomes a final result once its delay has elapsed. The script loops when it
+//! reaches the end.
+
+use crate::vosk_stt::{
+    RecognitionResult, RecognizerDebug, RecognizerStats, SpeechError, SpeechRecognizer,
+};
+use parking_lot::Mutex;
+use std::path::Path;
+
+/// Rate the scripted timings are measured at
+const SCRIPT_SAMPLE_RATE: u32 = 16000;
+
+/// One canned transcription and when it is finalized
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptLine {
+    /// Milliseconds of audio after the previous line before this one is final
+    pub delay_ms: u64,
+    pub text: String,
+}
+
+/// Parse `<delay seconds> <text>` lines, skipping blanks and `#` comments
+pub fn parse_script(contents: &str) -> Result<Vec<ScriptLine>, SpeechError> {
+    let mut lines = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid =
+            |reason: &str| SpeechError::InvalidScript(format!("line {}: {}", number + 1, reason));
+        let (delay, text) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid("expected a delay followed by text"))?;
+        let seconds: f64 = delay
+            .parse()
+            .ok()
+            .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+            .ok_or_else(|| invalid("delay must be a non-negative number of seconds"))?;
+
+        lines.push(ScriptLine {
+            delay_ms: (seconds * 1000.0).round() as u64,
+            text: text.trim().to_string(),
+        });
+    }
+
+    if lines.is_empty() {
+        return Err(SpeechError::InvalidScript(
+            "script has no lines".to_string(),
+        ));
+    }
+    Ok(lines)
+}
+
+/// Position in the script
+#[derive(Debug, Default)]
+struct Progress {
+    /// Index of the pending line
+    line: usize,
+    /// Samples received since the previous line was finalized
+    samples: u64,
+    /// Words of the pending line already shown as a partial
+    revealed: usize,
+}
+
+/// Recognizer that replays a script rather than listening
+pub struct ScriptedRecognizer {
+    lines: Vec<ScriptLine>,
+    progress: Mutex<Progress>,
+    stats: RecognizerStats,
+}
+
+impl ScriptedRecognizer {
+    pub fn new(lines: Vec<ScriptLine>) -> Result<Self, SpeechError> {
+        if lines.is_empty() {
+            return Err(SpeechError::InvalidScript(
+                "script has no lines".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            lines,
+            progress: Mutex::new(Progress::default()),
+            stats: RecognizerStats::default(),
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, SpeechError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            SpeechError::InvalidScript(format!("{}: {}", path.to_string_lossy(), e))
+        })?;
+        Self::new(parse_script(&contents)?)
+    }
+
+    /// Finalize the pending line and move on, looping at the end of the script
+    fn finalize(&self, progress: &mut Progress) -> RecognitionResult {
+        let text = self.lines[progress.line].text.clone();
+        *progress = Progress {
+            line: (progress.line + 1) % self.lines.len(),
+            ..Progress::default()
+        };
+        result(text, true)
+    }
+}
+
+fn result(text: String, is_final: bool) -> RecognitionResult {
+    RecognitionResult {
+        text,
+        is_final,
+        confidence: Some(1.0),
+        endpoint: is_final,
+        offset_ms: 0,
+        words: Vec::new(),
+    }
+}
+
+impl SpeechRecognizer for ScriptedRecognizer {
+    fn load_model(&self, _model_path: &Path) -> Result<(), SpeechError> {
+        Ok(())
+    }
+
+    fn is_model_loaded(&self) -> bool {
+        true
+    }
+
+    fn process_audio(&self, samples: &[i16]) -> Result<Option<RecognitionResult>, SpeechError> {
+        let mut progress = self.progress.lock();
+        progress.samples += samples.len() as u64;
+        self.stats.record_samples(samples.len());
+
+        let line = &self.lines[progress.line];
+        let elapsed_ms = progress.samples * 1000 / SCRIPT_SAMPLE_RATE as u64;
+        if elapsed_ms >= line.delay_ms {
+            let result = self.finalize(&mut progress);
+            self.stats.record_result(&result);
+            return Ok(Some(result));
+        }
+
+        // Reveal words in proportion to how much of the delay has passed
+        let words: Vec<&str> = line.text.split_whitespace().collect();
+        let revealed = (words.len() as u64 * elapsed_ms / line.delay_ms) as usize;
+        if revealed == 0 || revealed == progress.revealed {
+            return Ok(None);
+        }
+        progress.revealed = revealed;
+
+        let partial = result(words[..revealed].join(" "), false);
+        self.stats.record_result(&partial);
+        Ok(Some(partial))
+    }
+
+    fn get_final_result(&self) -> Result<RecognitionResult, SpeechError> {
+        let mut progress = self.progress.lock();
+
+        // A line that had started to appear is completed when recording stops
+        if progress.revealed > 0 {
+            let result = self.finalize(&mut progress);
+            self.stats.record_result(&result);
+            return Ok(result);
+        }
+        Ok(result(String::new(), true))
+    }
+
+    fn reset(&self) {
+        *self.progress.lock() = Progress::default();
+    }
+
+    fn unload_model(&self) {}
+
+    fn sample_rate(&self) -> u32 {
+        SCRIPT_SAMPLE_RATE
+    }
+
+    fn set_sample_rate(&self, sample_rate: f32) -> Result<(), SpeechError> {
+        if sample_rate as u32 == SCRIPT_SAMPLE_RATE {
+            return Ok(());
+        }
+        Err(SpeechError::RecognizerError(format!(
+            "Scripted recognizer only accepts {} Hz audio",
+            SCRIPT_SAMPLE_RATE
+        )))
+    }
+
+    fn model_sample_rate(&self) -> Option<u32> {
+        None
+    }
+
+    fn debug_info(&self) -> RecognizerDebug {
+        self.stats.snapshot(true, SCRIPT_SAMPLE_RATE)
+    }
+
+    fn set_keep_unknown_words(&self, _keep: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `ms` milliseconds of silence at the script rate
+    fn audio(ms: u64) -> Vec<i16> {
+        vec![0; (ms * SCRIPT_SAMPLE_RATE as u64 / 1000) as usize]
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let lines = parse_script("# demo\n\n1.5 hello there\n  2   how are you  \n").unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                ScriptLine {
+                    delay_ms: 1500,
+                    text: "hello there".to_string()
+                },
+                ScriptLine {
+                    delay_ms: 2000,
+                    text: "how are you".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_script_errors() {
+        assert!(matches!(
+            parse_script("soon hello"),
+            Err(SpeechError::InvalidScript(msg)) if msg.starts_with("line 1")
+        ));
+        assert!(matches!(
+            parse_script("# only comments\n"),
+            Err(SpeechError::InvalidScript(_))
+        ));
+        assert!(parse_script("1.0").is_err());
+        assert!(parse_script("-1 hello").is_err());
+    }
+
+    #[test]
+    fn test_emits_lines_in_order() {
+        let recognizer =
+            ScriptedRecognizer::new(parse_script("1 first line\n0.5 second line").unwrap())
+                .unwrap();
+
+        let mut finals = Vec::new();
+        for _ in 0..15 {
+            if let Some(result) = recognizer.process_audio(&audio(100)).unwrap() {
+                if result.is_final {
+                    finals.push(result.text);
+                }
+            }
+        }
+
+        // 1.5s of audio covers both lines, then the script starts over
+        assert_eq!(finals, vec!["first line", "second line"]);
+        recognizer.process_audio(&audio(1000)).unwrap();
+        assert_eq!(recognizer.progress.lock().line, 1);
+    }
+
+    #[test]
+    fn test_reveals_partials_before_final() {
+        let recognizer =
+            ScriptedRecognizer::new(parse_script("1 one two three four").unwrap()).unwrap();
+
+        let results: Vec<RecognitionResult> = (0..10)
+            .filter_map(|_| recognizer.process_audio(&audio(100)).unwrap())
+            .collect();
+        let texts: Vec<(&str, bool)> = results
+            .iter()
+            .map(|r| (r.text.as_str(), r.is_final))
+            .collect();
+
+        assert_eq!(
+            texts,
+            vec![
+                ("one", false),
+                ("one two", false),
+                ("one two three", false),
+                ("one two three four", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_final_result_completes_pending_line() {
+        let recognizer =
+            ScriptedRecognizer::new(parse_script("1 hello world\n1 next").unwrap()).unwrap();
+
+        assert_eq!(recognizer.get_final_result().unwrap().text, "");
+
+        recognizer.process_audio(&audio(600)).unwrap();
+        assert_eq!(recognizer.get_final_result().unwrap().text, "hello world");
+
+        recognizer.process_audio(&audio(600)).unwrap();
+        recognizer.reset();
+        assert_eq!(recognizer.get_final_result().unwrap().text, "");
+        recognizer.process_audio(&audio(1000)).unwrap();
+        assert_eq!(recognizer.progress.lock().line, 1);
+    }
+}
@@ -0,0 +1,190 @@
+//! Settings persistence module.
+//!
+//! Stores user preferences as `settings.json` in the app data directory
+//! so they survive restarts.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+/// Name of the settings file inside the app data directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Settings errors
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Failed to write settings: {0}")]
+    WriteError(String),
+    #[error("Failed to serialize settings: {0}")]
+    SerializeError(String),
+}
+
+impl SettingsError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SettingsError::WriteError(_) => "settings_write_error",
+            SettingsError::SerializeError(_) => "settings_serialize_error",
+        }
+    }
+}
+
+/// Persisted user preferences
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Input device used when recording starts without an explicit device,
+    /// as long as it is still connected
+    pub selected_device: Option<String>,
+    /// Model loaded automatically on startup
+    pub model_path: Option<String>,
+    /// Explicit Claude Code binary, bypassing the PATH search
+    pub claude_path: Option<String>,
+    /// Global push-to-talk accelerator, e.g. "CmdOrCtrl+Shift+Space"
+    pub ptt_shortcut: Option<String>,
+    /// Software gain applied to the microphone before recognition (None is unity)
+    pub input_gain: Option<f32>,
+    /// Automatic gain control for quiet microphones
+    pub agc: bool,
+    /// Noise gate RMS threshold (0.0–1.0); 0 disables the gate
+    pub noise_gate_threshold: f32,
+    /// How long the noise gate stays open after the signal drops
+    pub noise_gate_release_ms: u32,
+    /// Fixed capture buffer size in frames; smaller lowers latency but costs CPU
+    pub buffer_frames: Option<u32>,
+    /// Sequence that submits a line to Claude ("\r", "\n" or "\r\n"); None is "\r"
+    pub submit_sequence: Option<String>,
+    /// Results with a lower average word confidence (0.0–1.0) are dropped; 0 disables
+    pub min_confidence: f32,
+    /// Apply `min_confidence` to partial results too
+    pub filter_partials: bool,
+    /// Keep Vosk's `[unk]` token for unrecognized words in final results
+    pub keep_unknown_words: bool,
+    /// Extra folders scanned for models, added with `add_model_dir`
+    pub model_dirs: Vec<String>,
+    /// Milliseconds a partial result may go unchanged before it is reported stale; 0 disables
+    pub partial_timeout_ms: u32,
+    /// Size of the chunks fed to the recognizer in samples (None is 3200, 0 disables chunking)
+    pub chunk_frames: Option<usize>,
+    /// Audio time in which a final repeating the previous one is dropped (None is 1500ms, 0 disables)
+    pub duplicate_window_ms: Option<u32>,
+    /// Character edits up to which consecutive finals count as duplicates
+    pub duplicate_max_distance: usize,
+    /// Audio backend to capture through, e.g. "JACK" (None is the platform default)
+    pub audio_host: Option<String>,
+    /// Minimum milliseconds between partial results sent to the UI; 0 sends every partial
+    pub partial_emit_interval_ms: u32,
+    /// Directory Claude was last started in, reused when no directory is given
+    pub last_working_dir: Option<String>,
+    /// Seconds after which recording stops on its own; 0 records without limit
+    pub max_recording_secs: u32,
+}
+
+impl Settings {
+    /// Load settings from the app data directory.
+    /// Falls back to defaults if the file is missing or corrupt.
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(SETTINGS_FILE);
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => return Self::default(),
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring corrupt settings file {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    /// Save settings to the app data directory
+    pub fn save(&self, app_data_dir: &Path) -> Result<(), SettingsError> {
+        std::fs::create_dir_all(app_data_dir)
+            .map_err(|e| SettingsError::WriteError(e.to_string()))?;
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SettingsError::SerializeError(e.to_string()))?;
+
+        // Write to a temp file first so a crash mid-write can't corrupt existing settings
+        let path = app_data_dir.join(SETTINGS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json).map_err(|e| SettingsError::WriteError(e.to_string()))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| SettingsError::WriteError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            selected_device: Some("USB Microphone".to_string()),
+            model_path: Some("/models/vosk-model-en-us-0.22".to_string()),
+            claude_path: Some("/opt/claude/bin/claude".to_string()),
+            ptt_shortcut: Some("CmdOrCtrl+Shift+Space".to_string()),
+            input_gain: Some(2.5),
+            agc: true,
+            noise_gate_threshold: 0.02,
+            noise_gate_release_ms: 300,
+            buffer_frames: Some(256),
+            submit_sequence: Some("\r\n".to_string()),
+            min_confidence: 0.6,
+            filter_partials: true,
+            keep_unknown_words: true,
+            model_dirs: vec!["/mnt/share/models".to_string()],
+            partial_timeout_ms: 1500,
+            chunk_frames: Some(1600),
+            duplicate_window_ms: Some(2000),
+            duplicate_max_distance: 2,
+            audio_host: Some("JACK".to_string()),
+            partial_emit_interval_ms: 250,
+            last_working_dir: Some("/home/user/project".to_string()),
+            max_recording_secs: 600,
+        };
+
+        settings.save(temp_dir.path()).unwrap();
+        let loaded = Settings::load(temp_dir.path());
+
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(Settings::load(temp_dir.path()), Settings::default());
+    }
+
+    #[test]
+    fn test_corrupt_file_uses_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join(SETTINGS_FILE), "{ not json").unwrap();
+
+        assert_eq!(Settings::load(temp_dir.path()), Settings::default());
+    }
+
+    #[test]
+    fn test_partial_file_fills_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join(SETTINGS_FILE),
+            r#"{"selected_device": "Built-in Mic", "unknown_field": 1}"#,
+        )
+        .unwrap();
+
+        let loaded = Settings::load(temp_dir.path());
+        assert_eq!(loaded.selected_device.as_deref(), Some("Built-in Mic"));
+        assert_eq!(loaded.model_path, None);
+    }
+
+    #[test]
+    fn test_save_creates_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("nested").join("dir");
+
+        Settings::default().save(&nested).unwrap();
+        assert!(nested.join(SETTINGS_FILE).exists());
+    }
+}
@@ -0,0 +1,486 @@
+//! Transcription post-processing module.
+//!
+//! Small Vosk models output lowercase, unpunctuated text. This module turns
+//! spoken punctuation commands ("period", "new line", ...) into symbols and
+//! capitalizes sentence starts so the text reads well when sent to Claude.
+//! A second, configurable dictionary maps spoken programming symbols
+//! ("double colon", "arrow", ...) to code for dictating source. It starts
+//! empty, since words like "dot" and "plus" are just as common in prose.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// Symbol dictionary loading errors
+#[derive(Error, Debug)]
+pub enum SymbolDictionaryError {
+    #[error("Failed to read symbol dictionary: {0}")]
+    ReadError(#[from] std::io::Error),
+    #[error("Invalid symbol dictionary: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+impl SymbolDictionaryError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SymbolDictionaryError::ReadError(_) => "symbol_dictionary_read_error",
+            SymbolDictionaryError::ParseError(_) => "symbol_dictionary_parse_error",
+        }
+    }
+}
+
+/// Default spoken punctuation commands and the text they produce
+pub const DEFAULT_SPOKEN_COMMANDS: &[(&str, &str)] = &[
+    ("period", "."),
+    ("full stop", "."),
+    ("comma", ","),
+    ("question mark", "?"),
+    ("exclamation mark", "!"),
+    ("exclamation point", "!"),
+    ("colon", ":"),
+    ("semicolon", ";"),
+    ("new line", "\n"),
+    ("new paragraph", "\n\n"),
+];
+
+/// Spoken programming symbols for code dictation, tuned for Rust. Unlike punctuation,
+/// symbols keep exactly the spacing they are written with, so " -> " stands
+/// apart from its neighbours while "::" joins them.
+pub const DEFAULT_SYMBOLS: &[(&str, &str)] = &[
+    ("open paren", "("),
+    ("close paren", ")"),
+    ("open bracket", "["),
+    ("close bracket", "]"),
+    ("open brace", " {"),
+    ("close brace", "}"),
+    ("open angle", "<"),
+    ("close angle", ">"),
+    ("double colon", "::"),
+    ("arrow", " -> "),
+    ("fat arrow", " => "),
+    ("dot", "."),
+    ("dot dot", ".."),
+    ("underscore", "_"),
+    ("ampersand", "&"),
+    ("double ampersand", " && "),
+    ("pipe", " | "),
+    ("double pipe", " || "),
+    ("bang", "!"),
+    ("hash", "#"),
+    ("equals", " = "),
+    ("double equals", " == "),
+    ("not equals", " != "),
+    ("less than", " < "),
+    ("greater than", " > "),
+    ("plus", " + "),
+    ("minus", " - "),
+    ("plus equals", " += "),
+    ("single quote", "'"),
+    ("double quote", "\""),
+    ("backtick", "`"),
+];
+
+/// Read a symbol dictionary from a JSON object of `{"spoken phrase": "symbol"}`
+pub fn load_symbols(path: &Path) -> Result<Vec<(String, String)>, SymbolDictionaryError> {
+    let contents = std::fs::read_to_string(path)?;
+    let symbols: BTreeMap<String, String> = serde_json::from_str(&contents)?;
+    Ok(symbols.into_iter().collect())
+}
+
+/// Applies spoken punctuation commands, programming symbols and
+/// capitalization to transcribed text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextProcessor {
+    /// (spoken phrase split into lowercase words, replacement), longest phrase first
+    commands: Vec<(Vec<String>, String)>,
+    /// Programming symbols, in the same form as `commands`
+    symbols: Vec<(Vec<String>, String)>,
+}
+
+enum Token {
+    Word(String),
+    Symbol(String),
+    Code(String),
+}
+
+/// Default spoken punctuation commands and no symbols
+impl Default for TextProcessor {
+    fn default() -> Self {
+        Self::new(to_owned_pairs(DEFAULT_SPOKEN_COMMANDS))
+    }
+}
+
+impl TextProcessor {
+    /// Create a processor with a custom spoken command map and no symbols
+    pub fn new(commands: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            commands: phrase_table(commands),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Use `symbols` as the programming symbol dictionary
+    pub fn with_symbols(mut self, symbols: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.set_symbols(symbols);
+        self
+    }
+
+    /// Replace the spoken command map, keeping the symbol dictionary
+    pub fn set_commands(&mut self, commands: impl IntoIterator<Item = (String, String)>) {
+        self.commands = phrase_table(commands);
+    }
+
+    /// Replace the symbol dictionary, keeping the spoken command map
+    pub fn set_symbols(&mut self, symbols: impl IntoIterator<Item = (String, String)>) {
+        self.symbols = phrase_table(symbols);
+    }
+
+    /// Capitalize sentence starts, replace spoken punctuation and collapse whitespace
+    pub fn polish(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut capitalize_next = true;
+        // The previous token was a code symbol, whose own spacing stands
+        let mut glue_next = false;
+
+        for token in self.tokenize(text) {
+            match token {
+                Token::Word(word) => {
+                    if !output.is_empty() && !output.ends_with('\n') && !glue_next {
+                        output.push(' ');
+                    }
+
+                    // "i" and contractions like "i'm" are always capitalized
+                    if capitalize_next || word == "i" || word.starts_with("i'") {
+                        output.push_str(&capitalize(&word));
+                    } else {
+                        output.push_str(&word);
+                    }
+                    capitalize_next = false;
+                    glue_next = false;
+                }
+                Token::Symbol(symbol) => {
+                    // Punctuation attaches to the preceding word
+                    while output.ends_with(' ') {
+                        output.pop();
+                    }
+                    output.push_str(&symbol);
+
+                    if symbol.ends_with(['.', '?', '!', '\n']) {
+                        capitalize_next = true;
+                    }
+                    glue_next = false;
+                }
+                Token::Code(symbol) => {
+                    // Don't double up spacing left by a previous symbol
+                    if symbol.starts_with(' ') {
+                        while output.ends_with(' ') {
+                            output.pop();
+                        }
+                    }
+                    output.push_str(&symbol);
+                    capitalize_next = false;
+                    glue_next = true;
+                }
+            }
+        }
+
+        let trimmed = output.trim_end_matches(' ').len();
+        output.truncate(trimmed);
+        output
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut tokens = Vec::with_capacity(words.len());
+        let mut i = 0;
+
+        while i < words.len() {
+            let rest = &words[i..];
+            let command = match_phrase(&self.commands, rest);
+            // Longest phrase wins ("double colon" over "colon"); symbols win ties
+            let (len, token) = match (match_phrase(&self.symbols, rest), command) {
+                (Some((len, symbol)), command)
+                    if command.is_none_or(|(command_len, _)| len >= command_len) =>
+                {
+                    (len, Token::Code(symbol.to_string()))
+                }
+                (_, Some((len, command))) => (len, Token::Symbol(command.to_string())),
+                _ => (1, Token::Word(words[i].to_string())),
+            };
+
+            tokens.push(token);
+            i += len;
+        }
+
+        tokens
+    }
+}
+
+pub fn to_owned_pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(phrase, symbol)| (phrase.to_string(), symbol.to_string()))
+        .collect()
+}
+
+/// Split phrases into lowercase words, longest phrase first
+fn phrase_table(entries: impl IntoIterator<Item = (String, String)>) -> Vec<(Vec<String>, String)> {
+    let mut table: Vec<(Vec<String>, String)> = entries
+        .into_iter()
+        .map(|(phrase, symbol)| {
+            let words: Vec<String> = phrase
+                .split_whitespace()
+                .map(|w| w.to_lowercase())
+                .collect();
+            (words, symbol)
+        })
+        .filter(|(words, _)| !words.is_empty())
+        .collect();
+
+    // Prefer "new paragraph" over a hypothetical "new" command
+    table.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+    table
+}
+
+/// The first (so longest) phrase in `table` that `words` starts with, as its
+/// length in words and replacement
+fn match_phrase<'a>(
+    table: &'a [(Vec<String>, String)],
+    words: &[&str],
+) -> Option<(usize, &'a str)> {
+    table
+        .iter()
+        .find(|(phrase, _)| {
+            phrase.len() <= words.len()
+                && words
+                    .iter()
+                    .zip(phrase)
+                    .all(|(word, expected)| word.to_lowercase() == *expected)
+        })
+        .map(|(phrase, symbol)| (phrase.len(), symbol.as_str()))
+}
+
+/// Polish text with the default spoken command map
+pub fn polish(text: &str) -> String {
+    TextProcessor::default().polish(text)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polish_table() {
+        let cases = [
+            ("", ""),
+            ("hello", "Hello"),
+            (
+                "open the file and run the tests",
+                "Open the file and run the tests",
+            ),
+            ("open the file period", "Open the file."),
+            (
+                "open the file period run the tests period",
+                "Open the file. Run the tests.",
+            ),
+            ("first comma second comma third", "First, second, third"),
+            ("is it working question mark", "Is it working?"),
+            ("stop exclamation mark", "Stop!"),
+            ("stop exclamation point", "Stop!"),
+            ("note colon fix this", "Note: fix this"),
+            ("one semicolon two", "One; two"),
+            ("first line new line second line", "First line\nSecond line"),
+            ("intro new paragraph body", "Intro\n\nBody"),
+            ("done period new line next", "Done.\nNext"),
+            ("  too   many    spaces  ", "Too many spaces"),
+            ("period", "."),
+            ("what period", "What."),
+            ("Period", "."),
+            ("i think i'm done", "I think I'm done"),
+            ("the iPhone is here", "The iPhone is here"),
+            ("hello world question mark yes period", "Hello world? Yes."),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(polish(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_default_leaves_prose_alone() {
+        // Symbol words in ordinary sentences aren't code unless code dictation is on
+        let cases = [
+            (
+                "put a dot at the end plus one more pipe",
+                "Put a dot at the end plus one more pipe",
+            ),
+            (
+                "the arrow shows it is less than ideal",
+                "The arrow shows it is less than ideal",
+            ),
+            (
+                "check the hash equals the one we sent",
+                "Check the hash equals the one we sent",
+            ),
+            (
+                "underscore the open bracket issue period",
+                "Underscore the open bracket issue.",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(polish(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_custom_commands() {
+        let processor = TextProcessor::new([
+            ("dash".to_string(), " -".to_string()),
+            ("open paren".to_string(), " (".to_string()),
+            ("stop".to_string(), ".".to_string()),
+        ]);
+
+        assert_eq!(processor.polish("a dash b stop c"), "A - b. C");
+        // Default commands are not applied by a custom processor
+        assert_eq!(processor.polish("a period"), "A period");
+    }
+
+    #[test]
+    fn test_longest_phrase_wins() {
+        let processor = TextProcessor::new([
+            ("new".to_string(), "*".to_string()),
+            ("new line".to_string(), "\n".to_string()),
+        ]);
+
+        assert_eq!(processor.polish("a new line b new c"), "A\nB* c");
+    }
+
+    #[test]
+    fn test_empty_phrase_ignored() {
+        let processor = TextProcessor::new([("  ".to_string(), "!".to_string())]);
+        assert_eq!(processor.polish("hello world"), "Hello world");
+    }
+
+    #[test]
+    fn test_rust_symbols_table() {
+        let cases = [
+            ("use std double colon io semicolon", "Use std::io;"),
+            (
+                "use serde double colon de double colon error semicolon",
+                "Use serde::de::error;",
+            ),
+            ("fn main open paren close paren open brace", "Fn main() {"),
+            (
+                "fn len open paren ampersand self close paren arrow usize",
+                "Fn len(&self) -> usize",
+            ),
+            (
+                "some open paren x close paren fat arrow x comma",
+                "Some(x) => x,",
+            ),
+            ("let x equals five semicolon", "Let x = five;"),
+            (
+                "let mut n equals self dot len open paren close paren",
+                "Let mut n = self.len()",
+            ),
+            ("x colon u32", "X: u32"),
+            ("v open bracket n close bracket", "V[n]"),
+            ("vec open angle string close angle", "Vec<string>"),
+            ("zero dot dot ten", "Zero..ten"),
+            ("my underscore var", "My_var"),
+            (
+                "hash open bracket derive open paren debug close paren close bracket",
+                "#[derive(debug)]",
+            ),
+            ("println bang open paren", "Println!("),
+            ("a double equals b", "A == b"),
+            ("a not equals b", "A != b"),
+            ("a double ampersand b double pipe c", "A && b || c"),
+            ("a pipe b", "A | b"),
+            ("a less than b", "A < b"),
+            ("a greater than b", "A > b"),
+            ("a plus b minus c", "A + b - c"),
+            ("total plus equals one", "Total += one"),
+            ("ampersand single quote a str", "&'a str"),
+            ("double quote hi double quote", "\"hi\""),
+            ("backtick foo backtick", "`foo`"),
+            (
+                "read open paren close paren question mark semicolon",
+                "Read()?;",
+            ),
+            ("close brace new line fn", "}\nFn"),
+            ("x equals", "X ="),
+            ("Double Colon", "::"),
+        ];
+
+        let processor = TextProcessor::default().with_symbols(to_owned_pairs(DEFAULT_SYMBOLS));
+        for (input, expected) in cases {
+            assert_eq!(processor.polish(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_symbols_replace_independently() {
+        let mut processor = TextProcessor::default();
+        processor.set_symbols([("bind".to_string(), " := ".to_string())]);
+
+        assert_eq!(processor.polish("x bind y period"), "X := y.");
+        assert_eq!(processor.polish("a arrow b"), "A arrow b");
+
+        // Replacing the commands keeps the symbols
+        processor.set_commands([("stop".to_string(), ".".to_string())]);
+        assert_eq!(processor.polish("x bind y stop"), "X := y.");
+    }
+
+    #[test]
+    fn test_symbols_win_ties_with_commands() {
+        let processor = TextProcessor::new([("colon".to_string(), ":".to_string())])
+            .with_symbols([("colon".to_string(), " : ".to_string())]);
+
+        assert_eq!(processor.polish("a colon b"), "A : b");
+    }
+
+    #[test]
+    fn test_load_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("symbols.json");
+        std::fs::write(&path, r#"{"walrus": " := ", "tilde": "~"}"#).unwrap();
+
+        let symbols = load_symbols(&path).unwrap();
+        assert_eq!(
+            symbols,
+            vec![
+                ("tilde".to_string(), "~".to_string()),
+                ("walrus".to_string(), " := ".to_string()),
+            ]
+        );
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert_eq!(
+            load_symbols(&path).unwrap_err().kind(),
+            "symbol_dictionary_parse_error"
+        );
+        assert_eq!(
+            load_symbols(&dir.path().join("missing.json"))
+                .unwrap_err()
+                .kind(),
+            "symbol_dictionary_read_error"
+        );
+    }
+
+    #[test]
+    fn test_capitalize_unicode() {
+        assert_eq!(capitalize("élan"), "Élan");
+        assert_eq!(capitalize(""), "");
+    }
+}
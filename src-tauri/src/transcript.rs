@@ -0,0 +1,228 @@
+//! Transcript export module.
+//!
+//! Writes the finalized results of a recording session to disk, as a JSON
+//! array or as SRT subtitles when the path ends in `.srt`.
+
+use crate::vosk_stt::RecognitionResult;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Transcript export errors
+#[derive(Error, Debug)]
+pub enum TranscriptError {
+    #[error("Failed to write transcript: {0}")]
+    WriteError(#[from] std::io::Error),
+    #[error("Failed to serialize transcript: {0}")]
+    SerializeError(#[from] serde_json::Error),
+}
+
+impl TranscriptError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TranscriptError::WriteError(_) => "transcript_write_error",
+            TranscriptError::SerializeError(_) => "transcript_serialize_error",
+        }
+    }
+}
+
+/// Whether `path` should be written as SRT rather than JSON
+fn is_srt(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("srt"))
+}
+
+/// The session's text so far: each result trimmed and joined by one space
+pub fn full_text(results: &[RecognitionResult]) -> String {
+    results
+        .iter()
+        .map(|r| r.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Results as a pretty-printed JSON array
+pub fn to_json(results: &[RecognitionResult]) -> Result<String, TranscriptError> {
+    Ok(serde_json::to_string_pretty(results)?)
+}
+
+/// Results as SRT subtitles, one cue per result.
+/// A cue spans the result's words; without word timings it runs from the
+/// previous result's offset to its own.
+pub fn to_srt(results: &[RecognitionResult]) -> String {
+    let mut srt = String::new();
+    let mut previous_end = 0;
+
+    for (index, result) in results
+        .iter()
+        .filter(|r| !r.text.trim().is_empty())
+        .enumerate()
+    {
+        let (start, end) = match (result.words.first(), result.words.last()) {
+            (Some(first), Some(last)) => (first.start_ms, last.end_ms),
+            _ => (previous_end, result.offset_ms.max(previous_end)),
+        };
+        previous_end = end;
+
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            srt_timestamp(start),
+            srt_timestamp(end),
+            result.text.trim()
+        );
+    }
+
+    srt
+}
+
+/// `HH:MM:SS,mmm` as used by SRT cues
+fn srt_timestamp(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// Write `results` to `path`, as SRT if it ends in `.srt` and JSON otherwise
+pub fn write_transcript(results: &[RecognitionResult], path: &Path) -> Result<(), TranscriptError> {
+    let contents = if is_srt(path) {
+        to_srt(results)
+    } else {
+        to_json(results)?
+    };
+
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vosk_stt::WordTiming;
+    use tempfile::tempdir;
+
+    fn result(text: &str, offset_ms: u64, words: &[(&str, u64, u64)]) -> RecognitionResult {
+        RecognitionResult {
+            text: text.to_string(),
+            is_final: true,
+            confidence: Some(0.9),
+            endpoint: true,
+            offset_ms,
+            words: words
+                .iter()
+                .map(|(word, start_ms, end_ms)| WordTiming {
+                    word: word.to_string(),
+                    start_ms: *start_ms,
+                    end_ms: *end_ms,
+                    confidence: 0.9,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_srt_timestamp() {
+        assert_eq!(srt_timestamp(0), "00:00:00,000");
+        assert_eq!(srt_timestamp(1250), "00:00:01,250");
+        assert_eq!(srt_timestamp(3_723_004), "01:02:03,004");
+    }
+
+    #[test]
+    fn test_full_text() {
+        assert_eq!(full_text(&[]), "");
+        assert_eq!(
+            full_text(&[
+                result("Hello there.", 0, &[]),
+                result("  ", 0, &[]),
+                result(" How are you? ", 0, &[]),
+                result("fine", 0, &[]),
+            ]),
+            "Hello there. How are you? fine"
+        );
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let results = vec![result("hello world", 1500, &[("hello", 200, 600)])];
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&results).unwrap()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["text"], "hello world");
+        assert_eq!(entries[0]["offset_ms"], 1500);
+        assert_eq!(entries[0]["words"][0]["word"], "hello");
+        assert_eq!(entries[0]["words"][0]["start_ms"], 200);
+    }
+
+    #[test]
+    fn test_srt_uses_word_timings() {
+        let results = vec![
+            result(
+                "hello world",
+                1500,
+                &[("hello", 200, 600), ("world", 650, 1100)],
+            ),
+            result(
+                "second line",
+                4000,
+                &[("second", 2500, 3000), ("line", 3000, 3400)],
+            ),
+        ];
+
+        assert_eq!(
+            to_srt(&results),
+            "1\n00:00:00,200 --> 00:00:01,100\nhello world\n\n\
+             2\n00:00:02,500 --> 00:00:03,400\nsecond line\n\n"
+        );
+    }
+
+    #[test]
+    fn test_srt_falls_back_to_offsets() {
+        let results = vec![
+            result("first", 1500, &[]),
+            result("   ", 2000, &[]),
+            result("second", 3000, &[]),
+        ];
+
+        assert_eq!(
+            to_srt(&results),
+            "1\n00:00:00,000 --> 00:00:01,500\nfirst\n\n\
+             2\n00:00:01,500 --> 00:00:03,000\nsecond\n\n"
+        );
+    }
+
+    #[test]
+    fn test_write_transcript_by_extension() {
+        let dir = tempdir().unwrap();
+        let results = vec![result("hello", 800, &[])];
+
+        let json_path = dir.path().join("session.json");
+        write_transcript(&results, &json_path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json[0]["text"], "hello");
+
+        let srt_path = dir.path().join("session.SRT");
+        write_transcript(&results, &srt_path).unwrap();
+        assert!(fs::read_to_string(&srt_path)
+            .unwrap()
+            .starts_with("1\n00:00:00,000 --> 00:00:00,800\nhello"));
+    }
+
+    #[test]
+    fn test_write_error_kind() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing").join("session.json");
+
+        let err = write_transcript(&[], &path).unwrap_err();
+        assert_eq!(err.kind(), "transcript_write_error");
+    }
+}
@@ -0,0 +1,152 @@
+//! Voice command routing module.
+//!
+//! Recognizes utterances like "stop listening" or "send message" that should
+//! control the app instead of being dictated to Claude. A phrase only matches
+//! when it is the whole utterance, so "please send message to bob" is still
+//! dictated as text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// App action triggered by a spoken phrase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceAction {
+    /// Stop capturing audio
+    StopRecording,
+    /// Submit the pending transcription to Claude
+    SendToClaude,
+    /// Clear the terminal output
+    ClearTerminal,
+    /// Discard the pending transcription
+    Cancel,
+}
+
+/// Default trigger phrases and the actions they fire
+pub const DEFAULT_VOICE_COMMANDS: &[(&str, VoiceAction)] = &[
+    ("stop listening", VoiceAction::StopRecording),
+    ("send message", VoiceAction::SendToClaude),
+    ("clear terminal", VoiceAction::ClearTerminal),
+    ("cancel that", VoiceAction::Cancel),
+];
+
+/// Maps trigger phrases to app actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceCommands {
+    /// Normalized phrase -> action
+    commands: HashMap<String, VoiceAction>,
+}
+
+impl Default for VoiceCommands {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_VOICE_COMMANDS
+                .iter()
+                .map(|(phrase, action)| (phrase.to_string(), *action)),
+        )
+    }
+}
+
+impl VoiceCommands {
+    /// Create a router with a custom phrase map; empty phrases are ignored
+    pub fn new(commands: impl IntoIterator<Item = (String, VoiceAction)>) -> Self {
+        let commands = commands
+            .into_iter()
+            .map(|(phrase, action)| (normalize(&phrase), action))
+            .filter(|(phrase, _)| !phrase.is_empty())
+            .collect();
+
+        Self { commands }
+    }
+
+    /// The action for an utterance, ignoring case, punctuation and extra whitespace
+    pub fn match_action(&self, text: &str) -> Option<VoiceAction> {
+        self.commands.get(&normalize(text)).copied()
+    }
+}
+
+/// Lowercase words with surrounding punctuation removed, joined by single spaces
+pub(crate) fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_commands() {
+        let commands = VoiceCommands::default();
+
+        assert_eq!(
+            commands.match_action("stop listening"),
+            Some(VoiceAction::StopRecording)
+        );
+        assert_eq!(
+            commands.match_action("send message"),
+            Some(VoiceAction::SendToClaude)
+        );
+        assert_eq!(
+            commands.match_action("clear terminal"),
+            Some(VoiceAction::ClearTerminal)
+        );
+        assert_eq!(
+            commands.match_action("cancel that"),
+            Some(VoiceAction::Cancel)
+        );
+    }
+
+    #[test]
+    fn test_match_ignores_case_and_punctuation() {
+        let commands = VoiceCommands::default();
+
+        assert_eq!(
+            commands.match_action("  Stop   Listening. "),
+            Some(VoiceAction::StopRecording)
+        );
+        assert_eq!(
+            commands.match_action("Send message!"),
+            Some(VoiceAction::SendToClaude)
+        );
+    }
+
+    #[test]
+    fn test_phrase_must_be_whole_utterance() {
+        let commands = VoiceCommands::default();
+
+        assert_eq!(commands.match_action("please send message to bob"), None);
+        assert_eq!(commands.match_action("stop"), None);
+        assert_eq!(commands.match_action(""), None);
+    }
+
+    #[test]
+    fn test_custom_commands_replace_defaults() {
+        let commands = VoiceCommands::new([
+            ("over and out".to_string(), VoiceAction::SendToClaude),
+            ("   ".to_string(), VoiceAction::Cancel),
+        ]);
+
+        assert_eq!(
+            commands.match_action("over and out"),
+            Some(VoiceAction::SendToClaude)
+        );
+        assert_eq!(commands.match_action("send message"), None);
+        assert_eq!(commands.match_action(""), None);
+    }
+
+    #[test]
+    fn test_action_serialization() {
+        assert_eq!(
+            serde_json::to_value(VoiceAction::StopRecording).unwrap(),
+            "StopRecording"
+        );
+        let action: VoiceAction = serde_json::from_str("\"ClearTerminal\"").unwrap();
+        assert_eq!(action, VoiceAction::ClearTerminal);
+    }
+}
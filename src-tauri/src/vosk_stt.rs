@@ -5,7 +5,14 @@
 
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 /// Speech recognition errors
@@ -21,15 +28,118 @@ pub enum SpeechError {
     RecognitionError(String),
     #[error("Model download failed: {0}")]
     DownloadError(String),
+    #[error("Download of {0} was cancelled")]
+    DownloadCancelled(String),
+    #[error("Insufficient disk space: {0}")]
+    InsufficientSpace(String),
+    #[error("Invalid recognizer script: {0}")]
+    InvalidScript(String),
+    #[error("Model directory not found: {0}")]
+    ModelDirNotFound(String),
+    #[error("Model directory already added: {0}")]
+    DuplicateModelDir(String),
 }
 
+impl SpeechError {
+    /// Stable snake_case name, so the frontend can react to the kind of failure
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpeechError::ModelNotFound(_) => "model_not_found",
+            SpeechError::ModelInitError(_) => "model_init_error",
+            SpeechError::RecognizerError(_) => "recognizer_error",
+            SpeechError::RecognitionError(_) => "recognition_error",
+            SpeechError::DownloadError(_) => "download_error",
+            SpeechError::DownloadCancelled(_) => "download_cancelled",
+            SpeechError::InsufficientSpace(_) => "insufficient_space",
+            SpeechError::InvalidScript(_) => "invalid_script",
+            SpeechError::ModelDirNotFound(_) => "model_dir_not_found",
+            SpeechError::DuplicateModelDir(_) => "duplicate_model_dir",
+        }
+    }
+}
+
+/// Language of a Vosk model, detected from its folder name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLanguage {
+    /// ISO 639-1 code with an optional region, e.g. "en-us" or "de".
+    /// "und" (undetermined) when the name doesn't match a known language
+    pub code: String,
+    /// Human-readable name, e.g. "English (US)"
+    pub display_name: String,
+}
+
+impl ModelLanguage {
+    fn new(code: &str, display_name: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            display_name: display_name.to_string(),
+        }
+    }
+
+    /// Language used for names that don't follow the Vosk naming scheme
+    pub fn unknown() -> Self {
+        Self::new("und", "Unknown")
+    }
+}
+
+/// Language code segments used in Vosk model names, mapped to
+/// (ISO code, display name). Regional variants are listed as "lang-region"
+/// and take precedence over the bare language.
+const MODEL_LANGUAGES: &[(&str, &str, &str)] = &[
+    ("en-us", "en-us", "English (US)"),
+    ("en-in", "en-in", "English (India)"),
+    ("en-gb", "en-gb", "English (UK)"),
+    ("en", "en", "English"),
+    ("de", "de", "German"),
+    ("fr", "fr", "French"),
+    ("es", "es", "Spanish"),
+    ("it", "it", "Italian"),
+    ("pt", "pt", "Portuguese"),
+    ("nl", "nl", "Dutch"),
+    ("ca", "ca", "Catalan"),
+    ("ru", "ru", "Russian"),
+    ("uk", "uk", "Ukrainian"),
+    ("pl", "pl", "Polish"),
+    ("cs", "cs", "Czech"),
+    ("sv", "sv", "Swedish"),
+    ("el", "el", "Greek"),
+    ("tr", "tr", "Turkish"),
+    ("ar", "ar", "Arabic"),
+    ("fa", "fa", "Persian"),
+    ("hi", "hi", "Hindi"),
+    ("te", "te", "Telugu"),
+    ("gu", "gu", "Gujarati"),
+    ("cn", "zh", "Chinese"),
+    ("zh", "zh", "Chinese"),
+    ("ja", "ja", "Japanese"),
+    ("jp", "ja", "Japanese"),
+    ("ko", "ko", "Korean"),
+    ("vn", "vi", "Vietnamese"),
+    ("vi", "vi", "Vietnamese"),
+    ("kz", "kk", "Kazakh"),
+    ("kk", "kk", "Kazakh"),
+    ("uz", "uz", "Uzbek"),
+    ("tg", "tg", "Tajik"),
+    ("eo", "eo", "Esperanto"),
+    ("br", "br", "Breton"),
+];
+
 /// Vosk model information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModelInfo {
     pub name: String,
     pub path: PathBuf,
-    pub language: String,
+    pub language: ModelLanguage,
     pub size_mb: u64,
+    /// Native sample rate from the model's conf files, if present
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    /// Model version from the README or the folder name
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Where the model archive is downloaded from (None for installed models)
+    #[serde(default)]
+    pub url: Option<String>,
 }
 
 /// Speech recognition result
@@ -38,6 +148,37 @@ pub struct RecognitionResult {
     pub text: String,
     pub is_final: bool,
     pub confidence: Option<f32>,
+    /// The recognizer detected the end of an utterance (silence), as opposed
+    /// to the result being forced by stopping the recording
+    #[serde(default)]
+    pub endpoint: bool,
+    /// Milliseconds of audio processed since recording started when this
+    /// result was produced (0 outside a recording session)
+    #[serde(default)]
+    pub offset_ms: u64,
+    /// Per-word timings of a final result, relative to the recognizer's last reset
+    #[serde(default)]
+    pub words: Vec<WordTiming>,
+}
+
+/// Timing and confidence of one recognized word
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub word: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub confidence: f32,
+}
+
+impl From<&vosk::Word<'_>> for WordTiming {
+    fn from(word: &vosk::Word) -> Self {
+        Self {
+            word: word.word.to_string(),
+            start_ms: (word.start * 1000.0).round() as u64,
+            end_ms: (word.end * 1000.0).round() as u64,
+            confidence: word.conf,
+        }
+    }
 }
 
 /// Trait for speech recognition abstraction (enables testing)
@@ -46,14 +187,257 @@ pub trait SpeechRecognizer: Send + Sync {
     fn is_model_loaded(&self) -> bool;
     fn process_audio(&self, samples: &[i16]) -> Result<Option<RecognitionResult>, SpeechError>;
     fn get_final_result(&self) -> Result<RecognitionResult, SpeechError>;
+    /// Replace the model while audio may still be arriving, returning the old
+    /// model's final result. Each buffer goes wholly to the old or the new model.
+    fn switch_model(&self, model_path: &Path) -> Result<RecognitionResult, SpeechError> {
+        let result = self.get_final_result()?;
+        self.load_model(model_path)?;
+        Ok(result)
+    }
     fn reset(&self);
+    /// Drop the model and recognizer to free their memory; `load_model` brings them back
+    fn unload_model(&self);
+    /// Rate in Hz of the audio fed to the recognizer
+    fn sample_rate(&self) -> u32;
+    /// Accept audio at `sample_rate` Hz from now on, recreating the recognizer
+    /// against the loaded model. Fails without a model.
+    fn set_sample_rate(&self, sample_rate: f32) -> Result<(), SpeechError>;
+    /// Rate in Hz the loaded model was trained at, from its conf files
+    fn model_sample_rate(&self) -> Option<u32>;
+    /// Snapshot of the recognizer's state, for bug reports
+    fn debug_info(&self) -> RecognizerDebug;
+    /// Keep `[unk]` tokens in final results instead of removing them
+    fn set_keep_unknown_words(&self, keep: bool);
+}
+
+/// Recognizer state reported for debugging stuck recognition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecognizerDebug {
+    pub model_loaded: bool,
+    pub sample_rate: u32,
+    /// Samples fed to the recognizer since the model was loaded
+    pub samples_processed: u64,
+    pub last_partial: Option<String>,
+    pub last_final: Option<String>,
+}
+
+/// Counters behind `RecognizerDebug`, kept by each recognizer implementation
+#[derive(Debug, Default)]
+pub struct RecognizerStats {
+    samples_processed: AtomicU64,
+    last_partial: Mutex<Option<String>>,
+    last_final: Mutex<Option<String>>,
+}
+
+impl RecognizerStats {
+    pub fn record_samples(&self, count: usize) {
+        self.samples_processed
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// Remember the text of a non-empty result
+    pub fn record_result(&self, result: &RecognitionResult) {
+        if result.text.is_empty() {
+            return;
+        }
+
+        let slot = if result.is_final {
+            &self.last_final
+        } else {
+            &self.last_partial
+        };
+        *slot.lock() = Some(result.text.clone());
+    }
+
+    pub fn clear(&self) {
+        self.samples_processed.store(0, Ordering::Relaxed);
+        *self.last_partial.lock() = None;
+        *self.last_final.lock() = None;
+    }
+
+    pub fn snapshot(&self, model_loaded: bool, sample_rate: u32) -> RecognizerDebug {
+        RecognizerDebug {
+            model_loaded,
+            sample_rate,
+            samples_processed: self.samples_processed.load(Ordering::Relaxed),
+            last_partial: self.last_partial.lock().clone(),
+            last_final: self.last_final.lock().clone(),
+        }
+    }
+}
+
+/// Chunk size used when feeding a pre-recorded buffer (250ms at 16kHz)
+const TRANSCRIBE_CHUNK_SAMPLES: usize = 4000;
+
+/// Run a complete buffer of 16kHz mono samples through a recognizer and
+/// return the aggregated final transcription
+pub fn transcribe_samples(
+    recognizer: &dyn SpeechRecognizer,
+    samples: &[i16],
+) -> Result<RecognitionResult, SpeechError> {
+    recognizer.reset();
+
+    let mut segments = Vec::new();
+    let mut words = Vec::new();
+    for chunk in samples.chunks(TRANSCRIBE_CHUNK_SAMPLES) {
+        if let Some(result) = recognizer.process_audio(chunk)? {
+            if result.is_final && !result.text.is_empty() {
+                segments.push(result.text);
+                words.extend(result.words);
+            }
+        }
+    }
+
+    let last = recognizer.get_final_result()?;
+    if !last.text.is_empty() {
+        segments.push(last.text);
+        words.extend(last.words);
+    }
+
+    Ok(RecognitionResult {
+        text: segments.join(" "),
+        is_final: true,
+        confidence: last.confidence,
+        endpoint: false,
+        offset_ms: 0,
+        words,
+    })
+}
+
+/// Length of the synthetic benchmark buffer in seconds
+pub const BENCHMARK_AUDIO_SECS: u32 = 5;
+
+/// Recognition speed relative to real time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BenchmarkResult {
+    /// Real-time factor: processing time divided by audio duration.
+    /// Below 1.0 the recognizer keeps up with live audio.
+    pub rtf: f32,
+    /// Milliseconds spent per second of audio
+    pub ms_per_second_audio: f32,
+}
+
+impl BenchmarkResult {
+    /// Compute the speed of processing `audio_samples` 16kHz samples in `elapsed`
+    pub fn from_timing(elapsed: std::time::Duration, audio_samples: usize) -> Self {
+        let audio_secs = audio_samples as f32 / 16000.0;
+        let rtf = if audio_secs > 0.0 {
+            elapsed.as_secs_f32() / audio_secs
+        } else {
+            0.0
+        };
+
+        Self {
+            rtf,
+            ms_per_second_audio: rtf * 1000.0,
+        }
+    }
+}
+
+/// Deterministic 16kHz test signal: a vowel-like harmonic tone in bursts over
+/// low-level noise, so the recognizer does real decoding work
+pub fn benchmark_audio() -> Vec<i16> {
+    let len = (BENCHMARK_AUDIO_SECS * 16000) as usize;
+    // Linear congruential generator, so every run measures the same input
+    let mut seed: u32 = 0x1234_5678;
+
+    (0..len)
+        .map(|i| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = ((seed >> 16) as i16 as f32) / 32768.0 * 0.02;
+
+            let t = i as f32 / 16000.0;
+            // 600ms bursts of "speech" separated by 400ms pauses
+            let voiced = (t % 1.0) < 0.6;
+            let tone = if voiced {
+                [150.0f32, 300.0, 450.0, 700.0]
+                    .iter()
+                    .map(|f| (2.0 * std::f32::consts::PI * f * t).sin())
+                    .sum::<f32>()
+                    * 0.1
+            } else {
+                0.0
+            };
+
+            ((tone + noise) * 32767.0) as i16
+        })
+        .collect()
+}
+
+/// Time a recognizer on `samples` fed in the chunks used for live audio.
+/// The recognizer is reset before and after so no utterance leaks in or out.
+pub fn benchmark_recognizer(
+    recognizer: &dyn SpeechRecognizer,
+    samples: &[i16],
+) -> Result<BenchmarkResult, SpeechError> {
+    recognizer.reset();
+
+    let start = std::time::Instant::now();
+    for chunk in samples.chunks(TRANSCRIBE_CHUNK_SAMPLES) {
+        recognizer.process_audio(chunk)?;
+    }
+    recognizer.get_final_result()?;
+    let elapsed = start.elapsed();
+
+    recognizer.reset();
+    Ok(BenchmarkResult::from_timing(elapsed, samples.len()))
+}
+
+/// Mean of the per-word confidence scores, or None without word results
+fn average_confidence(words: &[vosk::Word]) -> Option<f32> {
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.iter().map(|w| w.conf).sum::<f32>() / words.len() as f32)
+}
+
+/// Token Vosk emits for a word it couldn't recognize
+pub const UNKNOWN_WORD: &str = "[unk]";
+
+/// Trim a final transcription, collapse runs of whitespace and drop `[unk]`
+/// tokens unless `keep_unknown` is set
+pub fn clean_final(text: &str, keep_unknown: bool) -> String {
+    text.split_whitespace()
+        .filter(|word| keep_unknown || *word != UNKNOWN_WORD)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Apply [`clean_final`] to a final result, dropping `[unk]` word timings along with the text
+fn clean_final_result(result: &mut RecognitionResult, keep_unknown: bool) {
+    result.text = clean_final(&result.text, keep_unknown);
+    if !keep_unknown {
+        result.words.retain(|word| word.word != UNKNOWN_WORD);
+    }
+}
+
+/// Reported when the Vosk native library can't be used
+pub const VOSK_RUNTIME_UNAVAILABLE: &str = "Vosk runtime not available. Install the libvosk \
+    shared library (https://github.com/alphacep/vosk-api/releases) next to the app or on the \
+    library path, then restart.";
+
+/// Run a call into the Vosk native library, turning a panic from a missing or
+/// incompatible runtime into an error instead of aborting the command
+fn vosk_call<T>(call: impl FnOnce() -> T) -> Result<T, SpeechError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(call))
+        .map_err(|_| SpeechError::ModelInitError(VOSK_RUNTIME_UNAVAILABLE.to_string()))
+}
+
+/// Whether the Vosk native library can be called. Checked once and cached.
+pub fn vosk_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    // Warn is already Vosk's default level, so this only touches the runtime
+    *AVAILABLE.get_or_init(|| vosk_call(|| vosk::set_log_level(vosk::LogLevel::Warn)).is_ok())
 }
 
 /// Real Vosk recognizer implementation
 pub struct VoskRecognizer {
     model: Mutex<Option<vosk::Model>>,
     recognizer: Mutex<Option<vosk::Recognizer>>,
-    sample_rate: f32,
+    sample_rate: Mutex<f32>,
+    model_sample_rate: Mutex<Option<u32>>,
+    stats: RecognizerStats,
+    keep_unknown_words: AtomicBool,
 }
 
 impl VoskRecognizer {
@@ -61,7 +445,10 @@ impl VoskRecognizer {
         Self {
             model: Mutex::new(None),
             recognizer: Mutex::new(None),
-            sample_rate: 16000.0,
+            sample_rate: Mutex::new(16000.0),
+            model_sample_rate: Mutex::new(None),
+            stats: RecognizerStats::default(),
+            keep_unknown_words: AtomicBool::new(false),
         }
     }
 
@@ -69,8 +456,63 @@ impl VoskRecognizer {
         Self {
             model: Mutex::new(None),
             recognizer: Mutex::new(None),
-            sample_rate,
+            sample_rate: Mutex::new(sample_rate),
+            model_sample_rate: Mutex::new(None),
+            stats: RecognizerStats::default(),
+            keep_unknown_words: AtomicBool::new(false),
+        }
+    }
+
+    /// Load a model and create a recognizer for it, without installing them
+    fn open(&self, model_path: &Path) -> Result<(vosk::Model, vosk::Recognizer), SpeechError> {
+        if !model_path.exists() {
+            return Err(SpeechError::ModelNotFound(
+                model_path.to_string_lossy().to_string(),
+            ));
         }
+
+        let model = vosk_call(|| vosk::Model::new(model_path.to_string_lossy().as_ref()))?
+            .ok_or_else(|| SpeechError::ModelInitError("Failed to load Vosk model".to_string()))?;
+        let recognizer = Self::create_recognizer(&model, *self.sample_rate.lock())?;
+
+        Ok((model, recognizer))
+    }
+
+    fn create_recognizer(
+        model: &vosk::Model,
+        sample_rate: f32,
+    ) -> Result<vosk::Recognizer, SpeechError> {
+        let mut recognizer =
+            vosk_call(|| vosk::Recognizer::new(model, sample_rate))?.ok_or_else(|| {
+                SpeechError::RecognizerError("Failed to create recognizer".to_string())
+            })?;
+        // Word-level results carry the per-word confidence scores
+        recognizer.set_words(true);
+        Ok(recognizer)
+    }
+
+    /// Flush `recognizer`'s pending audio into a final result
+    fn flush(&self, recognizer: &mut vosk::Recognizer) -> RecognitionResult {
+        let final_result = recognizer.final_result().single();
+
+        let mut result = RecognitionResult {
+            text: final_result
+                .as_ref()
+                .map(|r| r.text.to_string())
+                .unwrap_or_default(),
+            is_final: true,
+            confidence: final_result
+                .as_ref()
+                .and_then(|r| average_confidence(&r.result)),
+            endpoint: false,
+            offset_ms: 0,
+            words: final_result
+                .map(|r| r.result.iter().map(WordTiming::from).collect())
+                .unwrap_or_default(),
+        };
+        clean_final_result(&mut result, self.keep_unknown_words.load(Ordering::SeqCst));
+        self.stats.record_result(&result);
+        result
     }
 
     fn parse_result(json: &str) -> RecognitionResult {
@@ -97,6 +539,9 @@ impl VoskRecognizer {
             text,
             is_final,
             confidence: None,
+            endpoint: false,
+            offset_ms: 0,
+            words: Vec::new(),
         }
     }
 }
@@ -109,20 +554,12 @@ impl Default for VoskRecognizer {
 
 impl SpeechRecognizer for VoskRecognizer {
     fn load_model(&self, model_path: &Path) -> Result<(), SpeechError> {
-        if !model_path.exists() {
-            return Err(SpeechError::ModelNotFound(
-                model_path.to_string_lossy().to_string(),
-            ));
-        }
-
-        let model = vosk::Model::new(model_path.to_string_lossy().as_ref())
-            .ok_or_else(|| SpeechError::ModelInitError("Failed to load Vosk model".to_string()))?;
-
-        let recognizer = vosk::Recognizer::new(&model, self.sample_rate)
-            .ok_or_else(|| SpeechError::RecognizerError("Failed to create recognizer".to_string()))?;
+        let (model, recognizer) = self.open(model_path)?;
 
         *self.model.lock() = Some(model);
         *self.recognizer.lock() = Some(recognizer);
+        *self.model_sample_rate.lock() = ModelManager::read_sample_rate(model_path);
+        self.stats.clear();
 
         Ok(())
     }
@@ -137,31 +574,48 @@ impl SpeechRecognizer for VoskRecognizer {
             .as_mut()
             .ok_or_else(|| SpeechError::RecognizerError("Recognizer not initialized".to_string()))?;
 
+        self.stats.record_samples(samples.len());
         let state = recognizer.accept_waveform(samples);
 
-        match state {
+        let result = match state {
             vosk::DecodingState::Running => {
                 let partial = recognizer.partial_result();
                 let result = Self::parse_result(partial.partial);
                 if result.text.is_empty() {
-                    Ok(None)
+                    None
                 } else {
-                    Ok(Some(result))
+                    Some(result)
                 }
             }
             vosk::DecodingState::Finalized => {
-                let final_result = recognizer.result();
-                let result = Self::parse_result(final_result.single().map(|r| r.text).unwrap_or(""));
-                Ok(Some(RecognitionResult {
-                    text: result.text,
+                let final_result = recognizer.result().single();
+                let mut result = RecognitionResult {
+                    text: final_result
+                        .as_ref()
+                        .map(|r| r.text.to_string())
+                        .unwrap_or_default(),
                     is_final: true,
-                    confidence: None,
-                }))
+                    confidence: final_result
+                        .as_ref()
+                        .and_then(|r| average_confidence(&r.result)),
+                    endpoint: true,
+                    offset_ms: 0,
+                    words: final_result
+                        .map(|r| r.result.iter().map(WordTiming::from).collect())
+                        .unwrap_or_default(),
+                };
+                clean_final_result(&mut result, self.keep_unknown_words.load(Ordering::SeqCst));
+                Some(result)
             }
             vosk::DecodingState::Failed => {
-                Err(SpeechError::RecognitionError("Decoding failed".to_string()))
+                return Err(SpeechError::RecognitionError("Decoding failed".to_string()));
             }
+        };
+
+        if let Some(result) = &result {
+            self.stats.record_result(result);
         }
+        Ok(result)
     }
 
     fn get_final_result(&self) -> Result<RecognitionResult, SpeechError> {
@@ -170,14 +624,30 @@ impl SpeechRecognizer for VoskRecognizer {
             .as_mut()
             .ok_or_else(|| SpeechError::RecognizerError("Recognizer not initialized".to_string()))?;
 
-        let final_result = recognizer.final_result();
-        let text = final_result.single().map(|r| r.text.to_string()).unwrap_or_default();
+        Ok(self.flush(recognizer))
+    }
+
+    fn switch_model(&self, model_path: &Path) -> Result<RecognitionResult, SpeechError> {
+        // Loading is slow, so audio keeps going to the old model meanwhile
+        let (model, recognizer) = self.open(model_path)?;
 
-        Ok(RecognitionResult {
-            text,
-            is_final: true,
-            confidence: None,
-        })
+        // process_audio holds this lock for a whole buffer, so none is split or lost
+        let mut current = self.recognizer.lock();
+        let result = match current.as_mut() {
+            Some(old) => self.flush(old),
+            None => RecognitionResult {
+                text: String::new(),
+                is_final: true,
+                confidence: None,
+                endpoint: false,
+                offset_ms: 0,
+                words: Vec::new(),
+            },
+        };
+        *current = Some(recognizer);
+        *self.model.lock() = Some(model);
+        *self.model_sample_rate.lock() = ModelManager::read_sample_rate(model_path);
+        Ok(result)
     }
 
     fn reset(&self) {
@@ -185,59 +655,384 @@ impl SpeechRecognizer for VoskRecognizer {
             recognizer.reset();
         }
     }
+
+    fn unload_model(&self) {
+        // Hold the recognizer lock so an in-flight process_audio finishes first,
+        // and drop the recognizer before the model it was created from
+        let mut recognizer = self.recognizer.lock();
+        *recognizer = None;
+        *self.model.lock() = None;
+        *self.model_sample_rate.lock() = None;
+        self.stats.clear();
+    }
+
+    fn sample_rate(&self) -> u32 {
+        *self.sample_rate.lock() as u32
+    }
+
+    fn set_sample_rate(&self, sample_rate: f32) -> Result<(), SpeechError> {
+        if sample_rate <= 0.0 || !sample_rate.is_finite() {
+            return Err(SpeechError::RecognizerError(format!(
+                "Invalid sample rate: {}",
+                sample_rate
+            )));
+        }
+
+        // Held so no buffer reaches a recognizer created for another rate
+        let mut current = self.recognizer.lock();
+        let model = self.model.lock();
+        let model = model
+            .as_ref()
+            .ok_or_else(|| SpeechError::RecognizerError("No model loaded".to_string()))?;
+        *current = Some(Self::create_recognizer(model, sample_rate)?);
+        *self.sample_rate.lock() = sample_rate;
+        Ok(())
+    }
+
+    fn model_sample_rate(&self) -> Option<u32> {
+        *self.model_sample_rate.lock()
+    }
+
+    fn debug_info(&self) -> RecognizerDebug {
+        self.stats
+            .snapshot(self.is_model_loaded(), self.sample_rate())
+    }
+
+    fn set_keep_unknown_words(&self, keep: bool) {
+        self.keep_unknown_words.store(keep, Ordering::SeqCst);
+    }
+}
+
+/// Expected SHA-256 of known models, as produced by [`model_hash`]: the hash
+/// of the archive for `.zip` files, or of the file manifest for extracted
+/// model directories. Models without an entry can't be verified. A wrong
+/// entry deletes every download of that model, so only add hashes taken with
+/// `sha256sum` from archives served by [`MODEL_DOWNLOAD_URL`].
+const KNOWN_MODEL_HASHES: &[(&str, &str)] = &[];
+
+/// Files that identify a model's contents, relative to its directory
+const FINGERPRINT_FILES: &[&str] = &[
+    "am/final.mdl",
+    "conf/mfcc.conf",
+    "conf/model.conf",
+    "graph/HCLG.fst",
+    "graph/HCLr.fst",
+    "graph/Gr.fst",
+    "graph/words.txt",
+    "ivector/final.ie",
+];
+
+/// Bytes hashed from the start of each fingerprinted file
+const FINGERPRINT_HEAD_BYTES: u64 = 1024 * 1024;
+
+/// Where Vosk model archives are downloaded from
+pub const MODEL_DOWNLOAD_URL: &str = "https://alphacephei.com/vosk/models";
+
+/// Catalog of every published Vosk model
+pub const MODEL_INDEX_URL: &str = "https://alphacephei.com/vosk/models/model-list.json";
+
+/// How long a fetched model catalog is reused before the index is fetched again
+const MODEL_INDEX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long to wait for the model index before falling back to the built-in list
+const MODEL_INDEX_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Entry of the Vosk model index
+#[derive(Debug, Deserialize)]
+struct ModelIndexEntry {
+    name: String,
+    lang: String,
+    lang_text: String,
+    /// Archive size in bytes
+    size: u64,
+    url: String,
+    #[serde(default)]
+    version: Option<String>,
+    /// "true" for models superseded by a newer release
+    #[serde(default)]
+    obsolete: String,
+    /// "small", "big", or "spk" for speaker identification models
+    #[serde(default, rename = "type")]
+    kind: String,
+}
+
+/// Parse the Vosk model index into the speech models it lists, leaving out
+/// obsolete and speaker identification models
+pub fn parse_model_index(json: &str, models_dir: &Path) -> Result<Vec<ModelInfo>, SpeechError> {
+    let entries: Vec<ModelIndexEntry> = serde_json::from_str(json)
+        .map_err(|e| SpeechError::DownloadError(format!("Invalid model index: {}", e)))?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.obsolete != "true" && entry.kind != "spk")
+        .map(|entry| {
+            // Prefer the names used for installed models
+            let language = match ModelManager::detect_language(&entry.name) {
+                language if language.code == "und" => {
+                    ModelLanguage::new(&entry.lang, &entry.lang_text)
+                }
+                language => language,
+            };
+            ModelInfo {
+                path: models_dir.join(&entry.name),
+                language,
+                size_mb: entry.size.div_ceil(1024 * 1024),
+                sample_rate: None,
+                version: entry.version,
+                url: Some(entry.url),
+                name: entry.name,
+            }
+        })
+        .collect())
+}
+
+/// Minimum number of bytes between `download_model` progress reports
+const DOWNLOAD_PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+/// Free space needed per byte of model: the archive plus its extracted copy
+const DOWNLOAD_SPACE_FACTOR: u64 = 2;
+
+/// Resident memory per byte on disk for small Vosk models, whose decoding
+/// graph expands when loaded (a 40 MB model uses about 250 MB)
+const SMALL_MODEL_MEMORY_FACTOR: f64 = 6.0;
+
+/// Resident memory per byte on disk for large Vosk models (the 1.8 GB English
+/// model uses about 5.5 GB with its rescoring data)
+const LARGE_MODEL_MEMORY_FACTOR: f64 = 3.0;
+
+/// whisper.cpp loads ggml weights nearly as stored, plus working buffers
+const WHISPER_MODEL_MEMORY_FACTOR: f64 = 1.2;
+
+/// Estimated resident memory of a model taking `disk_bytes` on disk
+pub fn estimate_memory(name: &str, disk_bytes: u64) -> u64 {
+    let factor = if name.ends_with(".bin") {
+        WHISPER_MODEL_MEMORY_FACTOR
+    } else if name.contains("-small-") {
+        SMALL_MODEL_MEMORY_FACTOR
+    } else {
+        LARGE_MODEL_MEMORY_FACTOR
+    };
+    (disk_bytes as f64 * factor) as u64
+}
+
+/// Progress of a model download
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DownloadProgress {
+    pub name: String,
+    /// Bytes on disk so far, including a resumed partial download
+    pub downloaded: u64,
+    /// Archive size, when the server reports it
+    pub total: Option<u64>,
+}
+
+/// Shared flag asking a running download to stop
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Answer to a model archive request
+pub enum FetchResponse {
+    /// The whole archive from its first byte
+    Full {
+        total: Option<u64>,
+        body: Box<dyn Read + Send>,
+    },
+    /// The archive from the requested offset onwards
+    Partial {
+        total: Option<u64>,
+        body: Box<dyn Read + Send>,
+    },
+    /// The offset is at or past the end of the archive
+    RangeNotSatisfiable { total: Option<u64> },
+}
+
+/// Trait for fetching model archives (enables testing without network access)
+pub trait ModelFetcher: Send + Sync {
+    /// Request `url`, asking for the bytes from `offset` onwards when it is non-zero.
+    /// A server may ignore the range and answer with the full archive.
+    fn fetch(&self, url: &str, offset: u64) -> Result<FetchResponse, SpeechError>;
+}
+
+/// Real fetcher using a blocking HTTP client
+#[derive(Default)]
+pub struct HttpModelFetcher {
+    /// Limit on a whole request, body included. None suits model archives,
+    /// which take minutes; only the connection attempt is bounded then.
+    timeout: Option<Duration>,
+}
+
+impl HttpModelFetcher {
+    /// Fetcher for small documents like the model index, giving up on
+    /// requests that take longer than `timeout`
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+        }
+    }
+}
+
+impl ModelFetcher for HttpModelFetcher {
+    fn fetch(&self, url: &str, offset: u64) -> Result<FetchResponse, SpeechError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(download_error)?;
+
+        let mut request = client.get(url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let response = request.send().map_err(download_error)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(FetchResponse::RangeNotSatisfiable {
+                total: content_range_total(&response),
+            });
+        }
+        if !status.is_success() {
+            return Err(SpeechError::DownloadError(format!(
+                "Server returned {} for {}",
+                status, url
+            )));
+        }
+
+        if status == reqwest::StatusCode::PARTIAL_CONTENT {
+            Ok(FetchResponse::Partial {
+                total: content_range_total(&response),
+                body: Box::new(response),
+            })
+        } else {
+            Ok(FetchResponse::Full {
+                total: response.content_length(),
+                body: Box::new(response),
+            })
+        }
+    }
+}
+
+/// Trait for querying free disk space (enables testing without filling a disk)
+pub trait DiskSpace: Send + Sync {
+    /// Bytes available to the current user on the volume containing `path`
+    fn available_space(&self, path: &Path) -> std::io::Result<u64>;
+}
+
+/// Real disk space query using the platform filesystem API
+pub struct FsDiskSpace;
+
+impl DiskSpace for FsDiskSpace {
+    fn available_space(&self, path: &Path) -> std::io::Result<u64> {
+        fs2::available_space(path)
+    }
+}
+
+/// Trait for querying free memory (enables testing low-memory warnings)
+pub trait SystemMemory: Send + Sync {
+    /// Bytes of RAM available without swapping, if the platform reports it
+    fn available_memory(&self) -> Option<u64>;
+}
+
+/// Real memory query using sysinfo
+pub struct SysinfoMemory;
+
+impl SystemMemory for SysinfoMemory {
+    fn available_memory(&self) -> Option<u64> {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        // sysinfo reports 0 on platforms it doesn't support
+        Some(system.available_memory()).filter(|&bytes| bytes > 0)
+    }
 }
 
 /// Model manager for downloading and managing Vosk models
+#[derive(Clone)]
 pub struct ModelManager {
     models_dir: PathBuf,
     additional_dirs: Vec<PathBuf>,
+    /// Folders added by the user at runtime, shared between clones
+    user_dirs: Arc<Mutex<Vec<PathBuf>>>,
+    download_url: String,
+    index_url: String,
+    /// Models from the last successful index fetch and when it was fetched
+    catalog: Arc<Mutex<Option<(Instant, Vec<ModelInfo>)>>>,
+    fetcher: Arc<dyn ModelFetcher>,
+    /// Fetches the model index, with a short timeout unlike `fetcher`
+    index_fetcher: Arc<dyn ModelFetcher>,
+    /// How long `fetch_model_catalog` waits for the index
+    index_timeout: Duration,
+    disk_space: Arc<dyn DiskSpace>,
+    memory: Arc<dyn SystemMemory>,
 }
 
 impl ModelManager {
-    pub fn new(models_dir: PathBuf) -> Self {
-        // Also check for models in common development locations
-        let mut additional_dirs = Vec::new();
-
-        eprintln!("[DEBUG] ModelManager::new - Primary dir: {:?}", models_dir);
+    /// Models are also looked for in `models` folders next to the working
+    /// directory and the executable. `scan_ancestors` extends the search to the
+    /// parent of the working directory and up to three levels above the
+    /// executable, which finds the repo's models during development.
+    pub fn new(models_dir: PathBuf, scan_ancestors: bool) -> Self {
+        let mut candidates = Vec::new();
 
-        // Helper to add a models dir if it exists and isn't already added
-        let mut try_add_models_dir = |dir: PathBuf| {
-            eprintln!("[DEBUG] Checking dir: {:?}, exists: {}", dir, dir.exists());
-            if dir.exists() && dir != models_dir && !additional_dirs.contains(&dir) {
-                eprintln!("[DEBUG] Adding additional dir: {:?}", dir);
-                additional_dirs.push(dir);
-            }
-        };
-
-        // Check current working directory's models folder
         if let Ok(cwd) = std::env::current_dir() {
-            try_add_models_dir(cwd.join("models"));
+            candidates.push(cwd.join("models"));
 
-            // Also check parent directory (for when CWD is src-tauri)
-            if let Some(parent) = cwd.parent() {
-                try_add_models_dir(parent.join("models"));
+            // For when CWD is src-tauri
+            if scan_ancestors {
+                if let Some(parent) = cwd.parent() {
+                    candidates.push(parent.join("models"));
+                }
             }
         }
 
-        // Check executable directory's models folder
         if let Ok(exe_path) = std::env::current_exe() {
             if let Some(exe_dir) = exe_path.parent() {
-                try_add_models_dir(exe_dir.join("models"));
-
-                // Check parent directories (for target/debug -> src-tauri -> project root)
-                if let Some(parent1) = exe_dir.parent() {
-                    try_add_models_dir(parent1.join("models"));
-                    if let Some(parent2) = parent1.parent() {
-                        try_add_models_dir(parent2.join("models"));
-                        if let Some(parent3) = parent2.parent() {
-                            try_add_models_dir(parent3.join("models"));
-                        }
-                    }
+                candidates.push(exe_dir.join("models"));
+
+                // target/debug -> target -> src-tauri -> project root
+                if scan_ancestors {
+                    candidates.extend(
+                        exe_dir
+                            .ancestors()
+                            .skip(1)
+                            .take(3)
+                            .map(|dir| dir.join("models")),
+                    );
                 }
             }
         }
 
-        Self { models_dir, additional_dirs }
+        log::trace!("ModelManager::new - primary dir: {:?}", models_dir);
+        let mut additional_dirs = Vec::new();
+        for dir in candidates {
+            let exists = dir.exists();
+            log::trace!("Checking models dir {:?}, exists: {}", dir, exists);
+            if exists && dir != models_dir && !additional_dirs.contains(&dir) {
+                additional_dirs.push(dir);
+            }
+        }
+
+        Self {
+            models_dir,
+            additional_dirs,
+            user_dirs: Arc::new(Mutex::new(Vec::new())),
+            download_url: MODEL_DOWNLOAD_URL.to_string(),
+            index_url: MODEL_INDEX_URL.to_string(),
+            catalog: Arc::new(Mutex::new(None)),
+            fetcher: Arc::new(HttpModelFetcher::default()),
+            index_fetcher: Arc::new(HttpModelFetcher::with_timeout(MODEL_INDEX_TIMEOUT)),
+            index_timeout: MODEL_INDEX_TIMEOUT,
+            disk_space: Arc::new(FsDiskSpace),
+            memory: Arc::new(SysinfoMemory),
+        }
     }
 
     /// Create a ModelManager that only scans the specified directory (for testing)
@@ -246,6 +1041,15 @@ impl ModelManager {
         Self {
             models_dir,
             additional_dirs: Vec::new(),
+            user_dirs: Arc::new(Mutex::new(Vec::new())),
+            download_url: MODEL_DOWNLOAD_URL.to_string(),
+            index_url: MODEL_INDEX_URL.to_string(),
+            catalog: Arc::new(Mutex::new(None)),
+            fetcher: Arc::new(HttpModelFetcher::default()),
+            index_fetcher: Arc::new(HttpModelFetcher::with_timeout(MODEL_INDEX_TIMEOUT)),
+            index_timeout: MODEL_INDEX_TIMEOUT,
+            disk_space: Arc::new(FsDiskSpace),
+            memory: Arc::new(SysinfoMemory),
         }
     }
 
@@ -254,14 +1058,63 @@ impl ModelManager {
     }
 
     /// Get all directories where models are searched
-    pub fn get_all_model_dirs(&self) -> Vec<&Path> {
-        let mut dirs = vec![self.models_dir.as_path()];
-        for dir in &self.additional_dirs {
-            dirs.push(dir.as_path());
-        }
+    pub fn get_all_model_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.models_dir.clone()];
+        dirs.extend(self.additional_dirs.iter().cloned());
+        dirs.extend(self.user_dirs.lock().iter().cloned());
         dirs
     }
 
+    /// Folders added with `add_model_dir`
+    pub fn user_model_dirs(&self) -> Vec<PathBuf> {
+        self.user_dirs.lock().clone()
+    }
+
+    /// Also scan `dir` for models, e.g. a network share or external drive
+    pub fn add_model_dir(&self, dir: &Path) -> Result<(), SpeechError> {
+        if !dir.is_dir() {
+            return Err(SpeechError::ModelDirNotFound(
+                dir.to_string_lossy().to_string(),
+            ));
+        }
+
+        // Compare resolved paths so "models/" and "./models" count as the same folder
+        let resolved = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let already_scanned = self.get_all_model_dirs().iter().any(|existing| {
+            existing.canonicalize().unwrap_or_else(|_| existing.clone()) == resolved
+        });
+        if already_scanned {
+            return Err(SpeechError::DuplicateModelDir(
+                dir.to_string_lossy().to_string(),
+            ));
+        }
+
+        self.user_dirs.lock().push(dir.to_path_buf());
+        Ok(())
+    }
+
+    /// Stop scanning a folder added with `add_model_dir`
+    pub fn remove_model_dir(&self, dir: &Path) -> Result<(), SpeechError> {
+        let mut user_dirs = self.user_dirs.lock();
+        let Some(index) = user_dirs.iter().position(|existing| existing == dir) else {
+            return Err(SpeechError::ModelDirNotFound(
+                dir.to_string_lossy().to_string(),
+            ));
+        };
+        user_dirs.remove(index);
+        Ok(())
+    }
+
+    /// Restore saved user folders, skipping ones that are missing or duplicated
+    pub fn set_user_model_dirs(&self, dirs: &[PathBuf]) {
+        self.user_dirs.lock().clear();
+        for dir in dirs {
+            if let Err(e) = self.add_model_dir(dir) {
+                eprintln!("Skipping saved model directory: {}", e);
+            }
+        }
+    }
+
     /// Check if a directory contains a valid Vosk model
     fn is_valid_vosk_model(path: &Path) -> bool {
         if !path.is_dir() {
@@ -277,84 +1130,172 @@ impl ModelManager {
         (has_am && has_graph) || (has_conf && has_graph) || has_model_conf
     }
 
-    /// Detect language from model folder name
-    fn detect_language(name: &str) -> String {
+    /// Detect language from the `vosk-model-[small-]<lang>[-<region>]-<ver>` folder name
+    fn detect_language(name: &str) -> ModelLanguage {
         let name_lower = name.to_lowercase();
-        // Use word boundaries with dashes/underscores for more accurate matching
-        if name_lower.contains("-en-us") || name_lower.contains("_en_us") || name_lower.contains("-en-us-") {
-            "English (US)".to_string()
-        } else if name_lower.contains("-en-in") || name_lower.contains("_en_in") {
-            "English (India)".to_string()
-        } else if name_lower.contains("-en-") || name_lower.contains("_en_") || name_lower.ends_with("-en") {
-            "English".to_string()
-        } else if name_lower.contains("-de-") || name_lower.contains("_de_") || name_lower.ends_with("-de") || name_lower.contains("-de-") {
-            "German".to_string()
-        } else if name_lower.contains("-fr-") || name_lower.contains("_fr_") || name_lower.ends_with("-fr") || name_lower.contains("-fr-") {
-            "French".to_string()
-        } else if name_lower.contains("-es-") || name_lower.contains("_es_") || name_lower.ends_with("-es") || name_lower.contains("-es-") {
-            "Spanish".to_string()
-        } else if name_lower.contains("-cn-") || name_lower.contains("-zh-") || name_lower.contains("_cn_") || name_lower.contains("_zh_") {
-            "Chinese".to_string()
-        } else if name_lower.contains("-ru-") || name_lower.contains("_ru_") || name_lower.ends_with("-ru") {
-            "Russian".to_string()
-        } else if name_lower.contains("-ja-") || name_lower.contains("-jp-") || name_lower.contains("_ja_") || name_lower.contains("_jp_") {
-            "Japanese".to_string()
-        } else {
-            "Unknown".to_string()
-        }
+        let mut segments = name_lower
+            .split(['-', '_'])
+            .skip_while(|segment| matches!(*segment, "vosk" | "model" | "small"));
+
+        let Some(lang) = segments.next() else {
+            return ModelLanguage::unknown();
+        };
+        let regional = segments.next().map(|region| format!("{}-{}", lang, region));
+
+        let lookup = |segment: &str| {
+            MODEL_LANGUAGES
+                .iter()
+                .find(|(key, _, _)| *key == segment)
+                .map(|(_, code, display_name)| ModelLanguage::new(code, display_name))
+        };
+
+        regional
+            .as_deref()
+            .and_then(lookup)
+            .or_else(|| lookup(lang))
+            .unwrap_or_else(ModelLanguage::unknown)
+    }
+
+    /// Read the feature extraction sample rate (`--sample-frequency=16000`) from the model's conf files
+    fn read_sample_rate(path: &Path) -> Option<u32> {
+        ["conf/mfcc.conf", "mfcc.conf", "conf/model.conf"]
+            .iter()
+            .filter_map(|file| std::fs::read_to_string(path.join(file)).ok())
+            .find_map(|contents| {
+                contents.lines().find_map(|line| {
+                    line.trim()
+                        .strip_prefix("--sample-frequency=")
+                        .and_then(|v| v.trim().parse::<f32>().ok())
+                        .map(|rate| rate as u32)
+                })
+            })
+    }
+
+    /// Read the model version from a `Version:` line in the README,
+    /// falling back to the trailing version in the folder name (e.g. `-0.22`)
+    fn read_version(path: &Path, name: &str) -> Option<String> {
+        let from_readme = ["README", "README.md", "README.txt"]
+            .iter()
+            .filter_map(|file| std::fs::read_to_string(path.join(file)).ok())
+            .find_map(|contents| {
+                contents.lines().find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    (key.trim().eq_ignore_ascii_case("version") && !value.trim().is_empty())
+                        .then(|| value.trim().to_string())
+                })
+            });
+
+        from_readme.or_else(|| Self::version_from_name(name))
+    }
+
+    fn version_from_name(name: &str) -> Option<String> {
+        let last = name.rsplit('-').next()?;
+        let is_version = last.contains('.')
+            && last
+                .split('.')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        is_version.then(|| last.to_string())
     }
 
     /// Calculate directory size in MB
     fn get_dir_size_mb(path: &Path) -> u64 {
-        fn dir_size(path: &Path) -> u64 {
-            let mut size = 0;
-            if let Ok(entries) = std::fs::read_dir(path) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        size += dir_size(&path);
-                    } else if let Ok(meta) = entry.metadata() {
-                        size += meta.len();
-                    }
+        Self::disk_size(path) / (1024 * 1024)
+    }
+
+    /// Bytes a model takes on disk, whether a folder or a single file
+    fn disk_size(path: &Path) -> u64 {
+        if path.is_file() {
+            return std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        }
+
+        let mut size = 0;
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    size += Self::disk_size(&path);
+                } else if let Ok(meta) = entry.metadata() {
+                    size += meta.len();
                 }
             }
-            size
         }
-        dir_size(path) / (1024 * 1024)
+        size
     }
 
+    /// Downloadable models, without network access: the last fetched catalog,
+    /// or else a built-in list of English models
     pub fn list_available_models(&self) -> Vec<ModelInfo> {
-        // Return suggestions for downloadable models
+        if let Some((_, models)) = self.catalog.lock().as_ref() {
+            return models.clone();
+        }
+
+        let model = |name: &str, size_mb: u64, version: &str| ModelInfo {
+            name: name.to_string(),
+            path: self.models_dir.join(name),
+            language: ModelLanguage::new("en-us", "English (US)"),
+            size_mb,
+            sample_rate: Some(16000),
+            version: Some(version.to_string()),
+            url: Some(format!("{}/{}.zip", MODEL_DOWNLOAD_URL, name)),
+        };
         vec![
-            ModelInfo {
-                name: "vosk-model-small-en-us-0.15".to_string(),
-                path: self.models_dir.join("vosk-model-small-en-us-0.15"),
-                language: "English (US)".to_string(),
-                size_mb: 40,
-            },
-            ModelInfo {
-                name: "vosk-model-en-us-0.22".to_string(),
-                path: self.models_dir.join("vosk-model-en-us-0.22"),
-                language: "English (US)".to_string(),
-                size_mb: 1800,
-            },
+            model("vosk-model-small-en-us-0.15", 40, "0.15"),
+            model("vosk-model-en-us-0.22", 1800, "0.22"),
         ]
     }
 
+    /// Every published model from the Vosk model index. The index is fetched
+    /// at most once per hour; when it can't be fetched within a few seconds
+    /// the models from `list_available_models` are returned.
+    pub fn fetch_model_catalog(&self) -> Vec<ModelInfo> {
+        if let Some((fetched, models)) = self.catalog.lock().as_ref() {
+            if fetched.elapsed() < MODEL_INDEX_TTL {
+                return models.clone();
+            }
+        }
+
+        match self.fetch_model_index() {
+            Ok(models) => {
+                *self.catalog.lock() = Some((Instant::now(), models.clone()));
+                models
+            }
+            Err(e) => {
+                eprintln!("Using the cached model list: {}", e);
+                self.list_available_models()
+            }
+        }
+    }
+
+    fn fetch_model_index(&self) -> Result<Vec<ModelInfo>, SpeechError> {
+        let fetcher = self.index_fetcher.clone();
+        let url = self.index_url.clone();
+        let models_dir = self.models_dir.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        // A stalled fetch is abandoned rather than holding up the listing
+        std::thread::spawn(move || {
+            let _ = tx.send(fetch_index(fetcher.as_ref(), &url, &models_dir));
+        });
+
+        rx.recv_timeout(self.index_timeout).map_err(|_| {
+            SpeechError::DownloadError(format!(
+                "Timed out fetching the model index from {}",
+                self.index_url
+            ))
+        })?
+    }
+
     pub fn list_installed_models(&self) -> Vec<ModelInfo> {
         let mut models = Vec::new();
         let mut seen_names = std::collections::HashSet::new();
 
-        eprintln!("[DEBUG] list_installed_models - scanning {} dirs", self.get_all_model_dirs().len());
-
         // Scan all model directories
         for dir in self.get_all_model_dirs() {
-            eprintln!("[DEBUG] Scanning: {:?}", dir);
+            log::trace!("Scanning models dir {:?}", dir);
             if let Ok(entries) = std::fs::read_dir(dir) {
                 for entry in entries.flatten() {
                     let path = entry.path();
                     let is_valid = Self::is_valid_vosk_model(&path);
-                    eprintln!("[DEBUG] Found {:?}, valid: {}", path, is_valid);
+                    log::trace!("Found {:?}, valid: {}", path, is_valid);
                     if is_valid {
                         let name = path
                             .file_name()
@@ -363,13 +1304,8 @@ impl ModelManager {
                             .to_string();
 
                         // Avoid duplicates if same model is in multiple dirs
-                        if seen_names.insert(name.clone()) {
-                            models.push(ModelInfo {
-                                language: Self::detect_language(&name),
-                                size_mb: Self::get_dir_size_mb(&path),
-                                name,
-                                path,
-                            });
+                        if seen_names.insert(name) {
+                            models.push(Self::model_info(&path));
                         }
                     }
                 }
@@ -381,6 +1317,337 @@ impl ModelManager {
         models
     }
 
+    /// Describe the model directory at `path`
+    pub fn model_info(path: &Path) -> ModelInfo {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        ModelInfo {
+            language: Self::detect_language(&name),
+            size_mb: Self::get_dir_size_mb(path),
+            sample_rate: Self::read_sample_rate(path),
+            version: Self::read_version(path, &name),
+            url: None,
+            name,
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Find an installed model (directory or `.zip` archive) by name
+    fn find_model_path(&self, name: &str) -> Option<PathBuf> {
+        self.get_all_model_dirs().into_iter().find_map(|dir| {
+            [dir.join(name), dir.join(format!("{}.zip", name))]
+                .into_iter()
+                .find(|path| path.exists())
+        })
+    }
+
+    /// Verify an installed model against its known SHA-256.
+    /// Returns `Ok(false)` when no checksum is known for the model. On a
+    /// mismatch the corrupted files are deleted.
+    pub fn verify_model(&self, name: &str) -> Result<bool, SpeechError> {
+        self.verify_model_against(name, KNOWN_MODEL_HASHES)
+    }
+
+    fn verify_model_against(
+        &self,
+        name: &str,
+        known_hashes: &[(&str, &str)],
+    ) -> Result<bool, SpeechError> {
+        validate_model_name(name)?;
+
+        let path = self
+            .find_model_path(name)
+            .ok_or_else(|| SpeechError::ModelNotFound(name.to_string()))?;
+
+        let Some((_, expected)) = known_hashes.iter().find(|(known, _)| *known == name) else {
+            return Ok(false);
+        };
+
+        verify_model_hash(&path, expected)?;
+        Ok(true)
+    }
+
+    /// Download model archives from `url` instead of the Vosk model server
+    pub fn with_download_url(mut self, url: impl Into<String>) -> Self {
+        self.download_url = url.into();
+        self
+    }
+
+    /// Fetch the model catalog from `url` instead of the Vosk model index
+    pub fn with_index_url(mut self, url: impl Into<String>) -> Self {
+        self.index_url = url.into();
+        self
+    }
+
+    /// Fetch model archives and the model index with `fetcher` instead of over HTTP
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn ModelFetcher>) -> Self {
+        self.index_fetcher = fetcher.clone();
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Wait at most `timeout` for the model index before falling back
+    pub fn with_index_timeout(mut self, timeout: Duration) -> Self {
+        self.index_timeout = timeout;
+        self
+    }
+
+    /// Query free space with `disk_space` instead of the filesystem
+    pub fn with_disk_space(mut self, disk_space: Arc<dyn DiskSpace>) -> Self {
+        self.disk_space = disk_space;
+        self
+    }
+
+    /// Check the models volume has room to download and extract `name`.
+    /// Models without a known size are not checked; a partial download counts
+    /// towards the space already used.
+    pub fn check_space_for_model(&self, name: &str) -> Result<(), SpeechError> {
+        let Some(model) = self
+            .list_available_models()
+            .into_iter()
+            .find(|m| m.name == name)
+        else {
+            return Ok(());
+        };
+
+        let downloaded = std::fs::metadata(self.partial_download_path(name))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let required =
+            (model.size_mb * 1024 * 1024 * DOWNLOAD_SPACE_FACTOR).saturating_sub(downloaded);
+
+        // The models directory may not have been created yet
+        let volume = self
+            .models_dir
+            .ancestors()
+            .find(|dir| dir.exists())
+            .unwrap_or(&self.models_dir);
+        let available = self
+            .disk_space
+            .available_space(volume)
+            .map_err(download_error)?;
+
+        if available < required {
+            return Err(SpeechError::InsufficientSpace(format!(
+                "{} needs {} MB free but only {} MB is available in {}",
+                name,
+                required / (1024 * 1024),
+                available / (1024 * 1024),
+                self.models_dir.display()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Estimated resident memory of the model `name` once loaded, from its size
+    /// on disk if installed or else its listed download size
+    pub fn estimate_model_memory(&self, name: &str) -> Result<u64, SpeechError> {
+        validate_model_name(name)?;
+
+        let disk_bytes = match self.find_model_path(name) {
+            Some(path) => Self::disk_size(&path),
+            None => self
+                .list_available_models()
+                .into_iter()
+                .find(|m| m.name == name)
+                .map(|m| m.size_mb * 1024 * 1024)
+                .ok_or_else(|| SpeechError::ModelNotFound(name.to_string()))?,
+        };
+        Ok(estimate_memory(name, disk_bytes))
+    }
+
+    /// Estimated memory for loading the model at `path` and the RAM available,
+    /// when the estimate doesn't fit
+    pub fn check_memory_for_model(&self, path: &Path) -> Option<(u64, u64)> {
+        let name = path.file_name()?.to_string_lossy();
+        let estimate = estimate_memory(&name, Self::disk_size(path));
+        let available = self.memory.available_memory()?;
+        (estimate > available).then_some((estimate, available))
+    }
+
+    /// Query free memory with `memory` instead of the system
+    pub fn with_memory(mut self, memory: Arc<dyn SystemMemory>) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Where an interrupted download of `name` is kept until it is resumed
+    pub fn partial_download_path(&self, name: &str) -> PathBuf {
+        self.models_dir.join(format!("{}.zip.download", name))
+    }
+
+    pub fn has_partial_download(&self, name: &str) -> bool {
+        self.partial_download_path(name).is_file()
+    }
+
+    /// Download a model archive and extract it into the models directory.
+    ///
+    /// Fails up front if the volume is too small for a model of known size.
+    /// An interrupted download is kept as a `.download` file and resumed with
+    /// an HTTP range request the next time. `progress` is called with the bytes
+    /// on disk, starting with any already downloaded. Cancelling through
+    /// `cancel` deletes the partial file rather than keeping it for a resume.
+    pub fn download_model(
+        &self,
+        name: &str,
+        cancel: &CancelToken,
+        progress: &dyn Fn(&DownloadProgress),
+    ) -> Result<ModelInfo, SpeechError> {
+        validate_model_name(name)?;
+        self.check_space_for_model(name)?;
+        self.ensure_models_dir().map_err(download_error)?;
+
+        let partial = self.partial_download_path(name);
+        self.fetch_archive(name, &partial, cancel, progress)?;
+
+        let archive = self.models_dir.join(format!("{}.zip", name));
+        std::fs::rename(&partial, &archive).map_err(download_error)?;
+
+        if let Some((_, expected)) = KNOWN_MODEL_HASHES.iter().find(|(known, _)| *known == name) {
+            verify_model_hash(&archive, expected)?;
+        }
+
+        extract_archive(&archive, &self.models_dir)?;
+        let _ = std::fs::remove_file(&archive);
+
+        let path = self.models_dir.join(name);
+        if !Self::is_valid_vosk_model(&path) {
+            return Err(SpeechError::DownloadError(format!(
+                "Archive for {} did not contain a Vosk model",
+                name
+            )));
+        }
+
+        Ok(Self::model_info(&path))
+    }
+
+    /// Download the archive for `name` into `partial`, resuming from its current length
+    fn fetch_archive(
+        &self,
+        name: &str,
+        partial: &Path,
+        cancel: &CancelToken,
+        progress: &dyn Fn(&DownloadProgress),
+    ) -> Result<(), SpeechError> {
+        let url = format!("{}/{}.zip", self.download_url.trim_end_matches('/'), name);
+        let mut downloaded = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+
+        let (resumed, total, mut body) = match self.fetcher.fetch(&url, downloaded)? {
+            FetchResponse::Partial { total, body } if downloaded > 0 => (true, total, body),
+            // A server that ignores the range sends the whole archive again
+            FetchResponse::Full { total, body } | FetchResponse::Partial { total, body } => {
+                downloaded = 0;
+                (false, total, body)
+            }
+            FetchResponse::RangeNotSatisfiable { total } => {
+                if total == Some(downloaded) {
+                    return Ok(());
+                }
+                if downloaded == 0 {
+                    return Err(SpeechError::DownloadError(format!(
+                        "Server rejected the request for {}",
+                        url
+                    )));
+                }
+
+                // The partial file doesn't match the archive; start over without a range
+                std::fs::remove_file(partial).map_err(download_error)?;
+                return self.fetch_archive(name, partial, cancel, progress);
+            }
+        };
+
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(partial)
+        } else {
+            File::create(partial)
+        }
+        .map_err(download_error)?;
+
+        let report = |downloaded| {
+            progress(&DownloadProgress {
+                name: name.to_string(),
+                downloaded,
+                total,
+            })
+        };
+        report(downloaded);
+
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut last_report = downloaded;
+        loop {
+            if cancel.is_cancelled() {
+                drop(file);
+                let _ = std::fs::remove_file(partial);
+                return Err(SpeechError::DownloadCancelled(name.to_string()));
+            }
+
+            let n = body.read(&mut buf).map_err(download_error)?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n]).map_err(download_error)?;
+            downloaded += n as u64;
+
+            if downloaded - last_report >= DOWNLOAD_PROGRESS_INTERVAL {
+                report(downloaded);
+                last_report = downloaded;
+            }
+        }
+        file.flush().map_err(download_error)?;
+        if last_report != downloaded {
+            report(downloaded);
+        }
+
+        match total {
+            Some(total) if total != downloaded => Err(SpeechError::DownloadError(format!(
+                "Incomplete download of {}: received {} of {} bytes",
+                name, downloaded, total
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Installed models with the same contents under different names, so the
+    /// UI can offer to remove the copies. Each group holds two or more models.
+    /// Models with matching fingerprints are confirmed with a full
+    /// `model_hash`, so this reads every byte of the likely duplicates.
+    pub fn find_duplicate_models(&self) -> Vec<Vec<ModelInfo>> {
+        let mut candidates: HashMap<String, Vec<ModelInfo>> = HashMap::new();
+        for model in self.list_installed_models() {
+            // Only extracted models have key files to fingerprint
+            if !model.path.is_dir() {
+                continue;
+            }
+            match model_fingerprint(&model.path) {
+                Ok(fingerprint) => candidates.entry(fingerprint).or_default().push(model),
+                Err(e) => eprintln!("Could not fingerprint {}: {}", model.path.display(), e),
+            }
+        }
+
+        // A fingerprint only samples the key files; models that differ further
+        // in mustn't be offered for removal
+        let mut duplicates = Vec::new();
+        for group in candidates.into_values().filter(|group| group.len() > 1) {
+            let mut confirmed: HashMap<String, Vec<ModelInfo>> = HashMap::new();
+            for model in group {
+                match model_hash(&model.path) {
+                    Ok(hash) => confirmed.entry(hash).or_default().push(model),
+                    Err(e) => eprintln!("Could not hash {}: {}", model.path.display(), e),
+                }
+            }
+            duplicates.extend(confirmed.into_values().filter(|group| group.len() > 1));
+        }
+
+        // Models are listed by name, so each group is too
+        duplicates.sort_by(|a, b| a[0].name.cmp(&b[0].name));
+        duplicates
+    }
+
     pub fn get_default_model(&self) -> Option<ModelInfo> {
         self.list_installed_models().into_iter().next()
     }
@@ -390,100 +1657,1032 @@ impl ModelManager {
     }
 }
 
+fn fetch_index(
+    fetcher: &dyn ModelFetcher,
+    url: &str,
+    models_dir: &Path,
+) -> Result<Vec<ModelInfo>, SpeechError> {
+    let mut body = match fetcher.fetch(url, 0)? {
+        FetchResponse::Full { body, .. } | FetchResponse::Partial { body, .. } => body,
+        FetchResponse::RangeNotSatisfiable { .. } => {
+            return Err(SpeechError::DownloadError("Empty model index".to_string()))
+        }
+    };
+    let mut json = String::new();
+    body.read_to_string(&mut json).map_err(download_error)?;
+    parse_model_index(&json, models_dir)
+}
+
+/// Reject names that could escape the models directory; they come from the frontend
+fn validate_model_name(name: &str) -> Result<(), SpeechError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+        return Err(SpeechError::ModelNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+fn download_error(e: impl std::fmt::Display) -> SpeechError {
+    SpeechError::DownloadError(e.to_string())
+}
+
+/// Total size from a `Content-Range: bytes 100-199/1000` (or `bytes */1000`) header
+fn parse_content_range_total(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+fn content_range_total(response: &reqwest::blocking::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()
+        .and_then(parse_content_range_total)
+}
+
+/// Extract a zip archive into `dir`
+fn extract_archive(archive: &Path, dir: &Path) -> Result<(), SpeechError> {
+    let file = File::open(archive).map_err(download_error)?;
+    zip::ZipArchive::new(file)
+        .and_then(|mut zip| zip.extract(dir))
+        .map_err(|e| {
+            SpeechError::DownloadError(format!("Failed to extract {}: {}", archive.display(), e))
+        })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// SHA-256 of a reader's contents as lowercase hex
+fn hash_reader(mut reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Collect all files under `dir` as (path relative to `root` with `/` separators, absolute path)
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, PathBuf)>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Compute the SHA-256 of a model. A file (e.g. the downloaded zip) is hashed
+/// directly; an extracted directory is hashed as a manifest of
+/// `<relative path>\0<file hash>\n` lines sorted by path, so the result
+/// doesn't depend on the platform or directory iteration order.
+pub fn model_hash(path: &Path) -> Result<String, SpeechError> {
+    let io_err = |e: std::io::Error| {
+        SpeechError::DownloadError(format!("Failed to hash {}: {}", path.display(), e))
+    };
+
+    if !path.is_dir() {
+        return std::fs::File::open(path)
+            .and_then(hash_reader)
+            .map_err(io_err);
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, path, &mut files).map_err(io_err)?;
+    files.sort();
+
+    let mut manifest = Sha256::new();
+    for (relative, file) in files {
+        let file_hash = std::fs::File::open(&file)
+            .and_then(hash_reader)
+            .map_err(io_err)?;
+        manifest.update(relative.as_bytes());
+        manifest.update(b"\0");
+        manifest.update(file_hash.as_bytes());
+        manifest.update(b"\n");
+    }
+    Ok(to_hex(&manifest.finalize()))
+}
+
+/// Fingerprint of a model directory's contents, independent of its name: the
+/// size and leading bytes of each key file present. Much quicker than
+/// `model_hash`, which reads every byte of a model that may be gigabytes, but
+/// models with equal fingerprints are only probably identical.
+pub fn model_fingerprint(path: &Path) -> std::io::Result<String> {
+    let mut fingerprint = Sha256::new();
+    let mut found = false;
+    for relative in FINGERPRINT_FILES {
+        let file = path.join(relative);
+        if !file.is_file() {
+            continue;
+        }
+
+        let size = file.metadata()?.len();
+        let head = File::open(&file).and_then(|f| hash_reader(f.take(FINGERPRINT_HEAD_BYTES)))?;
+        fingerprint.update(format!("{}\0{}\0{}\n", relative, size, head).as_bytes());
+        found = true;
+    }
+
+    if !found {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no model files to fingerprint",
+        ));
+    }
+    Ok(to_hex(&fingerprint.finalize()))
+}
+
+/// Compare a model's hash to the expected value, deleting the model's files on mismatch
+pub fn verify_model_hash(path: &Path, expected: &str) -> Result<(), SpeechError> {
+    let actual = model_hash(path)?;
+    if actual.eq_ignore_ascii_case(expected) {
+        return Ok(());
+    }
+
+    let removed = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    let cleanup = match removed {
+        Ok(()) => "the corrupted files were deleted, please download the model again".to_string(),
+        Err(e) => format!("failed to delete the corrupted files: {}", e),
+    };
+
+    Err(SpeechError::DownloadError(format!(
+        "Checksum mismatch for {}: expected {}, got {}; {}",
+        path.display(),
+        expected,
+        actual,
+        cleanup
+    )))
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
     /// Mock speech recognizer for testing
     pub struct MockSpeechRecognizer {
         model_loaded: AtomicBool,
         process_count: AtomicUsize,
         mock_text: Mutex<String>,
+        mock_final: AtomicBool,
+        mock_confidence: Mutex<Option<f32>>,
         should_fail: AtomicBool,
+        last_samples: Mutex<Vec<i16>>,
+        model_sample_rate: Mutex<Option<u32>>,
+        sample_rate: AtomicU32,
+        /// Number of times `set_sample_rate` recreated the recognizer
+        recreate_count: AtomicUsize,
+        /// Set by `unload_model`; processing fails like an uninitialized recognizer
+        unloaded: AtomicBool,
+        /// Processing fails like a failed decode until `reset` is called
+        broken: AtomicBool,
+        loaded_path: Mutex<Option<PathBuf>>,
+        /// Length of every buffer passed to `process_audio`
+        buffer_sizes: Mutex<Vec<usize>>,
+        stats: RecognizerStats,
+        keep_unknown_words: AtomicBool,
+    }
+
+    impl MockSpeechRecognizer {
+        pub fn new() -> Self {
+            Self {
+                model_loaded: AtomicBool::new(false),
+                process_count: AtomicUsize::new(0),
+                mock_text: Mutex::new("Hello world".to_string()),
+                mock_final: AtomicBool::new(false),
+                mock_confidence: Mutex::new(None),
+                should_fail: AtomicBool::new(false),
+                last_samples: Mutex::new(Vec::new()),
+                model_sample_rate: Mutex::new(None),
+                sample_rate: AtomicU32::new(16000),
+                recreate_count: AtomicUsize::new(0),
+                unloaded: AtomicBool::new(false),
+                broken: AtomicBool::new(false),
+                loaded_path: Mutex::new(None),
+                buffer_sizes: Mutex::new(Vec::new()),
+                stats: RecognizerStats::default(),
+                keep_unknown_words: AtomicBool::new(false),
+            }
+        }
+
+        /// Samples passed to the most recent `process_audio` call
+        pub fn last_samples(&self) -> Vec<i16> {
+            self.last_samples.lock().clone()
+        }
+
+        pub fn set_mock_text(&self, text: &str) {
+            *self.mock_text.lock() = text.to_string();
+        }
+
+        /// Report `confidence` instead of the default scores
+        pub fn set_mock_confidence(&self, confidence: f32) {
+            *self.mock_confidence.lock() = Some(confidence);
+        }
+
+        /// Make `process_audio` report final (end of utterance) results
+        pub fn set_mock_final(&self, is_final: bool) {
+            self.mock_final.store(is_final, Ordering::SeqCst);
+        }
+
+        pub fn set_should_fail(&self, fail: bool) {
+            self.should_fail.store(fail, Ordering::SeqCst);
+        }
+
+        pub fn process_count(&self) -> usize {
+            self.process_count.load(Ordering::SeqCst)
+        }
+
+        pub fn recreate_count(&self) -> usize {
+            self.recreate_count.load(Ordering::SeqCst)
+        }
+
+        /// Lengths of the buffers passed to `process_audio`, in order
+        pub fn buffer_sizes(&self) -> Vec<usize> {
+            self.buffer_sizes.lock().clone()
+        }
+
+        /// Path passed to the last successful `load_model`
+        pub fn loaded_path(&self) -> Option<PathBuf> {
+            self.loaded_path.lock().clone()
+        }
+
+        /// Fail processing, like Vosk after a failed decode, until the next `reset`
+        pub fn set_fail_until_reset(&self) {
+            self.broken.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl SpeechRecognizer for MockSpeechRecognizer {
+        fn load_model(&self, model_path: &Path) -> Result<(), SpeechError> {
+            if self.should_fail.load(Ordering::SeqCst) {
+                return Err(SpeechError::ModelNotFound(
+                    model_path.to_string_lossy().to_string(),
+                ));
+            }
+            self.model_loaded.store(true, Ordering::SeqCst);
+            self.unloaded.store(false, Ordering::SeqCst);
+            *self.loaded_path.lock() = Some(model_path.to_path_buf());
+            *self.model_sample_rate.lock() = ModelManager::read_sample_rate(model_path);
+            self.stats.clear();
+            Ok(())
+        }
+
+        fn is_model_loaded(&self) -> bool {
+            self.model_loaded.load(Ordering::SeqCst)
+        }
+
+        fn process_audio(&self, samples: &[i16]) -> Result<Option<RecognitionResult>, SpeechError> {
+            if self.should_fail.load(Ordering::SeqCst) {
+                return Err(SpeechError::RecognitionError("Mock error".to_string()));
+            }
+
+            if self.broken.load(Ordering::SeqCst) {
+                return Err(SpeechError::RecognitionError("Decoding failed".to_string()));
+            }
+
+            if self.unloaded.load(Ordering::SeqCst) {
+                return Err(SpeechError::RecognizerError(
+                    "Recognizer not initialized".to_string(),
+                ));
+            }
+
+            self.process_count.fetch_add(1, Ordering::SeqCst);
+            *self.last_samples.lock() = samples.to_vec();
+            self.buffer_sizes.lock().push(samples.len());
+            self.stats.record_samples(samples.len());
+
+            let text = self.mock_text.lock().clone();
+            if text.is_empty() {
+                Ok(None)
+            } else {
+                // A final result mid-stream means the mock reached an endpoint
+                let is_final = self.mock_final.load(Ordering::SeqCst);
+                let mut result = RecognitionResult {
+                    text,
+                    is_final,
+                    confidence: Some(self.mock_confidence.lock().unwrap_or(0.95)),
+                    endpoint: is_final,
+                    offset_ms: 0,
+                    words: Vec::new(),
+                };
+                if is_final {
+                    clean_final_result(&mut result, self.keep_unknown_words.load(Ordering::SeqCst));
+                }
+                self.stats.record_result(&result);
+                Ok(Some(result))
+            }
+        }
+
+        fn get_final_result(&self) -> Result<RecognitionResult, SpeechError> {
+            if self.should_fail.load(Ordering::SeqCst) {
+                return Err(SpeechError::RecognizerError("Mock error".to_string()));
+            }
+
+            let mut result = RecognitionResult {
+                text: self.mock_text.lock().clone(),
+                is_final: true,
+                confidence: Some(self.mock_confidence.lock().unwrap_or(0.98)),
+                endpoint: false,
+                offset_ms: 0,
+                words: Vec::new(),
+            };
+            clean_final_result(&mut result, self.keep_unknown_words.load(Ordering::SeqCst));
+            self.stats.record_result(&result);
+            Ok(result)
+        }
+
+        fn reset(&self) {
+            self.process_count.store(0, Ordering::SeqCst);
+            self.broken.store(false, Ordering::SeqCst);
+        }
+
+        fn unload_model(&self) {
+            self.model_loaded.store(false, Ordering::SeqCst);
+            self.unloaded.store(true, Ordering::SeqCst);
+            *self.model_sample_rate.lock() = None;
+            self.stats.clear();
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate.load(Ordering::SeqCst)
+        }
+
+        fn set_sample_rate(&self, sample_rate: f32) -> Result<(), SpeechError> {
+            if !self.is_model_loaded() {
+                return Err(SpeechError::RecognizerError("No model loaded".to_string()));
+            }
+            self.sample_rate.store(sample_rate as u32, Ordering::SeqCst);
+            self.recreate_count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn model_sample_rate(&self) -> Option<u32> {
+            *self.model_sample_rate.lock()
+        }
+
+        fn debug_info(&self) -> RecognizerDebug {
+            self.stats
+                .snapshot(self.is_model_loaded(), self.sample_rate())
+        }
+
+        fn set_keep_unknown_words(&self, keep: bool) {
+            self.keep_unknown_words.store(keep, Ordering::SeqCst);
+        }
+    }
+
+    /// Mock disk space query reporting a fixed number of free bytes
+    pub struct MockDiskSpace {
+        available: AtomicU64,
+    }
+
+    impl MockDiskSpace {
+        pub fn new(available: u64) -> Self {
+            Self {
+                available: AtomicU64::new(available),
+            }
+        }
+    }
+
+    impl DiskSpace for MockDiskSpace {
+        fn available_space(&self, _path: &Path) -> std::io::Result<u64> {
+            Ok(self.available.load(Ordering::SeqCst))
+        }
+    }
+
+    /// Mock memory query reporting a fixed number of available bytes
+    pub struct MockSystemMemory(pub u64);
+
+    impl SystemMemory for MockSystemMemory {
+        fn available_memory(&self) -> Option<u64> {
+            Some(self.0)
+        }
+    }
+
+    /// Mock model fetcher serving an in-memory archive
+    pub struct MockModelFetcher {
+        archive: Vec<u8>,
+        requests: Mutex<Vec<(String, u64)>>,
+        should_fail: AtomicBool,
+        ignore_range: AtomicBool,
+        truncate_at: Mutex<Option<usize>>,
+        cancel_at: Mutex<Option<(usize, CancelToken)>>,
+        delay: Mutex<Duration>,
+    }
+
+    impl MockModelFetcher {
+        pub fn new(archive: Vec<u8>) -> Self {
+            Self {
+                archive,
+                requests: Mutex::new(Vec::new()),
+                should_fail: AtomicBool::new(false),
+                ignore_range: AtomicBool::new(false),
+                truncate_at: Mutex::new(None),
+                cancel_at: Mutex::new(None),
+                delay: Mutex::new(Duration::ZERO),
+            }
+        }
+
+        pub fn set_should_fail(&self, fail: bool) {
+            self.should_fail.store(fail, Ordering::SeqCst);
+        }
+
+        /// Answer range requests with the full archive, like servers without range support
+        pub fn set_ignore_range(&self, ignore: bool) {
+            self.ignore_range.store(ignore, Ordering::SeqCst);
+        }
+
+        /// Drop the connection after sending this many body bytes
+        pub fn set_truncate_at(&self, bytes: Option<usize>) {
+            *self.truncate_at.lock() = bytes;
+        }
+
+        /// Cancel `token` once this many body bytes have been read
+        pub fn set_cancel_at(&self, bytes: usize, token: CancelToken) {
+            *self.cancel_at.lock() = Some((bytes, token));
+        }
+
+        /// Wait this long before answering, like a stalled server
+        pub fn set_delay(&self, delay: Duration) {
+            *self.delay.lock() = delay;
+        }
+
+        /// (url, offset) of every request
+        pub fn requests(&self) -> Vec<(String, u64)> {
+            self.requests.lock().clone()
+        }
+    }
+
+    /// Yields its bytes, then fails like a dropped connection
+    struct TruncatedBody(std::io::Cursor<Vec<u8>>);
+
+    impl Read for TruncatedBody {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(std::io::ErrorKind::UnexpectedEof.into()),
+                n => Ok(n),
+            }
+        }
+    }
+
+    /// Cancels its token after yielding `remaining` bytes, like a user
+    /// pressing cancel partway through a download
+    struct CancellingBody {
+        body: Box<dyn Read + Send>,
+        remaining: usize,
+        token: CancelToken,
+    }
+
+    impl Read for CancellingBody {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let limit = match self.remaining {
+                0 => buf.len(),
+                remaining => buf.len().min(remaining),
+            };
+            let n = self.body.read(&mut buf[..limit])?;
+            self.remaining = self.remaining.saturating_sub(n);
+            if self.remaining == 0 {
+                self.token.cancel();
+            }
+            Ok(n)
+        }
+    }
+
+    impl ModelFetcher for MockModelFetcher {
+        fn fetch(&self, url: &str, offset: u64) -> Result<FetchResponse, SpeechError> {
+            self.requests.lock().push((url.to_string(), offset));
+            std::thread::sleep(*self.delay.lock());
+
+            if self.should_fail.load(Ordering::SeqCst) {
+                return Err(SpeechError::DownloadError("Mock error".to_string()));
+            }
+
+            let total = Some(self.archive.len() as u64);
+            let offset = if self.ignore_range.load(Ordering::SeqCst) {
+                0
+            } else {
+                offset as usize
+            };
+            if offset >= self.archive.len() && offset > 0 {
+                return Ok(FetchResponse::RangeNotSatisfiable { total });
+            }
+
+            let mut bytes = self.archive[offset..].to_vec();
+            let body: Box<dyn Read + Send> = match *self.truncate_at.lock() {
+                Some(n) if n < bytes.len() => {
+                    bytes.truncate(n);
+                    Box::new(TruncatedBody(std::io::Cursor::new(bytes)))
+                }
+                _ => Box::new(std::io::Cursor::new(bytes)),
+            };
+            let body = match self.cancel_at.lock().clone() {
+                Some((remaining, token)) => Box::new(CancellingBody {
+                    body,
+                    remaining,
+                    token,
+                }),
+                None => body,
+            };
+
+            Ok(if offset > 0 {
+                FetchResponse::Partial { total, body }
+            } else {
+                FetchResponse::Full { total, body }
+            })
+        }
+    }
+
+    /// Model manager in a temp dir that downloads through a mock fetcher
+    fn mock_download_manager(
+        dir: &Path,
+        archive: Vec<u8>,
+    ) -> (ModelManager, Arc<MockModelFetcher>) {
+        let fetcher = Arc::new(MockModelFetcher::new(archive));
+        let manager = ModelManager::new_isolated(dir.to_path_buf()).with_fetcher(fetcher.clone());
+        (manager, fetcher)
+    }
+
+    #[test]
+    fn test_mock_fetcher_download() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+
+        let reports = Mutex::new(Vec::new());
+        let info = manager
+            .download_model("vosk-model-small-test-0.1", &CancelToken::default(), &|p| {
+                reports.lock().push(p.clone())
+            })
+            .unwrap();
+
+        assert_eq!(
+            fetcher.requests(),
+            vec![(
+                format!("{}/vosk-model-small-test-0.1.zip", MODEL_DOWNLOAD_URL),
+                0
+            )]
+        );
+        assert!(ModelManager::is_valid_vosk_model(&info.path));
+        assert_eq!(
+            reports.lock().last().unwrap().downloaded,
+            archive.len() as u64
+        );
+    }
+
+    #[test]
+    fn test_mock_fetcher_resumes_partial() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+
+        let partial = manager.partial_download_path("vosk-model-small-test-0.1");
+        std::fs::write(&partial, &archive[..100]).unwrap();
+
+        let reports = Mutex::new(Vec::new());
+        manager
+            .download_model("vosk-model-small-test-0.1", &CancelToken::default(), &|p| {
+                reports.lock().push(p.clone())
+            })
+            .unwrap();
+
+        assert_eq!(fetcher.requests()[0].1, 100);
+        assert_eq!(reports.lock()[0].downloaded, 100);
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_mock_fetcher_ignored_range_restarts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+        fetcher.set_ignore_range(true);
+
+        // Garbage that would corrupt the archive if appended to
+        let partial = manager.partial_download_path("vosk-model-small-test-0.1");
+        std::fs::write(&partial, b"stale bytes").unwrap();
+
+        let reports = Mutex::new(Vec::new());
+        let info = manager
+            .download_model("vosk-model-small-test-0.1", &CancelToken::default(), &|p| {
+                reports.lock().push(p.clone())
+            })
+            .unwrap();
+
+        assert_eq!(fetcher.requests()[0].1, 11);
+        assert_eq!(reports.lock()[0].downloaded, 0);
+        assert!(ModelManager::is_valid_vosk_model(&info.path));
+    }
+
+    #[test]
+    fn test_mock_fetcher_complete_partial_is_not_refetched() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+
+        std::fs::write(
+            manager.partial_download_path("vosk-model-small-test-0.1"),
+            &archive,
+        )
+        .unwrap();
+
+        let info = manager
+            .download_model(
+                "vosk-model-small-test-0.1",
+                &CancelToken::default(),
+                &|_| {},
+            )
+            .unwrap();
+        assert_eq!(fetcher.requests().len(), 1);
+        assert!(ModelManager::is_valid_vosk_model(&info.path));
+    }
+
+    #[test]
+    fn test_mock_fetcher_interrupted_then_resumed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+        fetcher.set_truncate_at(Some(500));
+
+        let result = manager.download_model(
+            "vosk-model-small-test-0.1",
+            &CancelToken::default(),
+            &|_| {},
+        );
+        assert!(matches!(result, Err(SpeechError::DownloadError(_))));
+        assert!(manager.has_partial_download("vosk-model-small-test-0.1"));
+
+        fetcher.set_truncate_at(None);
+        manager
+            .download_model(
+                "vosk-model-small-test-0.1",
+                &CancelToken::default(),
+                &|_| {},
+            )
+            .unwrap();
+        assert_eq!(fetcher.requests()[1].1, 500);
+    }
+
+    #[test]
+    fn test_cancelled_download_stops_and_removes_partial() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), archive.clone());
+        let cancel = CancelToken::default();
+        fetcher.set_cancel_at(100, cancel.clone());
+
+        let reports = Mutex::new(Vec::new());
+        let result = manager.download_model("vosk-model-small-test-0.1", &cancel, &|p| {
+            reports.lock().push(p.downloaded)
+        });
+
+        assert!(matches!(result, Err(SpeechError::DownloadCancelled(_))));
+        assert!(cancel.is_cancelled());
+        // The loop stopped at the next chunk instead of reading the whole body
+        assert_eq!(*reports.lock(), vec![0]);
+        assert!(!manager.has_partial_download("vosk-model-small-test-0.1"));
+        assert!(!temp_dir.path().join("vosk-model-small-test-0.1").exists());
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_mock_fetcher_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), Vec::new());
+        fetcher.set_should_fail(true);
+
+        let result = manager.download_model(
+            "vosk-model-small-test-0.1",
+            &CancelToken::default(),
+            &|_| {},
+        );
+        assert!(matches!(result, Err(SpeechError::DownloadError(_))));
+        assert!(!temp_dir.path().join("vosk-model-small-test-0.1").exists());
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_check_space_for_model() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(temp_dir.path().join("models"));
+        let space = |available| {
+            manager
+                .clone()
+                .with_disk_space(Arc::new(MockDiskSpace::new(available)))
+        };
+
+        // The 40 MB small English model needs room for the archive and the extracted copy
+        let result = space(79 * MB).check_space_for_model("vosk-model-small-en-us-0.15");
+        match result {
+            Err(SpeechError::InsufficientSpace(message)) => {
+                assert!(message.contains("needs 80 MB"), "{}", message);
+                assert!(message.contains("only 79 MB"), "{}", message);
+            }
+            other => panic!("expected InsufficientSpace, got {:?}", other),
+        }
+        assert!(space(80 * MB)
+            .check_space_for_model("vosk-model-small-en-us-0.15")
+            .is_ok());
+
+        // Unknown sizes can't be checked
+        assert!(space(0).check_space_for_model("vosk-model-custom").is_ok());
+    }
+
+    #[test]
+    fn test_check_space_counts_partial_download() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf())
+            .with_disk_space(Arc::new(MockDiskSpace::new(70 * MB)));
+        let name = "vosk-model-small-en-us-0.15";
+        assert!(manager.check_space_for_model(name).is_err());
+
+        let partial = std::fs::File::create(manager.partial_download_path(name)).unwrap();
+        partial.set_len(10 * MB).unwrap();
+        assert!(manager.check_space_for_model(name).is_ok());
+    }
+
+    #[test]
+    fn test_estimate_model_memory_scales_with_size() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+        let small = "vosk-model-small-en-us-0.15";
+        let large = "vosk-model-en-us-0.22-lgraph";
+        write_fixture_model(&temp_dir.path().join(small));
+        write_fixture_model(&temp_dir.path().join(large));
+
+        let fixture_bytes = ModelManager::disk_size(&temp_dir.path().join(small));
+        let base = manager.estimate_model_memory(small).unwrap();
+        assert_eq!(base, estimate_memory(small, fixture_bytes));
+        assert!(base > fixture_bytes);
+
+        let graph = std::fs::File::create(temp_dir.path().join(small).join("graph.fst")).unwrap();
+        graph.set_len(MB).unwrap();
+        let grown = manager.estimate_model_memory(small).unwrap();
+        assert_eq!(grown - base, 6 * MB);
+        assert!(manager.estimate_model_memory(large).unwrap() < base);
+
+        // Not installed: falls back to the listed download size
+        assert_eq!(
+            manager
+                .estimate_model_memory("vosk-model-en-us-0.22")
+                .unwrap(),
+            5400 * MB
+        );
+        assert!(matches!(
+            manager.estimate_model_memory("vosk-model-missing"),
+            Err(SpeechError::ModelNotFound(_))
+        ));
+        assert_eq!(estimate_memory("ggml-base.en.bin", 100 * MB), 120 * MB);
+    }
+
+    #[test]
+    fn test_check_memory_for_model() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        write_fixture_model(&path);
+        let estimate = estimate_memory(
+            "vosk-model-small-en-us-0.15",
+            ModelManager::disk_size(&path),
+        );
+        let manager = |available| {
+            ModelManager::new_isolated(temp_dir.path().to_path_buf())
+                .with_memory(Arc::new(MockSystemMemory(available)))
+        };
+
+        assert_eq!(
+            manager(estimate - 1).check_memory_for_model(&path),
+            Some((estimate, estimate - 1))
+        );
+        assert_eq!(manager(estimate).check_memory_for_model(&path), None);
+    }
+
+    #[test]
+    fn test_download_model_checks_space_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), Vec::new());
+        let manager = manager.with_disk_space(Arc::new(MockDiskSpace::new(MB)));
+
+        let result =
+            manager.download_model("vosk-model-en-us-0.22", &CancelToken::default(), &|_| {});
+        assert!(matches!(result, Err(SpeechError::InsufficientSpace(_))));
+        assert!(fetcher.requests().is_empty());
     }
 
-    impl MockSpeechRecognizer {
-        pub fn new() -> Self {
-            Self {
-                model_loaded: AtomicBool::new(false),
-                process_count: AtomicUsize::new(0),
-                mock_text: Mutex::new("Hello world".to_string()),
-                should_fail: AtomicBool::new(false),
-            }
-        }
+    #[test]
+    fn test_benchmark_result_from_timing() {
+        let result = BenchmarkResult::from_timing(std::time::Duration::from_millis(500), 32000);
+        assert!((result.rtf - 0.25).abs() < 1e-6);
+        assert!((result.ms_per_second_audio - 250.0).abs() < 1e-3);
 
-        pub fn set_mock_text(&self, text: &str) {
-            *self.mock_text.lock() = text.to_string();
-        }
+        let empty = BenchmarkResult::from_timing(std::time::Duration::from_millis(500), 0);
+        assert_eq!(empty.rtf, 0.0);
+    }
 
-        pub fn set_should_fail(&self, fail: bool) {
-            self.should_fail.store(fail, Ordering::SeqCst);
-        }
+    #[test]
+    fn test_benchmark_recognizer_with_mock() {
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer.load_model(Path::new("/test/model")).unwrap();
+        let samples = benchmark_audio();
+        assert_eq!(samples.len(), BENCHMARK_AUDIO_SECS as usize * 16000);
+        assert_eq!(samples, benchmark_audio());
+
+        let result = benchmark_recognizer(&recognizer, &samples).unwrap();
+        assert!(result.rtf >= 0.0);
+        assert!((result.ms_per_second_audio - result.rtf * 1000.0).abs() < 1e-3);
+        // Reset afterwards
+        assert_eq!(recognizer.process_count(), 0);
 
-        pub fn process_count(&self) -> usize {
-            self.process_count.load(Ordering::SeqCst)
-        }
+        recognizer.set_should_fail(true);
+        assert!(benchmark_recognizer(&recognizer, &samples).is_err());
     }
 
-    impl SpeechRecognizer for MockSpeechRecognizer {
-        fn load_model(&self, model_path: &Path) -> Result<(), SpeechError> {
-            if self.should_fail.load(Ordering::SeqCst) {
-                return Err(SpeechError::ModelNotFound(
-                    model_path.to_string_lossy().to_string(),
-                ));
-            }
-            self.model_loaded.store(true, Ordering::SeqCst);
-            Ok(())
-        }
+    #[test]
+    fn test_average_confidence() {
+        let word = |word, conf| vosk::Word {
+            conf,
+            start: 0.0,
+            end: 0.5,
+            word,
+        };
 
-        fn is_model_loaded(&self) -> bool {
-            self.model_loaded.load(Ordering::SeqCst)
-        }
+        assert_eq!(average_confidence(&[]), None);
+        assert_eq!(average_confidence(&[word("hello", 0.5)]), Some(0.5));
+        assert_eq!(
+            average_confidence(&[word("hello", 1.0), word("world", 0.5)]),
+            Some(0.75)
+        );
+    }
 
-        fn process_audio(&self, _samples: &[i16]) -> Result<Option<RecognitionResult>, SpeechError> {
-            if self.should_fail.load(Ordering::SeqCst) {
-                return Err(SpeechError::RecognitionError("Mock error".to_string()));
+    #[test]
+    fn test_word_timing_from_vosk_word() {
+        let timing = WordTiming::from(&vosk::Word {
+            conf: 0.8,
+            start: 1.25,
+            end: 1.7,
+            word: "hello",
+        });
+
+        assert_eq!(
+            timing,
+            WordTiming {
+                word: "hello".to_string(),
+                start_ms: 1250,
+                end_ms: 1700,
+                confidence: 0.8,
             }
+        );
+    }
 
-            self.process_count.fetch_add(1, Ordering::SeqCst);
+    #[test]
+    fn test_mock_load_model() {
+        let recognizer = MockSpeechRecognizer::new();
+        assert!(!recognizer.is_model_loaded());
 
-            let text = self.mock_text.lock().clone();
-            if text.is_empty() {
-                Ok(None)
-            } else {
-                Ok(Some(RecognitionResult {
-                    text,
-                    is_final: false,
-                    confidence: Some(0.95),
-                }))
-            }
-        }
+        recognizer.load_model(Path::new("/test/model")).unwrap();
+        assert!(recognizer.is_model_loaded());
+    }
 
-        fn get_final_result(&self) -> Result<RecognitionResult, SpeechError> {
-            if self.should_fail.load(Ordering::SeqCst) {
-                return Err(SpeechError::RecognizerError("Mock error".to_string()));
-            }
+    #[test]
+    fn test_unload_model() {
+        let recognizer = VoskRecognizer::new();
+        recognizer.unload_model();
 
-            Ok(RecognitionResult {
-                text: self.mock_text.lock().clone(),
-                is_final: true,
-                confidence: Some(0.98),
-            })
-        }
+        assert!(!recognizer.is_model_loaded());
+        assert!(matches!(
+            recognizer.process_audio(&[0i16; 1600]),
+            Err(SpeechError::RecognizerError(_))
+        ));
+        assert!(matches!(
+            recognizer.get_final_result(),
+            Err(SpeechError::RecognizerError(_))
+        ));
+    }
 
-        fn reset(&self) {
-            self.process_count.store(0, Ordering::SeqCst);
-        }
+    #[test]
+    fn test_set_sample_rate_needs_model() {
+        let recognizer = VoskRecognizer::new();
+
+        assert!(matches!(
+            recognizer.set_sample_rate(8000.0),
+            Err(SpeechError::RecognizerError(_))
+        ));
+        assert!(matches!(
+            recognizer.set_sample_rate(0.0),
+            Err(SpeechError::RecognizerError(_))
+        ));
+        assert_eq!(recognizer.sample_rate(), 16000);
+        assert_eq!(recognizer.debug_info().sample_rate, 16000);
     }
 
     #[test]
-    fn test_mock_load_model() {
+    fn test_mock_unload_model() {
         let recognizer = MockSpeechRecognizer::new();
+        recognizer.load_model(Path::new("/test/model")).unwrap();
+
+        recognizer.unload_model();
         assert!(!recognizer.is_model_loaded());
+        assert!(matches!(
+            recognizer.process_audio(&[0i16; 1600]),
+            Err(SpeechError::RecognizerError(_))
+        ));
 
         recognizer.load_model(Path::new("/test/model")).unwrap();
-        assert!(recognizer.is_model_loaded());
+        assert!(recognizer.process_audio(&[0i16; 1600]).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_clean_final() {
+        let cases = [
+            ("hello world", false, "hello world"),
+            ("  hello world  ", false, "hello world"),
+            ("hello   \t world\n", false, "hello world"),
+            ("[unk] open the [unk] readme", false, "open the readme"),
+            ("[unk]  [unk]", false, ""),
+            ("", false, ""),
+            ("   ", false, ""),
+            (
+                "[unk] open the  [unk] readme ",
+                true,
+                "[unk] open the [unk] readme",
+            ),
+            // Only the exact token is dropped
+            ("[unknown] word", false, "[unknown] word"),
+        ];
+
+        for (input, keep_unknown, expected) in cases {
+            assert_eq!(clean_final(input, keep_unknown), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_mock_final_result_is_cleaned() {
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer.set_mock_text(" [unk] list  the files ");
+        assert_eq!(
+            recognizer.get_final_result().unwrap().text,
+            "list the files"
+        );
+
+        recognizer.set_keep_unknown_words(true);
+        assert_eq!(
+            recognizer.get_final_result().unwrap().text,
+            "[unk] list the files"
+        );
+    }
+
+    #[test]
+    fn test_recognizer_stats() {
+        let result = |text: &str, is_final: bool| RecognitionResult {
+            text: text.to_string(),
+            is_final,
+            confidence: None,
+            endpoint: false,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+        let stats = RecognizerStats::default();
+        stats.record_samples(1600);
+        stats.record_samples(800);
+        stats.record_result(&result("hello", false));
+        stats.record_result(&result("", false));
+        stats.record_result(&result("hello world", true));
+
+        assert_eq!(
+            stats.snapshot(true, 16000),
+            RecognizerDebug {
+                model_loaded: true,
+                sample_rate: 16000,
+                samples_processed: 2400,
+                last_partial: Some("hello".to_string()),
+                last_final: Some("hello world".to_string()),
+            }
+        );
+
+        stats.clear();
+        assert_eq!(stats.snapshot(false, 16000).samples_processed, 0);
+        assert_eq!(stats.snapshot(false, 16000).last_final, None);
     }
 
     #[test]
@@ -546,26 +2745,305 @@ pub(crate) mod tests {
         assert_eq!(recognizer.process_count(), 0);
     }
 
+    #[test]
+    fn test_mock_endpoint_transition() {
+        let recognizer = MockSpeechRecognizer::new();
+
+        let partial = recognizer.process_audio(&[0i16; 100]).unwrap().unwrap();
+        assert!(!partial.is_final);
+        assert!(!partial.endpoint);
+
+        // Recognizer finalizes the utterance on its own
+        recognizer.set_mock_final(true);
+        let finalized = recognizer.process_audio(&[0i16; 100]).unwrap().unwrap();
+        assert!(finalized.is_final);
+        assert!(finalized.endpoint);
+
+        // Forced by stop, not an endpoint
+        let stopped = recognizer.get_final_result().unwrap();
+        assert!(stopped.is_final);
+        assert!(!stopped.endpoint);
+    }
+
+    #[test]
+    fn test_recognition_result_endpoint_defaults_false() {
+        let result: RecognitionResult =
+            serde_json::from_str(r#"{"text":"hi","is_final":true,"confidence":null}"#).unwrap();
+        assert!(!result.endpoint);
+    }
+
+    #[test]
+    fn test_transcribe_samples_chunks_and_aggregates() {
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer.set_mock_text("from file");
+
+        let result = transcribe_samples(&recognizer, &vec![0i16; 16000]).unwrap();
+
+        assert_eq!(result.text, "from file");
+        assert!(result.is_final);
+        assert_eq!(recognizer.process_count(), 4);
+    }
+
+    #[test]
+    fn test_transcribe_samples_propagates_errors() {
+        let recognizer = MockSpeechRecognizer::new();
+        recognizer.set_should_fail(true);
+
+        let result = transcribe_samples(&recognizer, &[0i16; 1600]);
+        assert!(matches!(result, Err(SpeechError::RecognitionError(_))));
+    }
+
+    #[test]
+    fn test_vosk_call_reports_missing_runtime() {
+        assert_eq!(vosk_call(|| 42).unwrap(), 42);
+
+        let err = vosk_call(|| -> u32 { panic!("libvosk.so: cannot open shared object file") })
+            .unwrap_err();
+        assert!(
+            matches!(&err, SpeechError::ModelInitError(msg) if msg == VOSK_RUNTIME_UNAVAILABLE)
+        );
+        assert!(err.to_string().contains("Vosk runtime not available"));
+        assert_eq!(err.kind(), "model_init_error");
+    }
+
     #[test]
     fn test_model_manager_list_available() {
-        let manager = ModelManager::new(PathBuf::from("/test/models"));
+        let manager = ModelManager::new(PathBuf::from("/test/models"), false);
         let models = manager.list_available_models();
 
         assert!(!models.is_empty());
         assert!(models.iter().any(|m| m.name.contains("small-en-us")));
     }
 
+    /// Excerpt of the Vosk model index
+    const MODEL_INDEX_FIXTURE: &str = r#"[
+        {"lang": "en-us", "lang_text": "US English", "md5": "09ce2b1b",
+         "name": "vosk-model-small-en-us-0.15", "obsolete": "false", "size": 41205931,
+         "size_text": "39.3MiB", "type": "small", "version": "0.15",
+         "url": "https://alphacephei.com/vosk/models/vosk-model-small-en-us-0.15.zip"},
+        {"lang": "de", "lang_text": "German", "md5": "2f1b1f2c",
+         "name": "vosk-model-de-0.21", "obsolete": "false", "size": 1991817184,
+         "size_text": "1.9GiB", "type": "big", "version": "0.21",
+         "url": "https://alphacephei.com/vosk/models/vosk-model-de-0.21.zip"},
+        {"lang": "xx", "lang_text": "Klingon", "md5": "00000000",
+         "name": "vosk-model-small-xx-0.1", "obsolete": "false", "size": 1048576,
+         "size_text": "1MiB", "type": "small", "version": "0.1",
+         "url": "https://example.com/vosk-model-small-xx-0.1.zip"},
+        {"lang": "en-us", "lang_text": "US English", "md5": "5e9d1c6d",
+         "name": "vosk-model-small-en-us-0.3", "obsolete": "true", "size": 36700160,
+         "size_text": "35MiB", "type": "small", "version": "0.3",
+         "url": "https://alphacephei.com/vosk/models/vosk-model-small-en-us-0.3.zip"},
+        {"lang": "speaker", "lang_text": "Speaker identification", "md5": "4d2f7c5a",
+         "name": "vosk-model-spk-0.4", "obsolete": "false", "size": 13790681,
+         "size_text": "13MiB", "type": "spk", "version": "0.4",
+         "url": "https://alphacephei.com/vosk/models/vosk-model-spk-0.4.zip"}
+    ]"#;
+
+    #[test]
+    fn test_parse_model_index() {
+        let models = parse_model_index(MODEL_INDEX_FIXTURE, Path::new("/models")).unwrap();
+
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "vosk-model-small-en-us-0.15",
+                "vosk-model-de-0.21",
+                "vosk-model-small-xx-0.1"
+            ]
+        );
+        assert_eq!(
+            models[0].language,
+            ModelLanguage::new("en-us", "English (US)")
+        );
+        assert_eq!(models[0].size_mb, 40);
+        assert_eq!(models[0].version.as_deref(), Some("0.15"));
+        assert_eq!(
+            models[0].path,
+            Path::new("/models/vosk-model-small-en-us-0.15")
+        );
+        assert_eq!(
+            models[1].url.as_deref(),
+            Some("https://alphacephei.com/vosk/models/vosk-model-de-0.21.zip")
+        );
+        assert_eq!(models[1].size_mb, 1900);
+        // Languages missing from the table keep the index's name
+        assert_eq!(models[2].language, ModelLanguage::new("xx", "Klingon"));
+
+        assert!(matches!(
+            parse_model_index("<html>", Path::new("/models")),
+            Err(SpeechError::DownloadError(_))
+        ));
+    }
+
+    #[test]
+    fn test_model_catalog_is_cached() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (manager, fetcher) =
+            mock_download_manager(temp_dir.path(), MODEL_INDEX_FIXTURE.as_bytes().to_vec());
+        let manager = manager.with_index_url("https://example.com/index.json");
+
+        assert_eq!(manager.fetch_model_catalog().len(), 3);
+        assert_eq!(manager.fetch_model_catalog().len(), 3);
+        assert_eq!(
+            fetcher.requests(),
+            vec![("https://example.com/index.json".to_string(), 0)]
+        );
+        // The catalog also backs the offline listing
+        assert_eq!(manager.list_available_models().len(), 3);
+
+        // Once expired the index is fetched again, keeping the old catalog if that fails
+        fetcher.set_should_fail(true);
+        let expired = Instant::now().checked_sub(MODEL_INDEX_TTL);
+        if let Some(expired) = expired {
+            manager.catalog.lock().as_mut().unwrap().0 = expired;
+            assert_eq!(manager.fetch_model_catalog().len(), 3);
+            assert_eq!(fetcher.requests().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_model_catalog_offline_fallback() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (manager, fetcher) = mock_download_manager(temp_dir.path(), Vec::new());
+        fetcher.set_should_fail(true);
+
+        let models = manager.fetch_model_catalog();
+        assert_eq!(models, manager.list_available_models());
+        assert!(models
+            .iter()
+            .any(|m| m.name == "vosk-model-small-en-us-0.15"));
+        assert_eq!(fetcher.requests().len(), 1);
+
+        // An unreadable index falls back too
+        let (manager, _fetcher) = mock_download_manager(temp_dir.path(), b"not json".to_vec());
+        assert_eq!(
+            manager.fetch_model_catalog(),
+            manager.list_available_models()
+        );
+
+        // So does a server that stops responding, without waiting for it
+        let (manager, fetcher) =
+            mock_download_manager(temp_dir.path(), MODEL_INDEX_FIXTURE.as_bytes().to_vec());
+        let manager = manager.with_index_timeout(Duration::from_millis(50));
+        fetcher.set_delay(Duration::from_secs(30));
+        let started = Instant::now();
+        assert_eq!(
+            manager.fetch_model_catalog(),
+            manager.list_available_models()
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(manager.catalog.lock().is_none());
+    }
+
+    #[test]
+    fn test_model_manager_skips_ancestors_by_default() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let exe_path = std::env::current_exe().unwrap();
+        let exe_dir = exe_path.parent().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let mut ancestors: Vec<PathBuf> = exe_dir
+            .ancestors()
+            .skip(1)
+            .take(3)
+            .map(|dir| dir.join("models"))
+            .collect();
+        ancestors.extend(cwd.parent().map(|dir| dir.join("models")));
+
+        let manager = ModelManager::new(temp_dir.path().to_path_buf(), false);
+        let dirs = manager.get_all_model_dirs();
+        assert_eq!(dirs[0], temp_dir.path());
+        assert!(ancestors.iter().all(|dir| !dirs.contains(dir)));
+
+        let isolated = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+        assert_eq!(
+            isolated.get_all_model_dirs(),
+            vec![temp_dir.path().to_path_buf()]
+        );
+    }
+
     #[test]
     fn test_detect_language() {
-        assert_eq!(ModelManager::detect_language("vosk-model-en-us-0.22"), "English (US)");
-        assert_eq!(ModelManager::detect_language("vosk-model-small-en-us-0.15"), "English (US)");
-        assert_eq!(ModelManager::detect_language("vosk-model-de-0.21"), "German");
-        assert_eq!(ModelManager::detect_language("vosk-model-fr-0.22"), "French");
-        assert_eq!(ModelManager::detect_language("vosk-model-es-0.42"), "Spanish");
-        assert_eq!(ModelManager::detect_language("vosk-model-cn-0.22"), "Chinese");
-        assert_eq!(ModelManager::detect_language("vosk-model-ru-0.42"), "Russian");
-        assert_eq!(ModelManager::detect_language("vosk-model-ja-0.22"), "Japanese");
-        assert_eq!(ModelManager::detect_language("some-random-model"), "Unknown");
+        assert_eq!(ModelManager::detect_language("vosk-model-en-us-0.22").display_name, "English (US)");
+        assert_eq!(ModelManager::detect_language("vosk-model-small-en-us-0.15").display_name, "English (US)");
+        assert_eq!(ModelManager::detect_language("vosk-model-de-0.21").display_name, "German");
+        assert_eq!(ModelManager::detect_language("vosk-model-fr-0.22").display_name, "French");
+        assert_eq!(ModelManager::detect_language("vosk-model-es-0.42").display_name, "Spanish");
+        assert_eq!(ModelManager::detect_language("vosk-model-cn-0.22").display_name, "Chinese");
+        assert_eq!(ModelManager::detect_language("vosk-model-ru-0.42").display_name, "Russian");
+        assert_eq!(ModelManager::detect_language("vosk-model-ja-0.22").display_name, "Japanese");
+        assert_eq!(ModelManager::detect_language("some-random-model").display_name, "Unknown");
+    }
+
+    #[test]
+    fn test_detect_language_table() {
+        let cases = [
+            ("vosk-model-en-us-0.22", "en-us", "English (US)"),
+            ("vosk-model-small-en-us-0.15", "en-us", "English (US)"),
+            ("vosk-model-en-us-0.22-lgraph", "en-us", "English (US)"),
+            ("vosk-model-en-in-0.5", "en-in", "English (India)"),
+            ("vosk-model-small-en-in-0.4", "en-in", "English (India)"),
+            ("vosk-model-en-gb-0.1", "en-gb", "English (UK)"),
+            ("vosk-model-en-0.1", "en", "English"),
+            ("vosk_model_en_us_0.22", "en-us", "English (US)"),
+            ("VOSK-MODEL-SMALL-EN-US-0.15", "en-us", "English (US)"),
+            ("vosk-model-de-0.21", "de", "German"),
+            ("vosk-model-small-de-0.15", "de", "German"),
+            ("vosk-model-de-tuda-0.6-900k", "de", "German"),
+            ("vosk-model-fr-0.22", "fr", "French"),
+            ("vosk-model-small-fr-0.22", "fr", "French"),
+            ("vosk-model-es-0.42", "es", "Spanish"),
+            ("vosk-model-small-es-0.42", "es", "Spanish"),
+            ("vosk-model-it-0.22", "it", "Italian"),
+            ("vosk-model-small-it-0.22", "it", "Italian"),
+            ("vosk-model-pt-fb-v0.1.1-20220516_2113", "pt", "Portuguese"),
+            ("vosk-model-small-pt-0.3", "pt", "Portuguese"),
+            ("vosk-model-nl-spraakherkenning-0.6", "nl", "Dutch"),
+            ("vosk-model-small-nl-0.22", "nl", "Dutch"),
+            ("vosk-model-small-ca-0.4", "ca", "Catalan"),
+            ("vosk-model-ru-0.42", "ru", "Russian"),
+            ("vosk-model-small-ru-0.22", "ru", "Russian"),
+            ("vosk-model-uk-v3", "uk", "Ukrainian"),
+            ("vosk-model-small-uk-v3-nano", "uk", "Ukrainian"),
+            ("vosk-model-small-pl-0.22", "pl", "Polish"),
+            ("vosk-model-small-cs-0.4-rhasspy", "cs", "Czech"),
+            ("vosk-model-small-sv-rhasspy-0.15", "sv", "Swedish"),
+            ("vosk-model-el-gr-0.7", "el", "Greek"),
+            ("vosk-model-small-tr-0.3", "tr", "Turkish"),
+            ("vosk-model-ar-mgb2-0.4", "ar", "Arabic"),
+            ("vosk-model-ar-0.22-linto-1.1.0", "ar", "Arabic"),
+            ("vosk-model-fa-0.5", "fa", "Persian"),
+            ("vosk-model-small-fa-0.5", "fa", "Persian"),
+            ("vosk-model-hi-0.22", "hi", "Hindi"),
+            ("vosk-model-small-hi-0.22", "hi", "Hindi"),
+            ("vosk-model-small-te-0.42", "te", "Telugu"),
+            ("vosk-model-gu-0.42", "gu", "Gujarati"),
+            ("vosk-model-cn-0.22", "zh", "Chinese"),
+            ("vosk-model-small-cn-0.22", "zh", "Chinese"),
+            ("vosk-model-zh-0.1", "zh", "Chinese"),
+            ("vosk-model-ja-0.22", "ja", "Japanese"),
+            ("vosk-model-small-ja-0.22", "ja", "Japanese"),
+            ("vosk-model-jp-0.1", "ja", "Japanese"),
+            ("vosk-model-small-ko-0.22", "ko", "Korean"),
+            ("vosk-model-small-vn-0.4", "vi", "Vietnamese"),
+            ("vosk-model-vn-0.4", "vi", "Vietnamese"),
+            ("vosk-model-kz-0.15", "kk", "Kazakh"),
+            ("vosk-model-small-kz-0.15", "kk", "Kazakh"),
+            ("vosk-model-small-uz-0.22", "uz", "Uzbek"),
+            ("vosk-model-tg-0.22", "tg", "Tajik"),
+            ("vosk-model-small-eo-0.42", "eo", "Esperanto"),
+            ("vosk-model-br-0.8", "br", "Breton"),
+            ("some-random-model", "und", "Unknown"),
+            ("vosk-model-xx-0.1", "und", "Unknown"),
+            ("vosk-model-small", "und", "Unknown"),
+            ("", "und", "Unknown"),
+        ];
+
+        for (name, code, display_name) in cases {
+            let language = ModelManager::detect_language(name);
+            assert_eq!(language.code, code, "name: {:?}", name);
+            assert_eq!(language.display_name, display_name, "name: {:?}", name);
+        }
     }
 
     #[test]
@@ -669,10 +3147,117 @@ pub(crate) mod tests {
 
         // Check language detection
         let en_model = installed.iter().find(|m| m.name.contains("en-us")).unwrap();
-        assert_eq!(en_model.language, "English (US)");
+        assert_eq!(en_model.language.display_name, "English (US)");
 
         let de_model = installed.iter().find(|m| m.name.contains("-de-")).unwrap();
-        assert_eq!(de_model.language, "German");
+        assert_eq!(de_model.language.display_name, "German");
+        assert_eq!(de_model.language.code, "de");
+    }
+
+    #[test]
+    fn test_add_model_dir() {
+        let models_dir = tempfile::tempdir().unwrap();
+        let external = tempfile::tempdir().unwrap();
+        let model_dir = external.path().join("vosk-model-small-fr-0.22");
+        std::fs::create_dir_all(model_dir.join("am")).unwrap();
+        std::fs::create_dir_all(model_dir.join("graph")).unwrap();
+
+        let manager = ModelManager::new_isolated(models_dir.path().to_path_buf());
+        assert!(manager.list_installed_models().is_empty());
+
+        manager.add_model_dir(external.path()).unwrap();
+        let installed = manager.list_installed_models();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].name, "vosk-model-small-fr-0.22");
+        assert_eq!(installed[0].path, model_dir);
+
+        // Clones share the added folders
+        assert_eq!(manager.clone().user_model_dirs(), vec![external.path()]);
+
+        manager.remove_model_dir(external.path()).unwrap();
+        assert!(manager.list_installed_models().is_empty());
+    }
+
+    #[test]
+    fn test_add_model_dir_rejects_missing_and_duplicate() {
+        let models_dir = tempfile::tempdir().unwrap();
+        let external = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(models_dir.path().to_path_buf());
+
+        assert!(matches!(
+            manager.add_model_dir(&external.path().join("missing")),
+            Err(SpeechError::ModelDirNotFound(_))
+        ));
+        assert!(matches!(
+            manager.add_model_dir(models_dir.path()),
+            Err(SpeechError::DuplicateModelDir(_))
+        ));
+
+        manager.add_model_dir(external.path()).unwrap();
+        assert!(matches!(
+            manager.add_model_dir(&external.path().join(".")),
+            Err(SpeechError::DuplicateModelDir(_))
+        ));
+        assert_eq!(manager.user_model_dirs().len(), 1);
+
+        assert!(matches!(
+            manager.remove_model_dir(models_dir.path()),
+            Err(SpeechError::ModelDirNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_installed_models_reads_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_dir = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        std::fs::create_dir_all(model_dir.join("conf")).unwrap();
+        std::fs::create_dir_all(model_dir.join("graph")).unwrap();
+        std::fs::write(
+            model_dir.join("conf/mfcc.conf"),
+            "--use-energy=false\n--sample-frequency=8000\n--num-mel-bins=40\n",
+        )
+        .unwrap();
+        std::fs::write(
+            model_dir.join("README"),
+            "US English model for mobile Vosk applications\n\nVersion: 0.15-beta\n",
+        )
+        .unwrap();
+
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+        let installed = manager.list_installed_models();
+
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].sample_rate, Some(8000));
+        assert_eq!(installed[0].version.as_deref(), Some("0.15-beta"));
+    }
+
+    #[test]
+    fn test_model_metadata_missing_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_dir = temp_dir.path().join("custom-model");
+        std::fs::create_dir_all(model_dir.join("am")).unwrap();
+        std::fs::create_dir_all(model_dir.join("graph")).unwrap();
+
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+        let installed = manager.list_installed_models();
+
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].sample_rate, None);
+        assert_eq!(installed[0].version, None);
+    }
+
+    #[test]
+    fn test_version_from_name() {
+        assert_eq!(
+            ModelManager::version_from_name("vosk-model-en-us-0.22").as_deref(),
+            Some("0.22")
+        );
+        assert_eq!(
+            ModelManager::version_from_name("vosk-model-en-us-0.22-lgraph"),
+            None
+        );
+        assert_eq!(ModelManager::version_from_name("vosk-model-en-us"), None);
+        assert_eq!(ModelManager::version_from_name("model-1."), None);
     }
 
     #[test]
@@ -718,6 +3303,9 @@ pub(crate) mod tests {
             text: "Test text".to_string(),
             is_final: true,
             confidence: Some(0.95),
+            endpoint: false,
+            offset_ms: 1250,
+            words: Vec::new(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -728,6 +3316,7 @@ pub(crate) mod tests {
         let deserialized: RecognitionResult = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.text, result.text);
         assert_eq!(deserialized.is_final, result.is_final);
+        assert_eq!(deserialized.offset_ms, 1250);
     }
 
     #[test]
@@ -744,12 +3333,377 @@ pub(crate) mod tests {
         let info = ModelInfo {
             name: "test-model".to_string(),
             path: PathBuf::from("/test/path"),
-            language: "English".to_string(),
+            language: ModelLanguage::new("en", "English"),
             size_mb: 100,
+            sample_rate: Some(16000),
+            version: None,
+            url: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
         assert!(json.contains("test-model"));
         assert!(json.contains("English"));
     }
+
+    /// Build a small fake model directory for checksum tests
+    pub(crate) fn write_fixture_model(dir: &Path) {
+        std::fs::create_dir_all(dir.join("am")).unwrap();
+        std::fs::create_dir_all(dir.join("conf")).unwrap();
+        std::fs::write(dir.join("am/final.mdl"), b"acoustic model bytes").unwrap();
+        std::fs::write(dir.join("conf/mfcc.conf"), b"--sample-frequency=16000\n").unwrap();
+    }
+
+    /// Zip archive containing a minimal model folder called `name`
+    fn fixture_archive(name: &str) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(format!("{}/conf/mfcc.conf", name), options)
+            .unwrap();
+        zip.write_all(b"--sample-frequency=16000\n").unwrap();
+        zip.start_file(format!("{}/am/final.mdl", name), options)
+            .unwrap();
+        zip.write_all(&[7u8; 4096]).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Serve `body` to a single HTTP request, honouring `Range: bytes=N-`.
+    /// Set `truncate_at` to drop the connection after that many body bytes.
+    /// The handle yields the request head.
+    fn serve_once(
+        body: Vec<u8>,
+        truncate_at: Option<usize>,
+    ) -> (String, std::thread::JoinHandle<String>) {
+        use std::io::{BufRead, BufReader};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push_str(&line.to_ascii_lowercase());
+            }
+
+            let start = request
+                .lines()
+                .find_map(|l| l.strip_prefix("range: bytes="))
+                .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+            let head = match start {
+                Some(start) => format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                     Content-Range: bytes {}-{}/{}\r\n",
+                    body.len() - start,
+                    start,
+                    body.len() - 1,
+                    body.len()
+                ),
+                None => format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", body.len()),
+            };
+            let sent = &body[start.unwrap_or(0)..];
+            let sent = &sent[..truncate_at.unwrap_or(sent.len()).min(sent.len())];
+
+            stream
+                .write_all(format!("{}Connection: close\r\n\r\n", head).as_bytes())
+                .unwrap();
+            stream.write_all(sent).unwrap();
+            request
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_download_model_extracts_archive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (url, server) = serve_once(archive.clone(), None);
+        let manager =
+            ModelManager::new_isolated(temp_dir.path().to_path_buf()).with_download_url(url);
+
+        let reports = Mutex::new(Vec::new());
+        let info = manager
+            .download_model("vosk-model-small-test-0.1", &CancelToken::default(), &|p| {
+                reports.lock().push(p.clone())
+            })
+            .unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("get /vosk-model-small-test-0.1.zip "));
+        assert!(!request.contains("range:"));
+
+        assert_eq!(info.name, "vosk-model-small-test-0.1");
+        assert!(ModelManager::is_valid_vosk_model(&info.path));
+        assert!(!manager.has_partial_download("vosk-model-small-test-0.1"));
+        assert!(!temp_dir
+            .path()
+            .join("vosk-model-small-test-0.1.zip")
+            .exists());
+
+        let last = reports.lock().last().cloned().unwrap();
+        assert_eq!(last.downloaded, archive.len() as u64);
+        assert_eq!(last.total, Some(archive.len() as u64));
+    }
+
+    #[test]
+    fn test_download_model_resumes_partial_download() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (url, server) = serve_once(archive.clone(), None);
+        let manager =
+            ModelManager::new_isolated(temp_dir.path().to_path_buf()).with_download_url(url);
+
+        let half = archive.len() / 2;
+        std::fs::write(
+            manager.partial_download_path("vosk-model-small-test-0.1"),
+            &archive[..half],
+        )
+        .unwrap();
+        assert!(manager.has_partial_download("vosk-model-small-test-0.1"));
+
+        let reports = Mutex::new(Vec::new());
+        let info = manager
+            .download_model("vosk-model-small-test-0.1", &CancelToken::default(), &|p| {
+                reports.lock().push(p.clone())
+            })
+            .unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.contains(&format!("range: bytes={}-", half)));
+        assert!(ModelManager::is_valid_vosk_model(&info.path));
+
+        // Progress starts from the bytes already on disk
+        let reports = reports.lock();
+        assert_eq!(reports[0].downloaded, half as u64);
+        assert_eq!(reports.last().unwrap().downloaded, archive.len() as u64);
+        assert!(reports
+            .iter()
+            .all(|p| p.total == Some(archive.len() as u64)));
+    }
+
+    #[test]
+    fn test_download_model_keeps_partial_when_interrupted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = fixture_archive("vosk-model-small-test-0.1");
+        let (url, server) = serve_once(archive, Some(1000));
+        let manager =
+            ModelManager::new_isolated(temp_dir.path().to_path_buf()).with_download_url(url);
+
+        let result = manager.download_model(
+            "vosk-model-small-test-0.1",
+            &CancelToken::default(),
+            &|_| {},
+        );
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(SpeechError::DownloadError(_))));
+        let partial = manager.partial_download_path("vosk-model-small-test-0.1");
+        assert_eq!(std::fs::metadata(partial).unwrap().len(), 1000);
+        assert!(!temp_dir.path().join("vosk-model-small-test-0.1").exists());
+    }
+
+    #[test]
+    fn test_download_model_rejects_path_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+
+        for name in ["", "..", "../escape", "a\\b"] {
+            assert!(matches!(
+                manager.download_model(name, &CancelToken::default(), &|_| {}),
+                Err(SpeechError::ModelNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_content_range_total() {
+        assert_eq!(parse_content_range_total("bytes 100-199/1000"), Some(1000));
+        assert_eq!(parse_content_range_total("bytes */1000"), Some(1000));
+        assert_eq!(parse_content_range_total("bytes 0-99/*"), None);
+        assert_eq!(parse_content_range_total("garbage"), None);
+    }
+
+    #[test]
+    fn test_model_hash_of_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let zip = temp_dir.path().join("model.zip");
+        std::fs::write(&zip, b"abc").unwrap();
+
+        assert_eq!(
+            model_hash(&zip).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_model_hash_of_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        write_fixture_model(&a);
+        write_fixture_model(&b);
+
+        // Same contents in a different location hash the same
+        let hash = model_hash(&a).unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, model_hash(&b).unwrap());
+
+        // Changed contents change the hash
+        std::fs::write(b.join("am/final.mdl"), b"truncated").unwrap();
+        assert_ne!(hash, model_hash(&b).unwrap());
+
+        // So does moving a file
+        write_fixture_model(&b);
+        std::fs::rename(b.join("am/final.mdl"), b.join("final.mdl")).unwrap();
+        assert_ne!(hash, model_hash(&b).unwrap());
+    }
+
+    #[test]
+    fn test_verify_model_hash_match() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        write_fixture_model(&model);
+        let expected = model_hash(&model).unwrap();
+
+        verify_model_hash(&model, &expected).unwrap();
+        verify_model_hash(&model, &expected.to_uppercase()).unwrap();
+        assert!(model.exists());
+    }
+
+    #[test]
+    fn test_verify_model_hash_mismatch_deletes_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        write_fixture_model(&model);
+
+        let err = verify_model_hash(&model, &"0".repeat(64)).unwrap_err();
+        assert!(matches!(err, SpeechError::DownloadError(_)));
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(!model.exists(), "Corrupted model should be deleted");
+
+        let zip = temp_dir.path().join("model.zip");
+        std::fs::write(&zip, b"partial download").unwrap();
+        assert!(verify_model_hash(&zip, &"0".repeat(64)).is_err());
+        assert!(!zip.exists());
+    }
+
+    #[test]
+    fn test_known_model_hashes_well_formed() {
+        for (name, hash) in KNOWN_MODEL_HASHES {
+            assert!(validate_model_name(name).is_ok(), "name: {:?}", name);
+            assert_eq!(hash.len(), 64, "hash of {}", name);
+            assert!(
+                hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')),
+                "hash of {} must be lowercase hex",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_model_against_known_hashes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let good = temp_dir.path().join("vosk-model-good-0.1");
+        let bad = temp_dir.path().join("vosk-model-bad-0.1");
+        write_fixture_model(&good);
+        write_fixture_model(&bad);
+        std::fs::write(temp_dir.path().join("vosk-model-archive-0.1.zip"), b"abc").unwrap();
+
+        let good_hash = model_hash(&good).unwrap();
+        let known = [
+            ("vosk-model-good-0.1", good_hash.as_str()),
+            ("vosk-model-bad-0.1", "deadbeef"),
+            (
+                "vosk-model-archive-0.1",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+        ];
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+
+        assert!(manager
+            .verify_model_against("vosk-model-good-0.1", &known)
+            .unwrap());
+        assert!(manager
+            .verify_model_against("vosk-model-archive-0.1", &known)
+            .unwrap());
+
+        let err = manager
+            .verify_model_against("vosk-model-bad-0.1", &known)
+            .unwrap_err();
+        assert!(matches!(err, SpeechError::DownloadError(_)));
+        assert!(!bad.exists());
+        assert!(good.exists());
+    }
+
+    #[test]
+    fn test_verify_model_unknown_or_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model = temp_dir.path().join("vosk-model-custom");
+        write_fixture_model(&model);
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+
+        // No known checksum: reported as unverified, files untouched
+        assert!(!manager
+            .verify_model_against("vosk-model-custom", &[])
+            .unwrap());
+        assert!(model.exists());
+
+        assert!(matches!(
+            manager.verify_model("vosk-model-missing"),
+            Err(SpeechError::ModelNotFound(_))
+        ));
+        assert!(matches!(
+            manager.verify_model("../vosk-model-custom"),
+            Err(SpeechError::ModelNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_duplicate_models() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::new_isolated(temp_dir.path().to_path_buf());
+        let original = temp_dir.path().join("vosk-model-en-us-0.22");
+        let renamed = temp_dir.path().join("english-big");
+        let other = temp_dir.path().join("vosk-model-small-en-us-0.15");
+        write_fixture_model(&original);
+        write_fixture_model(&renamed);
+        write_fixture_model(&other);
+        std::fs::write(other.join("am/final.mdl"), b"other acoustic model").unwrap();
+
+        // Same size and leading bytes as the original, different after them
+        let retrained = temp_dir.path().join("vosk-model-en-us-retrained");
+        write_fixture_model(&retrained);
+        let mut acoustic = vec![0u8; FINGERPRINT_HEAD_BYTES as usize + 16];
+        std::fs::write(original.join("am/final.mdl"), &acoustic).unwrap();
+        std::fs::write(renamed.join("am/final.mdl"), &acoustic).unwrap();
+        acoustic[FINGERPRINT_HEAD_BYTES as usize + 8] = 1;
+        std::fs::write(retrained.join("am/final.mdl"), &acoustic).unwrap();
+        assert_eq!(
+            model_fingerprint(&original).unwrap(),
+            model_fingerprint(&retrained).unwrap()
+        );
+        // An archive isn't fingerprinted
+        std::fs::write(temp_dir.path().join("english-big.zip"), b"zip").unwrap();
+
+        let duplicates = manager.find_duplicate_models();
+        assert_eq!(duplicates.len(), 1);
+        let names: Vec<&str> = duplicates[0].iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["english-big", "vosk-model-en-us-0.22"]);
+
+        assert_eq!(
+            model_fingerprint(&original).unwrap(),
+            model_fingerprint(&renamed).unwrap()
+        );
+        assert_ne!(
+            model_fingerprint(&original).unwrap(),
+            model_fingerprint(&other).unwrap()
+        );
+        assert!(model_fingerprint(temp_dir.path()).is_err());
+    }
 }
@@ -0,0 +1,221 @@
+//! Wake-word gating module.
+//!
+//! For always-on listening, nothing is forwarded until a wake phrase like
+//! "hey tool" is heard. The gate then stays armed until an end phrase is
+//! spoken or no speech is heard for a while. The phrases themselves are
+//! removed from the utterances that contain them, so "hey tool list files"
+//! dictates "list files".
+
+use crate::voice_commands::normalize;
+use std::ops::Range;
+
+/// Outcome of passing a final utterance through the gate
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gated {
+    /// Words of the utterance to forward, by whitespace-separated index
+    pub keep: Option<Range<usize>>,
+    /// The wake phrase armed the gate
+    pub woke: bool,
+    /// The end phrase disarmed the gate
+    pub ended: bool,
+}
+
+/// Arms on a wake phrase and disarms on an end phrase or timeout
+#[derive(Debug, Clone, PartialEq)]
+pub struct WakeGate {
+    /// Normalized words of the wake phrase
+    wake: Vec<String>,
+    /// Normalized words of the end phrase; empty if there is none
+    end: Vec<String>,
+    /// Audio without speech after which the gate disarms; 0 never times out
+    timeout_ms: u64,
+    /// Audio position speech was last heard at while armed; None while
+    /// waiting for the wake phrase
+    last_heard_ms: Option<u64>,
+}
+
+impl WakeGate {
+    /// Create a disarmed gate, or None if the wake phrase has no words
+    pub fn new(phrase: &str, end_phrase: Option<&str>, timeout_ms: u64) -> Option<Self> {
+        let wake = phrase_words(phrase);
+        if wake.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            wake,
+            end: end_phrase.map(phrase_words).unwrap_or_default(),
+            timeout_ms,
+            last_heard_ms: None,
+        })
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.last_heard_ms.is_some()
+    }
+
+    /// Wait for the wake phrase again
+    pub fn disarm(&mut self) {
+        self.last_heard_ms = None;
+    }
+
+    /// Note speech heard at `now_ms`, which keeps an armed gate open
+    pub fn heard(&mut self, now_ms: u64) {
+        if self.is_armed() {
+            self.last_heard_ms = Some(now_ms);
+        }
+    }
+
+    /// Disarm if no speech has been heard for the timeout, returning true if
+    /// the gate timed out just now
+    pub fn check_timeout(&mut self, now_ms: u64) -> bool {
+        match self.last_heard_ms {
+            Some(heard)
+                if self.timeout_ms > 0 && now_ms.saturating_sub(heard) >= self.timeout_ms =>
+            {
+                self.disarm();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pass a final utterance heard at `now_ms` through the gate, arming or
+    /// disarming it on the phrases it contains
+    pub fn filter_final(&mut self, text: &str, now_ms: u64) -> Gated {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut gated = Gated::default();
+
+        let mut start = if self.is_armed() {
+            0
+        } else {
+            let Some(found) = find_phrase(&words, &self.wake) else {
+                return gated;
+            };
+            gated.woke = true;
+            found.end
+        };
+        self.last_heard_ms = Some(now_ms);
+
+        let mut end = words.len();
+        if let Some(found) = find_phrase(&words[start..], &self.end) {
+            end = start + found.start;
+            gated.ended = true;
+            self.disarm();
+        }
+        // Don't forward punctuation left over from a removed phrase
+        while start < end && normalize(words[start]).is_empty() {
+            start += 1;
+        }
+
+        gated.keep = (start < end).then_some(start..end);
+        gated
+    }
+}
+
+fn phrase_words(phrase: &str) -> Vec<String> {
+    normalize(phrase)
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Index range of the first occurrence of `phrase` in `words`, comparing
+/// normalized words and skipping any that are only punctuation
+fn find_phrase(words: &[&str], phrase: &[String]) -> Option<Range<usize>> {
+    if phrase.is_empty() {
+        return None;
+    }
+
+    let normalized: Vec<(usize, String)> = words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| (i, normalize(word)))
+        .filter(|(_, word)| !word.is_empty())
+        .collect();
+    normalized
+        .windows(phrase.len())
+        .find(|window| window.iter().zip(phrase).all(|((_, a), b)| a == b))
+        .map(|window| window[0].0..window[window.len() - 1].0 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate() -> WakeGate {
+        WakeGate::new("Hey tool", Some("over and out"), 5000).unwrap()
+    }
+
+    #[test]
+    fn test_suppressed_until_wake_phrase() {
+        let mut gate = gate();
+
+        assert_eq!(gate.filter_final("list files", 1000), Gated::default());
+        assert!(!gate.is_armed());
+
+        let gated = gate.filter_final("hey, tool! list files", 2000);
+        assert!(gated.woke);
+        assert_eq!(gated.keep, Some(2..4));
+        assert!(gate.is_armed());
+
+        let gated = gate.filter_final("run the tests", 3000);
+        assert!(!gated.woke);
+        assert_eq!(gated.keep, Some(0..3));
+    }
+
+    #[test]
+    fn test_wake_phrase_alone_forwards_nothing() {
+        let mut gate = gate();
+
+        let gated = gate.filter_final("Hey tool.", 1000);
+        assert!(gated.woke);
+        assert_eq!(gated.keep, None);
+        assert!(gate.is_armed());
+    }
+
+    #[test]
+    fn test_end_phrase_disarms() {
+        let mut gate = gate();
+        gate.filter_final("hey tool", 1000);
+
+        let gated = gate.filter_final("commit it over and out", 2000);
+        assert!(gated.ended);
+        assert_eq!(gated.keep, Some(0..2));
+        assert!(!gate.is_armed());
+
+        // Spoken while disarmed, the end phrase is just suppressed
+        assert_eq!(gate.filter_final("over and out", 3000), Gated::default());
+
+        let gated = gate.filter_final("hey tool - stop over and out", 4000);
+        assert!(gated.woke && gated.ended);
+        assert_eq!(gated.keep, Some(3..4));
+    }
+
+    #[test]
+    fn test_timeout_disarms() {
+        let mut gate = gate();
+        assert!(!gate.check_timeout(60_000));
+
+        gate.filter_final("hey tool", 1000);
+        gate.heard(3000);
+        assert!(!gate.check_timeout(7999));
+        assert!(gate.check_timeout(8000));
+        assert!(!gate.is_armed());
+        assert!(!gate.check_timeout(9000));
+
+        // Speech while disarmed doesn't arm the gate
+        gate.heard(10_000);
+        assert!(!gate.is_armed());
+
+        let mut gate = WakeGate::new("hey tool", None, 0).unwrap();
+        gate.filter_final("hey tool", 1000);
+        assert!(!gate.check_timeout(u64::MAX));
+    }
+
+    #[test]
+    fn test_wake_phrase_must_have_words() {
+        assert_eq!(WakeGate::new("  ?! ", None, 0), None);
+    }
+}
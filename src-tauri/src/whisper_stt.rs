@@ -0,0 +1,317 @@
+//! whisper.cpp speech recognizer.
+//!
+//! Whisper transcribes whole clips instead of streaming, so audio is buffered
+//! until the speaker pauses and the utterance is then transcribed in one pass.
+//! Only final results are produced; there are no partials while speaking.
+
+use crate::audio::compute_level;
+use crate::vosk_stt::{
+    RecognitionResult, RecognizerDebug, RecognizerStats, SpeechError, SpeechRecognizer,
+};
+use parking_lot::Mutex;
+use std::path::Path;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Rate whisper models are trained at
+const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Buffer RMS (0.0–1.0) above which a buffer counts as speech
+const SPEECH_RMS: f32 = 0.01;
+
+/// Silence after speech that ends an utterance
+const ENDPOINT_SILENCE_MS: u64 = 800;
+
+/// Leading silence kept before speech starts, so the first word isn't clipped
+const LEAD_IN_MS: u64 = 300;
+
+/// Whisper's context window; longer utterances are transcribed in chunks
+const MAX_UTTERANCE_MS: u64 = 30_000;
+
+fn ms_to_samples(ms: u64) -> usize {
+    (ms * WHISPER_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+/// Audio of the utterance in progress
+#[derive(Debug, Default)]
+struct Utterance {
+    samples: Vec<i16>,
+    /// Whether any buffer so far was loud enough to be speech
+    heard_speech: bool,
+    /// Samples of silence since the last speech
+    silence: usize,
+}
+
+impl Utterance {
+    /// Add a buffer, returning the utterance's audio once it has ended
+    fn push(&mut self, samples: &[i16]) -> Option<Vec<i16>> {
+        self.samples.extend_from_slice(samples);
+
+        if compute_level(samples).rms >= SPEECH_RMS {
+            self.heard_speech = true;
+            self.silence = 0;
+        } else {
+            self.silence += samples.len();
+        }
+
+        if !self.heard_speech {
+            let excess = self.samples.len().saturating_sub(ms_to_samples(LEAD_IN_MS));
+            self.samples.drain(..excess);
+            return None;
+        }
+
+        if self.silence >= ms_to_samples(ENDPOINT_SILENCE_MS)
+            || self.samples.len() >= ms_to_samples(MAX_UTTERANCE_MS)
+        {
+            return self.take();
+        }
+        None
+    }
+
+    /// The buffered speech, if any, starting a new utterance
+    fn take(&mut self) -> Option<Vec<i16>> {
+        let utterance = std::mem::take(self);
+        utterance.heard_speech.then_some(utterance.samples)
+    }
+}
+
+/// Recognizer backed by a whisper.cpp ggml model (e.g. `ggml-base.en.bin`)
+pub struct WhisperRecognizer {
+    state: Mutex<Option<whisper_rs::WhisperState>>,
+    utterance: Mutex<Utterance>,
+    stats: RecognizerStats,
+}
+
+impl Default for WhisperRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WhisperRecognizer {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(None),
+            utterance: Mutex::new(Utterance::default()),
+            stats: RecognizerStats::default(),
+        }
+    }
+
+    /// Run the model over a complete utterance
+    fn transcribe(&self, samples: &[i16]) -> Result<RecognitionResult, SpeechError> {
+        let mut state = self.state.lock();
+        let state = state.as_mut().ok_or_else(|| {
+            SpeechError::RecognizerError("Recognizer not initialized".to_string())
+        })?;
+
+        let mut audio = vec![0.0f32; samples.len()];
+        whisper_rs::convert_integer_to_float_audio(samples, &mut audio)
+            .map_err(|e| SpeechError::RecognitionError(e.to_string()))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        // Each utterance is independent; earlier text would bias the decode
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        state
+            .full(params, &audio)
+            .map_err(|e| SpeechError::RecognitionError(e.to_string()))?;
+
+        let segments = state
+            .full_n_segments()
+            .map_err(|e| SpeechError::RecognitionError(e.to_string()))?;
+        let mut text = Vec::new();
+        for segment in 0..segments {
+            let segment_text = state
+                .full_get_segment_text(segment)
+                .map_err(|e| SpeechError::RecognitionError(e.to_string()))?;
+            text.push(segment_text.trim().to_string());
+        }
+
+        let result = RecognitionResult {
+            text: text.join(" ").trim().to_string(),
+            is_final: true,
+            // Whisper has no per-word confidence comparable to Vosk's
+            confidence: None,
+            endpoint: true,
+            offset_ms: 0,
+            words: Vec::new(),
+        };
+        self.stats.record_result(&result);
+        Ok(result)
+    }
+}
+
+fn empty_final() -> RecognitionResult {
+    RecognitionResult {
+        text: String::new(),
+        is_final: true,
+        confidence: None,
+        endpoint: false,
+        offset_ms: 0,
+        words: Vec::new(),
+    }
+}
+
+impl SpeechRecognizer for WhisperRecognizer {
+    fn load_model(&self, model_path: &Path) -> Result<(), SpeechError> {
+        if !model_path.is_file() {
+            return Err(SpeechError::ModelNotFound(
+                model_path.to_string_lossy().to_string(),
+            ));
+        }
+
+        let context = WhisperContext::new_with_params(
+            &model_path.to_string_lossy(),
+            WhisperContextParameters::default(),
+        )
+        .map_err(|e| SpeechError::ModelInitError(e.to_string()))?;
+        let state = context
+            .create_state()
+            .map_err(|e| SpeechError::RecognizerError(e.to_string()))?;
+
+        *self.state.lock() = Some(state);
+        *self.utterance.lock() = Utterance::default();
+        self.stats.clear();
+        Ok(())
+    }
+
+    fn is_model_loaded(&self) -> bool {
+        self.state.lock().is_some()
+    }
+
+    fn process_audio(&self, samples: &[i16]) -> Result<Option<RecognitionResult>, SpeechError> {
+        if !self.is_model_loaded() {
+            return Err(SpeechError::RecognizerError(
+                "Recognizer not initialized".to_string(),
+            ));
+        }
+        self.stats.record_samples(samples.len());
+
+        let utterance = self.utterance.lock().push(samples);
+        match utterance {
+            Some(utterance) => self.transcribe(&utterance).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn get_final_result(&self) -> Result<RecognitionResult, SpeechError> {
+        let utterance = self.utterance.lock().take();
+        match utterance {
+            Some(utterance) => self.transcribe(&utterance).map(|mut result| {
+                // Stopped by the user rather than a pause
+                result.endpoint = false;
+                result
+            }),
+            None => Ok(empty_final()),
+        }
+    }
+
+    fn reset(&self) {
+        *self.utterance.lock() = Utterance::default();
+    }
+
+    fn unload_model(&self) {
+        *self.state.lock() = None;
+        *self.utterance.lock() = Utterance::default();
+        self.stats.clear();
+    }
+
+    fn sample_rate(&self) -> u32 {
+        WHISPER_SAMPLE_RATE
+    }
+
+    fn set_sample_rate(&self, sample_rate: f32) -> Result<(), SpeechError> {
+        if sample_rate as u32 == WHISPER_SAMPLE_RATE {
+            return Ok(());
+        }
+        Err(SpeechError::RecognizerError(format!(
+            "Whisper only accepts {} Hz audio",
+            WHISPER_SAMPLE_RATE
+        )))
+    }
+
+    fn model_sample_rate(&self) -> Option<u32> {
+        None
+    }
+
+    fn debug_info(&self) -> RecognizerDebug {
+        self.stats
+            .snapshot(self.is_model_loaded(), WHISPER_SAMPLE_RATE)
+    }
+
+    // Whisper always guesses a word, it has no unknown-word token
+    fn set_keep_unknown_words(&self, _keep: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `ms` milliseconds of audio at a constant `amplitude`
+    fn audio(ms: u64, amplitude: i16) -> Vec<i16> {
+        vec![amplitude; ms_to_samples(ms)]
+    }
+
+    #[test]
+    fn test_utterance_ends_after_silence() {
+        let mut utterance = Utterance::default();
+
+        assert_eq!(utterance.push(&audio(1000, 0)), None);
+        assert_eq!(utterance.push(&audio(500, 4000)), None);
+        assert_eq!(utterance.push(&audio(500, 0)), None);
+
+        // Leading silence is trimmed to the lead-in
+        let samples = utterance.push(&audio(300, 0)).unwrap();
+        assert_eq!(samples.len(), ms_to_samples(LEAD_IN_MS + 500 + 800));
+        assert_eq!(utterance.push(&audio(100, 0)), None);
+    }
+
+    #[test]
+    fn test_long_utterance_is_chunked() {
+        let mut utterance = Utterance::default();
+
+        let mut chunks = 0;
+        for _ in 0..35 {
+            if utterance.push(&audio(1000, 4000)).is_some() {
+                chunks += 1;
+            }
+        }
+        assert_eq!(chunks, 1);
+        assert_eq!(utterance.samples.len(), ms_to_samples(5000));
+    }
+
+    #[test]
+    fn test_take_without_speech() {
+        let mut utterance = Utterance::default();
+        utterance.push(&audio(500, 0));
+        assert_eq!(utterance.take(), None);
+
+        utterance.push(&audio(500, 4000));
+        assert_eq!(utterance.take().unwrap().len(), ms_to_samples(500));
+        assert!(utterance.samples.is_empty());
+    }
+
+    #[test]
+    fn test_recognizer_contract_without_model() {
+        let recognizer = WhisperRecognizer::new();
+
+        assert!(!recognizer.is_model_loaded());
+        assert_eq!(recognizer.sample_rate(), 16000);
+        assert!(matches!(
+            recognizer.process_audio(&audio(100, 0)),
+            Err(SpeechError::RecognizerError(_))
+        ));
+        assert!(matches!(
+            recognizer.load_model(Path::new("/nonexistent/ggml-base.en.bin")),
+            Err(SpeechError::ModelNotFound(_))
+        ));
+
+        let result = recognizer.get_final_result().unwrap();
+        assert!(result.is_final);
+        assert_eq!(result.text, "");
+        assert!(!recognizer.debug_info().model_loaded);
+    }
+}
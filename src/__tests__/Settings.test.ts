@@ -8,13 +8,13 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn((cmd: string) => {
     if (cmd === 'list_audio_devices') {
       return Promise.resolve([
-        { name: 'Test Microphone', is_default: true },
-        { name: 'Secondary Mic', is_default: false },
+        { name: 'Test Microphone', is_default: true, is_loopback: false },
+        { name: 'Secondary Mic', is_default: false, is_loopback: false },
       ]);
     }
     if (cmd === 'list_models') {
       return Promise.resolve([
-        { name: 'vosk-model-small-en', path: '/models/small', language: 'English', size_mb: 40 },
+        { name: 'vosk-model-small-en', path: '/models/small', language: { code: 'en', display_name: 'English' }, size_mb: 40 },
       ]);
     }
     if (cmd === 'list_installed_models') {
@@ -122,7 +122,7 @@ describe('VoiceControl Component', () => {
     expect(button.hasAttribute('disabled')).toBe(false);
   });
 
-  it('should call send_to_claude with Enter when fire button clicked', async () => {
+  it('should send the enter key to Claude when fire button clicked', async () => {
     const { invoke } = await import('@tauri-apps/api/core');
     vi.mocked(invoke).mockResolvedValue(undefined);
 
@@ -135,7 +135,7 @@ describe('VoiceControl Component', () => {
     // Wait for async invoke call
     await new Promise((resolve) => setTimeout(resolve, 50));
 
-    expect(invoke).toHaveBeenCalledWith('send_to_claude', { input: '\r' });
+    expect(invoke).toHaveBeenCalledWith('send_key_to_claude', { key: 'enter' });
   });
 });
 
@@ -174,4 +174,22 @@ describe('VoiceControl keyboard events', () => {
     // Should have called start_recording
     expect(invoke).toHaveBeenCalledWith('start_recording', { deviceName: null });
   });
+
+  it('should ask for microphone access and retry when it is undetermined', async () => {
+    const { invoke } = await import('@tauri-apps/api/core');
+    vi.mocked(invoke).mockReset();
+    vi.mocked(invoke)
+      .mockRejectedValueOnce({ kind: 'microphone_permission_undetermined', message: '' })
+      .mockResolvedValueOnce('granted')
+      .mockResolvedValue(undefined);
+
+    render(VoiceControl);
+    await new Promise((resolve) => setTimeout(resolve, 50));
+
+    await fireEvent.keyDown(window, { code: 'Space' });
+    await new Promise((resolve) => setTimeout(resolve, 50));
+
+    expect(invoke).toHaveBeenCalledWith('request_microphone_permission');
+    expect(vi.mocked(invoke).mock.calls.filter(([cmd]) => cmd === 'start_recording')).toHaveLength(2);
+  });
 });
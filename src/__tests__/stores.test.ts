@@ -21,6 +21,7 @@ import {
   clearTerminalOutput,
   updateTranscription,
   clearTranscription,
+  commandErrorMessage,
   loadSettings,
   saveSettings,
   type RecognitionResult,
@@ -40,8 +41,8 @@ describe('Audio Stores', () => {
 
   it('should update audioDevices', () => {
     const devices = [
-      { name: 'Microphone 1', is_default: true },
-      { name: 'Microphone 2', is_default: false },
+      { name: 'Microphone 1', is_default: true, is_loopback: false },
+      { name: 'Microphone 2', is_default: false, is_loopback: false },
     ];
     audioDevices.set(devices);
     expect(get(audioDevices)).toEqual(devices);
@@ -71,6 +72,8 @@ describe('Transcription Stores', () => {
       text: 'partial text',
       is_final: false,
       confidence: 0.8,
+      endpoint: false,
+      offset_ms: 0,
     };
     updateTranscription(result);
     expect(get(partialTranscription)).toBe('partial text');
@@ -82,6 +85,8 @@ describe('Transcription Stores', () => {
       text: 'final text',
       is_final: true,
       confidence: 0.95,
+      endpoint: true,
+      offset_ms: 1500,
     };
     updateTranscription(result);
     expect(get(currentTranscription)).toBe('final text');
@@ -107,7 +112,7 @@ describe('Model Stores', () => {
 
   it('should track available models', () => {
     const models = [
-      { name: 'model-1', path: '/path/1', language: 'en', size_mb: 100 },
+      { name: 'model-1', path: '/path/1', language: { code: 'en', display_name: 'English' }, size_mb: 100, sample_rate: null, version: null, url: null },
     ];
     availableModels.set(models);
     expect(get(availableModels)).toEqual(models);
@@ -171,6 +176,18 @@ describe('Terminal Output', () => {
   });
 });
 
+describe('Command Errors', () => {
+  it('should use the message of a command error', () => {
+    expect(
+      commandErrorMessage({ kind: 'model_not_found', message: 'Model not found at path: /x' })
+    ).toBe('Model not found at path: /x');
+  });
+
+  it('should stringify other errors', () => {
+    expect(commandErrorMessage('plain failure')).toBe('plain failure');
+  });
+});
+
 describe('Derived canRecord Store', () => {
   beforeEach(() => {
     isModelLoaded.set(false);
@@ -10,27 +10,167 @@ import { writable, derived, type Readable } from 'svelte/store';
 export interface AudioDevice {
   name: string;
   is_default: boolean;
+  // Output device captured as input (system audio); only listed on Windows
+  is_loopback: boolean;
+}
+
+// Input configuration range supported by a device
+export interface DeviceConfig {
+  min_rate: number;
+  max_rate: number;
+  channels: number;
+  format: string;
+  usable: boolean;
+}
+
+// Payload of the `recording-started` event
+export interface StreamInfo {
+  device: string;
+  sample_rate: number;
+  channels: number;
+  // Fixed buffer size, or null when using the device default
+  buffer_frames: number | null;
+}
+
+// Payload of the `recognition-error` and `audio-error` events
+export interface BackendErrorEvent {
+  kind: string;
+  message: string;
+}
+
+// Rejection value of backend commands; `kind` is a stable snake_case name
+// such as 'model_not_found' or 'device_not_found'
+export type CommandError = BackendErrorEvent;
+
+// App action fired by a spoken trigger phrase
+export type VoiceAction = 'StopRecording' | 'SendToClaude' | 'ClearTerminal' | 'Cancel';
+
+// Payload of the `transcription-rejected` event
+export interface RejectedTranscription {
+  text: string;
+  confidence: number | null;
+  is_final: boolean;
+}
+
+// Payload of the `voice-command` event
+export interface VoiceCommandEvent {
+  action: VoiceAction;
+  text: string;
+}
+
+export interface ModelLanguage {
+  code: string;
+  display_name: string;
 }
 
 export interface ModelInfo {
   name: string;
   path: string;
-  language: string;
+  language: ModelLanguage;
   size_mb: number;
+  sample_rate: number | null;
+  version: string | null;
+  url: string | null;
+}
+
+// Payload of the `download-progress` event
+export interface DownloadProgress {
+  name: string;
+  downloaded: number;
+  total: number | null;
 }
 
 export interface RecognitionResult {
   text: string;
   is_final: boolean;
   confidence: number | null;
+  endpoint: boolean;
+  // Milliseconds since recording started
+  offset_ms: number;
+  // Per-word timings of final results
+  words: WordTiming[];
+}
+
+export interface WordTiming {
+  word: string;
+  start_ms: number;
+  end_ms: number;
+  confidence: number;
 }
 
 export interface OutputEvent {
   data: string;
   is_error: boolean;
+  plain: string | null;
+  session_id?: string;
+}
+
+export interface ClaudeHeartbeat {
+  session_id: string;
+  alive: boolean;
+  uptime_secs: number;
 }
 
-export type ProcessStatus = 'Starting' | 'Running' | 'Stopped' | { Error: string };
+// Result of the `shutdown` command
+export interface ShutdownSummary {
+  stopped_recording: boolean;
+  stopped_sessions: string[];
+}
+
+// Result of the `benchmark_model` command; rtf below 1.0 keeps up with live audio
+export interface BenchmarkResult {
+  rtf: number;
+  ms_per_second_audio: number;
+}
+
+export type SelfTestStage = 'devices' | 'model' | 'capture' | 'level' | 'recognition';
+
+// Result of the `self_test` command; stages after `failed_stage` were not run
+export interface SelfTestReport {
+  devices: AudioDevice[];
+  device: string | null;
+  model_loaded: boolean;
+  samples_captured: number;
+  rms: number;
+  transcript: string | null;
+  failed_stage: SelfTestStage | null;
+  message: string | null;
+}
+
+// Recognition engine passed to set_backend
+export type SpeechBackend = 'vosk' | 'whisper';
+
+export interface SessionStats {
+  total_samples: number;
+  duration_secs: number;
+  final_count: number;
+}
+
+export interface RecognizerDebug {
+  model_loaded: boolean;
+  sample_rate: number;
+  samples_processed: number;
+  last_partial: string | null;
+  last_final: string | null;
+}
+
+// Claude session used when no session id is given
+export const DEFAULT_CLAUDE_SESSION = 'default';
+
+// Result of `search_claude_output`; offsets are into the output with escape codes removed
+export interface OutputMatch {
+  line: string;
+  line_number: number;
+  offset: number;
+  len: number;
+}
+
+export type ProcessStatus =
+  | 'Starting'
+  | 'Running'
+  | 'Stopped'
+  | { Exited: number }
+  | { Error: string };
 
 export type RecordingMode = 'toggle' | 'push-to-talk';
 
@@ -139,6 +279,14 @@ export function updateTranscription(result: RecognitionResult): void {
   }
 }
 
+// Readable message for an error thrown by `invoke`
+export function commandErrorMessage(e: unknown): string {
+  if (typeof e === 'object' && e !== null && 'message' in e) {
+    return String((e as CommandError).message);
+  }
+  return String(e);
+}
+
 export function clearTranscription(): void {
   currentTranscription.set('');
   partialTranscription.set('');